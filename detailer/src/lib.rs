@@ -1,12 +1,16 @@
+use drawing::headless::ExportFormat;
 use drawing::Handler;
 use drawing::CONSTRUCTION_IMG;
 use drawing::{
-    handler::ToolResponse, tools, Data, Feature, FeatureKey, FeatureMeta, SelectedElement,
+    handler::ToolResponse, tools, Data, Feature, FeatureKey, FeatureMeta, PointStyle,
+    SelectedElement,
 };
 use drawing::{Axis, Constraint, ConstraintKey, ConstraintMeta, DimensionDisplay};
 use drawing::{Group, GroupType};
 
 const FEATURE_NAME_WIDTH: f32 = 88.0;
+/// Angle (in degrees) given to a line when it is first marked as a bend.
+const DEFAULT_BEND_ANGLE: f32 = 90.0;
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub enum Tab {
@@ -19,12 +23,66 @@ pub enum Tab {
 #[derive(Debug, Clone)]
 pub struct State {
     tab: Tab,
+    screen_dpi: f32,
+    /// Amounts applied by the bulk dimension adjustment.
+    adjust_factor: f32,
+    adjust_delta: f32,
+    /// Index of the selection entry moved to with Tab / Shift-Tab.
+    focused_entry: Option<usize>,
+    /// Whether a widget other than the canvas had keyboard focus once the
+    /// selection tab was last shown, in which case Tab moves between fields
+    /// instead of entries.
+    had_focus: bool,
 }
 
 impl Default for State {
     fn default() -> Self {
         let tab = Tab::default();
-        Self { tab }
+        Self {
+            tab,
+            screen_dpi: 96.0,
+            adjust_factor: 1.0,
+            adjust_delta: 0.0,
+            focused_entry: None,
+            had_focus: false,
+        }
+    }
+}
+
+impl State {
+    /// Moves the focused selection entry forwards or backwards, wrapping
+    /// around at either end.
+    fn step_focused_entry(&mut self, count: usize, backwards: bool) {
+        self.focused_entry = match (self.focused_entry, count) {
+            (_, 0) => None,
+            (Some(i), _) if i < count => Some(if backwards {
+                (i + count - 1) % count
+            } else {
+                (i + 1) % count
+            }),
+            _ => Some(if backwards { count - 1 } else { 0 }),
+        };
+    }
+}
+
+/// Set while showing the selection entry just stepped to with Tab, until its
+/// first field takes focus.
+const STEPPED_ENTRY_ID: &str = "detailer_stepped_entry";
+
+trait FocusIfStepped {
+    /// Gives keyboard focus to the field if its selection entry was just
+    /// stepped to with Tab. Called on the first field of each entry.
+    fn focus_if_stepped(self) -> Self;
+}
+
+impl FocusIfStepped for egui::Response {
+    fn focus_if_stepped(self) -> Self {
+        let id = egui::Id::new(STEPPED_ENTRY_ID);
+        if self.ctx.data(|d| d.get_temp::<()>(id).is_some()) {
+            self.ctx.data_mut(|d| d.remove::<()>(id));
+            self.request_focus();
+        }
+        self
     }
 }
 
@@ -126,15 +184,19 @@ impl<'a> Widget<'a> {
     fn show_selection_tab(&mut self, ui: &mut egui::Ui) {
         let mut commands: Vec<ToolResponse> = Vec::with_capacity(4);
         let mut changed = false;
-        let mut selected: Vec<FeatureKey> = self
+        // Listed in the order they were selected, so the entries (and the
+        // focus order of their fields) don't move around between frames.
+        let mut selected: Vec<(FeatureKey, usize)> = self
             .drawing
             .selected_map
-            .keys()
-            .filter_map(|k| match k {
-                SelectedElement::Feature(f) => Some(*f),
+            .iter()
+            .filter_map(|(k, idx)| match k {
+                SelectedElement::Feature(f) => Some((*f, *idx)),
                 _ => None,
             })
             .collect();
+        selected.sort_by_key(|(_, idx)| *idx);
+        let mut selected: Vec<FeatureKey> = selected.into_iter().map(|(f, _)| f).collect();
 
         for ck in self.drawing.selected_map.keys().filter_map(|e| {
             if let SelectedElement::Constraint(ck) = e {
@@ -152,9 +214,249 @@ impl<'a> Widget<'a> {
             }
         }
 
+        let has_constraints = self
+            .drawing
+            .selected_map
+            .keys()
+            .any(|e| matches!(e, SelectedElement::Constraint(_)));
+
+        let num_lines = self.drawing.selected_lines().len();
+        let num_points = self.drawing.selected_points().len();
+        let num_arcs = self.drawing.selected_arcs().len();
+        let num_circles = self.drawing.selected_circles().len();
+
+        // Tab / Shift-Tab step between entries while no field is being edited,
+        // focusing the first field of the entry stepped to. From there egui
+        // moves between fields in entry order, until Escape drops focus.
+        let (mut stepped, mut field_focused) = (false, false);
+        // Widgets shown earlier in the frame may already have taken focus
+        // for this Tab, so go by where focus was left last frame.
+        if !self.state.had_focus {
+            let (next, prev) = ui.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+                    i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab),
+                )
+            });
+            if next || prev {
+                self.state.step_focused_entry(selected.len(), prev);
+                stepped = true;
+            }
+        }
+        if self
+            .state
+            .focused_entry
+            .is_some_and(|i| i >= selected.len())
+        {
+            self.state.focused_entry = None;
+        }
+
         egui::ScrollArea::vertical().show(ui, |ui| {
-            for k in selected {
-                ui.push_id(k, |ui| {
+            if num_lines > 1 {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Equal lengths")
+                        .on_hover_text("Constrain each selected line to the next")
+                        .clicked()
+                    {
+                        commands.push(ToolResponse::SelectionLinesEqual);
+                    }
+                    if ui
+                        .button("Parallel")
+                        .on_hover_text("Constrain each selected line to the next")
+                        .clicked()
+                    {
+                        commands.push(ToolResponse::SelectionLinesParallel);
+                    }
+                });
+                ui.separator();
+            }
+            if num_points >= 3 {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Distribute (X)")
+                        .on_hover_text("Space the selected points evenly left to right")
+                        .clicked()
+                    {
+                        self.drawing.distribute_selection(true);
+                    }
+                    if ui
+                        .button("Distribute (Y)")
+                        .on_hover_text("Space the selected points evenly top to bottom")
+                        .clicked()
+                    {
+                        self.drawing.distribute_selection(false);
+                    }
+                });
+                ui.separator();
+            }
+            if num_lines > 0 {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Fix midpoint")
+                        .on_hover_text("Pin the midpoint of each selected line where it is")
+                        .clicked()
+                    {
+                        commands.push(ToolResponse::SelectionLinesMidpointFixed);
+                    }
+                    if ui
+                        .button("Length range")
+                        .on_hover_text("Bound the length of each selected line between a min and max")
+                        .clicked()
+                    {
+                        commands.push(ToolResponse::SelectionLinesLengthRange);
+                    }
+                });
+                ui.separator();
+            }
+            if num_points == 2 {
+                if ui
+                    .button("Fix offset")
+                    .on_hover_text("Keep the second point at its current offset from the first")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionPointsOffset);
+                }
+                ui.separator();
+            }
+            if num_points == 3 {
+                if ui
+                    .button("Bisector")
+                    .on_hover_text("Keep the last selected point equidistant from the other two")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionPointOnBisector);
+                }
+                ui.separator();
+            }
+            if num_points == 1 && num_lines == 1 {
+                if ui
+                    .button("Offset from line")
+                    .on_hover_text("Keep the point at its current perpendicular distance from the line")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionPointOffsetFromLine);
+                }
+                ui.separator();
+            }
+            if num_lines == 1 && num_circles == 2 {
+                if ui
+                    .button("Common tangent")
+                    .on_hover_text("Make the line tangent to both circles")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionLineTangentCircles);
+                }
+                ui.separator();
+            }
+            if num_lines == 3 {
+                if ui
+                    .button("Equal angles")
+                    .on_hover_text("Space the three lines 120 degrees apart")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionThreeLinesEqualAngle);
+                }
+                ui.separator();
+            }
+            if num_points == 1 && num_arcs == 1 {
+                if ui
+                    .button("Along arc")
+                    .on_hover_text("Keep the point at its current fraction of the way around the arc")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionPointAlongArc);
+                }
+                ui.separator();
+            }
+            if num_points >= 2 {
+                if ui
+                    .button("Point formula")
+                    .on_hover_text("Position the last selected point by a formula of the others, starting at their centroid")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::SelectionPointExpr);
+                }
+                ui.separator();
+            }
+            if num_lines + num_points > 0 {
+                if ui
+                    .button("Auto-dimension")
+                    .on_hover_text(
+                        "Dimension selected lines at their current length, and selected points at their offset from a fixed point",
+                    )
+                    .clicked()
+                {
+                    self.drawing.auto_dimension_selection();
+                }
+                ui.separator();
+            }
+            if !selected.is_empty() {
+                if ui
+                    .button("To reference")
+                    .on_hover_text("Make the selection locked construction geometry, kept visible and in place but not exported")
+                    .clicked()
+                {
+                    self.drawing.selection_to_construction_locked();
+                }
+                ui.separator();
+            }
+            if has_constraints {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Center labels (X)")
+                        .on_hover_text("Alt-X")
+                        .clicked()
+                    {
+                        self.drawing.selection_labels_center(true);
+                    }
+                    if ui
+                        .button("Center labels (Y)")
+                        .on_hover_text("Alt-Y")
+                        .clicked()
+                    {
+                        self.drawing.selection_labels_center(false);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.state.adjust_factor)
+                            .clamp_range(0.0..=100.0)
+                            .speed(0.01)
+                            .prefix("×"),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut self.state.adjust_delta)
+                            .speed(0.1)
+                            .prefix("+")
+                            .suffix("mm"),
+                    );
+                    if ui
+                        .button("Adjust dimensions")
+                        .on_hover_text("Scale and offset the selected lengths and radii")
+                        .clicked()
+                    {
+                        self.drawing.adjust_selected_dimensions(
+                            self.state.adjust_delta,
+                            self.state.adjust_factor,
+                        );
+                    }
+                });
+                ui.separator();
+            }
+
+            let stepped_id = egui::Id::new(STEPPED_ENTRY_ID);
+            for (i, k) in selected.into_iter().enumerate() {
+                let stepped_to = stepped && self.state.focused_entry == Some(i);
+                if stepped_to {
+                    ui.data_mut(|d| d.insert_temp(stepped_id, ()));
+                }
+                let line_angle = self.drawing.line_angle_degrees(k);
+                let arc_metrics = self
+                    .drawing
+                    .arc_metrics(k)
+                    .filter(|_| self.drawing.arc_is_valid(k));
+                let entry = ui.push_id(k, |ui| {
                     match self.drawing.feature_mut(k) {
                         Some(Feature::Point(meta, x, y)) => Widget::show_selection_entry_point(
                             ui,
@@ -172,6 +474,7 @@ impl<'a> Widget<'a> {
                                 &mut changed,
                                 &k,
                                 meta,
+                                line_angle,
                             )
                         }
                         Some(Feature::Arc(meta, ..)) => Widget::show_selection_entry_arc(
@@ -180,6 +483,7 @@ impl<'a> Widget<'a> {
                             &mut changed,
                             &k,
                             meta,
+                            arc_metrics,
                         ),
                         Some(Feature::Circle(meta, _p, radius)) => {
                             Widget::show_selection_entry_circle(
@@ -221,6 +525,30 @@ impl<'a> Widget<'a> {
                                 meta,
                             )
                         }
+                        Some(Feature::Ellipse(meta, _p, rx, ry, rotation)) => {
+                            Widget::show_selection_entry_ellipse(
+                                ui,
+                                &mut commands,
+                                &mut changed,
+                                &k,
+                                rx,
+                                ry,
+                                rotation,
+                                meta,
+                            )
+                        }
+                        Some(Feature::ArcCenter(meta, _p, radius, start_deg, end_deg)) => {
+                            Widget::show_selection_entry_arc_center(
+                                ui,
+                                &mut commands,
+                                &mut changed,
+                                &k,
+                                radius,
+                                start_deg,
+                                end_deg,
+                                meta,
+                            )
+                        }
                         None => {}
                     }
 
@@ -274,6 +602,15 @@ impl<'a> Widget<'a> {
                                                 meta,
                                             )
                                         }
+                                        Some(Constraint::PointAlongArc(_, _, _, t)) => {
+                                            Widget::show_constraint_point_along_arc(
+                                                ui,
+                                                &mut commands,
+                                                &mut changed,
+                                                &ck,
+                                                t,
+                                            )
+                                        }
                                         Some(Constraint::LineLengthsEqual(
                                             _meta,
                                             _k1,
@@ -287,6 +624,47 @@ impl<'a> Widget<'a> {
                                             &mut changed,
                                             &ck,
                                         ),
+                                        Some(Constraint::PointOffsetFromLine(
+                                            _,
+                                            _,
+                                            _,
+                                            d,
+                                            side,
+                                        )) => Widget::show_constraint_point_offset_from_line(
+                                            ui,
+                                            &mut commands,
+                                            &mut changed,
+                                            &ck,
+                                            d,
+                                            side,
+                                        ),
+                                        Some(Constraint::LineTangentTwoCircles(
+                                            _,
+                                            _,
+                                            _,
+                                            _,
+                                            internal,
+                                        )) => Widget::show_constraint_line_tangent_circles(
+                                            ui,
+                                            &mut commands,
+                                            &mut changed,
+                                            &ck,
+                                            internal,
+                                        ),
+                                        Some(Constraint::OnPerpendicularBisector(..)) => {
+                                            Widget::show_constraint_on_bisector(
+                                                ui,
+                                                &mut commands,
+                                                &ck,
+                                            )
+                                        }
+                                        Some(Constraint::ThreeLinesEqualAngle(..)) => {
+                                            Widget::show_constraint_equal_angles(
+                                                ui,
+                                                &mut commands,
+                                                &ck,
+                                            )
+                                        }
                                         Some(Constraint::LinesParallel(..)) => {
                                             Widget::show_constraint_lines_parallel(
                                                 ui,
@@ -330,15 +708,83 @@ impl<'a> Widget<'a> {
                                             angle_radians,
                                             meta,
                                         ),
+                                        Some(Constraint::LineMidpointFixed(_meta, _line, x, y)) => {
+                                            Widget::show_constraint_line_midpoint_fixed(
+                                                ui,
+                                                &mut commands,
+                                                &mut changed,
+                                                &ck,
+                                                x,
+                                                y,
+                                            )
+                                        }
+                                        Some(Constraint::LineLengthRange(_meta, _line, min, max)) => {
+                                            Widget::show_constraint_line_length_range(
+                                                ui,
+                                                &mut commands,
+                                                &mut changed,
+                                                &ck,
+                                                min,
+                                                max,
+                                            )
+                                        }
+                                        Some(Constraint::PointExpr(_meta, _p, refs, fx, fy)) => {
+                                            Widget::show_constraint_point_expr(
+                                                ui,
+                                                &mut commands,
+                                                &mut changed,
+                                                &ck,
+                                                refs,
+                                                fx,
+                                                fy,
+                                            )
+                                        }
+                                        Some(Constraint::PointOffset(_meta, _p1, _p2, dx, dy)) => {
+                                            Widget::show_constraint_point_offset(
+                                                ui,
+                                                &mut commands,
+                                                &mut changed,
+                                                &ck,
+                                                dx,
+                                                dy,
+                                            )
+                                        }
                                         None => {}
                                     });
                                 }
                             });
                     }
                 });
+
+                if stepped_to {
+                    field_focused = ui.data(|d| d.get_temp::<()>(stepped_id).is_none());
+                    ui.data_mut(|d| d.remove::<()>(stepped_id));
+                }
+                if self.state.focused_entry == Some(i) {
+                    let rect = entry.response.rect.expand(2.0);
+                    ui.painter()
+                        .rect_stroke(rect, 2.0, ui.visuals().selection.stroke);
+                    if stepped {
+                        ui.scroll_to_rect(rect, None);
+                    }
+                }
             }
         });
 
+        // egui hands focus to the first widget when Tab is pressed with
+        // nothing focused, which would swallow the next step if the entry
+        // stepped to has no field to focus instead.
+        // Clicking the canvas focuses it, which shouldn't stop Tab stepping.
+        let focus = ui
+            .memory(|m| m.focus())
+            .filter(|id| Some(*id) != drawing::canvas_id(ui.ctx()));
+        if stepped && !field_focused {
+            if let Some(id) = focus {
+                ui.memory_mut(|m| m.surrender_focus(id));
+            }
+        }
+        self.state.had_focus = focus.is_some();
+
         for c in commands.drain(..) {
             self.handler.handle(self.drawing, self.tools, c);
         }
@@ -359,14 +805,92 @@ impl<'a> Widget<'a> {
             let r = ui.available_size();
             let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
 
+            let polar_id = egui::Id::new("fixed_polar").with(k);
+            let mut polar = ui.data(|d| d.get_temp::<bool>(polar_id).unwrap_or(false));
+
             let text_rect = ui.add(egui::Label::new("Fixed").wrap(false)).rect;
             ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
+            if polar {
+                // Shown as polar, but always stored as X & Y.
+                let (mut radius, mut theta) = drawing::l::cartesian_to_polar(*px, *py);
+                let r_changed = ui
+                    .add_sized(
+                        [50., text_height * 1.4],
+                        egui::DragValue::new(&mut radius)
+                            .clamp_range(0.0..=f32::MAX)
+                            .prefix("r "),
+                    )
+                    .focus_if_stepped()
+                    .changed();
+                let theta_changed = ui
+                    .add_sized(
+                        [50., text_height * 1.4],
+                        egui::DragValue::new(&mut theta).suffix("°"),
+                    )
+                    .changed();
+                if r_changed || theta_changed {
+                    (*px, *py) = drawing::l::polar_to_cartesian(radius, theta);
+                    *changed = true;
+                }
+            } else {
+                *changed |= ui
+                    .add_sized([50., text_height * 1.4], egui::DragValue::new(px))
+                    .focus_if_stepped()
+                    .changed();
+                *changed |= ui
+                    .add_sized([50., text_height * 1.4], egui::DragValue::new(py))
+                    .changed();
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+                if ui
+                    .selectable_label(polar, "∠")
+                    .on_hover_text("Enter as radius & angle from the origin")
+                    .clicked()
+                {
+                    polar = !polar;
+                    ui.data_mut(|d| d.insert_temp(polar_id, polar));
+                }
+            });
+        });
+    }
+
+    fn show_constraint_line_length_range(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        min: &mut f32,
+        max: &mut f32,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+            let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+
+            let text_rect = ui.add(egui::Label::new("Length range").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
             *changed |= ui
-                .add_sized([50., text_height * 1.4], egui::DragValue::new(px))
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(min)
+                        .speed(0.1)
+                        .clamp_range(0.0..=*max)
+                        .prefix("min "),
+                )
+                .focus_if_stepped()
                 .changed();
             *changed |= ui
-                .add_sized([50., text_height * 1.4], egui::DragValue::new(py))
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(max)
+                        .speed(0.1)
+                        .clamp_range(*min..=f32::MAX)
+                        .prefix("max "),
+                )
                 .changed();
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                 if ui.button("⊗").clicked() {
@@ -376,56 +900,205 @@ impl<'a> Widget<'a> {
         });
     }
 
-    fn show_constraint_line_length(
+    fn show_constraint_line_midpoint_fixed(
         ui: &mut egui::Ui,
         commands: &mut Vec<ToolResponse>,
         changed: &mut bool,
         k: &ConstraintKey,
-        d: &mut f32,
-        aa_info: &mut Option<(Axis, bool)>,
-        ref_pt: &mut DimensionDisplay,
-        _meta: &mut ConstraintMeta,
+        x: &mut f32,
+        y: &mut f32,
     ) {
-        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
         ui.horizontal(|ui| {
             let r = ui.available_size();
+            let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
 
-            let text_rect = ui.add(egui::Label::new("Length").wrap(false)).rect;
+            let text_rect = ui.add(egui::Label::new("Midpoint").wrap(false)).rect;
             ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-            let dv = ui.add_sized([50., text_height * 1.4], egui::DragValue::new(d));
-            *changed |= dv.changed();
-
-            if *changed && *d < 0. {
-                *d = 0.;
-            }
-
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(x).speed(0.1).prefix("x "),
+                )
+                .focus_if_stepped()
+                .changed();
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(y).speed(0.1).prefix("y "),
+                )
+                .changed();
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                 if ui.button("⊗").clicked() {
                     commands.push(ToolResponse::ConstraintDelete(*k));
                 }
-                if ui.button("V🔃").clicked() {
-                    ref_pt.next_variant();
-                    *changed = true;
-                }
             });
         });
+    }
+
+    fn show_constraint_point_expr(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        refs: &[FeatureKey],
+        fx: &mut eq::Expression,
+        fy: &mut eq::Expression,
+    ) {
+        use slotmap::Key;
+        let legend = refs
+            .iter()
+            .enumerate()
+            .map(|(i, fk)| format!("x{}, y{}: Point {:?}", i, i, fk.data()))
+            .collect::<Vec<_>>()
+            .join("\n");
 
         ui.horizontal(|ui| {
             let r = ui.available_size();
 
-            match aa_info {
-                Some((a, is_neg)) => {
-                    let text_rect = ui.add(egui::Label::new("⏵ Cardinality").wrap(false)).rect;
-                    ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+            let text_rect = ui.add(egui::Label::new("Formula").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-                    let text_rect = match (a, &is_neg) {
-                        (Axis::TopBottom, false) => ui.label("+V"),
-                        (Axis::TopBottom, true) => ui.label("-V"),
-                        (Axis::LeftRight, false) => ui.label("+H"),
-                        (Axis::LeftRight, true) => ui.label("-H"),
-                    }
-                    .rect;
+            for (axis, f) in [("x", fx), ("y", fy)] {
+                let id = ui.make_persistent_id((k, axis));
+                Widget::point_expr_edit(ui, id, f, refs.len(), changed).on_hover_text(&legend);
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
+    /// Shows a text field editing a formula of a point expression constraint.
+    /// The formula is only updated while the text parses; the text being
+    /// edited is kept in egui memory until the field loses focus.
+    fn point_expr_edit(
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        f: &mut eq::Expression,
+        num_refs: usize,
+        changed: &mut bool,
+    ) -> egui::Response {
+        let parse = |text: &str| {
+            eq::Expression::parse(text, false)
+                .ok()
+                .filter(|e| Constraint::check_point_expr(e, num_refs).is_ok())
+        };
+        let mut text = ui
+            .data_mut(|d| d.get_temp::<String>(id))
+            .unwrap_or_else(|| f.to_string());
+
+        let valid = parse(&text).is_some();
+        let mut edit = egui::TextEdit::singleline(&mut text).desired_width(60.0);
+        if !valid {
+            edit = edit.text_color(ui.visuals().error_fg_color);
+        }
+        let response = ui.add(edit);
+        if response.changed() {
+            if let Some(e) = parse(&text) {
+                *f = e;
+                *changed = true;
+            }
+        }
+
+        if response.has_focus() {
+            ui.data_mut(|d| d.insert_temp(id, text));
+        } else {
+            ui.data_mut(|d| d.remove::<String>(id));
+        }
+        response
+    }
+
+    fn show_constraint_point_offset(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        dx: &mut f32,
+        dy: &mut f32,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+            let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+
+            let text_rect = ui.add(egui::Label::new("Offset").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(dx).speed(0.1).prefix("dx "),
+                )
+                .focus_if_stepped()
+                .changed();
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(dy).speed(0.1).prefix("dy "),
+                )
+                .changed();
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
+    fn show_constraint_line_length(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        d: &mut f32,
+        aa_info: &mut Option<(Axis, bool)>,
+        ref_pt: &mut DimensionDisplay,
+        meta: &mut ConstraintMeta,
+    ) {
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("Length").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            let dv = ui
+                .add_sized([50., text_height * 1.4], egui::DragValue::new(d))
+                .focus_if_stepped();
+            *changed |= dv.changed();
+
+            if *changed && *d < 0. {
+                *d = 0.;
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+                if ui.button("V🔃").clicked() {
+                    ref_pt.next_variant();
+                    *changed = true;
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            match aa_info {
+                Some((a, is_neg)) => {
+                    let text_rect = ui.add(egui::Label::new("⏵ Cardinality").wrap(false)).rect;
+                    ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+                    let text_rect = match (a, &is_neg) {
+                        (Axis::TopBottom, false) => ui.label("+V"),
+                        (Axis::TopBottom, true) => ui.label("-V"),
+                        (Axis::LeftRight, false) => ui.label("+H"),
+                        (Axis::LeftRight, true) => ui.label("-H"),
+                    }
+                    .rect;
                     ui.add_space(
                         ui.spacing().interact_size.x + (ui.spacing().item_spacing.x * 7.0 / 6.0)
                             - text_rect.width(),
@@ -470,6 +1143,21 @@ impl<'a> Widget<'a> {
                 }
             };
         });
+
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("⏵ Priority").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(&mut meta.priority),
+                )
+                .on_hover_text("Lower priorities yield to conflicting constraints")
+                .changed();
+        });
     }
 
     fn show_constraint_line_cardinal_align(
@@ -528,12 +1216,14 @@ impl<'a> Widget<'a> {
             let text_rect = ui.add(egui::Label::new("Point lerp").wrap(false)).rect;
             ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-            let dv = ui.add_sized(
-                [50., text_height * 1.4],
-                egui::DragValue::new(amt)
-                    .clamp_range(0.0..=1.0)
-                    .speed(0.005),
-            );
+            let dv = ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(amt)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.005),
+                )
+                .focus_if_stepped();
             *changed |= dv.changed();
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
@@ -544,6 +1234,72 @@ impl<'a> Widget<'a> {
         });
     }
 
+    fn show_constraint_point_along_arc(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        t: &mut f32,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("Along arc").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            let s = ui.add(egui::Slider::new(t, 0.0..=1.0).show_value(true));
+            *changed |= s.changed();
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
+    fn show_constraint_point_offset_from_line(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        d: &mut f32,
+        side: &mut bool,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+            let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+
+            let text_rect = ui.add(egui::Label::new("Line offset").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(d)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f32::MAX)
+                        .suffix("mm"),
+                )
+                .focus_if_stepped()
+                .changed();
+            if ui
+                .button("⇄")
+                .on_hover_text("Move the point to the other side of the line")
+                .clicked()
+            {
+                *side = !*side;
+                *changed = true;
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
     fn show_constraint_line_equal(
         ui: &mut egui::Ui,
         commands: &mut Vec<ToolResponse>,
@@ -585,10 +1341,13 @@ impl<'a> Widget<'a> {
                 let text_rect = ui.add(egui::Label::new("⏵ Multiplier").wrap(false)).rect;
                 ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-                let dv = ui.add_sized(
-                    [50., text_height * 1.4],
-                    egui::DragValue::new(m).clamp_range(0.05..=20.0).speed(0.01),
-                );
+                let dv = ui
+                    .add_sized(
+                        [50., text_height * 1.4],
+                        egui::DragValue::new(m).clamp_range(0.05..=20.0).speed(0.01),
+                    )
+                    .focus_if_stepped()
+                    .on_hover_text("Length of the second line = multiplier × length of the first");
                 *changed |= dv.changed();
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
@@ -600,6 +1359,70 @@ impl<'a> Widget<'a> {
         }
     }
 
+    fn show_constraint_on_bisector(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        k: &ConstraintKey,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("On bisector").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
+    fn show_constraint_equal_angles(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        k: &ConstraintKey,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("Equal angles").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
+    fn show_constraint_line_tangent_circles(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &ConstraintKey,
+        internal: &mut bool,
+    ) {
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            let text_rect = ui.add(egui::Label::new("Common tangent").wrap(false)).rect;
+            ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
+
+            *changed |= ui
+                .checkbox(internal, "Internal")
+                .on_hover_text("Pass between the circles rather than along the outside of both")
+                .changed();
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::ConstraintDelete(*k));
+                }
+            });
+        });
+    }
+
     fn show_constraint_lines_parallel(
         ui: &mut egui::Ui,
         commands: &mut Vec<ToolResponse>,
@@ -635,12 +1458,14 @@ impl<'a> Widget<'a> {
             let text_rect = ui.add(egui::Label::new("Radius").wrap(false)).rect;
             ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-            let dv = ui.add_sized(
-                [50., text_height * 1.4],
-                egui::DragValue::new(amt)
-                    .clamp_range(0.0..=200.0)
-                    .speed(0.05),
-            );
+            let dv = ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(amt)
+                        .clamp_range(0.0..=200.0)
+                        .speed(0.05),
+                )
+                .focus_if_stepped();
             *changed |= dv.changed();
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
@@ -692,10 +1517,12 @@ impl<'a> Widget<'a> {
                 let text_rect = ui.add(egui::Label::new("⏵ Multiplier").wrap(false)).rect;
                 ui.add_space(r.x / 2. - text_rect.width() - 3.0 * ui.spacing().item_spacing.x);
 
-                let dv = ui.add_sized(
-                    [50., text_height * 1.4],
-                    egui::DragValue::new(m).clamp_range(0.05..=20.0).speed(0.01),
-                );
+                let dv = ui
+                    .add_sized(
+                        [50., text_height * 1.4],
+                        egui::DragValue::new(m).clamp_range(0.05..=20.0).speed(0.01),
+                    )
+                    .focus_if_stepped();
                 *changed |= dv.changed();
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
@@ -724,13 +1551,15 @@ impl<'a> Widget<'a> {
 
             let mut degrees = (*amt + (0.5 * std::f32::consts::PI)).to_degrees();
 
-            let dv = ui.add_sized(
-                [50., text_height * 1.4],
-                egui::DragValue::new(&mut degrees)
-                    .clamp_range(-360.0..=360.0)
-                    .speed(0.1)
-                    .suffix("°"),
-            );
+            let dv = ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(&mut degrees)
+                        .clamp_range(-360.0..=360.0)
+                        .speed(0.1)
+                        .suffix("°"),
+                )
+                .focus_if_stepped();
 
             if dv.changed() {
                 *amt = degrees.to_radians() - (0.5 * std::f32::consts::PI);
@@ -779,6 +1608,7 @@ impl<'a> Widget<'a> {
 
             *changed |= ui
                 .add_sized([50., text_height * 1.4], egui::DragValue::new(px))
+                .focus_if_stepped()
                 .changed();
             *changed |= ui
                 .add_sized([50., text_height * 1.4], egui::DragValue::new(py))
@@ -797,6 +1627,7 @@ impl<'a> Widget<'a> {
         changed: &mut bool,
         k: &FeatureKey,
         meta: &mut FeatureMeta,
+        angle: Option<f32>,
     ) {
         ui.horizontal(|ui| {
             let r = ui.available_size();
@@ -826,6 +1657,47 @@ impl<'a> Widget<'a> {
                 }
             });
         });
+
+        ui.horizontal(|ui| {
+            ui.add_space(FEATURE_NAME_WIDTH);
+            let mut bend = meta.bend_angle.is_some();
+            if ui
+                .checkbox(&mut bend, "Bend")
+                .on_hover_text("Export as a sheet-metal bend line")
+                .changed()
+            {
+                meta.bend_angle = bend.then_some(DEFAULT_BEND_ANGLE);
+                *changed = true;
+            }
+            if let Some(angle) = meta.bend_angle.as_mut() {
+                *changed |= ui
+                    .add(
+                        egui::DragValue::new(angle)
+                            .clamp_range(-180.0..=180.0)
+                            .suffix("°")
+                            .speed(0.5),
+                    )
+                    .focus_if_stepped()
+                    .changed();
+            }
+        });
+        Widget::show_feature_layer(ui, changed, meta);
+
+        if let Some(angle) = angle {
+            ui.horizontal(|ui| {
+                ui.add_space(FEATURE_NAME_WIDTH);
+                // The angle is measured from vertical, so the slope (rise over
+                // run) is its cotangent.
+                let slope = 1.0 / angle.to_radians().tan();
+                let slope = if slope.is_finite() && slope.abs() < 1e6 {
+                    format!("{:.3}", slope)
+                } else {
+                    "∞".to_string()
+                };
+                ui.label(format!("Angle: {:.2}°  Slope: {}", angle, slope))
+                    .on_hover_text("Current angle from vertical, and slope");
+            });
+        }
     }
 
     fn show_selection_entry_arc(
@@ -834,6 +1706,7 @@ impl<'a> Widget<'a> {
         changed: &mut bool,
         k: &FeatureKey,
         meta: &mut FeatureMeta,
+        metrics: Option<drawing::ArcMetrics>,
     ) {
         ui.horizontal(|ui| {
             let r = ui.available_size();
@@ -861,8 +1734,35 @@ impl<'a> Widget<'a> {
                 if ui.button("⊗").clicked() {
                     commands.push(ToolResponse::Delete(*k));
                 }
+                if ui
+                    .button("○")
+                    .on_hover_text("Close into a full circle, deleting points constrained along it")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::ArcToCircle(*k));
+                }
             });
         });
+
+        ui.horizontal(|ui| {
+            ui.add_space(FEATURE_NAME_WIDTH);
+            match metrics {
+                Some(m) => {
+                    ui.label(format!(
+                        "Length: {:.2}  Chord: {:.2}  Sagitta: {:.2}",
+                        m.length, m.chord, m.sagitta
+                    ))
+                    .on_hover_text("Current length along the arc, chord length, and bulge height");
+                }
+                None => {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠ Degenerate arc")
+                        .on_hover_text(
+                            "The points are collinear, or the start or end sits on the center, so the arc has no circle and cannot be exported",
+                        );
+                }
+            }
+        });
+        Widget::show_feature_layer(ui, changed, meta);
     }
 
     fn show_selection_entry_circle(
@@ -903,19 +1803,45 @@ impl<'a> Widget<'a> {
                         .clamp_range(0.0..=5000.0)
                         .speed(0.05),
                 )
+                .focus_if_stepped()
                 .changed();
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                 if ui.button("⊗").clicked() {
                     commands.push(ToolResponse::Delete(*k));
                 }
+                if ui
+                    .button("◔")
+                    .on_hover_text("Open into an arc, deleting radius constraints")
+                    .clicked()
+                {
+                    commands.push(ToolResponse::CircleToArc(*k));
+                }
             });
         });
+        Widget::show_feature_layer(ui, changed, meta);
     }
 
-    fn show_selection_entry_spur_gear(
-        ui: &mut egui::Ui,
-        commands: &mut Vec<ToolResponse>,
-        changed: &mut bool,
+    /// Shows the layer the feature is exported on, left empty for the default.
+    fn show_feature_layer(ui: &mut egui::Ui, changed: &mut bool, meta: &mut FeatureMeta) {
+        ui.horizontal(|ui| {
+            ui.add_space(FEATURE_NAME_WIDTH);
+            ui.label("Layer");
+            let mut layer = meta.layer.clone().unwrap_or_default();
+            if ui
+                .add(egui::TextEdit::singleline(&mut layer).desired_width(80.))
+                .on_hover_text("DXF layer to export on, or empty for the default. Groups export on the layer of their first member which sets one")
+                .changed()
+            {
+                meta.layer = (!layer.is_empty()).then_some(layer);
+                *changed = true;
+            }
+        });
+    }
+
+    fn show_selection_entry_spur_gear(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
         k: &FeatureKey,
         module: &mut f32,
         teeth: &mut usize,
@@ -954,6 +1880,7 @@ impl<'a> Widget<'a> {
                         .prefix("m")
                         .speed(1.0),
                 )
+                .focus_if_stepped()
                 .changed();
             *changed |= ui
                 .add_sized(
@@ -1019,6 +1946,7 @@ impl<'a> Widget<'a> {
                         .speed(1.0)
                         .suffix(" sides"),
                 )
+                .focus_if_stepped()
                 .changed();
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                 if ui.button("⊗").clicked() {
@@ -1045,17 +1973,182 @@ impl<'a> Widget<'a> {
         });
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn show_selection_entry_ellipse(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &FeatureKey,
+        rx: &mut f32,
+        ry: &mut f32,
+        rotation: &mut f32,
+        meta: &mut FeatureMeta,
+    ) {
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            use slotmap::Key;
+            ui.add(
+                egui::Label::new(format!("Ellipse {:?}", k.data()))
+                    .wrap(false)
+                    .truncate(true),
+            );
+            if r.x - ui.available_width() < FEATURE_NAME_WIDTH {
+                ui.add_space(FEATURE_NAME_WIDTH - (r.x - ui.available_width()));
+            }
+
+            *changed |= ui
+                .add(egui::Checkbox::without_text(&mut meta.construction))
+                .changed();
+            ui.add(egui::Image::new(CONSTRUCTION_IMG).rounding(5.0));
+
+            if ui.available_width() > r.x / 2. - ui.spacing().item_spacing.x {
+                ui.add_space(ui.available_width() - r.x / 2. - ui.spacing().item_spacing.x);
+            }
+
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(rx)
+                        .clamp_range(0.0..=5000.0)
+                        .speed(0.05),
+                )
+                .focus_if_stepped()
+                .changed();
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(ry)
+                        .clamp_range(0.0..=5000.0)
+                        .speed(0.05),
+                )
+                .changed();
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::Delete(*k));
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            let aw = ui.available_width();
+            let text_rect = ui.add(egui::Label::new("⏵ Rotation").wrap(false)).rect;
+            ui.add_space(aw / 2. - text_rect.width() - 2.0 * ui.spacing().item_spacing.x);
+
+            let mut degrees = rotation.to_degrees();
+            if ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(&mut degrees)
+                        .clamp_range(-360.0..=360.0)
+                        .speed(0.5)
+                        .suffix("°"),
+                )
+                .changed()
+            {
+                *rotation = degrees.to_radians();
+                *changed |= true;
+            }
+        });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn show_selection_entry_arc_center(
+        ui: &mut egui::Ui,
+        commands: &mut Vec<ToolResponse>,
+        changed: &mut bool,
+        k: &FeatureKey,
+        radius: &mut f32,
+        start_deg: &mut f32,
+        end_deg: &mut f32,
+        meta: &mut FeatureMeta,
+    ) {
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+        ui.horizontal(|ui| {
+            let r = ui.available_size();
+
+            use slotmap::Key;
+            ui.add(
+                egui::Label::new(format!("Arc {:?}", k.data()))
+                    .wrap(false)
+                    .truncate(true),
+            );
+            if r.x - ui.available_width() < FEATURE_NAME_WIDTH {
+                ui.add_space(FEATURE_NAME_WIDTH - (r.x - ui.available_width()));
+            }
+
+            *changed |= ui
+                .add(egui::Checkbox::without_text(&mut meta.construction))
+                .changed();
+            ui.add(egui::Image::new(CONSTRUCTION_IMG).rounding(5.0));
+
+            if ui.available_width() > r.x / 2. - ui.spacing().item_spacing.x {
+                ui.add_space(ui.available_width() - r.x / 2. - ui.spacing().item_spacing.x);
+            }
+
+            *changed |= ui
+                .add_sized(
+                    [50., text_height * 1.4],
+                    egui::DragValue::new(radius)
+                        .clamp_range(0.0..=5000.0)
+                        .speed(0.05),
+                )
+                .focus_if_stepped()
+                .changed();
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if ui.button("⊗").clicked() {
+                    commands.push(ToolResponse::Delete(*k));
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            let aw = ui.available_width();
+            let text_rect = ui.add(egui::Label::new("⏵ Angles").wrap(false)).rect;
+            ui.add_space(aw / 2. - text_rect.width() - 2.0 * ui.spacing().item_spacing.x);
+
+            for angle in [start_deg, end_deg] {
+                *changed |= ui
+                    .add_sized(
+                        [50., text_height * 1.4],
+                        egui::DragValue::new(angle)
+                            .clamp_range(-360.0..=360.0)
+                            .speed(0.5)
+                            .suffix("°"),
+                    )
+                    .changed();
+            }
+        });
+    }
+
     fn show_groups_tab<F>(&mut self, ui: &mut egui::Ui, export_save: F)
     where
         F: FnOnce(&'static str, &'static str, Vec<u8>),
     {
         let mut commands: Vec<ToolResponse> = Vec::with_capacity(4);
         let mut boundary_group_set: Option<usize> = None;
+        let mut add_from_selection: Option<usize> = None;
 
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.label("Groups are a collection of drawing elements that form a path. Use them to label collections of elements as interior geometry, boundary geometry, etc.");
             ui.add_space(10.0);
 
+            // Members flipped to construction since being added are left out of the path.
+            let stale: Vec<Vec<FeatureKey>> = self
+                .drawing
+                .groups
+                .iter()
+                .map(|g| g.stale_features(self.drawing))
+                .collect();
+            // All of a group's paths go on one layer, so other members' layers are ignored.
+            let mixed_layers: Vec<Option<String>> = self
+                .drawing
+                .groups
+                .iter()
+                .map(|g| g.has_mixed_layers(self.drawing).then(|| g.layer(self.drawing).unwrap_or("the default").to_string()))
+                .collect();
+
             for (i, group) in self.drawing.groups.iter_mut().enumerate() {
                 ui.push_id(i, |ui| {
                     let id = ui.make_persistent_id("header_group");
@@ -1078,6 +2171,7 @@ impl<'a> Widget<'a> {
                                         ui.selectable_value(&mut group.typ, GroupType::Hole, "Hole");
                                         ui.selectable_value(&mut group.typ, GroupType::Extrude, "Extrude");
                                         ui.selectable_value(&mut group.typ, GroupType::Bore, "Bore");
+                                        ui.selectable_value(&mut group.typ, GroupType::Engrave, "Engrave");
                                         if ui.selectable_value(&mut group.typ, GroupType::Boundary, "Boundary").changed() {
                                             boundary_group_set = Some(i);
                                         };
@@ -1093,20 +2187,22 @@ impl<'a> Widget<'a> {
                         })
                         .body(|ui| {
                             match group.typ {
-                                GroupType::Boundary | GroupType::Extrude | GroupType::Bore => {
+                                GroupType::Boundary | GroupType::Extrude | GroupType::Bore | GroupType::Engrave => {
                                     ui.horizontal(|ui| {
                                         let r = ui.available_size();
                                         let text_rect = ui.add(egui::Label::new(match group.typ {
                                             GroupType::Boundary => "Part thickness",
                                             GroupType::Extrude => "Extrusion thickness",
                                             GroupType::Bore => "Bore depth",
+                                            GroupType::Engrave => "Engrave depth",
                                             _ => unreachable!(),
                                         }).wrap(false)).rect;
 
                                         if text_rect.width() < r.x / 2. {
                                             ui.add_space(r.x / 2. - text_rect.width());
                                         }
-                                        let mut amt = group.amt.unwrap_or(3.0);
+                                        let default_amt = group.typ.default_amt();
+                                        let mut amt = group.amt.unwrap_or(default_amt);
                                         if ui.add(
                                                     egui::DragValue::new(&mut amt)
                                                         .clamp_range(0.1..=1000.0)
@@ -1114,14 +2210,31 @@ impl<'a> Widget<'a> {
                                                         .speed(0.1)
                                                         .min_decimals(2),
                                                 ).changed() {
-                                            if amt == 3.0 {
+                                            if amt == default_amt {
                                                 group.amt = None;
                                             } else {
                                                 group.amt = Some(amt);
                                             }
                                         }
 
-                                        if group.typ == GroupType::Extrude || group.typ == GroupType::Bore {
+                                        if matches!(group.typ, GroupType::Boundary | GroupType::Extrude) {
+                                            // 0 = top, 1 = bottom, 2 = symmetric
+                                            let mut side = match (group.symmetric, group.bottom) {
+                                                (Some(()), _) => 2,
+                                                (None, Some(())) => 1,
+                                                (None, None) => 0,
+                                            };
+                                            let labels = ["Top", "Bottom", "Symmetric"];
+                                            let before = side;
+                                            egui::ComboBox::from_id_source(("group side", i))
+                                                .width(80.0)
+                                                .selected_text(labels[side])
+                                                .show_index(ui, &mut side, labels.len(), |s| labels[s]);
+                                            if side != before {
+                                                group.bottom = (side == 1).then_some(());
+                                                group.symmetric = (side == 2).then_some(());
+                                            }
+                                        } else if matches!(group.typ, GroupType::Bore | GroupType::Engrave) {
                                             let mut bottom = group.bottom.is_some();
                                             if ui.checkbox(
                                                 &mut bottom,
@@ -1134,6 +2247,27 @@ impl<'a> Widget<'a> {
                                 }
                                 _ => {}
                             }
+                            ui.horizontal(|ui| {
+                                let r = ui.available_size();
+                                let text_rect = ui.add(egui::Label::new("Flatten tolerance").wrap(false)).rect;
+
+                                if text_rect.width() < r.x / 2. {
+                                    ui.add_space(r.x / 2. - text_rect.width());
+                                }
+                                let mut custom = group.flatten_tolerance.is_some();
+                                if ui.checkbox(&mut custom, "").on_hover_text("Override the drawing's flatten tolerance for this group").changed() {
+                                    group.flatten_tolerance = custom.then_some(self.drawing.props.flatten_tolerance);
+                                }
+                                if let Some(tolerance) = group.flatten_tolerance.as_mut() {
+                                    ui.add(
+                                        egui::DragValue::new(tolerance)
+                                            .clamp_range(0.0001..=5.0)
+                                            .suffix("mm")
+                                            .speed(0.001)
+                                            .min_decimals(4),
+                                    );
+                                }
+                            });
                             ui.horizontal(|ui| {
                                 let r = ui.available_size();
                                 let text_rect = ui.add(egui::Label::new(format!("{} features", group.features.len())).wrap(false)).rect;
@@ -1151,18 +2285,25 @@ impl<'a> Widget<'a> {
                                 };
                             });
 
+                            let stale = &stale[i];
+                            if !stale.is_empty() {
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::Label::new(egui::RichText::new(format!("⚠ {} construction features ignored", stale.len()))
+                                        .color(ui.visuals().warn_fg_color)));
+                                    if ui.button("Remove").clicked() {
+                                        group.features.retain(|fk| !stale.contains(fk));
+                                    }
+                                });
+                            }
+                            if let Some(layer) = &mixed_layers[i] {
+                                ui.add(egui::Label::new(egui::RichText::new(format!("⚠ Members are on different layers, exporting on {}", layer))
+                                    .color(ui.visuals().warn_fg_color)))
+                                    .on_hover_text("A group exports on the layer of its first member which sets one");
+                            }
+
                             ui.horizontal(|ui| {
                                 if ui.button("+ Add from selection").clicked() {
-                                    for fk in self.drawing.selected_map.keys().filter_map(|e| if let SelectedElement::Feature(f) = e { Some(f) } else { None }) {
-                                        if let Some(f) = self.drawing.features.get(*fk) {
-                                            if f.is_point() || f.is_construction() {
-                                                continue;
-                                            }
-                                        }
-                                        if group.features.iter().position(|k| k == fk).is_none() {
-                                            group.features.push(*fk);
-                                        }
-                                    }
+                                    add_from_selection = Some(i);
                                 };
                             });
                         });
@@ -1191,10 +2332,39 @@ impl<'a> Widget<'a> {
             ui.add_space(2.0);
             ui.add(egui::Slider::new(&mut self.drawing.props.flatten_tolerance, 0.0001..=5.0)
                     .text("Flatten tolerance").suffix("mm").logarithmic(true));
+            ui.horizontal(|ui| {
+                let mut kerf = self.drawing.props.kerf.is_some();
+                if ui.checkbox(&mut kerf, "Kerf compensation").on_hover_text("Grows boundaries and shrinks holes by half the kerf in 2D exports").changed() {
+                    self.drawing.props.kerf = kerf.then_some(0.1);
+                }
+                if let Some(kerf) = self.drawing.props.kerf.as_mut() {
+                    ui.add(egui::DragValue::new(kerf).clamp_range(0.0..=10.0).suffix("mm").speed(0.01).min_decimals(2));
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut stroke_width = self.drawing.props.svg_stroke_width();
+                if ui.add(egui::DragValue::new(&mut stroke_width).clamp_range(0.01..=10.0).suffix("mm").speed(0.01).min_decimals(2)).changed() {
+                    self.drawing.props.svg_stroke_width = Some(stroke_width);
+                }
+                ui.label("SVG stroke width");
+            });
+            let mut normalize = self.drawing.props.normalize_winding.is_some();
+            if ui.checkbox(&mut normalize, "Normalize winding").on_hover_text("Emit boundaries counter-clockwise and holes clockwise in 2D exports").changed() {
+                self.drawing.props.normalize_winding = normalize.then_some(());
+            }
+            let mut scad_module = self.drawing.props.openscad_module.is_some();
+            if ui.checkbox(&mut scad_module, "OpenSCAD module").on_hover_text("Wrap OpenSCAD exports in a module named after the drawing").changed() {
+                self.drawing.props.openscad_module = scad_module.then_some(());
+            }
+            let mut binary_stl = self.drawing.props.stl_ascii.is_none();
+            if ui.checkbox(&mut binary_stl, "Binary STL").changed() {
+                self.drawing.props.stl_ascii = (!binary_stl).then_some(());
+            }
 
             if let Some(err) = self.drawing.last_solve_error {
                 ui.add(egui::Label::new(egui::RichText::new(format!("⚠ Solver is inconsistent!! avg err: {:.3}mm", err))
                 .color(ui.visuals().warn_fg_color)));
+                ui.checkbox(&mut self.drawing.export_inconsistent, "Export anyway");
                 ui.add_space(5.0);
             }
 
@@ -1204,6 +2374,47 @@ impl<'a> Widget<'a> {
             let mut export_fn = OnceCell::new();
             export_fn.set(export_save).ok();
 
+            ui.horizontal(|ui| {
+                let r = ui.available_size();
+                let format = self.drawing.props.export_format.unwrap_or(ExportFormat::Dxf);
+                let text_rect = egui::ComboBox::from_id_source("export format combo")
+                    .selected_text(format.name())
+                    .show_ui(ui, |ui| {
+                        for f in ExportFormat::all() {
+                            if ui.selectable_label(format == *f, f.name()).clicked() {
+                                self.drawing.props.export_format = Some(*f);
+                            }
+                        }
+                    }).response.rect;
+                if text_rect.width() < r.x / 2. {
+                    ui.add_space(r.x / 2. - text_rect.width());
+                }
+
+                if ui.add_enabled(!self.drawing.groups.is_empty(), egui::Button::new("Export 📥")).clicked() {
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
+                    } else {
+                        match drawing::headless::export(self.drawing, format) {
+                            Ok(b) => {
+                                if let Some(f) = export_fn.take() {
+                                    f(format.name(), format.extension(), b);
+                                }
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
+                    }
+                }
+            });
+            ui.add_space(5.0);
+
+            if !self.drawing.groups.is_empty() {
+                if let Ok(length) = self.drawing.total_cut_length(self.drawing.props.flatten_tolerance) {
+                    ui.label(format!("Cut length: {:.1} mm", length))
+                        .on_hover_text("Total length of the boundary and holes, for estimating cutting time");
+                    ui.add_space(5.0);
+                }
+            }
+
             ui.horizontal(|ui| {
                 let r = ui.available_size();
                 let text_rect = ui.add(egui::Label::new("OpenSCAD Polygon")).rect;
@@ -1212,36 +2423,34 @@ impl<'a> Widget<'a> {
                 }
 
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("Clipboard 📋")).clicked() {
-                    if let Ok(t) = self.drawing.serialize_openscad(self.drawing.props.flatten_tolerance) {
-                        ui.ctx().output_mut(|o| o.copied_text = t);
-                        self.toasts.add(egui_toast::Toast {
-                            text: "OpenSCAD code copied to clipboard!".into(),
-                            kind: egui_toast::ToastKind::Info,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(3.5)
-                                .show_progress(true)
-                        });
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
                     } else {
-                        self.toasts.add(egui_toast::Toast {
-                            text: "Export failed!".into(),
-                            kind: egui_toast::ToastKind::Error,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(4.0)
-                                .show_progress(true)
-                        });
+                        match self.drawing.serialize_openscad(self.drawing.props.flatten_tolerance) {
+                            Ok(t) => {
+                                ui.ctx().output_mut(|o| o.copied_text = t);
+                                self.toasts.add(egui_toast::Toast {
+                                    text: "OpenSCAD code copied to clipboard!".into(),
+                                    kind: egui_toast::ToastKind::Info,
+                                    options: egui_toast::ToastOptions::default()
+                                        .duration_in_seconds(3.5)
+                                        .show_progress(true)
+                                });
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
                     }
                 }
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("File 📥")).clicked() {
-                    if let Ok(t) = self.drawing.serialize_openscad(self.drawing.props.flatten_tolerance) {
-                        export_fn.take().map(|f| f("OpenSCAD", "scad", t.into()));
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
                     } else {
-                        self.toasts.add(egui_toast::Toast {
-                            text: "Export failed!".into(),
-                            kind: egui_toast::ToastKind::Error,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(4.0)
-                                .show_progress(true)
-                        });
+                        match self.drawing.serialize_openscad(self.drawing.props.flatten_tolerance) {
+                            Ok(t) => {
+                                export_fn.take().map(|f| f("OpenSCAD", "scad", t.into()));
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
                     }
                 }
             });
@@ -1254,36 +2463,75 @@ impl<'a> Widget<'a> {
                 }
 
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("Clipboard 📋")).clicked() {
-                    if let Ok(t) = self.drawing.serialize_dxf(self.drawing.props.flatten_tolerance) {
-                        ui.ctx().output_mut(|o| o.copied_text = t);
-                        self.toasts.add(egui_toast::Toast {
-                            text: "DXF code copied to clipboard!".into(),
-                            kind: egui_toast::ToastKind::Info,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(3.5)
-                                .show_progress(true)
-                        });
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
                     } else {
-                        self.toasts.add(egui_toast::Toast {
-                            text: "Export failed!".into(),
-                            kind: egui_toast::ToastKind::Error,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(4.0)
-                                .show_progress(true)
-                        });
+                        match self.drawing.serialize_dxf(self.drawing.props.flatten_tolerance) {
+                            Ok(t) => {
+                                ui.ctx().output_mut(|o| o.copied_text = t);
+                                self.toasts.add(egui_toast::Toast {
+                                    text: "DXF code copied to clipboard!".into(),
+                                    kind: egui_toast::ToastKind::Info,
+                                    options: egui_toast::ToastOptions::default()
+                                        .duration_in_seconds(3.5)
+                                        .show_progress(true)
+                                });
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
                     }
                 }
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("File 📥")).clicked() {
-                    if let Ok(t) = self.drawing.serialize_dxf(self.drawing.props.flatten_tolerance) {
-                        export_fn.take().map(|f| f("AutoCAD DXF", "dxf", t.into()));
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
                     } else {
-                        self.toasts.add(egui_toast::Toast {
-                            text: "Export failed!".into(),
-                            kind: egui_toast::ToastKind::Error,
-                            options: egui_toast::ToastOptions::default()
-                                .duration_in_seconds(4.0)
-                                .show_progress(true)
-                        });
+                        match self.drawing.serialize_dxf(self.drawing.props.flatten_tolerance) {
+                            Ok(t) => {
+                                export_fn.take().map(|f| f("AutoCAD DXF", "dxf", t.into()));
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let r = ui.available_size();
+                let text_rect = ui.add(egui::Label::new("DXF (selection)")).rect;
+                if text_rect.width() < r.x / 2. {
+                    ui.add_space(r.x / 2. - text_rect.width());
+                }
+
+                if ui.add_enabled(!self.drawing.selected_map.is_empty(), egui::Button::new("File 📥"))
+                    .on_hover_text("Exports the selected lines, arcs and circles, ignoring groups")
+                    .clicked() {
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
+                    } else {
+                        match self.drawing.serialize_dxf_selection(self.drawing.props.flatten_tolerance) {
+                            Ok(t) => {
+                                if let Some(f) = export_fn.take() {
+                                    f("AutoCAD DXF", "dxf", t.into());
+                                }
+                            }
+                            Err(err) => self.export_failed(err),
+                        }
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let r = ui.available_size();
+                let text_rect = ui.add(egui::Label::new("Equations")).rect;
+                if text_rect.width() < r.x / 2. {
+                    ui.add_space(r.x / 2. - text_rect.width());
+                }
+
+                if ui.add_enabled(self.drawing.constraints.iter().next().is_some(), egui::Button::new("File 📥"))
+                    .on_hover_text("Exports the equations of every constraint as text")
+                    .clicked() {
+                    if let Some(f) = export_fn.take() {
+                        f("Equations", "txt", self.drawing.export_equations().into());
                     }
                 }
             });
@@ -1298,36 +2546,32 @@ impl<'a> Widget<'a> {
                 }
 
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("STL 📥")).clicked() {
-                    match self.drawing.as_solid() {
-                        Ok(solid) => {
-                            use drawing::l::three_d::*;
-                            export_fn.take().map(|f| f("STL", "stl", solid_to_stl(solid, self.drawing.props.flatten_tolerance)));
-                        },
-                        Err(err) => {
-                            self.toasts.add(egui_toast::Toast {
-                                text: format!("Export failed!\n\nErr: {:?}", err).into(),
-                                kind: egui_toast::ToastKind::Error,
-                                options: egui_toast::ToastOptions::default()
-                                    .duration_in_seconds(4.0)
-                                    .show_progress(true)
-                            });
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
+                    } else {
+                        match self.drawing.as_solid() {
+                            Ok(solid) => {
+                                use drawing::l::three_d::*;
+                                let tolerance = self.drawing.props.mesh_tolerance();
+                                export_fn.take().map(|f| f("STL", "stl", match self.drawing.props.stl_ascii {
+                                    Some(()) => solid_to_stl_ascii(solid, tolerance),
+                                    None => solid_to_stl(solid, tolerance),
+                                }));
+                            },
+                            Err(err) => self.export_failed(err),
                         }
                     }
                 }
                 if ui.add_enabled(self.drawing.groups.len() > 0, egui::Button::new("OBJ 📥")).clicked() {
-                    match self.drawing.as_solid() {
-                        Ok(solid) => {
-                            use drawing::l::three_d::*;
-                            export_fn.take().map(|f| f("OBJ", "obj", solid_to_obj(solid, self.drawing.props.flatten_tolerance)));
-                        },
-                        Err(err) => {
-                            self.toasts.add(egui_toast::Toast {
-                                text: format!("Export failed!\n\nErr: {:?}", err).into(),
-                                kind: egui_toast::ToastKind::Error,
-                                options: egui_toast::ToastOptions::default()
-                                    .duration_in_seconds(4.0)
-                                    .show_progress(true)
-                            });
+                    if !self.drawing.export_allowed() {
+                        self.warn_inconsistent_export();
+                    } else {
+                        match self.drawing.as_solid() {
+                            Ok(solid) => {
+                                use drawing::l::three_d::*;
+                                export_fn.take().map(|f| f("OBJ", "obj", solid_to_obj(solid, self.drawing.props.mesh_tolerance())));
+                            },
+                            Err(err) => self.export_failed(err),
                         }
                     }
                 }
@@ -1341,11 +2585,93 @@ impl<'a> Widget<'a> {
                 }
             }
         }
+        if let Some(idx) = add_from_selection {
+            self.add_selection_to_group(idx);
+        }
         for c in commands.drain(..) {
             self.handler.handle(self.drawing, self.tools, c);
         }
     }
 
+    /// Adds the selected features which continue the group's path, or outside
+    /// the boundary group close a loop of their own, warning about the rest.
+    fn add_selection_to_group(&mut self, idx: usize) {
+        let mut pending: Vec<FeatureKey> = self
+            .drawing
+            .selected_map
+            .keys()
+            .filter_map(|e| match e {
+                SelectedElement::Feature(fk) => Some(*fk),
+                _ => None,
+            })
+            .filter(|fk| match self.drawing.features.get(*fk) {
+                Some(f) => !f.is_point() && !f.is_construction(),
+                None => false,
+            })
+            .filter(|fk| !self.drawing.groups[idx].features.contains(fk))
+            .collect();
+
+        // Selected features may connect to the group through each other, so
+        // keep going for as long as some are accepted.
+        let mut group = self.drawing.groups[idx].clone();
+        // Groups other than the boundary can hold several paths, so take any
+        // loops the selection closes on its own, wherever they are.
+        if group.typ != GroupType::Boundary {
+            let closed = Group::closed_loops(&pending, self.drawing);
+            pending.retain(|fk| !closed.contains(fk));
+            group.features.extend(closed);
+        }
+        loop {
+            let before = pending.len();
+            pending.retain(|fk| {
+                if group.can_accept(*fk, self.drawing) {
+                    group.features.push(*fk);
+                    false
+                } else {
+                    true
+                }
+            });
+            if pending.len() == before {
+                break;
+            }
+        }
+        self.drawing.groups[idx] = group;
+
+        if !pending.is_empty() {
+            self.toasts.add(egui_toast::Toast {
+                text: format!(
+                    "{} selected feature(s) don't connect to the group's path and were not added.",
+                    pending.len()
+                )
+                .into(),
+                kind: egui_toast::ToastKind::Warning,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(4.0)
+                    .show_progress(true),
+            });
+        }
+    }
+
+    fn warn_inconsistent_export(&mut self) {
+        self.toasts.add(egui_toast::Toast {
+            text: "Solver is inconsistent, exported geometry may be wrong!\n\nTick 'Export anyway' and click again to export.".into(),
+            kind: egui_toast::ToastKind::Warning,
+            options: egui_toast::ToastOptions::default()
+                .duration_in_seconds(5.0)
+                .show_progress(true),
+        });
+    }
+
+    fn export_failed(&mut self, err: drawing::ExportErr) {
+        self.toasts.add(egui_toast::Toast {
+            text: format!("Export failed: {}.", err).into(),
+            kind: egui_toast::ToastKind::Error,
+            options: egui_toast::ToastOptions::default()
+                .duration_in_seconds(4.0)
+                .show_progress(true),
+        });
+    }
+
     fn show_general_tab(&mut self, ui: &mut egui::Ui) {
         ui.add_space(2.0);
         ui.add(
@@ -1381,6 +2707,18 @@ impl<'a> Widget<'a> {
         {
             self.drawing.changed_in_ui();
         };
+        ui.label(match self.drawing.last_solve_duration {
+            Some(d) => format!(
+                "Last solve: {} iterations in {:.2}ms",
+                self.drawing.last_solve_iterations,
+                d.as_secs_f64() * 1000.0
+            ),
+            None => format!(
+                "Last solve: {} iterations",
+                self.drawing.last_solve_iterations
+            ),
+        })
+        .on_hover_text("Iterations of the numeric solver, which is skipped when unneeded");
         ui.add(
             egui::Slider::new(&mut self.drawing.props.flatten_tolerance, 0.0001..=5.0)
                 .text("Flatten tolerance")
@@ -1388,5 +2726,365 @@ impl<'a> Widget<'a> {
                 .min_decimals(7)
                 .logarithmic(true),
         );
+        ui.horizontal(|ui| {
+            let mut custom = self.drawing.props.mesh_tolerance.is_some();
+            if ui
+                .checkbox(&mut custom, "")
+                .on_hover_text("Use a separate tolerance when meshing 3D exports")
+                .changed()
+            {
+                self.drawing.props.mesh_tolerance =
+                    custom.then_some(self.drawing.props.flatten_tolerance);
+            }
+            let mut tolerance = self.drawing.props.mesh_tolerance();
+            if ui
+                .add_enabled(
+                    custom,
+                    egui::Slider::new(&mut tolerance, 0.0001..=5.0)
+                        .text("Mesh tolerance")
+                        .suffix("mm")
+                        .min_decimals(7)
+                        .logarithmic(true),
+                )
+                .changed()
+            {
+                self.drawing.props.mesh_tolerance = Some(tolerance);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.drawing.props.z_offset)
+                    .suffix("mm")
+                    .speed(0.1)
+                    .min_decimals(2),
+            )
+            .on_hover_text("Raises the part off the sketch plane in 3D exports");
+            ui.label("Z offset");
+        });
+        ui.add(
+            egui::Slider::new(&mut self.drawing.hover_sensitivity, 0.25..=4.0)
+                .text("Hover sensitivity")
+                .suffix("x"),
+        );
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("pan button combo")
+                .selected_text(match self.drawing.pan_button {
+                    egui::PointerButton::Middle => "Middle",
+                    _ => "Right",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.drawing.pan_button,
+                        egui::PointerButton::Secondary,
+                        "Right",
+                    );
+                    ui.selectable_value(
+                        &mut self.drawing.pan_button,
+                        egui::PointerButton::Middle,
+                        "Middle",
+                    );
+                });
+            ui.label("Pan mouse button");
+        });
+        ui.checkbox(&mut self.drawing.zoom_to_cursor, "Zoom to cursor")
+            .on_hover_text("Scrolling zooms about the cursor rather than the center of the view");
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("point style combo")
+                .selected_text(self.drawing.point_style.name())
+                .show_ui(ui, |ui| {
+                    for style in [PointStyle::Dot, PointStyle::Cross, PointStyle::Square] {
+                        ui.selectable_value(&mut self.drawing.point_style, style, style.name());
+                    }
+                });
+            ui.label("Point style");
+        });
+        ui.add(
+            egui::Slider::new(&mut self.drawing.stroke_width, 0.5..=4.0)
+                .step_by(0.5)
+                .text("Line width"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.drawing.point_radius, 1.0..=10.0)
+                .step_by(0.5)
+                .text("Point size"),
+        );
+        ui.checkbox(
+            &mut self.drawing.snap_points_enabled,
+            "Snap new points onto lines & construction circles",
+        );
+        ui.checkbox(&mut self.drawing.show_fill, "Shade part")
+            .on_hover_text("Fill the boundary, less any holes");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.state.screen_dpi)
+                    .clamp_range(10.0..=1200.0)
+                    .suffix(" DPI"),
+            );
+            if ui
+                .button("Zoom to 1:1")
+                .on_hover_text("Show one drawing millimeter at its physical size on this display")
+                .clicked()
+            {
+                let ppp = ui.ctx().pixels_per_point();
+                self.drawing.pending_scale = Some(drawing::Viewport::true_scale_zoom(
+                    self.state.screen_dpi,
+                    ppp,
+                ));
+            }
+        });
+
+        if let Some(img) = self.drawing.reference_image.as_mut() {
+            let mut remove = false;
+            egui::CollapsingHeader::new("Reference image")
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.add(egui::Label::new(&img.path).truncate(true));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut img.x).speed(0.1).prefix("x "));
+                        ui.add(egui::DragValue::new(&mut img.y).speed(0.1).prefix("y "));
+                        ui.label("Position");
+                    });
+                    ui.horizontal(|ui| {
+                        let mut scale = img.scale;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut scale)
+                                    .speed(0.001)
+                                    .clamp_range(0.0001..=100.0)
+                                    .suffix(" mm/px"),
+                            )
+                            .changed()
+                        {
+                            img.scale_about(img.drawing_rect().center(), scale);
+                        }
+                        ui.label("Scale");
+                    });
+                    ui.add(egui::Slider::new(&mut img.opacity, 0.0..=1.0).text("Opacity"));
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut img.locked, "Locked")
+                            .on_hover_text("Stop the image being dragged on the canvas");
+                        remove = ui.button("Remove").clicked();
+                    });
+                    if img.bytes.is_none() {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Image not loaded");
+                    }
+                });
+            if remove {
+                self.drawing.reference_image = None;
+            }
+        }
+
+        egui::CollapsingHeader::new("Advanced").show(ui, |ui| {
+            let params = &mut self.drawing.bruteforce_params;
+            ui.add(
+                egui::Slider::new(&mut params.subdivision_bits, 1..=6)
+                    .text("Brute-force subdivision bits"),
+            )
+            .on_hover_text(
+                "Each unknown is tried at 2^bits positions per pass, using fewer bits when there are many unknowns",
+            );
+            ui.add(egui::Slider::new(&mut params.passes, 1..=10).text("Brute-force passes"));
+            let searching = self.drawing.pending_bruteforce.is_running();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!searching, egui::Button::new("Brute-force solve"))
+                    .on_hover_text("Search for a solution near the current geometry")
+                    .clicked()
+                {
+                    self.drawing.start_bruteforce_solve();
+                }
+                if searching {
+                    ui.spinner();
+                }
+            });
+        });
+
+        ui.add_space(8.0);
+        if ui
+            .button("Copy dimension report 📋")
+            .on_hover_text("Copy every dimension and its measured value as CSV")
+            .clicked()
+        {
+            let t = self.drawing.dimension_report_csv();
+            ui.ctx().output_mut(|o| o.copied_text = t);
+            self.toasts.add(egui_toast::Toast {
+                text: "Dimension report copied to clipboard!".into(),
+                kind: egui_toast::ToastKind::Info,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(3.5)
+                    .show_progress(true),
+            });
+        }
+
+        ui.checkbox(&mut self.drawing.show_equations, "Show equations (debug)");
+        if self.drawing.show_equations {
+            egui::CollapsingHeader::new("Equations")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for e in self.drawing.equations_debug() {
+                        ui.monospace(e);
+                    }
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_focused_entry() {
+        let mut state = State::default();
+        assert_eq!(state.focused_entry, None);
+
+        state.step_focused_entry(3, false);
+        assert_eq!(state.focused_entry, Some(0));
+        state.step_focused_entry(3, false);
+        state.step_focused_entry(3, false);
+        assert_eq!(state.focused_entry, Some(2));
+        state.step_focused_entry(3, false);
+        assert_eq!(state.focused_entry, Some(0));
+        state.step_focused_entry(3, true);
+        assert_eq!(state.focused_entry, Some(2));
+
+        // Entries went away since the last step.
+        state.step_focused_entry(2, false);
+        assert_eq!(state.focused_entry, Some(0));
+        state.step_focused_entry(0, false);
+        assert_eq!(state.focused_entry, None);
+        state.step_focused_entry(4, true);
+        assert_eq!(state.focused_entry, Some(3));
+    }
+
+    #[test]
+    fn tab_focuses_entry_field() {
+        let mut drawing = Data::default();
+        let p = drawing
+            .features
+            .insert(Feature::Point(Default::default(), 1.0, 2.0));
+        drawing.select_feature(p, true);
+        let (mut state, mut tools, mut handler) = Default::default();
+        let mut toasts = egui_toast::Toasts::new();
+
+        let ctx = egui::Context::default();
+        let mut frame = |events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                Widget::new(
+                    &mut state,
+                    &mut drawing,
+                    &mut tools,
+                    &mut handler,
+                    &mut toasts,
+                )
+                .show(ctx, |_, _, _| {});
+            });
+        };
+        let key = |key, modifiers| egui::Event::Key {
+            key,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        };
+        frame(vec![]);
+        frame(vec![key(egui::Key::Tab, egui::Modifiers::NONE)]);
+
+        // Typing goes to the entry's first field, the point's x.
+        frame(vec![]);
+        frame(vec![
+            key(egui::Key::A, egui::Modifiers::COMMAND),
+            egui::Event::Text("7".into()),
+            key(egui::Key::Enter, egui::Modifiers::NONE),
+        ]);
+        frame(vec![]);
+
+        assert_eq!(state.focused_entry, Some(0));
+        assert!(matches!(
+            drawing.features.get(p),
+            Some(Feature::Point(_, x, _)) if *x == 7.0
+        ));
+    }
+
+    #[test]
+    fn tab_steps_entries_after_canvas_click() {
+        let mut drawing = Data::default();
+        let p = drawing
+            .features
+            .insert(Feature::Point(Default::default(), 1.0, 2.0));
+        let (mut state, mut tools, mut handler) = Default::default();
+        let mut toasts = egui_toast::Toasts::new();
+
+        let ctx = egui::Context::default();
+        let mut frame = |events: Vec<egui::Event>, select: bool| {
+            if select {
+                drawing.select_feature(p, true);
+            }
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(1000.0, 700.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    drawing::Widget::new(&mut drawing, &mut handler, &mut tools).show(ui);
+                });
+                Widget::new(
+                    &mut state,
+                    &mut drawing,
+                    &mut tools,
+                    &mut handler,
+                    &mut toasts,
+                )
+                .show(ctx, |_, _, _| {});
+            });
+        };
+        let key = |key, modifiers| egui::Event::Key {
+            key,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        };
+        let click = |pressed| egui::Event::PointerButton {
+            pos: egui::pos2(100.0, 600.0),
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+
+        // Click empty canvas, which takes focus, then select the point.
+        frame(
+            vec![egui::Event::PointerMoved(egui::pos2(100.0, 600.0))],
+            false,
+        );
+        frame(vec![click(true)], false);
+        frame(vec![click(false)], false);
+        frame(vec![], true);
+        assert!(ctx.memory(|m| m.focus()).is_some());
+        assert_eq!(ctx.memory(|m| m.focus()), drawing::canvas_id(&ctx));
+
+        frame(vec![key(egui::Key::Tab, egui::Modifiers::NONE)], false);
+        frame(vec![], false);
+        frame(
+            vec![
+                key(egui::Key::A, egui::Modifiers::COMMAND),
+                egui::Event::Text("7".into()),
+                key(egui::Key::Enter, egui::Modifiers::NONE),
+            ],
+            false,
+        );
+        frame(vec![], false);
+
+        assert_eq!(state.focused_entry, Some(0));
+        assert!(matches!(
+            drawing.features.get(p),
+            Some(Feature::Point(_, x, _)) if *x == 7.0
+        ));
     }
 }
@@ -7,6 +7,9 @@ slotmap::new_key_type! {
     pub struct ConstraintKey;
 }
 
+/// Screen offset of a length range's label from the middle of its line.
+const RANGE_LABEL_OFFSET: egui::Vec2 = egui::Vec2 { x: 0.0, y: -12.0 };
+
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
 pub enum DimensionVariant {
     #[default]
@@ -22,8 +25,51 @@ impl DimensionVariant {
     }
 }
 
-#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
-pub struct ConstraintMeta {}
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ConstraintMeta {
+    /// How strongly the constraint is enforced. Constraints below
+    /// REQUIRED_PRIORITY are soft, and yield to higher-priority ones.
+    #[serde(default = "ConstraintMeta::required_priority")]
+    pub priority: u8,
+    /// Identifies the constraint across saves, unlike its key. Zero until
+    /// the drawing assigns one.
+    #[serde(default)]
+    pub id: u64,
+}
+
+impl Default for ConstraintMeta {
+    fn default() -> Self {
+        Self {
+            priority: Self::REQUIRED_PRIORITY,
+            id: 0,
+        }
+    }
+}
+
+// As with features, the id is left out of comparisons.
+impl PartialEq for ConstraintMeta {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl ConstraintMeta {
+    pub const REQUIRED_PRIORITY: u8 = u8::MAX;
+
+    fn required_priority() -> u8 {
+        Self::REQUIRED_PRIORITY
+    }
+
+    pub fn is_soft(&self) -> bool {
+        self.priority < Self::REQUIRED_PRIORITY
+    }
+
+    /// The weight given to the constraint's residuals by the solver,
+    /// in the range (0, 1].
+    pub fn weight(&self) -> f64 {
+        (self.priority as f64 + 1.0) / (Self::REQUIRED_PRIORITY as f64 + 1.0)
+    }
+}
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct DimensionDisplay {
@@ -36,6 +82,14 @@ impl DimensionDisplay {
     pub fn next_variant(&mut self) {
         self.variant = DimensionVariant::next(&self.variant);
     }
+
+    /// Returns true if the label offsets are within tolerance of each other,
+    /// and the variants match.
+    pub fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        (self.x - other.x).abs() <= tolerance
+            && (self.y - other.y).abs() <= tolerance
+            && self.variant == other.variant
+    }
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
@@ -60,14 +114,21 @@ pub struct SerializedConstraint {
     pub meta: ConstraintMeta,
     pub feature_idx: Vec<usize>,
 
-    /// Only used for Constraint::Fixed
+    /// Only used for Constraint::Fixed, Constraint::PointOffset, Constraint::LineMidpointFixed
+    /// & Constraint::LineLengthRange (as min, max)
     pub at: (f32, f32),
-    /// Only used for Constraint::LineLength & Constraint::PointLerpLine
+    /// Only used for Constraint::LineLength, Constraint::PointLerpLine, Constraint::PointAlongArc
+    /// & Constraint::PointOffsetFromLine
     pub amt: f32,
     /// Only used for Constraint::LineLength
     pub cardinality: Option<(Axis, bool)>,
     /// Only used for Constraint::LineLength
     pub ref_offset: DimensionDisplay,
+    /// Only used for Constraint::PointExpr
+    pub exprs: Option<(String, String)>,
+    /// Only used for Constraint::PointOffsetFromLine, set if the point is on the right, and
+    /// Constraint::LineTangentTwoCircles, set if the tangent is internal
+    pub side: Option<()>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -80,61 +141,158 @@ pub enum Constraint {
         Option<(Axis, bool)>, // true = negative relationship
         DimensionDisplay,
     ),
+    LineLengthRange(ConstraintMeta, FeatureKey, f32, f32), // min <= length <= max
     LineAlongCardinal(ConstraintMeta, FeatureKey, Axis),
     PointLerpLine(ConstraintMeta, FeatureKey, FeatureKey, f32),
-    LineLengthsEqual(ConstraintMeta, FeatureKey, FeatureKey, Option<f32>),
+    PointAlongArc(ConstraintMeta, FeatureKey, FeatureKey, f32), // point, arc, fraction of sweep
+    LineLengthsEqual(ConstraintMeta, FeatureKey, FeatureKey, Option<f32>), // len(l2) = multiplier * len(l1)
     LinesParallel(ConstraintMeta, FeatureKey, FeatureKey),
     LineAngle(ConstraintMeta, FeatureKey, f32),
 
     CircleRadius(ConstraintMeta, FeatureKey, f32, DimensionDisplay),
-    CircleRadiusEqual(ConstraintMeta, FeatureKey, FeatureKey, Option<f32>),
+    CircleRadiusEqual(ConstraintMeta, FeatureKey, FeatureKey, Option<f32>), // r2 = multiplier * r1
+
+    PointOffset(ConstraintMeta, FeatureKey, FeatureKey, f32, f32), // p2 = p1 + (dx, dy)
+    OnPerpendicularBisector(ConstraintMeta, FeatureKey, FeatureKey, FeatureKey), // p, a, b
+    // Point held at a perpendicular distance from a line, on its right (looking
+    // from the start to the end of the line) if true.
+    PointOffsetFromLine(ConstraintMeta, FeatureKey, FeatureKey, f32, bool), // p, line, d, side
+    // Line tangent to both circles, passing between them if true.
+    LineTangentTwoCircles(ConstraintMeta, FeatureKey, FeatureKey, FeatureKey, bool), // line, c1, c2, internal
+    // Lines spaced 120 degrees apart, pointing away from the point they share
+    // (such as a Y joint) or else from their start points.
+    ThreeLinesEqualAngle(ConstraintMeta, FeatureKey, FeatureKey, FeatureKey),
+    LineMidpointFixed(ConstraintMeta, FeatureKey, f32, f32), // (a + b) / 2 = (x, y)
+    // p = (fx, fy), where the formulas use x0, y0, x1 ... for the referenced points.
+    PointExpr(
+        ConstraintMeta,
+        FeatureKey,
+        Vec<FeatureKey>,
+        Expression,
+        Expression,
+    ),
 }
 
 impl Constraint {
+    pub fn meta(&self) -> &ConstraintMeta {
+        match self {
+            Constraint::Fixed(meta, ..)
+            | Constraint::LineLength(meta, ..)
+            | Constraint::LineLengthRange(meta, ..)
+            | Constraint::LineAlongCardinal(meta, ..)
+            | Constraint::PointLerpLine(meta, ..)
+            | Constraint::PointAlongArc(meta, ..)
+            | Constraint::LineLengthsEqual(meta, ..)
+            | Constraint::LinesParallel(meta, ..)
+            | Constraint::LineAngle(meta, ..)
+            | Constraint::CircleRadius(meta, ..)
+            | Constraint::CircleRadiusEqual(meta, ..)
+            | Constraint::PointOffset(meta, ..)
+            | Constraint::OnPerpendicularBisector(meta, ..)
+            | Constraint::PointOffsetFromLine(meta, ..)
+            | Constraint::LineTangentTwoCircles(meta, ..)
+            | Constraint::ThreeLinesEqualAngle(meta, ..)
+            | Constraint::LineMidpointFixed(meta, ..)
+            | Constraint::PointExpr(meta, ..) => meta,
+        }
+    }
+
+    pub fn meta_mut(&mut self) -> &mut ConstraintMeta {
+        match self {
+            Constraint::Fixed(meta, ..)
+            | Constraint::LineLength(meta, ..)
+            | Constraint::LineLengthRange(meta, ..)
+            | Constraint::LineAlongCardinal(meta, ..)
+            | Constraint::PointLerpLine(meta, ..)
+            | Constraint::PointAlongArc(meta, ..)
+            | Constraint::LineLengthsEqual(meta, ..)
+            | Constraint::LinesParallel(meta, ..)
+            | Constraint::LineAngle(meta, ..)
+            | Constraint::CircleRadius(meta, ..)
+            | Constraint::CircleRadiusEqual(meta, ..)
+            | Constraint::PointOffset(meta, ..)
+            | Constraint::OnPerpendicularBisector(meta, ..)
+            | Constraint::PointOffsetFromLine(meta, ..)
+            | Constraint::LineTangentTwoCircles(meta, ..)
+            | Constraint::ThreeLinesEqualAngle(meta, ..)
+            | Constraint::LineMidpointFixed(meta, ..)
+            | Constraint::PointExpr(meta, ..) => meta,
+        }
+    }
+
     pub fn affecting_features(&self) -> Vec<FeatureKey> {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match self {
             Fixed(_, fk, ..) => vec![fk.clone()],
             LineLength(_, fk, ..) => vec![fk.clone()],
+            LineLengthRange(_, fk, ..) => vec![*fk],
             LineAlongCardinal(_, fk, ..) => vec![fk.clone()],
             PointLerpLine(_, l_fk, p_fk, _) => vec![l_fk.clone(), p_fk.clone()],
+            PointAlongArc(_, p_fk, a_fk, _) => vec![*p_fk, *a_fk],
             LineLengthsEqual(_, l1, l2, ..) => vec![l1.clone(), l2.clone()],
             LinesParallel(_, l1, l2, ..) => vec![l1.clone(), l2.clone()],
             LineAngle(_, fk, ..) => vec![fk.clone()],
             CircleRadius(_, fk, ..) => vec![fk.clone()],
             CircleRadiusEqual(_, c1, c2, ..) => vec![c1.clone(), c2.clone()],
+            PointOffset(_, p1, p2, ..) => vec![*p1, *p2],
+            OnPerpendicularBisector(_, p, a, b) => vec![*p, *a, *b],
+            PointOffsetFromLine(_, p, l, ..) => vec![*p, *l],
+            LineTangentTwoCircles(_, l, c1, c2, _) => vec![*l, *c1, *c2],
+            ThreeLinesEqualAngle(_, l1, l2, l3) => vec![*l1, *l2, *l3],
+            LineMidpointFixed(_, fk, ..) => vec![*fk],
+            PointExpr(_, p, refs, ..) => std::iter::once(*p).chain(refs.iter().copied()).collect(),
         }
     }
 
     pub fn valid_for_feature(&self, ft: &Feature) -> bool {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match self {
             Fixed(..) => matches!(ft, &Feature::Point(..)),
             LineLength(..) => matches!(ft, &Feature::LineSegment(..)),
+            LineLengthRange(..) => matches!(ft, &Feature::LineSegment(..)),
             LineAlongCardinal(..) => matches!(ft, &Feature::LineSegment(..)),
             PointLerpLine(..) => matches!(ft, &Feature::LineSegment(..)),
+            PointAlongArc(..) => matches!(ft, &Feature::Arc(..)),
             LineLengthsEqual(..) => matches!(ft, &Feature::LineSegment(..)),
             LinesParallel(..) => matches!(ft, &Feature::LineSegment(..)),
             LineAngle(..) => matches!(ft, &Feature::LineSegment(..)),
             CircleRadius(..) => matches!(ft, &Feature::Circle(..)),
             CircleRadiusEqual(..) => matches!(ft, &Feature::Circle(..)),
+            PointOffset(..) => matches!(ft, &Feature::Point(..)),
+            OnPerpendicularBisector(..) => matches!(ft, &Feature::Point(..)),
+            PointOffsetFromLine(..) => {
+                matches!(ft, &Feature::Point(..) | &Feature::LineSegment(..))
+            }
+            LineTangentTwoCircles(..) => {
+                matches!(ft, &Feature::LineSegment(..) | &Feature::Circle(..))
+            }
+            ThreeLinesEqualAngle(..) => matches!(ft, &Feature::LineSegment(..)),
+            LineMidpointFixed(..) => matches!(ft, &Feature::LineSegment(..)),
+            PointExpr(..) => matches!(ft, &Feature::Point(..)),
         }
     }
 
     pub fn conflicts(&self, other: &Constraint) -> bool {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match (self, other) {
             (Fixed(_, f1, _, _), Fixed(_, f2, _, _)) => f1 == f2,
             (LineLength(_, f1, ..), LineLength(_, f2, ..)) => f1 == f2,
+            (LineLengthRange(_, f1, ..), LineLengthRange(_, f2, ..)) => f1 == f2,
             (LineLength(_, f1, _d, Some(_axis), ..), LineAlongCardinal(_, f2, ..)) => f1 == f2,
             (LineAlongCardinal(_, f2, ..), LineLength(_, f1, _d, Some(_axis), ..)) => f1 == f2,
             (LineAlongCardinal(_, f1, ..), LineAlongCardinal(_, f2, ..)) => f1 == f2,
@@ -148,14 +306,76 @@ impl Constraint {
                 (l11 == l21 && l12 == l22) || (l11 == l22 && l12 == l21)
             }
             (LineAngle(_, f1, ..), LineAngle(_, f2, ..)) => f1 == f2,
+            (LineAngle(_, f1, angle, ..), LineAlongCardinal(_, f2, axis, ..)) => {
+                f1 == f2 && !Self::angle_along_axis(*angle, axis)
+            }
+            (LineAlongCardinal(_, f2, axis, ..), LineAngle(_, f1, angle, ..)) => {
+                f1 == f2 && !Self::angle_along_axis(*angle, axis)
+            }
+            (LineAngle(_, f1, ..), LineLength(_, f2, _d, Some(_axis), ..)) => f1 == f2,
+            (LineLength(_, f2, _d, Some(_axis), ..), LineAngle(_, f1, ..)) => f1 == f2,
             (CircleRadius(_, f1, ..), CircleRadius(_, f2, ..)) => f1 == f2,
             (CircleRadiusEqual(_, c11, c12, ..), CircleRadiusEqual(_, c21, c22, ..)) => {
                 (c11 == c21 && c12 == c22) || (c11 == c22 && c12 == c21)
             }
+            (PointOffset(_, p11, p12, ..), PointOffset(_, p21, p22, ..)) => {
+                (p11 == p21 && p12 == p22) || (p11 == p22 && p12 == p21)
+            }
+            (PointOffset(_, _, p2, ..), Fixed(_, f, ..)) => p2 == f,
+            (Fixed(_, f, ..), PointOffset(_, _, p2, ..)) => p2 == f,
+            (LineMidpointFixed(_, l1, ..), LineMidpointFixed(_, l2, ..)) => l1 == l2,
+            (PointExpr(_, p1, ..), PointExpr(_, p2, ..)) => p1 == p2,
+            (OnPerpendicularBisector(_, p1, a1, b1), OnPerpendicularBisector(_, p2, a2, b2)) => {
+                p1 == p2 && ((a1 == a2 && b1 == b2) || (a1 == b2 && b1 == a2))
+            }
+            (PointAlongArc(_, p1, ..), PointAlongArc(_, p2, ..)) => p1 == p2,
+            (PointOffsetFromLine(_, p1, l1, ..), PointOffsetFromLine(_, p2, l2, ..)) => {
+                p1 == p2 && l1 == l2
+            }
+            (
+                LineTangentTwoCircles(_, l1, a1, b1, ..),
+                LineTangentTwoCircles(_, l2, a2, b2, ..),
+            ) => l1 == l2 && ((a1 == a2 && b1 == b2) || (a1 == b2 && b1 == a2)),
+            (ThreeLinesEqualAngle(_, a1, b1, c1), ThreeLinesEqualAngle(_, a2, b2, c2)) => {
+                let (mut l1, mut l2) = ([a1, b1, c1], [a2, b2, c2]);
+                l1.sort();
+                l2.sort();
+                l1 == l2
+            }
+            (PointAlongArc(_, p, ..), Fixed(_, f, ..)) => p == f,
+            (Fixed(_, f, ..), PointAlongArc(_, p, ..)) => p == f,
+            (PointExpr(_, p, ..), Fixed(_, f, ..)) => p == f,
+            (Fixed(_, f, ..), PointExpr(_, p, ..)) => p == f,
             _ => false,
         }
     }
 
+    // Whether a global line angle points along the given axis, in
+    // either direction.
+    fn angle_along_axis(angle: f32, axis: &Axis) -> bool {
+        let (s, c) = angle.sin_cos();
+        match axis {
+            Axis::LeftRight => s.abs() < 1e-4,
+            Axis::TopBottom => c.abs() < 1e-4,
+        }
+    }
+
+    /// Returns a description of why the constraint is redundant given the
+    /// other, if it is. Unlike conflicts(), such constraints may still be
+    /// added, but can over-constrain the drawing.
+    pub fn redundant_with(&self, other: &Constraint) -> Option<&'static str> {
+        use Constraint::{LineAlongCardinal, LineAngle};
+        match (self, other) {
+            (LineAngle(_, f1, angle, ..), LineAlongCardinal(_, f2, axis, ..))
+            | (LineAlongCardinal(_, f2, axis, ..), LineAngle(_, f1, angle, ..))
+                if f1 == f2 && Self::angle_along_axis(*angle, axis) =>
+            {
+                Some("line angle is already set by a horizontal/vertical constraint")
+            }
+            _ => None,
+        }
+    }
+
     pub fn screen_dist_sq(
         &self,
         drawing: &crate::Data,
@@ -164,7 +384,9 @@ impl Constraint {
     ) -> Option<f32> {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match self {
             Fixed(..) => None,
@@ -185,7 +407,10 @@ impl Constraint {
                     let text_center = vp.translate_point(a.lerp(b, 0.5))
                         + egui::Vec2::angled(t) * reference.length();
 
-                    let bounds = egui::Rect::from_center_size(text_center, (60., 15.).into());
+                    let bounds = egui::Rect::from_center_size(
+                        text_center,
+                        crate::l::draw::DIMENSION_LABEL_SIZE,
+                    );
                     Some(bounds.distance_sq_to_pos(hp))
                 } else {
                     unreachable!();
@@ -219,16 +444,41 @@ impl Constraint {
 
                     let reference = egui::Vec2::new(dd.x, dd.y);
                     let text_center = vp.translate_point(center) + reference;
-                    let bounds = egui::Rect::from_center_size(text_center, (60., 15.).into());
+                    let bounds = egui::Rect::from_center_size(
+                        text_center,
+                        crate::l::draw::DIMENSION_LABEL_SIZE,
+                    );
                     Some(bounds.distance_sq_to_pos(hp))
                 } else {
                     unreachable!();
                 }
             }
-            PointLerpLine(..) => None,
+            LineLengthRange(_, fk, ..) => {
+                let (a, b) = drawing.get_line_points(*fk)?;
+                let bounds = egui::Rect::from_center_size(
+                    vp.translate_point(a.lerp(b, 0.5)) + RANGE_LABEL_OFFSET,
+                    (40., 15.).into(),
+                );
+                Some(bounds.distance_sq_to_pos(hp))
+            }
+            PointLerpLine(..) | PointAlongArc(..) => None,
             LineLengthsEqual(..) | CircleRadiusEqual(..) => None,
             LinesParallel(..) => None,
             LineAngle(..) => None,
+            PointOffset(..) => None,
+            OnPerpendicularBisector(_, _, a, b) => {
+                let mid = drawing
+                    .get_point_pos(*a)?
+                    .lerp(drawing.get_point_pos(*b)?, 0.5);
+                let bounds =
+                    egui::Rect::from_center_size(vp.translate_point(mid), (12., 12.).into());
+                Some(bounds.distance_sq_to_pos(hp))
+            }
+            PointOffsetFromLine(..) => None,
+            LineTangentTwoCircles(..) => None,
+            ThreeLinesEqualAngle(..) => None,
+            LineMidpointFixed(..) => None,
+            PointExpr(..) => None,
         }
     }
 
@@ -241,7 +491,9 @@ impl Constraint {
     ) {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match self {
             Fixed(_, k, _, _) => {
@@ -278,7 +530,7 @@ impl Constraint {
                             Some((Axis::LeftRight, false)) => format!("H+{:.3}", d),
                             Some((Axis::LeftRight, true)) => format!("H-{:.3}", d),
                             Some((Axis::TopBottom, false)) => format!("V+{:.3}", d),
-                            Some((Axis::TopBottom, true)) => format!("V+{:.3}", d),
+                            Some((Axis::TopBottom, true)) => format!("V-{:.3}", d),
                         },
                         reference: egui::Vec2::new(dd.x, dd.y),
                         hovered: params.hovered,
@@ -286,6 +538,10 @@ impl Constraint {
                         arrow_fill: matches!(dd.variant, Some(DimensionVariant::FullLines)),
                     }
                     .draw(painter, params);
+
+                    if let Some((axis, is_neg)) = aa_info {
+                        crate::l::draw::cardinal_arrow(a, b, axis, *is_neg, painter, params);
+                    }
                 }
             }
 
@@ -309,13 +565,155 @@ impl Constraint {
                         params.font_id.clone(),
                         egui::Color32::WHITE,
                     );
+
+                    // Only the axis is enforced, so point along the line as drawn.
+                    let is_neg = match axis {
+                        Axis::LeftRight => b.x < a.x,
+                        Axis::TopBottom => b.y < a.y,
+                    };
+                    crate::l::draw::cardinal_arrow(a, b, axis, is_neg, painter, params);
+                }
+            }
+
+            LineLengthRange(_, fk, min, max) => {
+                if let Some((a, b)) = drawing.get_line_points(*fk) {
+                    painter.text(
+                        params.vp.translate_point(a.lerp(b, 0.5)) + RANGE_LABEL_OFFSET,
+                        egui::Align2::CENTER_CENTER,
+                        format!("{:.2}–{:.2}", min, max),
+                        params.font_id.clone(),
+                        if params.hovered {
+                            params.colors.hover
+                        } else {
+                            params.colors.text
+                        },
+                    );
                 }
             }
 
-            PointLerpLine(..) => {}
+            PointLerpLine(..) | PointAlongArc(..) => {}
             LineLengthsEqual(..) | CircleRadiusEqual(..) => {}
             LinesParallel(..) => {}
             LineAngle(..) => {}
+            PointOffset(..) => {}
+
+            OnPerpendicularBisector(_, p, a, b) => {
+                if let (Some(p), Some(a), Some(b)) = (
+                    drawing.get_point_pos(*p),
+                    drawing.get_point_pos(*a),
+                    drawing.get_point_pos(*b),
+                ) {
+                    // Mark the midpoint of the pair, joined to the point held on its bisector.
+                    let mid = params.vp.translate_point(a.lerp(b, 0.5));
+                    let stroke = egui::Stroke {
+                        width: 1.,
+                        color: if params.hovered {
+                            params.colors.hover
+                        } else {
+                            params.colors.text
+                        },
+                    };
+                    painter.circle_stroke(mid, 3., stroke);
+                    painter.line_segment([mid, params.vp.translate_point(p)], stroke);
+                }
+            }
+
+            PointOffsetFromLine(_, p, l, ..) => {
+                if let (Some(p), Some((a, b))) =
+                    (drawing.get_point_pos(*p), drawing.get_line_points(*l))
+                {
+                    // Join the point to the foot of its perpendicular on the line.
+                    let ab = b - a;
+                    let foot = a + ab * ((p - a).dot(ab) / ab.length_sq().max(f32::EPSILON));
+                    painter.line_segment(
+                        [
+                            params.vp.translate_point(foot),
+                            params.vp.translate_point(p),
+                        ],
+                        egui::Stroke {
+                            width: 1.,
+                            color: if params.hovered {
+                                params.colors.hover
+                            } else {
+                                params.colors.text
+                            },
+                        },
+                    );
+                }
+            }
+
+            LineTangentTwoCircles(_, l, c1, c2, _) => {
+                if let Some((a, b)) = drawing.get_line_points(*l) {
+                    let stroke = egui::Stroke {
+                        width: 1.,
+                        color: if params.hovered {
+                            params.colors.hover
+                        } else {
+                            params.colors.text
+                        },
+                    };
+                    // Join each center to the point the line touches its circle.
+                    let ab = b - a;
+                    for c in [c1, c2] {
+                        if let Some(Feature::Circle(_, p, ..)) = drawing.features.get(*c) {
+                            if let Some(p) = drawing.get_point_pos(*p) {
+                                let foot =
+                                    a + ab * ((p - a).dot(ab) / ab.length_sq().max(f32::EPSILON));
+                                let foot = params.vp.translate_point(foot);
+                                painter.circle_stroke(foot, 3., stroke);
+                                painter.line_segment([foot, params.vp.translate_point(p)], stroke);
+                            }
+                        }
+                    }
+                }
+            }
+
+            ThreeLinesEqualAngle(_, l1, l2, l3) => {
+                if let Some(p) = Constraint::shared_point(drawing, [*l1, *l2, *l3])
+                    .and_then(|p| drawing.get_point_pos(p))
+                {
+                    painter.circle_stroke(
+                        params.vp.translate_point(p),
+                        8.,
+                        egui::Stroke {
+                            width: 1.,
+                            color: if params.hovered {
+                                params.colors.hover
+                            } else {
+                                params.colors.text
+                            },
+                        },
+                    );
+                }
+            }
+
+            PointExpr(_, k, ..) => {
+                if let Some(Feature::Point(_, x, y)) = drawing.features.get(*k) {
+                    let c = params.vp.translate_point(egui::Pos2 { x: *x, y: *y });
+                    painter.rect_stroke(
+                        egui::Rect::from_center_size(c, egui::Vec2::splat(9.)),
+                        0.0,
+                        egui::Stroke {
+                            width: 1.,
+                            color: params.colors.text,
+                        },
+                    );
+                }
+            }
+
+            LineMidpointFixed(_, k, ..) => {
+                if let Some((a, b)) = drawing.get_line_points(*k) {
+                    let c = params.vp.translate_point(a.lerp(b, 0.5));
+                    painter.circle_stroke(
+                        c,
+                        5.,
+                        egui::Stroke {
+                            width: 1.,
+                            color: params.colors.text,
+                        },
+                    );
+                }
+            }
 
             CircleRadius(_meta, fk, radius, dd) => {
                 if let Some(Feature::Circle(_, center_fk, ..)) = drawing.features.get(*fk) {
@@ -371,7 +769,9 @@ impl Constraint {
     pub fn equations(&self, drawing: &mut crate::Data) -> Vec<Expression> {
         use Constraint::{
             CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
-            LineLengthsEqual, LinesParallel, PointLerpLine,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
         };
         match self {
             Fixed(_, k, x, y) => {
@@ -407,6 +807,291 @@ impl Constraint {
                     )),
                 )]
             }
+            PointOffset(_, p1, p2, dx, dy) => {
+                let (x1, y1, x2, y2) = (
+                    &drawing.terms.get_feature_term(*p1, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*p1, TermType::PositionY),
+                    &drawing.terms.get_feature_term(*p2, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*p2, TermType::PositionY),
+                );
+                vec![
+                    Expression::Equal(
+                        Box::new(Expression::Variable(x2.into())),
+                        Box::new(Expression::Sum(
+                            Box::new(Expression::Variable(x1.into())),
+                            Box::new(Expression::Rational(
+                                Rational::from_float(*dx).unwrap(),
+                                true,
+                            )),
+                        )),
+                    ),
+                    Expression::Equal(
+                        Box::new(Expression::Variable(y2.into())),
+                        Box::new(Expression::Sum(
+                            Box::new(Expression::Variable(y1.into())),
+                            Box::new(Expression::Rational(
+                                Rational::from_float(*dy).unwrap(),
+                                true,
+                            )),
+                        )),
+                    ),
+                ]
+            }
+            LineMidpointFixed(_, k, x, y) => {
+                if let Some(Feature::LineSegment(_, f1, f2)) = drawing.features.get(*k) {
+                    let (x1, y1, x2, y2) = (
+                        &drawing.terms.get_feature_term(*f1, TermType::PositionX),
+                        &drawing.terms.get_feature_term(*f1, TermType::PositionY),
+                        &drawing.terms.get_feature_term(*f2, TermType::PositionX),
+                        &drawing.terms.get_feature_term(*f2, TermType::PositionY),
+                    );
+                    // a = 2 * mid - b
+                    let mirror = |mid: f32, other: &TermRef| {
+                        Box::new(Expression::Difference(
+                            Box::new(Expression::Rational(
+                                Rational::from_float(2.0 * mid).unwrap(),
+                                true,
+                            )),
+                            Box::new(Expression::Variable(other.into())),
+                        ))
+                    };
+                    vec![
+                        Expression::Equal(
+                            Box::new(Expression::Variable(x1.into())),
+                            mirror(*x, x2),
+                        ),
+                        Expression::Equal(
+                            Box::new(Expression::Variable(y1.into())),
+                            mirror(*y, y2),
+                        ),
+                    ]
+                } else {
+                    unreachable!();
+                }
+            }
+            PointAlongArc(_, p, arc, t) => {
+                if let Some(Feature::Arc(_, start, center, _end)) = drawing.features.get(*arc) {
+                    let (sx, sy, cx, cy, px, py) = (
+                        &drawing.terms.get_feature_term(*start, TermType::PositionX),
+                        &drawing.terms.get_feature_term(*start, TermType::PositionY),
+                        &drawing.terms.get_feature_term(*center, TermType::PositionX),
+                        &drawing.terms.get_feature_term(*center, TermType::PositionY),
+                        &drawing.terms.get_feature_term(*p, TermType::PositionX),
+                        &drawing.terms.get_feature_term(*p, TermType::PositionY),
+                    );
+                    // The sweep is taken from the arc as currently drawn, which keeps
+                    // the point a rotation of the start about the center: something
+                    // the substitution solver can resolve directly. Solves are
+                    // repeated until the sweep stops changing.
+                    let angle = drawing.get_arc(*arc).unwrap().sweep() * t;
+                    let (sin, cos) = angle.sin_cos();
+
+                    let var = |t: &TermRef| Box::new(Expression::Variable(t.into()));
+                    let diff =
+                        |a: &TermRef, b: &TermRef| Box::new(Expression::Difference(var(a), var(b)));
+                    let scaled = |e: Box<Expression>, by: f32| {
+                        Box::new(Expression::Product(
+                            e,
+                            Box::new(Expression::Rational(
+                                Rational::from_float(by).unwrap(),
+                                true,
+                            )),
+                        ))
+                    };
+
+                    vec![
+                        Expression::Equal(
+                            var(px),
+                            Box::new(Expression::Sum(
+                                var(cx),
+                                Box::new(Expression::Difference(
+                                    scaled(diff(sx, cx), cos),
+                                    scaled(diff(sy, cy), sin),
+                                )),
+                            )),
+                        ),
+                        Expression::Equal(
+                            var(py),
+                            Box::new(Expression::Sum(
+                                var(cy),
+                                Box::new(Expression::Sum(
+                                    scaled(diff(sx, cx), sin),
+                                    scaled(diff(sy, cy), cos),
+                                )),
+                            )),
+                        ),
+                    ]
+                } else {
+                    unreachable!();
+                }
+            }
+            OnPerpendicularBisector(_, p, a, b) => {
+                let (px, py, ax, ay, bx, by) = (
+                    &drawing.terms.get_feature_term(*p, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*p, TermType::PositionY),
+                    &drawing.terms.get_feature_term(*a, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*a, TermType::PositionY),
+                    &drawing.terms.get_feature_term(*b, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*b, TermType::PositionY),
+                );
+                let var = |t: &TermRef| Box::new(Expression::Variable(t.into()));
+                // Twice the offset of p from the midpoint of a & b, along one axis.
+                let from_mid = |p: &TermRef, a: &TermRef, b: &TermRef| {
+                    Box::new(Expression::Difference(
+                        Box::new(Expression::Difference(
+                            Box::new(Expression::Product(
+                                Box::new(Expression::Integer(2.into())),
+                                var(p),
+                            )),
+                            var(a),
+                        )),
+                        var(b),
+                    ))
+                };
+                let along =
+                    |a: &TermRef, b: &TermRef| Box::new(Expression::Difference(var(b), var(a)));
+
+                // The bisector is the line through the midpoint, perpendicular to
+                // a -> b: (b - a) . (2p - a - b) = 0. The substitution solver
+                // rearranges for the first term, so lead with the axis a -> b runs
+                // furthest along to avoid dividing by a near-zero.
+                let (x_term, y_term) = (
+                    Box::new(Expression::Product(from_mid(px, ax, bx), along(ax, bx))),
+                    Box::new(Expression::Product(from_mid(py, ay, by), along(ay, by))),
+                );
+                let x_major = match (drawing.get_point_pos(*a), drawing.get_point_pos(*b)) {
+                    (Some(a), Some(b)) => (b.x - a.x).abs() >= (b.y - a.y).abs(),
+                    _ => true,
+                };
+                vec![Expression::Equal(
+                    Box::new(Expression::Integer(0.into())),
+                    Box::new(if x_major {
+                        Expression::Sum(x_term, y_term)
+                    } else {
+                        Expression::Sum(y_term, x_term)
+                    }),
+                )]
+            }
+            PointOffsetFromLine(_, p, l, d, side) => {
+                let signed = if *side { *d } else { -*d };
+                vec![offset_from_line_eq(
+                    drawing,
+                    *p,
+                    *l,
+                    Expression::Rational(Rational::from_float(signed).unwrap(), true),
+                )]
+            }
+            LineTangentTwoCircles(_, l, c1, c2, internal) => {
+                if let (Some(Feature::Circle(_, p1, ..)), Some(Feature::Circle(_, p2, ..))) =
+                    (drawing.features.get(*c1), drawing.features.get(*c2))
+                {
+                    let (p1, p2) = (*p1, *p2);
+                    let (r1, r2) = (
+                        &drawing.terms.get_feature_term(*c1, TermType::ScalarRadius),
+                        &drawing.terms.get_feature_term(*c2, TermType::ScalarRadius),
+                    );
+
+                    // Each center is a radius from the line. The first stays on
+                    // whichever side of the line it currently is, and the second
+                    // joins it for an external tangent or opposes it for an internal one.
+                    let right = match (drawing.get_point_pos(p1), drawing.get_line_points(*l)) {
+                        (Some(p), Some((a, b))) => {
+                            let (ab, ap) = (b - a, p - a);
+                            ab.x * ap.y - ab.y * ap.x >= 0.0
+                        }
+                        _ => true,
+                    };
+                    let signed = |r: &TermRef, right: bool| {
+                        let r = Expression::Variable(r.into());
+                        if right {
+                            r
+                        } else {
+                            Expression::Neg(Box::new(r))
+                        }
+                    };
+                    vec![
+                        offset_from_line_eq(drawing, p1, *l, signed(r1, right)),
+                        offset_from_line_eq(drawing, p2, *l, signed(r2, right != *internal)),
+                    ]
+                } else {
+                    unreachable!();
+                }
+            }
+            ThreeLinesEqualAngle(_, l1, l2, l3) => {
+                let shared = Constraint::shared_point(drawing, [*l1, *l2, *l3]);
+                let mut out = Vec::with_capacity(5);
+                let (mut sum_cos, mut sum_sin) = (Vec::with_capacity(3), Vec::with_capacity(3));
+                for l in [l1, l2, l3] {
+                    let (f1, f2) = match drawing.features.get(*l) {
+                        Some(Feature::LineSegment(_, f1, f2)) => (*f1, *f2),
+                        _ => unreachable!(),
+                    };
+                    let td = &drawing.terms.get_feature_term(*l, TermType::ScalarDistance);
+                    let (x1, y1, x2, y2) = (
+                        &drawing.terms.get_feature_term(f1, TermType::PositionX),
+                        &drawing.terms.get_feature_term(f1, TermType::PositionY),
+                        &drawing.terms.get_feature_term(f2, TermType::PositionX),
+                        &drawing.terms.get_feature_term(f2, TermType::PositionY),
+                    );
+                    out.push(Expression::Equal(
+                        Box::new(Expression::Variable(td.into())),
+                        Box::new(distance_eq(td, x1, y1, x2, y2)),
+                    ));
+
+                    // The direction points from the end of the line to its start,
+                    // so is negated for lines which leave from their start.
+                    let outward = |e: Expression| {
+                        if shared == Some(f2) {
+                            Box::new(e)
+                        } else {
+                            Box::new(Expression::Neg(Box::new(e)))
+                        }
+                    };
+                    sum_cos.push(outward(cosine_angle_eq(td, x1, x2)));
+                    sum_sin.push(outward(sine_angle_eq(td, y1, y2)));
+                }
+
+                // Three unit vectors sum to zero only when 120 degrees apart.
+                for mut terms in [sum_cos, sum_sin] {
+                    let (c, b, a) = (
+                        terms.pop().unwrap(),
+                        terms.pop().unwrap(),
+                        terms.pop().unwrap(),
+                    );
+                    out.push(Expression::Equal(
+                        Box::new(Expression::Integer(0.into())),
+                        Box::new(Expression::Sum(Box::new(Expression::Sum(a, b)), c)),
+                    ));
+                }
+                out
+            }
+            PointExpr(_, p, refs, fx, fy) => {
+                let (px, py) = (
+                    &drawing.terms.get_feature_term(*p, TermType::PositionX),
+                    &drawing.terms.get_feature_term(*p, TermType::PositionY),
+                );
+                // Swap the formula's variables for those of the referenced points.
+                let mut resolve = |f: &Expression| {
+                    let mut f = f.clone();
+                    f.walk_mut(&mut |e| {
+                        if let Expression::Variable(v) = e {
+                            if let Some((t, fk)) =
+                                point_expr_var(v).and_then(|(t, i)| Some((t, *refs.get(i)?)))
+                            {
+                                *e = Expression::Variable(
+                                    (&drawing.terms.get_feature_term(fk, t)).into(),
+                                );
+                            }
+                        }
+                        true
+                    });
+                    Box::new(f)
+                };
+                vec![
+                    Expression::Equal(Box::new(Expression::Variable(px.into())), resolve(fx)),
+                    Expression::Equal(Box::new(Expression::Variable(py.into())), resolve(fy)),
+                ]
+            }
             CircleRadiusEqual(_, c1, c2, multiplier, ..) => {
                 let (cr1, cr2) = (
                     &drawing.terms.get_feature_term(*c1, TermType::ScalarRadius),
@@ -428,6 +1113,8 @@ impl Constraint {
                 )]
             }
 
+            // Solved as a penalty instead, see penalty_residuals().
+            LineLengthRange(..) => vec![],
             LineLength(_, k, d, aa_info, _) => {
                 if let Some(Feature::LineSegment(_, f1, f2)) = drawing.features.get(*k) {
                     let td = &drawing.terms.get_feature_term(*k, TermType::ScalarDistance);
@@ -739,45 +1426,237 @@ impl Constraint {
         }
     }
 
-    /// Serialize returns a structure suitable for serialization to disk. Any feature
-    /// which maybe referenced from the current constraint must be present in fk_to_idx.
-    pub fn serialize(
-        &self,
-        fk_to_idx: &HashMap<FeatureKey, usize>,
-    ) -> Result<SerializedConstraint, ()> {
+    /// Returns residuals which are zero while the constraint is satisfied and
+    /// grow as it is violated, for constraints which bound a value rather than
+    /// setting it. These go to the numeric solver, never to substitution.
+    pub fn penalty_residuals(&self, drawing: &mut crate::Data) -> Vec<Expression> {
         match self {
-            Constraint::Fixed(meta, fk, x, y) => Ok(SerializedConstraint {
-                kind: "fixed".to_string(),
-                meta: meta.clone(),
-                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
-                at: (*x, *y),
-                ..SerializedConstraint::default()
-            }),
-            Constraint::LineLength(meta, fk, d, axis, ref_offset) => Ok(SerializedConstraint {
-                kind: "length".to_string(),
-                meta: meta.clone(),
-                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
-                amt: *d,
-                cardinality: axis.clone(),
-                ref_offset: ref_offset.clone(),
-                ..SerializedConstraint::default()
-            }),
-            Constraint::LineAngle(meta, fk, amt) => Ok(SerializedConstraint {
-                kind: "line_angle".to_string(),
-                meta: meta.clone(),
-                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
-                amt: *amt,
-                ..SerializedConstraint::default()
-            }),
-
-            Constraint::LineAlongCardinal(meta, fk, Axis::TopBottom) => Ok(SerializedConstraint {
-                kind: "vertical".to_string(),
+            Constraint::LineLengthRange(_, fk, min, max) => {
+                if let Some(Feature::LineSegment(_, p1, p2)) = drawing.features.get(*fk) {
+                    let (p1, p2) = (*p1, *p2);
+                    let (x1, y1, x2, y2) = (
+                        &drawing.terms.get_feature_term(p1, TermType::PositionX),
+                        &drawing.terms.get_feature_term(p1, TermType::PositionY),
+                        &drawing.terms.get_feature_term(p2, TermType::PositionX),
+                        &drawing.terms.get_feature_term(p2, TermType::PositionY),
+                    );
+                    let td = &drawing
+                        .terms
+                        .get_feature_term(*fk, TermType::ScalarDistance);
+                    let length = || Box::new(distance_eq(td, x1, y1, x2, y2));
+                    let bound = |v: &f32| {
+                        Box::new(Expression::Rational(
+                            Rational::from_float(*v).unwrap(),
+                            true,
+                        ))
+                    };
+
+                    vec![Expression::Sum(
+                        Box::new(hinge(Expression::Difference(length(), bound(max)))),
+                        Box::new(hinge(Expression::Difference(bound(min), length()))),
+                    )]
+                } else {
+                    unreachable!();
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Checks the constraint's parameters are usable by the solver, returning
+    /// a description of the first problem found.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        use Constraint::{
+            CircleRadius, CircleRadiusEqual, Fixed, LineAlongCardinal, LineAngle, LineLength,
+            LineLengthRange, LineLengthsEqual, LineMidpointFixed, LineTangentTwoCircles,
+            LinesParallel, OnPerpendicularBisector, PointAlongArc, PointExpr, PointLerpLine,
+            PointOffset, PointOffsetFromLine, ThreeLinesEqualAngle,
+        };
+        let finite = |v: &f32, what: &'static str| {
+            if v.is_finite() {
+                Ok(())
+            } else {
+                Err(what)
+            }
+        };
+        match self {
+            Fixed(_, _, x, y) => {
+                finite(x, "fixed x is not a number")?;
+                finite(y, "fixed y is not a number")
+            }
+            LineLengthRange(_, _, min, max) => {
+                finite(min, "minimum length is not a number")?;
+                finite(max, "maximum length is not a number")?;
+                if *min < 0.0 {
+                    return Err("minimum length is negative");
+                }
+                if min > max {
+                    return Err("minimum length exceeds the maximum");
+                }
+                Ok(())
+            }
+            LineLength(_, _, d, ..) => {
+                finite(d, "line length is not a number")?;
+                if *d < 0.0 {
+                    return Err("line length is negative");
+                }
+                Ok(())
+            }
+            CircleRadius(_, _, r, ..) => {
+                finite(r, "radius is not a number")?;
+                if *r < 0.0 {
+                    return Err("radius is negative");
+                }
+                Ok(())
+            }
+            PointLerpLine(_, _, _, amt) => {
+                finite(amt, "lerp amount is not a number")?;
+                if !(0.0..=1.0).contains(amt) {
+                    return Err("lerp amount is outside 0-1");
+                }
+                Ok(())
+            }
+            PointAlongArc(_, _, _, t) => {
+                finite(t, "arc fraction is not a number")?;
+                if !(0.0..=1.0).contains(t) {
+                    return Err("arc fraction is outside 0-1");
+                }
+                Ok(())
+            }
+            LineLengthsEqual(_, _, _, Some(m)) | CircleRadiusEqual(_, _, _, Some(m)) => {
+                finite(m, "multiplier is not a number")?;
+                if *m <= 0.0 {
+                    return Err("multiplier is not positive");
+                }
+                Ok(())
+            }
+            LineAngle(_, _, a) => finite(a, "angle is not a number"),
+            LineMidpointFixed(_, _, x, y) => {
+                finite(x, "midpoint x is not a number")?;
+                finite(y, "midpoint y is not a number")
+            }
+            PointOffset(_, _, _, dx, dy) => {
+                finite(dx, "offset dx is not a number")?;
+                finite(dy, "offset dy is not a number")
+            }
+            PointOffsetFromLine(_, _, _, d, _) => {
+                finite(d, "offset distance is not a number")?;
+                if *d < 0.0 {
+                    return Err("offset distance is negative");
+                }
+                Ok(())
+            }
+            PointExpr(_, p, refs, fx, fy) => {
+                if refs.contains(p) {
+                    return Err("point formula references its own point");
+                }
+                Constraint::check_point_expr(fx, refs.len())?;
+                Constraint::check_point_expr(fy, refs.len())
+            }
+            LineLengthsEqual(..) | CircleRadiusEqual(..) | LineAlongCardinal(..) => Ok(()),
+            LinesParallel(..) | OnPerpendicularBisector(..) | LineTangentTwoCircles(..) => Ok(()),
+            ThreeLinesEqualAngle(..) => Ok(()),
+        }
+    }
+
+    /// Returns the endpoint common to all the given lines, if any.
+    fn shared_point(drawing: &crate::Data, lines: [FeatureKey; 3]) -> Option<FeatureKey> {
+        let ends = |l: FeatureKey| match drawing.features.get(l) {
+            Some(Feature::LineSegment(_, a, b)) => Some([*a, *b]),
+            _ => None,
+        };
+        let (first, rest) = (ends(lines[0])?, [ends(lines[1])?, ends(lines[2])?]);
+        first
+            .into_iter()
+            .find(|p| rest.iter().all(|ends| ends.contains(p)))
+    }
+
+    /// Checks a formula of a Constraint::PointExpr is a plain expression, whose
+    /// only variables are the coordinates of the given number of points.
+    pub fn check_point_expr(f: &Expression, num_refs: usize) -> Result<(), &'static str> {
+        let mut result = Ok(());
+        f.walk(&mut |e| {
+            match e {
+                Expression::Variable(v) => match point_expr_var(v) {
+                    Some((_, i)) if i < num_refs => {}
+                    _ => result = Err("point formula references an unknown variable"),
+                },
+                Expression::Equal(..)
+                | Expression::GreaterEqual(..)
+                | Expression::LessEqual(..)
+                | Expression::Greater(..)
+                | Expression::Less(..) => result = Err("point formula is not an expression"),
+                _ => {}
+            }
+            result.is_ok()
+        });
+        result
+    }
+
+    /// Returns the name the constraint is saved under.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Constraint::Fixed(..) => "fixed",
+            Constraint::LineLength(..) => "length",
+            Constraint::LineAngle(..) => "line_angle",
+            Constraint::LineAlongCardinal(_, _, Axis::TopBottom) => "vertical",
+            Constraint::LineAlongCardinal(_, _, Axis::LeftRight) => "horizontal",
+            Constraint::PointLerpLine(..) => "point_lerp",
+            Constraint::LineLengthsEqual(..) => "line_lengths_equal",
+            Constraint::LinesParallel(..) => "lines_parallel",
+            Constraint::CircleRadius(..) => "radius",
+            Constraint::CircleRadiusEqual(..) => "radius_equal",
+            Constraint::PointAlongArc(..) => "point_along_arc",
+            Constraint::PointExpr(..) => "point_expr",
+            Constraint::LineMidpointFixed(..) => "line_midpoint_fixed",
+            Constraint::LineLengthRange(..) => "line_length_range",
+            Constraint::OnPerpendicularBisector(..) => "on_perpendicular_bisector",
+            Constraint::PointOffset(..) => "point_offset",
+            Constraint::PointOffsetFromLine(..) => "point_offset_from_line",
+            Constraint::ThreeLinesEqualAngle(..) => "three_lines_equal_angle",
+            Constraint::LineTangentTwoCircles(..) => "line_tangent_two_circles",
+        }
+    }
+
+    /// Serialize returns a structure suitable for serialization to disk. Any feature
+    /// which maybe referenced from the current constraint must be present in fk_to_idx.
+    pub fn serialize(
+        &self,
+        fk_to_idx: &HashMap<FeatureKey, usize>,
+    ) -> Result<SerializedConstraint, ()> {
+        match self {
+            Constraint::Fixed(meta, fk, x, y) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
+                at: (*x, *y),
+                ..SerializedConstraint::default()
+            }),
+            Constraint::LineLength(meta, fk, d, axis, ref_offset) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
+                amt: *d,
+                cardinality: axis.clone(),
+                ref_offset: ref_offset.clone(),
+                ..SerializedConstraint::default()
+            }),
+            Constraint::LineAngle(meta, fk, amt) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
+                amt: *amt,
+                ..SerializedConstraint::default()
+            }),
+
+            Constraint::LineAlongCardinal(meta, fk, Axis::TopBottom) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
                 meta: meta.clone(),
                 feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
                 ..SerializedConstraint::default()
             }),
             Constraint::LineAlongCardinal(meta, fk, Axis::LeftRight) => Ok(SerializedConstraint {
-                kind: "horizontal".to_string(),
+                kind: self.kind().to_string(),
                 meta: meta.clone(),
                 feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
                 ..SerializedConstraint::default()
@@ -788,7 +1667,7 @@ impl Constraint {
                     (fk_to_idx.get(fk1).ok_or(())?, fk_to_idx.get(fk2).ok_or(())?);
 
                 Ok(SerializedConstraint {
-                    kind: "point_lerp".to_string(),
+                    kind: self.kind().to_string(),
                     meta: meta.clone(),
                     feature_idx: vec![*fk1_idx, *fk2_idx],
                     amt: *amt,
@@ -800,7 +1679,7 @@ impl Constraint {
                     (fk_to_idx.get(fk1).ok_or(())?, fk_to_idx.get(fk2).ok_or(())?);
 
                 Ok(SerializedConstraint {
-                    kind: "line_lengths_equal".to_string(),
+                    kind: self.kind().to_string(),
                     meta: meta.clone(),
                     feature_idx: vec![*fk1_idx, *fk2_idx],
                     amt: ratio.unwrap_or(0.0),
@@ -813,7 +1692,7 @@ impl Constraint {
                     (fk_to_idx.get(fk1).ok_or(())?, fk_to_idx.get(fk2).ok_or(())?);
 
                 Ok(SerializedConstraint {
-                    kind: "lines_parallel".to_string(),
+                    kind: self.kind().to_string(),
                     meta: meta.clone(),
                     feature_idx: vec![*fk1_idx, *fk2_idx],
                     ..SerializedConstraint::default()
@@ -821,7 +1700,7 @@ impl Constraint {
             }
 
             Constraint::CircleRadius(meta, fk, r, ref_offset) => Ok(SerializedConstraint {
-                kind: "radius".to_string(),
+                kind: self.kind().to_string(),
                 meta: meta.clone(),
                 feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
                 amt: *r,
@@ -833,13 +1712,112 @@ impl Constraint {
                     (fk_to_idx.get(fk1).ok_or(())?, fk_to_idx.get(fk2).ok_or(())?);
 
                 Ok(SerializedConstraint {
-                    kind: "radius_equal".to_string(),
+                    kind: self.kind().to_string(),
                     meta: meta.clone(),
                     feature_idx: vec![*fk1_idx, *fk2_idx],
                     amt: ratio.unwrap_or(0.0),
                     ..SerializedConstraint::default()
                 })
             }
+
+            Constraint::PointAlongArc(meta, p, arc, t) => {
+                let (p_idx, arc_idx) = (fk_to_idx.get(p).ok_or(())?, fk_to_idx.get(arc).ok_or(())?);
+
+                Ok(SerializedConstraint {
+                    kind: self.kind().to_string(),
+                    meta: meta.clone(),
+                    feature_idx: vec![*p_idx, *arc_idx],
+                    amt: *t,
+                    ..SerializedConstraint::default()
+                })
+            }
+
+            Constraint::PointExpr(meta, p, refs, fx, fy) => {
+                let mut feature_idx = Vec::with_capacity(refs.len() + 1);
+                for fk in std::iter::once(p).chain(refs.iter()) {
+                    feature_idx.push(*fk_to_idx.get(fk).ok_or(())?);
+                }
+
+                Ok(SerializedConstraint {
+                    kind: self.kind().to_string(),
+                    meta: meta.clone(),
+                    feature_idx,
+                    exprs: Some((fx.to_string(), fy.to_string())),
+                    ..SerializedConstraint::default()
+                })
+            }
+
+            Constraint::LineMidpointFixed(meta, fk, x, y) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
+                at: (*x, *y),
+                ..SerializedConstraint::default()
+            }),
+
+            Constraint::LineLengthRange(meta, fk, min, max) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(fk).ok_or(())?],
+                at: (*min, *max),
+                ..SerializedConstraint::default()
+            }),
+
+            Constraint::OnPerpendicularBisector(meta, p, a, b) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![
+                    *fk_to_idx.get(p).ok_or(())?,
+                    *fk_to_idx.get(a).ok_or(())?,
+                    *fk_to_idx.get(b).ok_or(())?,
+                ],
+                ..SerializedConstraint::default()
+            }),
+
+            Constraint::PointOffset(meta, fk1, fk2, dx, dy) => {
+                let (fk1_idx, fk2_idx) =
+                    (fk_to_idx.get(fk1).ok_or(())?, fk_to_idx.get(fk2).ok_or(())?);
+
+                Ok(SerializedConstraint {
+                    kind: self.kind().to_string(),
+                    meta: meta.clone(),
+                    feature_idx: vec![*fk1_idx, *fk2_idx],
+                    at: (*dx, *dy),
+                    ..SerializedConstraint::default()
+                })
+            }
+
+            Constraint::PointOffsetFromLine(meta, p, l, d, side) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![*fk_to_idx.get(p).ok_or(())?, *fk_to_idx.get(l).ok_or(())?],
+                amt: *d,
+                side: side.then_some(()),
+                ..SerializedConstraint::default()
+            }),
+            Constraint::ThreeLinesEqualAngle(meta, l1, l2, l3) => Ok(SerializedConstraint {
+                kind: self.kind().to_string(),
+                meta: meta.clone(),
+                feature_idx: vec![
+                    *fk_to_idx.get(l1).ok_or(())?,
+                    *fk_to_idx.get(l2).ok_or(())?,
+                    *fk_to_idx.get(l3).ok_or(())?,
+                ],
+                ..SerializedConstraint::default()
+            }),
+            Constraint::LineTangentTwoCircles(meta, l, c1, c2, internal) => {
+                Ok(SerializedConstraint {
+                    kind: self.kind().to_string(),
+                    meta: meta.clone(),
+                    feature_idx: vec![
+                        *fk_to_idx.get(l).ok_or(())?,
+                        *fk_to_idx.get(c1).ok_or(())?,
+                        *fk_to_idx.get(c2).ok_or(())?,
+                    ],
+                    side: internal.then_some(()),
+                    ..SerializedConstraint::default()
+                })
+            }
         }
     }
 
@@ -914,6 +1892,17 @@ impl Constraint {
                     sc.amt,
                 ))
             }
+            "point_along_arc" => {
+                if sc.feature_idx.len() < 2 {
+                    return Err(());
+                }
+                Ok(Self::PointAlongArc(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    sc.amt,
+                ))
+            }
             "line_lengths_equal" => {
                 if sc.feature_idx.len() < 2 {
                     return Err(());
@@ -958,11 +1947,116 @@ impl Constraint {
                     if sc.amt == 0.0 { None } else { Some(sc.amt) },
                 ))
             }
+
+            "point_offset" => {
+                if sc.feature_idx.len() < 2 {
+                    return Err(());
+                }
+                Ok(Self::PointOffset(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    sc.at.0,
+                    sc.at.1,
+                ))
+            }
+            "line_length_range" => {
+                if sc.feature_idx.is_empty() {
+                    return Err(());
+                }
+                Ok(Self::LineLengthRange(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    sc.at.0,
+                    sc.at.1,
+                ))
+            }
+            "on_perpendicular_bisector" => {
+                if sc.feature_idx.len() < 3 {
+                    return Err(());
+                }
+                Ok(Self::OnPerpendicularBisector(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[2]).ok_or(())?,
+                ))
+            }
+            "point_offset_from_line" => {
+                if sc.feature_idx.len() < 2 {
+                    return Err(());
+                }
+                Ok(Self::PointOffsetFromLine(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    sc.amt,
+                    sc.side.is_some(),
+                ))
+            }
+            "three_lines_equal_angle" => {
+                if sc.feature_idx.len() < 3 {
+                    return Err(());
+                }
+                Ok(Self::ThreeLinesEqualAngle(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[2]).ok_or(())?,
+                ))
+            }
+            "line_tangent_two_circles" => {
+                if sc.feature_idx.len() < 3 {
+                    return Err(());
+                }
+                Ok(Self::LineTangentTwoCircles(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[1]).ok_or(())?,
+                    *idx_to_fk.get(&sc.feature_idx[2]).ok_or(())?,
+                    sc.side.is_some(),
+                ))
+            }
+            "line_midpoint_fixed" => {
+                if sc.feature_idx.is_empty() {
+                    return Err(());
+                }
+                Ok(Self::LineMidpointFixed(
+                    sc.meta,
+                    *idx_to_fk.get(&sc.feature_idx[0]).ok_or(())?,
+                    sc.at.0,
+                    sc.at.1,
+                ))
+            }
+            "point_expr" => {
+                let (fx, fy) = sc.exprs.as_ref().ok_or(())?;
+                let parse = |f: &str| Expression::parse(f, false).map_err(|_| ());
+                let mut keys = Vec::with_capacity(sc.feature_idx.len());
+                for idx in sc.feature_idx.iter() {
+                    keys.push(*idx_to_fk.get(idx).ok_or(())?);
+                }
+                if keys.is_empty() {
+                    return Err(());
+                }
+                let p = keys.remove(0);
+                Ok(Self::PointExpr(sc.meta, p, keys, parse(fx)?, parse(fy)?))
+            }
             _ => Err(()),
         }
     }
 }
 
+/// Splits a variable of a Constraint::PointExpr formula, such as `x0` or `y2`,
+/// into the coordinate it refers to and the index of the referenced point.
+fn point_expr_var(v: &eq::Variable) -> Option<(TermType, usize)> {
+    let t = match v.as_str().get(..1)? {
+        "x" => TermType::PositionX,
+        "y" => TermType::PositionY,
+        _ => return None,
+    };
+    Some((t, v.as_str().get(1..)?.parse().ok()?))
+}
+
 fn distance_eq(_d: &TermRef, x1: &TermRef, y1: &TermRef, x2: &TermRef, y2: &TermRef) -> Expression {
     Expression::Sqrt(
         Box::new(Expression::Sum(
@@ -985,6 +2079,82 @@ fn distance_eq(_d: &TermRef, x1: &TermRef, y1: &TermRef, x2: &TermRef, y2: &Term
     )
 }
 
+/// Returns an equation holding the point at the given signed perpendicular
+/// distance from the line, positive being to its right looking from its start
+/// to its end.
+fn offset_from_line_eq(
+    drawing: &mut crate::Data,
+    p: FeatureKey,
+    l: FeatureKey,
+    signed: Expression,
+) -> Expression {
+    let (a, b) = match drawing.features.get(l) {
+        Some(Feature::LineSegment(_, a, b)) => (*a, *b),
+        _ => unreachable!(),
+    };
+    let (px, py, ax, ay, bx, by) = (
+        &drawing.terms.get_feature_term(p, TermType::PositionX),
+        &drawing.terms.get_feature_term(p, TermType::PositionY),
+        &drawing.terms.get_feature_term(a, TermType::PositionX),
+        &drawing.terms.get_feature_term(a, TermType::PositionY),
+        &drawing.terms.get_feature_term(b, TermType::PositionX),
+        &drawing.terms.get_feature_term(b, TermType::PositionY),
+    );
+    let var = |t: &TermRef| Box::new(Expression::Variable(t.into()));
+    let diff = |a: &TermRef, b: &TermRef| Box::new(Expression::Difference(var(a), var(b)));
+
+    // The cross product of a -> b and a -> p is the signed distance of p from
+    // the line, scaled by its length. The substitution solver rearranges for the
+    // first variable it finds, so lead with p's coordinate on the axis a -> b runs
+    // furthest along, leaving it to divide by that span. Swapping the terms
+    // negates the product, so the offset is negated to match.
+    let (x_term, y_term) = (
+        Box::new(Expression::Product(diff(py, ay), diff(bx, ax))),
+        Box::new(Expression::Product(diff(px, ax), diff(by, ay))),
+    );
+    let x_major = match drawing.get_line_points(l) {
+        Some((a, b)) => (b.x - a.x).abs() >= (b.y - a.y).abs(),
+        None => true,
+    };
+    let (cross, signed) = if x_major {
+        (Expression::Difference(x_term, y_term), signed)
+    } else {
+        (
+            Expression::Difference(y_term, x_term),
+            Expression::Neg(Box::new(signed)),
+        )
+    };
+    let offset = Expression::Product(
+        Box::new(signed),
+        Box::new(Expression::Sqrt(
+            Box::new(Expression::Sum(
+                Box::new(Expression::Power(
+                    diff(bx, ax),
+                    Box::new(Expression::Integer(2.into())),
+                )),
+                Box::new(Expression::Power(
+                    diff(by, ay),
+                    Box::new(Expression::Integer(2.into())),
+                )),
+            )),
+            false,
+        )),
+    );
+    Expression::Equal(
+        Box::new(Expression::Integer(0.into())),
+        Box::new(Expression::Difference(Box::new(cross), Box::new(offset))),
+    )
+}
+
+/// Returns max(0, x), written as (x + |x|) / 2 so the solver can differentiate it.
+fn hinge(x: Expression) -> Expression {
+    let x = Box::new(x);
+    Expression::Quotient(
+        Box::new(Expression::Sum(x.clone(), Box::new(Expression::Abs(x)))),
+        Box::new(Expression::Integer(2.into())),
+    )
+}
+
 fn cosine_angle_eq(d: &TermRef, x1: &TermRef, x2: &TermRef) -> Expression {
     // dot = ax × bx + ay × by
     // a = [1, 0]
@@ -1154,6 +2324,136 @@ mod tests {
                 ..SerializedConstraint::default()
             }),
         );
+        assert_eq!(
+            Constraint::PointOffset(ConstraintMeta::default(), point_key, point_key, 3.0, -1.5)
+                .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "point_offset".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42, 42],
+                at: (3.0, -1.5),
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::LineLengthRange(ConstraintMeta::default(), point_key, 10.0, 20.0)
+                .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "line_length_range".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42],
+                at: (10.0, 20.0),
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::OnPerpendicularBisector(
+                ConstraintMeta::default(),
+                point_key,
+                point_key,
+                point_key
+            )
+            .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "on_perpendicular_bisector".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42, 42, 42],
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::PointOffsetFromLine(
+                ConstraintMeta::default(),
+                point_key,
+                point_key,
+                2.5,
+                true
+            )
+            .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "point_offset_from_line".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42, 42],
+                amt: 2.5,
+                side: Some(()),
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::LineTangentTwoCircles(
+                ConstraintMeta::default(),
+                point_key,
+                point_key,
+                point_key,
+                true
+            )
+            .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "line_tangent_two_circles".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42, 42, 42],
+                side: Some(()),
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::ThreeLinesEqualAngle(
+                ConstraintMeta::default(),
+                point_key,
+                point_key,
+                point_key
+            )
+            .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "three_lines_equal_angle".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42, 42, 42],
+                ..SerializedConstraint::default()
+            }),
+        );
+        assert_eq!(
+            Constraint::LineMidpointFixed(ConstraintMeta::default(), point_key, 4.0, 0.5)
+                .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedConstraint {
+                kind: "line_midpoint_fixed".to_string(),
+                meta: ConstraintMeta::default(),
+                feature_idx: vec![42],
+                at: (4.0, 0.5),
+                ..SerializedConstraint::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn validate() {
+        use slotmap::Key;
+        let k = FeatureKey::null();
+        let m = ConstraintMeta::default;
+
+        assert_eq!(Constraint::Fixed(m(), k, 1.0, 2.0).validate(), Ok(()));
+        assert!(Constraint::Fixed(m(), k, f32::INFINITY, 2.0)
+            .validate()
+            .is_err());
+        assert!(Constraint::PointLerpLine(m(), k, k, 1e9)
+            .validate()
+            .is_err());
+        assert!(Constraint::PointLerpLine(m(), k, k, f32::NAN)
+            .validate()
+            .is_err());
+        assert_eq!(
+            Constraint::PointLerpLine(m(), k, k, 0.25).validate(),
+            Ok(())
+        );
+        assert!(Constraint::LineLengthsEqual(m(), k, k, Some(0.0))
+            .validate()
+            .is_err());
+        assert_eq!(
+            Constraint::LineLengthsEqual(m(), k, k, None).validate(),
+            Ok(())
+        );
+        assert!(Constraint::PointOffsetFromLine(m(), k, k, -1.0, true)
+            .validate()
+            .is_err());
     }
 
     #[test]
@@ -1247,6 +2547,94 @@ mod tests {
             .unwrap(),
             Constraint::CircleRadiusEqual(ConstraintMeta::default(), k, k, None,),
         );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "point_offset".to_string(),
+                    feature_idx: vec![1, 1],
+                    at: (3.0, -1.5),
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::PointOffset(ConstraintMeta::default(), k, k, 3.0, -1.5),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "line_length_range".to_string(),
+                    feature_idx: vec![1],
+                    at: (10.0, 20.0),
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::LineLengthRange(ConstraintMeta::default(), k, 10.0, 20.0),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "on_perpendicular_bisector".to_string(),
+                    feature_idx: vec![1, 1, 1],
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::OnPerpendicularBisector(ConstraintMeta::default(), k, k, k),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "point_offset_from_line".to_string(),
+                    feature_idx: vec![1, 1],
+                    amt: 2.5,
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::PointOffsetFromLine(ConstraintMeta::default(), k, k, 2.5, false),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "line_tangent_two_circles".to_string(),
+                    feature_idx: vec![1, 1, 1],
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::LineTangentTwoCircles(ConstraintMeta::default(), k, k, k, false),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "three_lines_equal_angle".to_string(),
+                    feature_idx: vec![1, 1, 1],
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::ThreeLinesEqualAngle(ConstraintMeta::default(), k, k, k),
+        );
+        assert_eq!(
+            Constraint::deserialize(
+                SerializedConstraint {
+                    kind: "line_midpoint_fixed".to_string(),
+                    feature_idx: vec![1],
+                    at: (4.0, 0.5),
+                    ..SerializedConstraint::default()
+                },
+                &HashMap::from([(1, k)])
+            )
+            .unwrap(),
+            Constraint::LineMidpointFixed(ConstraintMeta::default(), k, 4.0, 0.5),
+        );
         // TODO: PointLerpLine, LinesParallel, CircleRadius
     }
 }
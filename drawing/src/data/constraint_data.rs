@@ -1,4 +1,4 @@
-use crate::FeatureKey;
+use crate::{Axis, FeatureKey};
 use crate::{Constraint, ConstraintKey};
 use slotmap::HopSlotMap;
 use std::collections::{HashMap, HashSet};
@@ -29,10 +29,22 @@ impl ConstraintData {
         self.constraints.iter()
     }
 
+    /// Iterates the constraints mutably. The features a constraint affects
+    /// must not be changed, as they are cached.
+    pub fn iter_mut(&mut self) -> slotmap::hop::IterMut<'_, ConstraintKey, Constraint> {
+        self.constraints.iter_mut()
+    }
+
+    /// Adds a constraint, returning None if it conflicts with (or duplicates)
+    /// a constraint already present on the same feature(s).
     pub fn add(&mut self, c: Constraint) -> Option<ConstraintKey> {
-        for c2 in self.constraints.values() {
-            if c.conflicts(c2) {
-                return None;
+        for fk in c.affecting_features() {
+            if let Some(set) = self.by_feature.get(&fk) {
+                for ck in set.iter() {
+                    if c.conflicts(self.constraints.get(*ck).unwrap()) {
+                        return None;
+                    }
+                }
             }
         }
 
@@ -81,25 +93,89 @@ impl ConstraintData {
         self.constraints.get(ck)
     }
 
-    pub fn get_using_feature_and_type(
-        &self,
+    /// Iterates over the constraints affecting the given feature.
+    pub fn iter_feature<'a>(
+        &'a self,
         k: &FeatureKey,
-        t: std::mem::Discriminant<Constraint>,
-    ) -> Option<&Constraint> {
-        match self.by_feature.get(k) {
-            Some(set) => {
-                for ck in set.iter() {
-                    let c = self.constraints.get(*ck);
-                    if let Some(c) = c {
-                        use std::mem::discriminant;
-                        if discriminant(c) == t {
-                            return Some(c);
-                        }
-                    }
-                }
+    ) -> impl Iterator<Item = (ConstraintKey, &'a Constraint)> + 'a {
+        self.by_feature
+            .get(k)
+            .into_iter()
+            .flatten()
+            .filter_map(|ck| self.constraints.get(*ck).map(|c| (*ck, c)))
+    }
+
+    /// Iterates over fixed constraints, as (key, point, x, y).
+    pub fn iter_fixed(&self) -> impl Iterator<Item = (ConstraintKey, FeatureKey, f32, f32)> + '_ {
+        self.constraints.iter().filter_map(|(ck, c)| match c {
+            Constraint::Fixed(_, fk, x, y) => Some((ck, *fk, *x, *y)),
+            _ => None,
+        })
+    }
+
+    /// Iterates over line length constraints, as (key, line, length, cardinality).
+    pub fn iter_line_lengths(
+        &self,
+    ) -> impl Iterator<Item = (ConstraintKey, FeatureKey, f32, Option<&(Axis, bool)>)> + '_ {
+        self.constraints.iter().filter_map(|(ck, c)| match c {
+            Constraint::LineLength(_, fk, d, cardinality, ..) => {
+                Some((ck, *fk, *d, cardinality.as_ref()))
             }
-            _ => {}
-        };
-        None
+            _ => None,
+        })
+    }
+
+    /// Iterates over horizontal/vertical constraints, as (key, line, axis).
+    pub fn iter_line_cardinals(
+        &self,
+    ) -> impl Iterator<Item = (ConstraintKey, FeatureKey, &Axis)> + '_ {
+        self.constraints.iter().filter_map(|(ck, c)| match c {
+            Constraint::LineAlongCardinal(_, fk, axis) => Some((ck, *fk, axis)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConstraintMeta, DimensionDisplay};
+    use slotmap::SlotMap;
+
+    #[test]
+    fn iter_typed() {
+        let mut keys: SlotMap<FeatureKey, ()> = SlotMap::with_key();
+        let (p, l1, l2) = (keys.insert(()), keys.insert(()), keys.insert(()));
+
+        let mut cd = ConstraintData::default();
+        cd.add(Constraint::Fixed(ConstraintMeta::default(), p, 1.0, 2.0));
+        let len = cd
+            .add(Constraint::LineLength(
+                ConstraintMeta::default(),
+                l1,
+                5.0,
+                None,
+                DimensionDisplay::default(),
+            ))
+            .unwrap();
+        cd.add(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            l2,
+            Axis::TopBottom,
+        ));
+        cd.add(Constraint::LinesParallel(ConstraintMeta::default(), l1, l2));
+
+        assert_eq!(
+            cd.iter_line_lengths().collect::<Vec<_>>(),
+            vec![(len, l1, 5.0, None)]
+        );
+        assert_eq!(
+            cd.iter_fixed()
+                .map(|(_, fk, x, y)| (fk, x, y))
+                .collect::<Vec<_>>(),
+            vec![(p, 1.0, 2.0)]
+        );
+        assert_eq!(cd.iter_line_cardinals().count(), 1);
+        assert_eq!(cd.iter_feature(&l2).count(), 2);
     }
 }
@@ -9,6 +9,19 @@ pub enum GroupType {
     Hole,
     Extrude,
     Bore,
+    /// Geometry marked on the part without cutting through it: a separate
+    /// layer in 2D exports and a shallow bore in 3D ones.
+    Engrave,
+}
+
+impl GroupType {
+    /// The thickness or depth used when the group does not specify one.
+    pub fn default_amt(&self) -> f64 {
+        match self {
+            GroupType::Engrave => 0.5,
+            _ => 3.0,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -19,6 +32,11 @@ pub struct Group {
 
     pub amt: Option<f64>,
     pub bottom: Option<()>,
+    /// Centers the part (for a boundary) or the extrusion (for an extrude
+    /// group) on the sketch plane, taking precedence over bottom.
+    pub symmetric: Option<()>,
+    /// Overrides the drawing's flatten tolerance for this group's paths.
+    pub flatten_tolerance: Option<f64>,
 }
 
 impl Group {
@@ -38,6 +56,8 @@ impl Group {
             name: self.name.clone(),
             amt: self.amt,
             bottom: self.bottom,
+            symmetric: self.symmetric,
+            flatten_tolerance: self.flatten_tolerance,
             features_idx,
         })
     }
@@ -60,6 +80,8 @@ impl Group {
             features,
             amt: sg.amt,
             bottom: sg.bottom,
+            symmetric: sg.symmetric,
+            flatten_tolerance: sg.flatten_tolerance,
         })
     }
 
@@ -69,9 +91,122 @@ impl Group {
         }
     }
 
+    /// Returns true if adding the feature would continue the group's path: the
+    /// group is empty, the feature closes on itself, or it shares an endpoint
+    /// with an existing member.
+    pub fn can_accept(&self, fk: FeatureKey, data: &super::Data) -> bool {
+        let f = match data.features.get(fk) {
+            Some(f) => f,
+            None => return false,
+        };
+        let (start, end) = (f.start_point(data), f.end_point(data));
+        if self.features.is_empty() || start == end {
+            return true;
+        }
+
+        self.features
+            .iter()
+            .filter_map(|k| data.features.get(*k))
+            .any(|m| {
+                let (m_start, m_end) = (m.start_point(data), m.end_point(data));
+                [m_start, m_end].contains(&start) || [m_start, m_end].contains(&end)
+            })
+    }
+
+    /// Returns those of the given features which form closed loops among
+    /// themselves, such as the sides of a rectangle, in the order given.
+    pub fn closed_loops(fks: &[FeatureKey], data: &super::Data) -> Vec<FeatureKey> {
+        let mut ends: Vec<(FeatureKey, egui::Pos2, egui::Pos2)> = fks
+            .iter()
+            .filter_map(|fk| {
+                let f = data.features.get(*fk)?;
+                Some((*fk, f.start_point(data), f.end_point(data)))
+            })
+            .collect();
+
+        // Drop features with an end no other feature meets until none are
+        // left, as they can't be part of a loop.
+        loop {
+            let meets = |fk: FeatureKey, p: egui::Pos2| {
+                ends.iter()
+                    .any(|(k, start, end)| *k != fk && (*start == p || *end == p))
+            };
+            let looped: Vec<_> = ends
+                .iter()
+                .filter(|(fk, start, end)| start == end || (meets(*fk, *start) && meets(*fk, *end)))
+                .copied()
+                .collect();
+            if looped.len() == ends.len() {
+                break;
+            }
+            ends = looped;
+        }
+        ends.into_iter().map(|(fk, ..)| fk).collect()
+    }
+
+    /// Returns members of the group which have since been marked as construction,
+    /// and hence are left out of the computed path.
+    pub fn stale_features(&self, data: &super::Data) -> Vec<FeatureKey> {
+        self.features
+            .iter()
+            .filter(|fk| {
+                data.features
+                    .get(**fk)
+                    .map(|f| f.is_construction())
+                    .unwrap_or(false)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns arcs in the group which have no well-defined circle, and
+    /// hence are left out of the computed path.
+    pub fn degenerate_arcs(&self, data: &super::Data) -> Vec<FeatureKey> {
+        self.features
+            .iter()
+            .filter(|fk| match data.features.get(**fk) {
+                Some(f @ crate::Feature::Arc(..)) => {
+                    !f.is_construction() && !data.arc_is_valid(**fk)
+                }
+                _ => false,
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns the layer of the first member which has one, which the
+    /// group's paths are exported on.
+    pub fn layer<'a>(&self, data: &'a super::Data) -> Option<&'a str> {
+        self.features
+            .iter()
+            .find_map(|fk| data.features.get(*fk)?.meta().layer.as_deref())
+    }
+
+    /// Whether members are set to different layers (or some to none), in
+    /// which case all but the group's layer are ignored on export.
+    pub fn has_mixed_layers(&self, data: &super::Data) -> bool {
+        let mut layers = self
+            .features
+            .iter()
+            .filter_map(|fk| data.features.get(*fk))
+            .map(|f| f.meta().layer.as_deref());
+        match layers.next() {
+            Some(first) => layers.any(|l| l != first),
+            None => false,
+        }
+    }
+
     pub fn compute_path(&self, data: &super::Data) -> Vec<kurbo::BezPath> {
-        // geometry that has been emitted
-        let mut remaining = self.features.clone();
+        // geometry that has been emitted, excluding construction geometry
+        // and arcs which cannot be drawn
+        let stale = self.stale_features(data);
+        let degenerate = self.degenerate_arcs(data);
+        let mut remaining: Vec<FeatureKey> = self
+            .features
+            .iter()
+            .filter(|fk| !stale.contains(fk) && !degenerate.contains(fk))
+            .copied()
+            .collect();
         remaining.reverse();
         // completed paths
         let mut paths: Vec<kurbo::BezPath> = Vec::with_capacity(2 * self.features.len());
@@ -163,6 +298,8 @@ pub struct SerializedGroup {
     pub features_idx: Vec<usize>,
     pub amt: Option<f64>,
     pub bottom: Option<()>,
+    pub symmetric: Option<()>,
+    pub flatten_tolerance: Option<f64>,
 }
 
 #[cfg(test)]
@@ -181,17 +318,120 @@ mod tests {
                 features: vec![point_key],
                 amt: None,
                 bottom: None,
+                symmetric: None,
+                flatten_tolerance: Some(0.01),
             }
             .serialize(&HashMap::from([(point_key, 42)])),
             Ok(SerializedGroup {
                 typ: GroupType::Boundary,
                 name: "Ye".into(),
                 features_idx: vec![42],
+                flatten_tolerance: Some(0.01),
                 ..SerializedGroup::default()
             }),
         );
     }
 
+    #[test]
+    fn can_accept() {
+        use crate::{Feature, FeatureMeta};
+        let mut data = crate::Data::default();
+        let mut point = |x: f32, y: f32| {
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), x, y))
+        };
+        let (p1, p2, p3, p4, p5) = (
+            point(0.0, 0.0),
+            point(5.0, 0.0),
+            point(5.0, 5.0),
+            point(20.0, 20.0),
+            point(25.0, 20.0),
+        );
+        let mut line = |a, b| {
+            data.features
+                .insert(Feature::LineSegment(FeatureMeta::default(), a, b))
+        };
+        let (l1, l2, l3) = (line(p1, p2), line(p2, p3), line(p4, p5));
+        let circle = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), p4, 2.0));
+
+        let mut group = Group::default();
+        assert!(group.can_accept(l1, &data));
+        group.features.push(l1);
+
+        assert!(group.can_accept(l2, &data));
+        assert!(!group.can_accept(l3, &data));
+        // Closed features form a path of their own.
+        assert!(group.can_accept(circle, &data));
+    }
+
+    #[test]
+    fn closed_loops() {
+        use crate::{Feature, FeatureMeta};
+        let mut data = crate::Data::default();
+        let mut point = |x: f32, y: f32| {
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), x, y))
+        };
+        let (p1, p2, p3, p4, p5) = (
+            point(0.0, 0.0),
+            point(5.0, 0.0),
+            point(5.0, 5.0),
+            point(20.0, 20.0),
+            point(25.0, 20.0),
+        );
+        let mut line = |a, b| {
+            data.features
+                .insert(Feature::LineSegment(FeatureMeta::default(), a, b))
+        };
+        let (l1, l2, l3, l4) = (line(p1, p2), line(p2, p3), line(p3, p1), line(p3, p4));
+        let l5 = line(p4, p5);
+        let circle = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), p5, 2.0));
+
+        assert_eq!(
+            Group::closed_loops(&[l5, l1, l4, circle, l2, l3], &data),
+            vec![l1, circle, l2, l3],
+        );
+        assert_eq!(Group::closed_loops(&[l1, l2], &data), vec![]);
+    }
+
+    #[test]
+    fn mixed_layers() {
+        use crate::{Feature, FeatureMeta};
+        let mut data = crate::Data::default();
+        let on = |layer: Option<&str>| FeatureMeta {
+            layer: layer.map(str::to_string),
+            ..FeatureMeta::default()
+        };
+        let (p1, p2) = (
+            data.features.insert(Feature::Point(on(None), 0.0, 0.0)),
+            data.features.insert(Feature::Point(on(None), 5.0, 0.0)),
+        );
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(on(Some("cut")), p1, p2));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(on(Some("cut")), p2, p1));
+        let l3 = data
+            .features
+            .insert(Feature::LineSegment(on(Some("mark")), p1, p2));
+        let l4 = data.features.insert(Feature::LineSegment(on(None), p1, p2));
+
+        let group = |features: Vec<FeatureKey>| Group {
+            features,
+            ..Group::default()
+        };
+        assert!(!group(vec![]).has_mixed_layers(&data));
+        assert!(!group(vec![l1, l2]).has_mixed_layers(&data));
+        assert!(group(vec![l1, l2, l3]).has_mixed_layers(&data));
+        assert!(group(vec![l1, l4]).has_mixed_layers(&data));
+        assert_eq!(group(vec![l4, l3, l1]).layer(&data), Some("mark"));
+    }
+
     #[test]
     fn deserialize() {
         use slotmap::Key;
@@ -1,10 +1,42 @@
 use crate::system::{TermAllocator, TermRef, TermType};
-use crate::{Constraint, ConstraintKey, SerializedConstraint};
-use crate::{Feature, FeatureKey, SerializedFeature};
+use crate::{Constraint, ConstraintKey, ConstraintMeta, DimensionDisplay, SerializedConstraint};
+use crate::{Feature, FeatureKey, FeatureMeta, SerializedFeature};
 use slotmap::HopSlotMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 const MAX_HOVER_DISTANCE: f32 = 120.0;
+/// DXF layer which engraved geometry is placed on.
+const DXF_ENGRAVE_LAYER: &str = "ENGRAVE";
+/// DXF layer which sheet-metal bend lines are placed on.
+const DXF_BEND_LAYER: &str = "BEND";
+/// Stroke width (in millimeters) of SVG exports, if the drawing doesn't set one.
+const DEFAULT_SVG_STROKE_WIDTH: f64 = 0.1;
+/// Lines shorter than this are treated as having coincident endpoints.
+const DEGENERATE_LINE_LENGTH: f32 = 1e-6;
+/// Arcs whose points are closer than this to collinear (as the sine of
+/// the angle swept) are treated as having no circle.
+const ARC_COLLINEAR_TOLERANCE: f32 = 1e-5;
+/// Most times a solve is repeated while points along an arc catch up with
+/// the arc's sweep, before giving up.
+const ARC_SWEEP_PASSES: usize = 16;
+/// Change in sweep (in radians) below which an arc is considered settled.
+const ARC_SWEEP_TOLERANCE: f32 = 1e-5;
+
+/// Values known after substitution, the variables left unresolved, the
+/// residuals to solve them with and their weights, and initial guesses.
+type SubsolveResult = (
+    HashMap<eq::Variable, eq::Concrete>,
+    Vec<eq::Variable>,
+    Vec<eq::Expression>,
+    Vec<f64>,
+    Vec<f64>,
+);
+
+/// The flattened paths of a group, along with the type of the group.
+type FlattenedGroup = (crate::GroupType, Vec<Vec<kurbo::Point>>);
+
+/// De-duplicated points, along with the indices making up each boundary and hole.
+type FlattenedIdxs = (Vec<kurbo::Point>, Vec<Vec<usize>>, Vec<Vec<usize>>);
 
 mod viewport;
 pub use viewport::Viewport;
@@ -12,6 +44,9 @@ pub use viewport::Viewport;
 mod constraint_data;
 pub use constraint_data::ConstraintData;
 
+mod ref_image;
+pub use ref_image::RefImage;
+
 pub mod group;
 use group::Group;
 
@@ -34,27 +69,265 @@ pub enum SelectedElement {
     Constraint(ConstraintKey),
 }
 
+/// Describes why a drawing could not be loaded. Indices refer to the
+/// position of the offending element in the serialized drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadErr {
+    BadFeature(usize),
+    BadConstraint(usize),
+    InvalidConstraint(usize, &'static str),
+    BadGroup(usize),
+    /// The drawing was saved by a newer version of the format.
+    UnsupportedVersion(u32),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ExportErr {
     NoBoundaryGroup,
     MultiBoundaryGroup,
     IntersectingGroups(usize, usize),
+    LoadFailed,
+    SolverInconsistent(f64),
+    OpenPath(usize),
+    NothingSelected,
+    /// An arc has collinear points, in the given group or (if None) the
+    /// selection.
+    DegenerateArc(Option<usize>),
+}
+
+impl std::fmt::Display for ExportErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportErr::NoBoundaryGroup => write!(f, "there is no boundary group"),
+            ExportErr::MultiBoundaryGroup => write!(f, "there is more than one boundary"),
+            ExportErr::IntersectingGroups(a, b) => write!(f, "groups {} and {} intersect", a, b),
+            ExportErr::LoadFailed => write!(f, "the drawing could not be loaded"),
+            ExportErr::SolverInconsistent(err) => {
+                write!(f, "the solver is inconsistent (avg: {:.3}mm)", err)
+            }
+            ExportErr::OpenPath(g) => write!(f, "group {} is not a closed path", g),
+            ExportErr::NothingSelected => write!(f, "no lines, arcs or circles are selected"),
+            ExportErr::DegenerateArc(Some(g)) => {
+                write!(f, "group {} has an arc whose points are collinear", g)
+            }
+            ExportErr::DegenerateArc(None) => {
+                write!(f, "the selection has an arc whose points are collinear")
+            }
+        }
+    }
+}
+
+/// The closed polylines making up the outline of a part.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutlineResult {
+    /// Outer edges, from boundary groups.
+    pub boundaries: Vec<Vec<kurbo::Point>>,
+    /// Cut-outs, from hole groups.
+    pub holes: Vec<Vec<kurbo::Point>>,
+}
+
+/// A dimensioned value in the drawing, as listed by Data::dimension_report().
+#[derive(Clone, Debug, PartialEq)]
+pub struct DimensionEntry {
+    pub constraint: ConstraintKey,
+    /// The kind of dimension, such as "Length".
+    pub kind: &'static str,
+    /// The dimensioned feature, such as "Line 3v1".
+    pub feature: String,
+    /// The value asked for by the constraint, in mm or degrees.
+    pub value: f32,
+    /// The value measured from the current geometry.
+    pub measured: f32,
+}
+
+/// Measurements of an arc, as returned by Data::arc_metrics(), in mm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArcMetrics {
+    /// Length along the curve from start to end.
+    pub length: f32,
+    /// Straight-line distance from start to end.
+    pub chord: f32,
+    /// Height of the curve above the middle of its chord.
+    pub sagitta: f32,
+}
+
+/// Summary of how well the last solve went, as shown by the status badge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SolveStatus {
+    Solved,
+    Converging,
+    Inconsistent,
+}
+
+impl SolveStatus {
+    /// Average errors (in mm) below this are treated as still converging.
+    const CONVERGING_ERR: f64 = 1.0;
+
+    pub fn from_error(err: Option<f64>) -> Self {
+        match err {
+            None => SolveStatus::Solved,
+            Some(err) if err < Self::CONVERGING_ERR => SolveStatus::Converging,
+            Some(_) => SolveStatus::Inconsistent,
+        }
+    }
+
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            SolveStatus::Solved => egui::Color32::GREEN,
+            SolveStatus::Converging => egui::Color32::YELLOW,
+            SolveStatus::Inconsistent => egui::Color32::RED,
+        }
+    }
+}
+
+/// A brute-force search for a solution, detached from the drawing so it can
+/// run on another thread. See Data::start_bruteforce_solve().
+pub struct BruteforceSearch {
+    params: eq::solve::ExpSearchParams,
+    known: HashMap<eq::Variable, eq::Concrete>,
+    unresolved: Vec<eq::Variable>,
+    terms: Vec<TermRef>,
+    residuals: Vec<eq::Expression>,
+    initials: Vec<f64>,
+}
+
+impl BruteforceSearch {
+    /// Runs the search, returning the solved value of each unknown or None
+    /// if nothing close to a solution was found.
+    pub fn run(self) -> Option<Vec<(TermRef, f64)>> {
+        let BruteforceSearch {
+            mut params,
+            known,
+            unresolved,
+            terms,
+            residuals,
+            mut initials,
+        } = self;
+
+        let mut last_best: Option<f64> = None;
+        for _ in 0..params.passes {
+            // TODO: Make SearchSolver take references to eliminate clones?
+            let ss = eq::solve::SearchSolver::new(
+                params.clone(),
+                known.clone(),
+                unresolved.clone(),
+                residuals.clone(),
+                initials.clone(),
+            );
+
+            let (residual_sq, guesses) = ss.bruteforce(params.subdivision_bits);
+            // println!("{}: {:?}", residual_sq, guesses);
+
+            if last_best.is_none() || last_best.unwrap() > residual_sq {
+                last_best = Some(residual_sq);
+                for (i, (_var, guess)) in guesses.into_iter().enumerate() {
+                    initials[i] = guess;
+                }
+            }
+
+            params.reduce();
+        }
+
+        match last_best {
+            Some(last_best_sq) if last_best_sq.sqrt() < 24.0 => {
+                Some(terms.into_iter().zip(initials).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Receives the result of a brute-force search running in the background.
+/// Clones of a drawing don't wait on it.
+#[derive(Default)]
+pub struct PendingSearch(Option<std::sync::mpsc::Receiver<Option<Vec<(TermRef, f64)>>>>);
+
+impl PendingSearch {
+    pub fn is_running(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl Clone for PendingSearch {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl std::fmt::Debug for PendingSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "PendingSearch(Some(..))"),
+            None => write!(f, "PendingSearch(None)"),
+        }
+    }
+}
+
+/// Callback invoked after each solve with the resulting `last_solve_error`,
+/// for embedders to react to. Clones of a drawing start without one.
+#[derive(Default)]
+pub struct SolveHook(pub Option<Box<dyn FnMut(Option<f64>)>>);
+
+impl Clone for SolveHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl std::fmt::Debug for SolveHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "SolveHook(Some(..))"),
+            None => write!(f, "SolveHook(None)"),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
 pub enum CADOp {
-    Extrude(f64, bool), // true = extrude on the bottom
-    Bore(f64, bool),    // true = bore from the bottom
+    Extrude(f64, bool),    // true = extrude on the bottom
+    ExtrudeSymmetric(f64), // half on the top, half on the bottom
+    Bore(f64, bool),       // true = bore from the bottom
     Hole,
 }
 
+/// Version of the [`SerializedDrawing`] format written by [`Data::serialize`].
+pub const SERIALIZED_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct SerializedDrawing {
+    /// Format version, which is 0 for files saved before versioning existed.
+    #[serde(default)]
+    pub version: u32,
     pub features: Vec<SerializedFeature>,
     pub constraints: Vec<SerializedConstraint>,
     pub groups: Vec<group::SerializedGroup>,
     pub viewport: Viewport,
     pub properties: Option<DrawingProperties>,
+    #[serde(default)]
+    pub reference_image: Option<RefImage>,
+    /// Stable id the next feature or constraint created will get, so the ids
+    /// of deleted ones aren't reused. 0 in files saved before it existed.
+    #[serde(default)]
+    pub next_id: u64,
+}
+
+impl SerializedDrawing {
+    /// Upgrades a drawing saved in an older format to the current
+    /// [`SERIALIZED_VERSION`].
+    pub fn migrate(mut self) -> Result<Self, LoadErr> {
+        if self.version > SERIALIZED_VERSION {
+            return Err(LoadErr::UnsupportedVersion(self.version));
+        }
+
+        if self.version < 1 {
+            // Version 0 omitted the properties when they were all default.
+            self.properties
+                .get_or_insert_with(DrawingProperties::default);
+            self.version = 1;
+        }
+        Ok(self)
+    }
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
@@ -65,6 +338,28 @@ pub struct DrawingProperties {
     pub solver_stop_err: f64,
 
     pub solve_continuously: Option<()>,
+
+    /// Format used by the export button in the groups tab.
+    pub export_format: Option<crate::headless::ExportFormat>,
+    /// Writes STL files in ASCII rather than binary.
+    pub stl_ascii: Option<()>,
+    /// Width of material removed by the cutter, compensated for in 2D exports
+    /// by growing boundaries and shrinking holes by half this amount.
+    pub kerf: Option<f64>,
+    /// Tolerance used when meshing solids for 3D export. Falls back to the
+    /// flatten tolerance if unset.
+    pub mesh_tolerance: Option<f64>,
+    /// Emits boundaries counter-clockwise and holes clockwise in 2D exports,
+    /// regardless of the direction they were drawn in.
+    pub normalize_winding: Option<()>,
+    /// Wraps OpenSCAD exports in a module named after the drawing, followed
+    /// by a call to it.
+    pub openscad_module: Option<()>,
+    /// Stroke width (in millimeters) of paths in SVG exports.
+    pub svg_stroke_width: Option<f64>,
+    /// Distance the part is raised off the sketch plane in 3D exports.
+    #[serde(default)]
+    pub z_offset: f64,
 }
 
 impl Default for DrawingProperties {
@@ -74,10 +369,28 @@ impl Default for DrawingProperties {
             flatten_tolerance: 0.05,
             solver_stop_err: 0.0005,
             solve_continuously: None,
+            export_format: None,
+            stl_ascii: None,
+            kerf: None,
+            mesh_tolerance: None,
+            normalize_winding: None,
+            openscad_module: None,
+            svg_stroke_width: None,
+            z_offset: 0.0,
         }
     }
 }
 
+impl DrawingProperties {
+    pub fn mesh_tolerance(&self) -> f64 {
+        self.mesh_tolerance.unwrap_or(self.flatten_tolerance)
+    }
+
+    pub fn svg_stroke_width(&self) -> f64 {
+        self.svg_stroke_width.unwrap_or(DEFAULT_SVG_STROKE_WIDTH)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub enum Direction {
     Up,
@@ -132,8 +445,57 @@ pub struct Data {
     pub drag_features_enabled: bool,
     pub drag_dimensions_enabled: bool,
     pub select_action_inc_construction: bool,
+    /// Snaps points created with the point tool onto a hovered line or construction circle.
+    pub snap_points_enabled: bool,
+    pub hover_sensitivity: f32,
+    /// Shows the equation system in the detailer, for debugging.
+    pub show_equations: bool,
+    /// Dims everything which isn't selected, to focus on the selection.
+    pub isolate_selection: bool,
+    /// Screen-space offsets dimension labels are painted at to keep them from
+    /// overlapping, see separate_dimension_labels(). Never saved.
+    pub label_nudges: HashMap<ConstraintKey, egui::Vec2>,
+    /// Shades the part (boundaries less holes) in the drawing view.
+    pub show_fill: bool,
+    /// Mouse button which pans the view when dragged, in addition to the middle button.
+    pub pan_button: egui::PointerButton,
+    /// Zooms about the cursor when scrolling, rather than the center of the view.
+    pub zoom_to_cursor: bool,
+    /// The glyph points are drawn with.
+    pub point_style: crate::PointStyle,
+    /// Width (in screen pixels) geometry is drawn with.
+    pub stroke_width: f32,
+    /// Radius (in screen pixels) points are drawn with.
+    pub point_radius: f32,
 
     pub last_solve_error: Option<f64>,
+    /// Iterations the numeric solver took during the last solve, which is
+    /// zero if it wasn't needed.
+    pub last_solve_iterations: usize,
+    /// Wall-time of the last solve. Not measured on the web.
+    pub last_solve_duration: Option<std::time::Duration>,
+    /// Allows export even when the solver is inconsistent.
+    pub export_inconsistent: bool,
+    /// Indices of groups whose closed path was broken open by deleting
+    /// features, for the UI to warn about. Drained by the UI.
+    pub broken_groups: Vec<usize>,
+    /// Warnings about constraints which were added despite being redundant,
+    /// for the UI to show. Drained by the UI.
+    pub constraint_warnings: Vec<String>,
+    /// Search settings used by bruteforce_solve().
+    pub bruteforce_params: eq::solve::ExpSearchParams,
+    /// Search started by start_bruteforce_solve(), if it's still running.
+    pub pending_bruteforce: PendingSearch,
+    /// Zoom (drawing millimeters per screen point) to apply about the
+    /// center of the canvas. Drained by the drawing widget.
+    pub pending_scale: Option<f32>,
+    /// Called once at the end of every solve. No-op by default.
+    pub on_solved: SolveHook,
+    /// Image shown behind the drawing for tracing, if any.
+    pub reference_image: Option<RefImage>,
+    /// Stable id given to the next feature or constraint added. Only ever
+    /// increases, and is saved with the drawing.
+    next_id: u64,
 }
 
 impl Default for Data {
@@ -150,12 +512,39 @@ impl Default for Data {
             drag_features_enabled: true,
             drag_dimensions_enabled: true,
             select_action_inc_construction: false,
+            snap_points_enabled: true,
+            hover_sensitivity: 1.0,
+            show_equations: false,
+            isolate_selection: false,
+            label_nudges: HashMap::new(),
+            show_fill: false,
+            pan_button: egui::PointerButton::Secondary,
+            zoom_to_cursor: true,
+            point_style: crate::PointStyle::default(),
+            stroke_width: 1.0,
+            point_radius: crate::feature::DEFAULT_POINT_RADIUS,
             last_solve_error: None,
+            last_solve_iterations: 0,
+            last_solve_duration: None,
+            export_inconsistent: false,
+            broken_groups: vec![],
+            constraint_warnings: vec![],
+            bruteforce_params: eq::solve::ExpSearchParams::default(),
+            pending_bruteforce: PendingSearch::default(),
+            pending_scale: None,
+            on_solved: SolveHook::default(),
+            reference_image: None,
+            next_id: 1,
         }
     }
 }
 
 impl Data {
+    /// Returns how well the last solve converged.
+    pub fn solve_status(&self) -> SolveStatus {
+        SolveStatus::from_error(self.last_solve_error)
+    }
+
     /// Call when feature or constraint fields have changed,
     /// independently of the drawing space or a handled event.
     pub fn changed_in_ui(&mut self) {
@@ -173,26 +562,246 @@ impl Data {
     }
 
     fn equations(&mut self) -> Vec<eq::Expression> {
-        self.constraints
+        let mut out: Vec<eq::Expression> = self
+            .constraints
             .iter()
+            .filter(|(_ck, c)| !c.meta().is_soft())
             .map(|(_ck, c)| c.clone())
             .collect::<Vec<Constraint>>()
             .iter()
             .map(|c| c.equations(self))
             .flatten()
+            .collect();
+        out.extend(self.reference_equations());
+        out
+    }
+
+    /// Returns equations holding reference geometry (features both locked
+    /// and construction) where it is, so the solver works around it as
+    /// known values rather than moving it.
+    fn reference_equations(&mut self) -> Vec<eq::Expression> {
+        let mut pinned: Vec<(FeatureKey, TermType, f32)> = Vec::new();
+        for (fk, f) in self.features.iter() {
+            let meta = f.meta();
+            if !(meta.locked && meta.construction) {
+                continue;
+            }
+            let points = match f {
+                Feature::Point(..) => vec![fk],
+                _ => f.depends_on().into_iter().flatten().collect(),
+            };
+            for p in points {
+                if pinned.iter().any(|(k, ..)| *k == p) {
+                    continue;
+                }
+                if let Some(Feature::Point(_, x, y)) = self.features.get(p) {
+                    pinned.push((p, TermType::PositionX, *x));
+                    pinned.push((p, TermType::PositionY, *y));
+                }
+            }
+            if let Feature::Circle(_, _, r) = f {
+                pinned.push((fk, TermType::ScalarRadius, *r));
+            }
+        }
+
+        pinned
+            .into_iter()
+            .filter_map(|(fk, t, v)| {
+                let term = &self.terms.get_feature_term(fk, t);
+                Some(eq::Expression::Equal(
+                    Box::new(eq::Expression::Variable(term.into())),
+                    Box::new(eq::Expression::Rational(eq::Rational::from_float(v)?, true)),
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the residuals of soft constraints, along with their weight.
+    /// These never take part in substitution, so they cannot override
+    /// required constraints. Penalties of constraints which bound a value,
+    /// such as a length range, are included whether soft or not.
+    fn soft_residuals(&mut self) -> Vec<(eq::Expression, f64)> {
+        let constraints: Vec<Constraint> =
+            self.constraints.iter().map(|(_ck, c)| c.clone()).collect();
+
+        let mut out = Vec::new();
+        for c in constraints {
+            let weight = c.meta().weight();
+            for mut r in c.penalty_residuals(self) {
+                r.simplify();
+                out.push((r, weight));
+            }
+            if !c.meta().is_soft() {
+                continue;
+            }
+            for e in c.equations(self) {
+                if let eq::Expression::Equal(a, b) = e {
+                    let mut r = eq::Expression::Difference(a, b);
+                    r.simplify();
+                    out.push((r, weight));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the equations produced by the drawing's constraints, formatted
+    /// for display.
+    pub fn equations_debug(&self) -> Vec<String> {
+        // Generating equations may allocate terms, so work on a copy.
+        self.clone()
+            .equations()
+            .iter()
+            .map(|e| e.to_string())
             .collect()
     }
 
+    /// Returns the equations of every constraint as text, one per line,
+    /// under a comment naming the constraint they came from.
+    pub fn export_equations(&self) -> String {
+        use slotmap::Key;
+        // Generating equations may allocate terms, so work on a copy.
+        let mut d = self.clone();
+        let mut constraints: Vec<(ConstraintKey, Constraint)> = self
+            .constraints
+            .iter()
+            .map(|(ck, c)| (ck, c.clone()))
+            .collect();
+        constraints.sort_by_key(|(ck, _)| *ck);
+
+        let mut out = String::new();
+        for (ck, c) in constraints {
+            if !out.is_empty() {
+                out += "\n";
+            }
+            let soft = if c.meta().is_soft() { " (soft)" } else { "" };
+            out += &format!("# Constraint {:?}: {}{}\n", ck.data(), c.kind(), soft);
+            for e in c.equations(&mut d) {
+                out += &format!("{}\n", e);
+            }
+        }
+        out
+    }
+
+    /// Returns the number of degrees of freedom left in the drawing: the number
+    /// of unknowns less the number of independent equations constraining them,
+    /// judged by the rank of the system's Jacobian at the current geometry.
+    pub fn degrees_of_freedom(&self) -> usize {
+        // Generating equations may allocate terms, so work on a copy.
+        let mut d = self.clone();
+        let keys: Vec<(FeatureKey, &[TermType])> = self
+            .features
+            .iter()
+            .filter_map(|(fk, f)| match f {
+                Feature::Point(..) => Some((fk, &[TermType::PositionX, TermType::PositionY][..])),
+                Feature::Circle(..) => Some((fk, &[TermType::ScalarRadius][..])),
+                _ => None,
+            })
+            .collect();
+
+        // Free geometry counts, even if no equation references it.
+        let mut vars: Vec<eq::Variable> = Vec::with_capacity(2 * keys.len());
+        for (fk, types) in keys {
+            for t in types {
+                vars.push((&d.terms.get_feature_term(fk, t.clone())).into());
+            }
+        }
+        let equations = d.equations();
+        for e in equations.iter() {
+            e.walk(&mut |e| {
+                if let eq::Expression::Variable(v) = e {
+                    if !vars.contains(v) {
+                        vars.push(v.clone());
+                    }
+                }
+                true
+            });
+        }
+
+        let mut resolver = d.resolver_from_geometry();
+        let jacobian: Vec<Vec<f64>> = equations
+            .iter()
+            .filter_map(|e| e.as_residual().ok())
+            .map(|r| {
+                vars.iter()
+                    .map(|v| {
+                        r.derivative_wrt(v)
+                            .evaluate_1(&mut resolver)
+                            .map(|c| c.as_f64())
+                            .unwrap_or(0.0)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        vars.len().saturating_sub(matrix_rank(jacobian))
+    }
+
+    /// Partitions equations into sets which share no variables, such that each
+    /// set can be solved independently of the others.
+    fn partition_equations(equations: Vec<eq::Expression>) -> Vec<Vec<eq::Expression>> {
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut var_idx: HashMap<eq::Variable, usize> = HashMap::new();
+        let mut parent: Vec<usize> = Vec::new();
+        let eq_vars: Vec<Vec<usize>> = equations
+            .iter()
+            .map(|e| {
+                let mut vars = Vec::with_capacity(4);
+                e.walk(&mut |e| {
+                    if let eq::Expression::Variable(v) = e {
+                        let idx = *var_idx.entry(v.clone()).or_insert_with(|| {
+                            parent.push(parent.len());
+                            parent.len() - 1
+                        });
+                        vars.push(idx);
+                    }
+                    true
+                });
+                vars
+            })
+            .collect();
+
+        // Union all variables which appear in the same equation.
+        for vars in eq_vars.iter() {
+            for w in vars.windows(2) {
+                let (a, b) = (find(&mut parent, w[0]), find(&mut parent, w[1]));
+                if a != b {
+                    parent[a] = b;
+                }
+            }
+        }
+
+        let mut partitions: Vec<Vec<eq::Expression>> = Vec::new();
+        let mut by_root: HashMap<usize, usize> = HashMap::new();
+        for (e, vars) in equations.into_iter().zip(eq_vars) {
+            match vars.first() {
+                Some(v) => {
+                    let root = find(&mut parent, *v);
+                    let idx = *by_root.entry(root).or_insert_with(|| {
+                        partitions.push(Vec::new());
+                        partitions.len() - 1
+                    });
+                    partitions[idx].push(e);
+                }
+                None => partitions.push(vec![e]),
+            }
+        }
+        partitions
+    }
+
     fn subsolve(
         &mut self,
-    ) -> Option<(
-        HashMap<eq::Variable, eq::Concrete>,
-        Vec<eq::Variable>,
-        Vec<eq::Expression>,
-        Vec<f64>,
-    )> {
-        let equations = self.equations();
-        if equations.len() == 0 {
+        equations: Vec<eq::Expression>,
+        soft: Vec<(eq::Expression, f64)>,
+    ) -> Option<SubsolveResult> {
+        if equations.is_empty() && soft.is_empty() {
             self.last_solve_error = None;
             return None;
         }
@@ -218,8 +827,22 @@ impl Data {
         }
 
         // Solve the rest using an iterative solver.
-        let residuals = solver.all_residuals(&mut sub_solver_state);
-        if residuals.len() == 0 {
+        let mut residuals = solver.all_residuals(&mut sub_solver_state);
+        let mut weights = vec![1.0; residuals.len()];
+        let mut unresolved = unresolved;
+        for (r, w) in soft {
+            r.walk(&mut |e| {
+                if let eq::Expression::Variable(v) = e {
+                    if !known.contains_key(v) && !unresolved.contains(v) {
+                        unresolved.push(v.clone());
+                    }
+                }
+                true
+            });
+            residuals.push(r);
+            weights.push(w);
+        }
+        if residuals.is_empty() {
             self.last_solve_error = None;
             return None;
         }
@@ -227,31 +850,231 @@ impl Data {
             .iter()
             .map(|v| {
                 let term = self.terms.get_var_ref(v).expect("no such var");
-                match self.term_current_value(&term) {
+                match self
+                    .term_current_value(&term)
+                    .or_else(|| self.term_guessed_value(&term))
+                {
                     Some(v) => v as f64,
                     None => 0.47,
                 }
             })
             .collect();
 
-        Some((known, unresolved, residuals, initials))
+        Some((known, unresolved, residuals, weights, initials))
+    }
+
+    /// Discards all allocated terms and allocates them afresh for the current
+    /// features and constraints. Needed after features or constraints are
+    /// added or removed directly, rather than through the methods which keep
+    /// terms in step.
+    pub fn rebuild_terms(&mut self) {
+        self.terms = TermAllocator::default();
+
+        let mut keys: Vec<FeatureKey> = self.features.keys().collect();
+        keys.sort();
+        for fk in keys {
+            // All terms of a feature share one base, so any type allocates it.
+            self.terms.get_feature_term(fk, TermType::PositionX);
+        }
+        for (ck, _) in self.constraints.iter() {
+            self.terms.inform_new_constraint(ck);
+        }
+    }
+
+    /// Returns a stable id which no feature or constraint has had before.
+    fn take_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Adds a feature, giving it a stable id of its own.
+    pub fn add_feature(&mut self, mut f: Feature) -> FeatureKey {
+        f.meta_mut().id = self.take_id();
+        self.features.insert(f)
+    }
+
+    /// Gives every feature and constraint without a stable id, or with one
+    /// already taken, an id of its own. Only needed for those inserted
+    /// directly rather than with add_feature() or add_constraint().
+    pub fn assign_ids(&mut self) {
+        let mut keys: Vec<FeatureKey> = self.features.keys().collect();
+        keys.sort();
+        let mut seen = HashSet::with_capacity(keys.len());
+        for fk in keys {
+            if self.features[fk].meta().id == 0 || !seen.insert(self.features[fk].meta().id) {
+                self.features[fk].meta_mut().id = self.take_id();
+            }
+        }
+
+        let mut constraints: Vec<ConstraintKey> =
+            self.constraints.iter().map(|(ck, _)| ck).collect();
+        constraints.sort();
+        let mut seen = HashSet::with_capacity(constraints.len());
+        for ck in constraints {
+            let id = self.constraints.get(ck).unwrap().meta().id;
+            if id == 0 || !seen.insert(id) {
+                let id = self.take_id();
+                self.constraints.get_mut(ck).unwrap().meta_mut().id = id;
+            }
+        }
+    }
+
+    /// Returns the key of the feature with the given stable id.
+    pub fn feature_by_id(&self, id: u64) -> Option<FeatureKey> {
+        self.features
+            .iter()
+            .find(|(_, f)| id != 0 && f.meta().id == id)
+            .map(|(fk, _)| fk)
+    }
+
+    /// Returns the key of the constraint with the given stable id.
+    pub fn constraint_by_id(&self, id: u64) -> Option<ConstraintKey> {
+        self.constraints
+            .iter()
+            .find(|(_, c)| id != 0 && c.meta().id == id)
+            .map(|(ck, _)| ck)
     }
 
     fn solve_and_apply(&mut self) {
-        let (known, unresolved, residuals, initials) = match self.subsolve() {
-            Some((k, u, r, i)) => (k, u, r, i),
+        let started = solve_clock();
+        self.last_solve_iterations = 0;
+        self.assign_ids();
+        self.solve_until_sweeps_settle(|data| (data.equations(), data.soft_residuals()));
+        self.finish_solve(started);
+    }
+
+    /// Solves and applies the equations produced by the given function,
+    /// repeating while the sweep of any arc a point is constrained along
+    /// keeps changing: those points are placed using the sweep from before
+    /// each pass. Gives up with an error after ARC_SWEEP_PASSES.
+    fn solve_until_sweeps_settle(
+        &mut self,
+        equations: impl Fn(&mut Self) -> (Vec<eq::Expression>, Vec<(eq::Expression, f64)>),
+    ) {
+        let mut sweeps = self.constrained_arc_sweeps();
+        let mut moved = 0.0;
+        for _ in 0..ARC_SWEEP_PASSES {
+            let (equations, soft) = equations(self);
+            self.solve_and_apply_equations(equations, soft);
+
+            let after = self.constrained_arc_sweeps();
+            moved = sweeps
+                .iter()
+                .zip(after.iter())
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            if moved <= ARC_SWEEP_TOLERANCE {
+                return;
+            }
+            sweeps = after;
+        }
+        self.last_solve_error = Some(moved as f64);
+    }
+
+    /// Records how long the solve begun at the given time took, and informs
+    /// the on_solved hook.
+    fn finish_solve(&mut self, started: Option<std::time::Instant>) {
+        self.last_solve_duration = started.map(|t| t.elapsed());
+        if let Some(hook) = self.on_solved.0.as_mut() {
+            hook(self.last_solve_error);
+        }
+    }
+
+    /// Returns the current sweep of each arc a point is constrained along.
+    fn constrained_arc_sweeps(&self) -> Vec<f32> {
+        self.constraints
+            .iter()
+            .filter_map(|(_ck, c)| match c {
+                Constraint::PointAlongArc(_, _, arc, _) => self.get_arc(*arc).map(|a| a.sweep()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Solves only the constraints connected to the given feature, leaving
+    /// unrelated geometry untouched.
+    fn solve_and_apply_for(&mut self, k: FeatureKey) {
+        // The previous error may have come from any part of the drawing,
+        // so only a full solve can tell if it has been resolved.
+        if self.last_solve_error.is_some() {
+            return self.solve_and_apply();
+        }
+        // Soft constraints and penalties are solved alongside everything they
+        // touch, which partitioning doesn't account for.
+        if self
+            .constraints
+            .iter()
+            .any(|(_ck, c)| c.meta().is_soft() || matches!(c, Constraint::LineLengthRange(..)))
+        {
+            return self.solve_and_apply();
+        }
+        let started = solve_clock();
+        self.last_solve_iterations = 0;
+        let vars: Vec<eq::Variable> = [TermType::PositionX, TermType::PositionY]
+            .into_iter()
+            .map(|t| (&self.terms.get_feature_term(k, t)).into())
+            .collect();
+        self.solve_until_sweeps_settle(|data| {
+            let affected = Self::partition_equations(data.equations())
+                .into_iter()
+                .filter(|eqs| {
+                    let mut found = false;
+                    for e in eqs.iter() {
+                        e.walk(&mut |e| {
+                            if let eq::Expression::Variable(v) = e {
+                                found |= vars.contains(v);
+                            }
+                            !found
+                        });
+                    }
+                    found
+                })
+                .flatten()
+                .collect();
+            (affected, Vec::new())
+        });
+        self.finish_solve(started);
+    }
+
+    fn solve_and_apply_equations(
+        &mut self,
+        equations: Vec<eq::Expression>,
+        soft: Vec<(eq::Expression, f64)>,
+    ) {
+        let has_soft = !soft.is_empty();
+        let (known, unresolved, residuals, weights, initials) = match self.subsolve(equations, soft)
+        {
+            Some(result) => result,
             None => {
                 return;
             }
         };
 
+        // Whatever substitution couldn't resolve may still be a linear system,
+        // which can be solved exactly rather than approximated.
+        if !has_soft {
+            if let Some(results) = solve_linear_exact(&known, &unresolved, &residuals) {
+                use num::ToPrimitive;
+                self.last_solve_error = None;
+                for (v, r) in unresolved.iter().zip(results) {
+                    let term = self.terms.get_var_ref(v).expect("no such var");
+                    self.apply_solved(&term, r.to_f64().unwrap());
+                }
+                return;
+            }
+        }
+
         let mut params = eq::solve::DumbassSolverParams::default();
         params.terminate_at_avg_fx = self.props.solver_stop_err;
-        let mut solver_state = eq::solve::DumbassSolverState::new(known, unresolved, residuals);
+        let mut solver_state =
+            eq::solve::DumbassSolverState::new(known, unresolved, residuals).with_weights(weights);
         // println!("solver input: {:?}", solver_state);
         let mut solver =
             eq::solve::DumbassSolver::new_with_initials(params, &solver_state, initials);
-        let results = match solver.solve(&mut solver_state) {
+        let (results, stats) = solver.solve(&mut solver_state);
+        self.last_solve_iterations += stats.iterations;
+        let results = match results {
             Ok(results) => {
                 self.last_solve_error = None;
                 Some(results)
@@ -274,45 +1097,81 @@ impl Data {
         }
     }
 
+    /// Brute-force searches for a solution near the current geometry,
+    /// blocking until the search finishes. See start_bruteforce_solve().
     pub fn bruteforce_solve(&mut self) {
-        let (known, unresolved, residuals, mut initials) = match self.subsolve() {
-            Some((k, u, r, i)) => (k, u, r, i),
-            None => {
-                return;
-            }
+        if let Some(results) = self.bruteforce_search().and_then(BruteforceSearch::run) {
+            self.apply_bruteforce(results);
+        }
+    }
+
+    /// Starts a brute-force search for a solution near the current geometry,
+    /// on another thread where there are threads. The result is applied by
+    /// poll_bruteforce_solve().
+    pub fn start_bruteforce_solve(&mut self) {
+        let Some(search) = self.bruteforce_search() else {
+            return;
         };
 
-        let mut params = eq::solve::ExpSearchParams::default();
-        let mut last_best: Option<f64> = None;
-        for _ in 0..3 {
-            // TODO: Make SearchSolver take references to eliminate clones?
-            let ss = eq::solve::SearchSolver::new(
-                params.clone(),
-                known.clone(),
-                unresolved.clone(),
-                residuals.clone(),
-                initials.clone(),
-            );
-
-            let (residual_sq, guesses) = ss.bruteforce(3);
-            // println!("{}: {:?}", residual_sq, guesses);
+        let (tx, rx) = std::sync::mpsc::channel();
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            let _ = tx.send(search.run());
+        });
+        #[cfg(target_arch = "wasm32")]
+        let _ = tx.send(search.run());
+        self.pending_bruteforce = PendingSearch(Some(rx));
+    }
 
-            if last_best.is_none() || last_best.unwrap() > residual_sq {
-                last_best = Some(residual_sq);
-                for (i, (_var, guess)) in guesses.into_iter().enumerate() {
-                    initials[i] = guess;
+    /// Applies the result of a search started by start_bruteforce_solve() if
+    /// it has finished. Returns true while it's still running.
+    pub fn poll_bruteforce_solve(&mut self) -> bool {
+        let Some(rx) = &self.pending_bruteforce.0 else {
+            return false;
+        };
+        match rx.try_recv() {
+            Err(std::sync::mpsc::TryRecvError::Empty) => true,
+            Ok(results) => {
+                self.pending_bruteforce = PendingSearch(None);
+                if let Some(results) = results {
+                    self.apply_bruteforce(results);
                 }
+                false
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.pending_bruteforce = PendingSearch(None);
+                false
             }
-
-            params.reduce();
         }
+    }
 
-        if let Some(last_best_sq) = last_best {
-            if last_best_sq.sqrt() < 24.0 {
-                for (v, f) in unresolved.into_iter().zip(initials.into_iter()) {
-                    let term = self.terms.get_var_ref(&v).expect("no such var");
-                    self.apply_solved(&term, f);
-                }
+    fn bruteforce_search(&mut self) -> Option<BruteforceSearch> {
+        let equations = self.equations();
+        // The search solver has no notion of weights, so soft constraints are left out.
+        let (known, unresolved, residuals, _weights, initials) =
+            self.subsolve(equations, Vec::new())?;
+        let terms = unresolved
+            .iter()
+            .map(|v| self.terms.get_var_ref(v).expect("no such var"))
+            .collect();
+
+        Some(BruteforceSearch {
+            params: self.bruteforce_params.clone(),
+            known,
+            unresolved,
+            terms,
+            residuals,
+            initials,
+        })
+    }
+
+    fn apply_bruteforce(&mut self, results: Vec<(TermRef, f64)>) {
+        for (term, f) in results {
+            // The drawing may have been edited while the search ran, in which
+            // case the term's base might now belong to another feature.
+            let current = self.terms.get_var_ref(&(&term).into());
+            if current.and_then(|t| t.for_feature) == term.for_feature {
+                self.apply_solved(&term, f);
             }
         }
     }
@@ -320,56 +1179,34 @@ impl Data {
     fn term_current_value(&self, term: &TermRef) -> Option<f32> {
         if let Some(feature) = term.for_feature {
             match self.features.get(feature) {
+                // A point which was never placed has no usable position.
                 Some(Feature::Point(_, x, y)) => match term.t {
-                    TermType::PositionX => Some(*x),
-                    TermType::PositionY => Some(*y),
+                    TermType::PositionX => Some(*x).filter(|x| x.is_finite()),
+                    TermType::PositionY => Some(*y).filter(|y| y.is_finite()),
                     TermType::ScalarDistance => unreachable!(),
                     TermType::ScalarRadius => unreachable!(),
                     TermType::ScalarGlobalCos => unreachable!(),
                     TermType::ScalarGlobalSin => unreachable!(),
                 },
-                Some(Feature::LineSegment(_, f1, f2)) => match term.t {
-                    TermType::ScalarDistance => {
-                        let (a, b) = match (
-                            self.features.get(*f1).unwrap(),
-                            self.features.get(*f2).unwrap(),
-                        ) {
-                            (Feature::Point(_, x1, y1), Feature::Point(_, x2, y2)) => {
-                                (egui::Pos2 { x: *x1, y: *y1 }, egui::Pos2 { x: *x2, y: *y2 })
-                            }
-                            _ => panic!("unexpected subkey types: {:?} & {:?}", f1, f2),
-                        };
-
-                        Some(a.distance(b))
-                    }
-                    TermType::ScalarGlobalCos => {
-                        let (a, b) = match (
-                            self.features.get(*f1).unwrap(),
-                            self.features.get(*f2).unwrap(),
-                        ) {
-                            (Feature::Point(_, x1, y1), Feature::Point(_, x2, y2)) => {
-                                (egui::Pos2 { x: *x1, y: *y1 }, egui::Pos2 { x: *x2, y: *y2 })
-                            }
-                            _ => panic!("unexpected subkey types: {:?} & {:?}", f1, f2),
-                        };
-                        Some((a - b).angle().cos())
+                Some(Feature::LineSegment(..)) => {
+                    let (a, b) = self.get_line_points(feature)?;
+                    // A zero-length line has no direction, and its length would
+                    // start the solver on a singularity. Neither can be measured
+                    // if an endpoint was never placed.
+                    let d = a.distance(b);
+                    if !d.is_finite() || d < DEGENERATE_LINE_LENGTH {
+                        return None;
                     }
-                    TermType::ScalarGlobalSin => {
-                        let (a, b) = match (
-                            self.features.get(*f1).unwrap(),
-                            self.features.get(*f2).unwrap(),
-                        ) {
-                            (Feature::Point(_, x1, y1), Feature::Point(_, x2, y2)) => {
-                                (egui::Pos2 { x: *x1, y: *y1 }, egui::Pos2 { x: *x2, y: *y2 })
-                            }
-                            _ => panic!("unexpected subkey types: {:?} & {:?}", f1, f2),
-                        };
-                        Some((a - b).angle().sin())
+
+                    match term.t {
+                        TermType::ScalarDistance => Some(a.distance(b)),
+                        TermType::ScalarGlobalCos => Some((a - b).angle().cos()),
+                        TermType::ScalarGlobalSin => Some((a - b).angle().sin()),
+                        TermType::PositionX => unreachable!(),
+                        TermType::PositionY => unreachable!(),
+                        TermType::ScalarRadius => unreachable!(),
                     }
-                    TermType::PositionX => unreachable!(),
-                    TermType::PositionY => unreachable!(),
-                    TermType::ScalarRadius => unreachable!(),
-                },
+                }
                 Some(Feature::Circle(_, _center, radius)) => match term.t {
                     TermType::ScalarRadius => Some(*radius),
                     TermType::PositionX => unreachable!(),
@@ -385,6 +1222,98 @@ impl Data {
         }
     }
 
+    /// Guesses the position of a point without a usable one from the centroid
+    /// of the other points on the lines and arcs it belongs to.
+    fn point_neighbor_pos(&self, fk: FeatureKey) -> Option<egui::Pos2> {
+        let neighbors: Vec<egui::Pos2> = self
+            .features
+            .iter()
+            .filter(|(_, f)| {
+                matches!(f, Feature::LineSegment(..) | Feature::Arc(..))
+                    && f.depends_on().contains(&Some(fk))
+            })
+            .flat_map(|(_, f)| f.depends_on())
+            .flatten()
+            .filter(|k| *k != fk)
+            .filter_map(|k| match self.features.get(k) {
+                Some(Feature::Point(_, x, y)) if x.is_finite() && y.is_finite() => {
+                    Some(egui::Pos2::new(*x, *y))
+                }
+                _ => None,
+            })
+            .collect();
+        if neighbors.is_empty() {
+            return None;
+        }
+
+        let sum = neighbors
+            .iter()
+            .fold(egui::Vec2::ZERO, |acc, p| acc + p.to_vec2());
+        let centroid = sum / neighbors.len() as f32;
+        // Nudged off the centroid, which for a lone neighbor would leave the
+        // line between them with no length or direction to solve from.
+        Some((centroid + egui::Vec2::splat(0.47)).to_pos2())
+    }
+
+    /// Guesses a value for a term which has no usable current one: points
+    /// are placed near their neighbors, and lines take their length and
+    /// direction from where their endpoints are (or are guessed to be).
+    fn term_guessed_value(&self, term: &TermRef) -> Option<f32> {
+        let fk = term.for_feature?;
+        let point_pos = |k: FeatureKey| match self.features.get(k) {
+            Some(Feature::Point(_, x, y)) if x.is_finite() && y.is_finite() => {
+                Some(egui::Pos2::new(*x, *y))
+            }
+            Some(Feature::Point(..)) => self.point_neighbor_pos(k),
+            _ => None,
+        };
+
+        match (self.features.get(fk)?, &term.t) {
+            (Feature::Point(..), TermType::PositionX) => point_pos(fk).map(|p| p.x),
+            (Feature::Point(..), TermType::PositionY) => point_pos(fk).map(|p| p.y),
+            (Feature::LineSegment(_, p1, p2), t) => {
+                let (a, b) = (point_pos(*p1)?, point_pos(*p2)?);
+                if a.distance(b) < DEGENERATE_LINE_LENGTH {
+                    return None;
+                }
+                match t {
+                    TermType::ScalarDistance => Some(a.distance(b)),
+                    TermType::ScalarGlobalCos => Some((a - b).angle().cos()),
+                    TermType::ScalarGlobalSin => Some((a - b).angle().sin()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a resolver which maps every allocated term to the current value
+    /// of its geometry, such that equations can be evaluated against the drawing.
+    pub fn resolver_from_geometry(&self) -> eq::StaticResolver {
+        self.features
+            .iter()
+            .flat_map(|(fk, f)| {
+                let types: &[TermType] = match f {
+                    Feature::Point(..) => &[TermType::PositionX, TermType::PositionY],
+                    Feature::LineSegment(..) => &[
+                        TermType::ScalarDistance,
+                        TermType::ScalarGlobalCos,
+                        TermType::ScalarGlobalSin,
+                    ],
+                    Feature::Circle(..) => &[TermType::ScalarRadius],
+                    _ => &[],
+                };
+                types
+                    .iter()
+                    .filter_map(move |t| self.terms.existing_feature_term(fk, t.clone()))
+            })
+            .filter_map(|term| {
+                self.term_current_value(&term)
+                    .map(|v| ((&term).into(), eq::Concrete::Float(v as f64)))
+            })
+            .collect()
+    }
+
     fn apply_solved(&mut self, term: &TermRef, v: f64) -> bool {
         if v.is_nan() || v.is_infinite() {
             return false;
@@ -407,6 +1336,8 @@ impl Data {
                     match term.t {
                         TermType::PositionX => unreachable!(),
                         TermType::PositionY => unreachable!(),
+                        // Only the point positions are stored, so the sign of a solved
+                        // distance never reaches the geometry.
                         TermType::ScalarDistance => {}
                         TermType::ScalarRadius => unreachable!(),
                         TermType::ScalarGlobalCos => {}
@@ -416,7 +1347,8 @@ impl Data {
                 }
                 Some(Feature::Circle(_, _, radius)) => {
                     match term.t {
-                        TermType::ScalarRadius => *radius = v as f32,
+                        // Radius is a magnitude: a negative solution describes the same circle.
+                        TermType::ScalarRadius => *radius = (v as f32).abs(),
                         TermType::PositionX => unreachable!(),
                         TermType::PositionY => unreachable!(),
                         TermType::ScalarDistance => unreachable!(),
@@ -432,6 +1364,22 @@ impl Data {
         }
     }
 
+    /// Returns the position of the given point.
+    pub fn get_point_pos(&self, point_fk: FeatureKey) -> Option<egui::Pos2> {
+        match self.features.get(point_fk)? {
+            Feature::Point(_, x, y) => Some(egui::Pos2 { x: *x, y: *y }),
+            _ => None,
+        }
+    }
+
+    /// Returns a point fixed at the origin, if there is one.
+    pub fn origin_point(&self) -> Option<FeatureKey> {
+        self.constraints
+            .iter_fixed()
+            .find(|(_, _, x, y)| *x == 0.0 && *y == 0.0)
+            .map(|(_, fk, ..)| fk)
+    }
+
     pub fn get_line_points(&self, line_fk: FeatureKey) -> Option<(egui::Pos2, egui::Pos2)> {
         self.features.get(line_fk).map(|line| {
             if let Feature::LineSegment(_, f1, f2, ..) = line {
@@ -450,6 +1398,18 @@ impl Data {
         })
     }
 
+    /// Returns the start, center and end of the given arc.
+    pub fn get_arc(&self, arc_fk: FeatureKey) -> Option<crate::l::Arc> {
+        match self.features.get(arc_fk)? {
+            Feature::Arc(_, start, center, end) => Some(crate::l::Arc {
+                start: self.features.get(*start)?.start_point(self),
+                center: self.features.get(*center)?.start_point(self),
+                end: self.features.get(*end)?.start_point(self),
+            }),
+            _ => None,
+        }
+    }
+
     /// Iterates through the features.
     pub fn features_iter(&self) -> slotmap::hop::Iter<'_, FeatureKey, Feature> {
         self.features.iter()
@@ -486,13 +1446,79 @@ impl Data {
         self.constraints.by_feature(k)
     }
 
-    /// Adds a constraint, solving to update based on any affects.
+    /// Returns the constraints coupled to the given one, by sharing a feature
+    /// with it directly or through a chain of other constraints. The given
+    /// constraint is not included.
+    pub fn related_constraints(&self, ck: ConstraintKey) -> Vec<ConstraintKey> {
+        let mut out: Vec<ConstraintKey> = Vec::with_capacity(8);
+        let mut seen_features: std::collections::HashSet<FeatureKey> =
+            std::collections::HashSet::with_capacity(16);
+        let mut pending: Vec<ConstraintKey> = vec![ck];
+
+        while let Some(next) = pending.pop() {
+            let features = match self.constraints.get(next) {
+                Some(c) => c.affecting_features(),
+                None => continue,
+            };
+            for fk in features {
+                if !seen_features.insert(fk) {
+                    continue;
+                }
+                for related in self.constraints_by_feature(&fk) {
+                    if related != ck && !out.contains(&related) {
+                        out.push(related);
+                        pending.push(related);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Adds a constraint, solving to update based on any affects. If the
+    /// constraint is redundant, it is still added but a warning is recorded
+    /// in constraint_warnings.
     pub fn add_constraint(&mut self, c: Constraint) {
+        let warning = self.redundancy_warning(&c);
         if self.add_constraint_impl(c) {
+            if let Some(w) = warning {
+                self.constraint_warnings.push(w);
+            }
             self.solve_and_apply();
         }
     }
-    fn add_constraint_impl(&mut self, c: Constraint) -> bool {
+
+    /// Returns a warning if the given constraint would be redundant with the
+    /// constraints already present on the same features.
+    pub fn redundancy_warning(&self, c: &Constraint) -> Option<String> {
+        for fk in c.affecting_features() {
+            for ck in self.constraints_by_feature(&fk) {
+                if let Some(reason) = self.constraints.get(ck).and_then(|o| c.redundant_with(o)) {
+                    return Some(format!("Redundant constraint: {}", reason));
+                }
+            }
+        }
+
+        // Dimensioning a line whose endpoints are both fixed has no effect.
+        if let Constraint::LineLength(_, fk, ..)
+        | Constraint::LineAngle(_, fk, ..)
+        | Constraint::LineAlongCardinal(_, fk, ..) = c
+        {
+            if let Some(Feature::LineSegment(_, p1, p2)) = self.features.get(*fk) {
+                let fixed = |p: &FeatureKey| {
+                    self.constraints_by_feature(p)
+                        .into_iter()
+                        .any(|ck| matches!(self.constraints.get(ck), Some(Constraint::Fixed(..))))
+                };
+                if fixed(p1) && fixed(p2) {
+                    return Some("Redundant constraint: both ends of the line are fixed".into());
+                }
+            }
+        }
+        None
+    }
+    fn add_constraint_impl(&mut self, mut c: Constraint) -> bool {
+        c.meta_mut().id = self.take_id();
         if let Some(ck) = self.constraints.add(c) {
             self.terms.inform_new_constraint(ck);
             true
@@ -510,8 +1536,26 @@ impl Data {
 
     /// NOTE: Only supports LineLength & CircleRadius constraints atm, and consumes a SCREEN coordinate.
     pub fn move_constraint(&mut self, k: ConstraintKey, pos: egui::Pos2) {
-        match self.constraints.get(k) {
-            Some(Constraint::LineLength(_, fk, ..)) => {
+        let reference = match self.constraints.get(k) {
+            Some(c) => self.dimension_reference_at(c, pos),
+            None => None,
+        };
+        match (self.constraint_mut(k), reference) {
+            (Some(Constraint::LineLength(.., dd)), Some(r))
+            | (Some(Constraint::CircleRadius(.., dd)), Some(r)) => {
+                dd.x = r.x;
+                dd.y = r.y;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the dimension display offset which would put the label of the
+    /// given constraint at the given screen position, for the constraints
+    /// which have one.
+    fn dimension_reference_at(&self, c: &Constraint, pos: egui::Pos2) -> Option<egui::Vec2> {
+        match c {
+            Constraint::LineLength(_, fk, ..) => {
                 let (a, b) = match self.features.get(*fk) {
                     Some(Feature::LineSegment(_, f1, f2)) => {
                         let (a, b) = match (
@@ -532,16 +1576,13 @@ impl Data {
                         )
                     }
                 };
-                if let Some(Constraint::LineLength(_, _fk, _, _, dd)) = self.constraint_mut(k) {
-                    let c = a.lerp(b, 0.5);
-                    let v = c.to_vec2() - pos.to_vec2();
-                    let reference = egui::Vec2::angled((a - b).angle() - v.angle()) * v.length();
-                    dd.x = -reference.x;
-                    dd.y = reference.y;
-                };
+                let c = a.lerp(b, 0.5);
+                let v = c.to_vec2() - pos.to_vec2();
+                let reference = egui::Vec2::angled((a - b).angle() - v.angle()) * v.length();
+                Some(egui::Vec2::new(-reference.x, reference.y))
             }
 
-            Some(Constraint::CircleRadius(_, fk, ..)) => {
+            Constraint::CircleRadius(_, fk, ..) => {
                 let center = match self.features.get(*fk) {
                     Some(Feature::Circle(_, f1, ..)) => {
                         let c = match self.features.get(*f1).unwrap() {
@@ -557,17 +1598,48 @@ impl Data {
                         )
                     }
                 };
+                Some(pos - center)
+            }
+            _ => None,
+        }
+    }
 
-                if let Some(Constraint::CircleRadius(_, _fk, _, dd)) = self.constraint_mut(k) {
-                    let v = center.to_vec2() - pos.to_vec2();
-                    dd.x = -v.x;
-                    dd.y = -v.y;
-                };
+    /// Returns the constraint as it should be painted: with its dimension label
+    /// nudged clear of other labels, if it overlaps one.
+    pub fn displayed_constraint<'a>(
+        &self,
+        k: ConstraintKey,
+        c: &'a Constraint,
+    ) -> std::borrow::Cow<'a, Constraint> {
+        let reference = match (self.label_nudges.get(&k), c.dimension_pos(self)) {
+            (Some(nudge), Some(pos)) => self.dimension_reference_at(c, pos + *nudge),
+            _ => None,
+        };
+        match reference {
+            Some(r) => {
+                let mut c = c.clone();
+                if let Constraint::LineLength(.., dd) | Constraint::CircleRadius(.., dd) = &mut c {
+                    dd.x = r.x;
+                    dd.y = r.y;
+                }
+                std::borrow::Cow::Owned(c)
             }
-            _ => {}
+            None => std::borrow::Cow::Borrowed(c),
         }
     }
 
+    /// Zooms the view in response to scrolling the mouse wheel, about the
+    /// cursor or the center of the view depending on `zoom_to_cursor`.
+    pub fn scroll_zoom(&mut self, scroll_delta: f32, cursor: egui::Pos2, view_center: egui::Pos2) {
+        let zoom = (self.vp.zoom * f32::exp(-1. * scroll_delta * 0.1823216 / 230.)).max(0.05);
+        let anchor = if self.zoom_to_cursor {
+            cursor
+        } else {
+            view_center
+        };
+        self.vp.zoom_about(anchor, zoom);
+    }
+
     /// Returns the 'thing' the screen coordinates are hovering over, if any.
     pub fn find_screen_hover(&self, hp: egui::Pos2) -> Hover {
         match self.find_screen_feature(hp) {
@@ -596,8 +1668,193 @@ impl Data {
             .next()
     }
 
+    /// Returns pairs of line segments between the same two points, in either
+    /// order. The first of each pair is the line which would be kept when
+    /// merging, and the second its duplicate.
+    pub fn find_duplicate_lines(&self) -> Vec<(FeatureKey, FeatureKey)> {
+        self.features
+            .iter()
+            .filter_map(|(fk, f)| match f {
+                Feature::LineSegment(_, p1, p2, ..) => match self.find_line_between(p1, p2) {
+                    Some(first) if first != fk => Some((first, fk)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Removes duplicate line segments found by find_duplicate_lines, returning
+    /// the number removed. Groups referencing a removed line are pointed at
+    /// the line kept in its place; constraints on a removed line are dropped.
+    pub fn merge_duplicate_lines(&mut self) -> usize {
+        let dupes = self.find_duplicate_lines();
+        for (keep, dupe) in dupes.iter() {
+            for g in self.groups.iter_mut() {
+                if g.features.contains(keep) {
+                    g.trim_feature_if_present(*dupe);
+                } else if let Some(f) = g.features.iter_mut().find(|f| *f == dupe) {
+                    *f = *keep;
+                }
+            }
+        }
+
+        self.delete_features(dupes.iter().map(|(_, dupe)| *dupe));
+        dupes.len()
+    }
+
+    /// Opens the given circle into an arc sweeping from start_deg to end_deg,
+    /// keeping its key, center and radius. Angles are measured as for
+    /// Arc::angles(). Constraints which only apply to circles are deleted.
+    /// Returns false if the feature is not a circle.
+    pub fn circle_to_arc(&mut self, fk: FeatureKey, start_deg: f32, end_deg: f32) -> bool {
+        let (meta, center, radius) = match self.features.get(fk) {
+            Some(Feature::Circle(meta, center, radius)) => (meta.clone(), *center, *radius),
+            _ => return false,
+        };
+        let c = match self.features.get(center) {
+            Some(Feature::Point(_, x, y)) => egui::Pos2::new(*x, *y),
+            _ => return false,
+        };
+
+        let mut point_at = |deg: f32| {
+            let (sin, cos) = deg.to_radians().sin_cos();
+            self.add_feature(Feature::Point(
+                FeatureMeta::default(),
+                c.x + radius * cos,
+                c.y + radius * sin,
+            ))
+        };
+        let (start, end) = (point_at(start_deg), point_at(end_deg));
+        self.features[fk] = Feature::Arc(meta, start, center, end);
+
+        self.delete_invalid_constraints(fk);
+        self.solve_and_apply();
+        true
+    }
+
+    /// Closes the given arc into a circle about its center, keeping its key
+    /// and radius. Constraints which only apply to arcs are deleted, as are
+    /// the arc's end points if nothing else uses them. Returns false if the
+    /// feature is not an arc.
+    pub fn arc_to_circle(&mut self, fk: FeatureKey) -> bool {
+        let (meta, start, center, end) = match self.features.get(fk) {
+            Some(Feature::Arc(meta, start, center, end)) => (meta.clone(), *start, *center, *end),
+            _ => return false,
+        };
+        let radius = match self.get_arc(fk) {
+            Some(arc) => arc.start.distance(arc.center),
+            None => return false,
+        };
+        self.features[fk] = Feature::Circle(meta, center, radius);
+        self.delete_invalid_constraints(fk);
+
+        let unused: Vec<FeatureKey> = [start, end]
+            .into_iter()
+            .filter(|p| {
+                self.constraints.by_feature(p).is_empty()
+                    && !self
+                        .features
+                        .values()
+                        .any(|f| f.depends_on().contains(&Some(*p)))
+                    && !self.groups.iter().any(|g| g.features.contains(p))
+            })
+            .collect();
+        if !self.delete_features(unused) {
+            self.solve_and_apply();
+        }
+        true
+    }
+
+    /// Deletes constraints on the given feature which no longer apply to it,
+    /// such as after its type changed.
+    fn delete_invalid_constraints(&mut self, fk: FeatureKey) {
+        let feature = self.features[fk].clone();
+        for ck in self.constraints.by_feature(&fk) {
+            if !self
+                .constraints
+                .get(ck)
+                .unwrap()
+                .valid_for_feature(&feature)
+            {
+                self.constraints.delete(ck);
+                self.terms.delete_constraint(ck);
+            }
+        }
+    }
+
+    /// Returns the crossing of two line segments nearest to the given screen
+    /// coordinates, if one is within the hover threshold. Lines which share
+    /// an endpoint are not considered to intersect.
+    pub fn find_screen_line_intersection(
+        &self,
+        hp: egui::Pos2,
+    ) -> Option<(FeatureKey, FeatureKey, egui::Pos2)> {
+        let lines: Vec<(FeatureKey, FeatureKey, FeatureKey)> = self
+            .features
+            .iter()
+            .filter_map(|(k, f)| match f {
+                Feature::LineSegment(_, p1, p2) => Some((k, *p1, *p2)),
+                _ => None,
+            })
+            .collect();
+
+        let threshold = self.hover_threshold();
+        let mut closest: Option<(FeatureKey, FeatureKey, egui::Pos2, f32)> = None;
+        for (i, (l1, a1, a2)) in lines.iter().enumerate() {
+            for (l2, b1, b2) in lines[i + 1..].iter() {
+                if a1 == b1 || a1 == b2 || a2 == b1 || a2 == b2 {
+                    continue;
+                }
+                let ((a1, a2), (b1, b2)) =
+                    match (self.get_line_points(*l1), self.get_line_points(*l2)) {
+                        (Some(a), Some(b)) => (a, b),
+                        _ => continue,
+                    };
+
+                if let Some(p) = crate::l::line_intersection(a1, a2, b1, b2) {
+                    let dist = self.vp.translate_point(p).distance_sq(hp);
+                    if dist < threshold && closest.map(|c| dist < c.3).unwrap_or(true) {
+                        closest = Some((*l1, *l2, p, dist));
+                    }
+                }
+            }
+        }
+
+        closest.map(|(l1, l2, p, _)| (l1, l2, p))
+    }
+
+    /// Works out how far dimension labels which overlap an earlier label should
+    /// be nudged to a clear spot nearby, so they remain legible. The nudges are
+    /// only applied when painting: the saved label positions are untouched.
+    pub fn separate_dimension_labels(&mut self) {
+        let labels: Vec<(ConstraintKey, egui::Pos2)> = self
+            .constraints_iter()
+            .filter_map(|(ck, c)| c.dimension_pos(self).map(|p| (ck, p)))
+            .collect();
+
+        self.label_nudges.clear();
+        let mut occupied: Vec<egui::Rect> = Vec::with_capacity(labels.len());
+        for (ck, center) in labels {
+            let rect = egui::Rect::from_center_size(center, crate::l::draw::DIMENSION_LABEL_SIZE);
+            let nudge = crate::l::draw::label_nudge(rect, &occupied);
+            if nudge != egui::Vec2::ZERO {
+                self.label_nudges.insert(ck, nudge);
+            }
+            occupied.push(rect.translate(nudge));
+        }
+    }
+
+    /// Returns the maximum (squared, screen-space) distance at which something
+    /// is considered hovered. Distances are measured on screen, so the pick
+    /// radius stays the same however far the view is zoomed.
+    fn hover_threshold(&self) -> f32 {
+        MAX_HOVER_DISTANCE * self.hover_sensitivity
+    }
+
     /// Returns the feature the screen coordinates are hovering over, if any.
     fn find_screen_feature(&self, hp: egui::Pos2) -> Option<(FeatureKey, Feature)> {
+        let threshold = self.hover_threshold();
         let mut closest: Option<(FeatureKey, f32, bool)> = None;
         for (k, v) in self.features.iter() {
             let is_point = v.is_point();
@@ -606,12 +1863,12 @@ impl Data {
             // they are chosen over a line segment when hovering near the end of
             // a line segment.
             let dist = if is_point {
-                v.screen_dist_sq(self, hp, &self.vp) - (MAX_HOVER_DISTANCE / 2.)
+                v.screen_dist_sq(self, hp, &self.vp) - (threshold / 2.)
             } else {
                 v.screen_dist_sq(self, hp, &self.vp)
             };
 
-            if dist < MAX_HOVER_DISTANCE {
+            if dist < threshold {
                 closest = Some(
                     closest
                         .map(|c| if dist < c.1 { (k, dist, is_point) } else { c })
@@ -628,14 +1885,16 @@ impl Data {
 
     /// Returns the constraint the screen coordinates are hovering over, if any.
     fn find_screen_constraint(&self, hp: egui::Pos2) -> Option<(ConstraintKey, Constraint)> {
+        let threshold = self.hover_threshold();
         let mut closest: Option<(ConstraintKey, f32)> = None;
         for (k, c) in self.constraints_iter() {
+            let c = self.displayed_constraint(k, c);
             let dist = match c.screen_dist_sq(self, hp, &self.vp) {
                 Some(dist) => dist,
                 None => continue,
             };
 
-            if dist < MAX_HOVER_DISTANCE {
+            if dist < threshold {
                 closest = Some(
                     closest
                         .map(|c| if dist < c.1 { (k, dist) } else { c })
@@ -644,15 +1903,18 @@ impl Data {
             }
         }
 
-        match closest {
-            Some((k, _dist)) => Some((k, self.constraints.get(k).unwrap().clone())),
-            None => None,
-        }
+        closest.map(|(k, _dist)| {
+            let c = self.constraints.get(k).unwrap();
+            (k, self.displayed_constraint(k, c).into_owned())
+        })
     }
 
     /// Moves the given point to the given coordinates, and solving to update based on
     /// any side-effects of the move.
     pub fn move_point(&mut self, k: FeatureKey, pos: egui::Pos2) {
+        if self.feature_locked(k) {
+            return;
+        }
         let did_move_something = match self.feature_mut(k) {
             Some(Feature::Point(_, x, y)) => {
                 *x = pos.x;
@@ -663,7 +1925,22 @@ impl Data {
         };
 
         if did_move_something {
-            self.solve_and_apply();
+            self.solve_and_apply_for(k);
+        }
+    }
+
+    /// Returns true if the feature, or any feature it is built from, is
+    /// locked against dragging.
+    pub fn feature_locked(&self, k: FeatureKey) -> bool {
+        match self.features.get(k) {
+            Some(f) => {
+                f.meta().locked
+                    || f.depends_on()
+                        .into_iter()
+                        .flatten()
+                        .any(|d| self.feature_locked(d))
+            }
+            None => false,
         }
     }
 
@@ -671,13 +1948,42 @@ impl Data {
     /// other features which depend on a removed feature. A solve occurs
     /// if a feature was deleted, to apply any side-effects of the delete.
     pub fn delete_feature(&mut self, k: FeatureKey) -> bool {
-        let out = self.delete_feature_impl(k);
+        self.delete_features([k])
+    }
+
+    /// Deletes the given features, noting any group whose closed path is left
+    /// open as a result in broken_groups.
+    fn delete_features(&mut self, keys: impl IntoIterator<Item = FeatureKey>) -> bool {
+        let closed_before = self.closed_groups();
+        let mut out = false;
+        for k in keys {
+            out |= self.delete_feature_impl(k);
+        }
+
         if out {
+            // Groups which lost all their geometry were deleted outright, not broken.
+            let closed_after = self.closed_groups();
+            for (i, g) in self.groups.iter().enumerate() {
+                if closed_before[i] && !closed_after[i] && !g.features.is_empty() {
+                    self.broken_groups.push(i);
+                }
+            }
             self.solve_and_apply();
         }
         out
     }
 
+    /// Returns, for each group, whether its geometry forms only closed paths.
+    fn closed_groups(&self) -> Vec<bool> {
+        self.groups
+            .iter()
+            .map(|g| {
+                let paths = g.compute_path(self);
+                !paths.is_empty() && paths.iter().all(path_closed)
+            })
+            .collect()
+    }
+
     fn delete_feature_impl(&mut self, k: FeatureKey) -> bool {
         self.selected_map.remove(&SelectedElement::Feature(k));
         for g in self.groups.iter_mut() {
@@ -723,36 +2029,118 @@ impl Data {
         }
     }
 
-    /// Returns the bounds of all geometry in the drawing.
-    pub fn bounds(&self) -> egui::Rect {
-        self.features
-            .values()
-            .collect::<Vec<_>>()
-            .into_iter()
-            .fold(None, |acc, x| match acc {
-                None => Some(x.bb(self)),
-                Some(e) => Some(e.union(x.bb(self))),
-            })
-            .unwrap_or(egui::Rect::ZERO)
-    }
-
-    /// Deletes the currently-selected features.
-    pub fn selection_delete(&mut self) {
-        let elements: Vec<_> = self
-            .selected_map
-            .drain()
+    /// Replaces the corner at the given point with a line segment, set back
+    /// the given distance along each of the two lines meeting there. The corner
+    /// point is removed, as are any length constraints on the two lines.
+    ///
+    /// Fails unless the point is used by exactly two lines and nothing else,
+    /// and the setback is shorter than both of them.
+    pub fn chamfer(&mut self, corner_point: FeatureKey, setback: f32) -> Result<FeatureKey, ()> {
+        let corner = match self.features.get(corner_point) {
+            Some(Feature::Point(_, x, y)) => egui::Pos2::new(*x, *y),
+            _ => return Err(()),
+        };
+        let dependents: Vec<FeatureKey> = self
+            .features
+            .iter()
+            .filter(|(_, f)| f.depends_on().contains(&Some(corner_point)))
             .map(|(k, _)| k)
-            .filter_map(|k| {
-                if let SelectedElement::Feature(f) = k {
-                    Some(f)
+            .collect();
+        let lines = match dependents.as_slice() {
+            [l1, l2] => [*l1, *l2],
+            _ => return Err(()),
+        };
+
+        let mut points = [egui::Pos2::ZERO; 2];
+        for (i, l) in lines.iter().enumerate() {
+            let far = match self.features.get(*l) {
+                Some(Feature::LineSegment(_, p1, p2)) => {
+                    let far = if *p1 == corner_point { *p2 } else { *p1 };
+                    match self.features.get(far) {
+                        Some(Feature::Point(_, x, y)) => egui::Pos2::new(*x, *y),
+                        _ => return Err(()),
+                    }
+                }
+                _ => return Err(()),
+            };
+            let len = corner.distance(far);
+            if !(setback > 0. && setback < len) {
+                return Err(());
+            }
+            points[i] = corner + (far - corner) * (setback / len);
+        }
+
+        // Only construction carries over: ids, locks, layers and bends
+        // belong to the original lines.
+        let meta = match self.features.get(lines[0]) {
+            Some(Feature::LineSegment(meta, ..)) => FeatureMeta {
+                construction: meta.construction,
+                ..FeatureMeta::default()
+            },
+            _ => unreachable!(),
+        };
+        let new_points = points.map(|p| self.add_feature(Feature::Point(meta.clone(), p.x, p.y)));
+        for (l, p) in lines.iter().zip(new_points) {
+            if let Some(Feature::LineSegment(_, p1, p2)) = self.features.get_mut(*l) {
+                if *p1 == corner_point {
+                    *p1 = p;
+                } else {
+                    *p2 = p;
+                }
+            }
+
+            let lengths: Vec<ConstraintKey> = self
+                .constraints
+                .iter_feature(l)
+                .filter(|(_, c)| matches!(c, Constraint::LineLength(..)))
+                .map(|(ck, _)| ck)
+                .collect();
+            for ck in lengths {
+                self.constraints.delete(ck);
+                self.terms.delete_constraint(ck);
+            }
+        }
+
+        let chamfer = self.add_feature(Feature::LineSegment(meta, new_points[0], new_points[1]));
+        for g in self.groups.iter_mut() {
+            if lines.iter().any(|l| g.features.contains(l)) {
+                g.features.push(chamfer);
+            }
+        }
+
+        self.delete_feature_impl(corner_point);
+        self.solve_and_apply();
+        Ok(chamfer)
+    }
+
+    /// Returns the bounds of all geometry in the drawing.
+    pub fn bounds(&self) -> egui::Rect {
+        self.features
+            .values()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .fold(None, |acc, x| match acc {
+                None => Some(x.bb(self)),
+                Some(e) => Some(e.union(x.bb(self))),
+            })
+            .unwrap_or(egui::Rect::ZERO)
+    }
+
+    /// Deletes the currently-selected features.
+    pub fn selection_delete(&mut self) {
+        let elements: Vec<_> = self
+            .selected_map
+            .drain()
+            .map(|(k, _)| k)
+            .filter_map(|k| {
+                if let SelectedElement::Feature(f) = k {
+                    Some(f)
                 } else {
                     None
                 }
             })
             .collect();
-        for k in elements {
-            self.delete_feature(k);
-        }
+        self.delete_features(elements);
     }
 
     /// Selects or de-selects the given feature.
@@ -767,6 +2155,79 @@ impl Data {
         }
     }
 
+    /// Returns the selected line segments, in the order they were selected.
+    pub fn selected_lines(&self) -> Vec<FeatureKey> {
+        self.selected_features_matching(|f| matches!(f, Feature::LineSegment(..)))
+    }
+
+    /// Returns the selected points, in the order they were selected.
+    pub fn selected_points(&self) -> Vec<FeatureKey> {
+        self.selected_features_matching(|f| matches!(f, Feature::Point(..)))
+    }
+
+    /// Returns the selected circles, in the order they were selected.
+    pub fn selected_circles(&self) -> Vec<FeatureKey> {
+        self.selected_features_matching(|f| matches!(f, Feature::Circle(..)))
+    }
+
+    /// Returns the selected arcs, in the order they were selected.
+    pub fn selected_arcs(&self) -> Vec<FeatureKey> {
+        self.selected_features_matching(|f| matches!(f, Feature::Arc(..)))
+    }
+
+    fn selected_features_matching(&self, pred: impl Fn(&Feature) -> bool) -> Vec<FeatureKey> {
+        let mut out: Vec<(usize, FeatureKey)> = self
+            .selected_map
+            .iter()
+            .filter_map(|(e, idx)| match e {
+                SelectedElement::Feature(fk) => match self.features.get(*fk) {
+                    Some(f) if pred(f) => Some((*idx, *fk)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        out.sort_by_key(|(idx, _)| *idx);
+        out.into_iter().map(|(_, fk)| fk).collect()
+    }
+
+    /// Flips whether each selected feature is construction geometry.
+    pub fn toggle_selection_construction(&mut self) {
+        let mut changed = false;
+        for se in self.selected_map.keys() {
+            if let SelectedElement::Feature(fk) = se {
+                if let Some(f) = self.features.get_mut(*fk) {
+                    let meta = f.meta_mut();
+                    meta.construction = !meta.construction;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.changed_in_ui();
+        }
+    }
+
+    /// Turns each selected feature into locked construction geometry, which
+    /// stays visible for reference but is left out of export, can't be dragged
+    /// and is held in place by the solver.
+    pub fn selection_to_construction_locked(&mut self) {
+        let mut changed = false;
+        for se in self.selected_map.keys() {
+            if let SelectedElement::Feature(fk) = se {
+                if let Some(f) = self.features.get_mut(*fk) {
+                    let meta = f.meta_mut();
+                    meta.construction = true;
+                    meta.locked = true;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.changed_in_ui();
+        }
+    }
+
     /// Selects or de-selects any features wholly within the given rectangle.
     pub fn select_features_in_rect(&mut self, rect: egui::Rect, select: bool) {
         let keys: Vec<_> = self
@@ -870,40 +2331,234 @@ impl Data {
         }
     }
 
-    pub fn serialize(&self) -> SerializedDrawing {
-        // First pass just get points
-        let mut feature_keys = HashMap::with_capacity(self.features.len());
-        let mut features: Vec<SerializedFeature> = self
-            .features
-            .iter()
-            .filter(|(_fk, f)| matches!(f, Feature::Point(..)))
-            .map(|(fk, f)| {
-                feature_keys.insert(fk, feature_keys.len());
-                f.serialize(&feature_keys).unwrap()
+    /// Sets every selected length and radius dimension to `value * factor + delta`,
+    /// never going below zero.
+    pub fn adjust_selected_dimensions(&mut self, delta: f32, factor: f32) {
+        let selected: Vec<ConstraintKey> = self
+            .selected_map
+            .keys()
+            .filter_map(|k| match k {
+                SelectedElement::Constraint(ck) => Some(*ck),
+                _ => None,
             })
             .collect();
 
-        features.reserve(self.features.len());
+        for ck in selected {
+            match self.constraints.get_mut(ck) {
+                Some(Constraint::LineLength(_, _, value, ..))
+                | Some(Constraint::CircleRadius(_, _, value, ..)) => {
+                    *value = (*value * factor + delta).max(0.0);
+                }
+                _ => {}
+            }
+        }
+        self.solve_and_apply();
+    }
 
-        // Second pass gets non-points
-        for (fk, f) in self.features.iter() {
-            if feature_keys.contains_key(&fk) {
-                continue;
+    /// Spaces the selected points evenly along the given axis, between the
+    /// two outermost points. The other co-ordinate of each point is untouched.
+    pub fn distribute_selection(&mut self, horizontal: bool) {
+        let mut points: Vec<(FeatureKey, f32)> = self
+            .selected_points()
+            .into_iter()
+            .filter_map(|k| match self.features.get(k) {
+                Some(Feature::Point(_, x, y)) => Some((k, if horizontal { *x } else { *y })),
+                _ => None,
+            })
+            .collect();
+        if points.len() < 3 {
+            return;
+        }
+        points.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (start, end) = (points[0].1, points[points.len() - 1].1);
+        let step = (end - start) / (points.len() - 1) as f32;
+        for (i, (k, _)) in points.iter().enumerate() {
+            if let Some(Feature::Point(_, x, y)) = self.features.get_mut(*k) {
+                let v = start + step * i as f32;
+                if horizontal {
+                    *x = v;
+                } else {
+                    *y = v;
+                }
+            }
+        }
+        self.solve_and_apply();
+    }
+
+    /// Dimensions the selected geometry at its current size: selected lines
+    /// get a length, and selected points are fixed at their offset from a fixed
+    /// point, preferring one which is selected. Points are left alone if
+    /// there is no fixed point.
+    pub fn auto_dimension_selection(&mut self) {
+        for fk in self.selected_lines() {
+            let has_length = self
+                .constraints
+                .iter_feature(&fk)
+                .any(|(_, c)| matches!(c, Constraint::LineLength(..)));
+            if let (false, Some((a, b))) = (has_length, self.get_line_points(fk)) {
+                self.add_constraint_impl(Constraint::LineLength(
+                    ConstraintMeta::default(),
+                    fk,
+                    a.distance(b),
+                    None,
+                    DimensionDisplay {
+                        x: 0.,
+                        y: 35.0,
+                        ..DimensionDisplay::default()
+                    },
+                ));
+            }
+        }
+
+        let origin = self
+            .constraints
+            .iter_fixed()
+            .map(|(_, fk, x, y)| (fk, egui::Pos2 { x, y }))
+            .min_by_key(|(fk, _)| !self.feature_selected(*fk));
+        if let Some((origin_fk, origin)) = origin {
+            for fk in self.selected_points() {
+                if fk == origin_fk {
+                    continue;
+                }
+                if let Some(Feature::Point(_, x, y)) = self.features.get(fk) {
+                    // Refused if the point is already fixed or offset from the origin.
+                    self.add_constraint_impl(Constraint::PointOffset(
+                        ConstraintMeta::default(),
+                        origin_fk,
+                        fk,
+                        x - origin.x,
+                        y - origin.y,
+                    ));
+                }
+            }
+        }
+
+        self.solve_and_apply();
+    }
+
+    /// Returns the current angle of the given line in degrees from vertical,
+    /// as displayed for line angle constraints.
+    pub fn line_angle_degrees(&self, fk: FeatureKey) -> Option<f32> {
+        if !matches!(self.features.get(fk), Some(Feature::LineSegment(..))) {
+            return None;
+        }
+        self.get_line_points(fk)
+            .map(|(a, b)| ((a - b).angle() + 0.5 * std::f32::consts::PI).to_degrees())
+    }
+
+    /// Returns the length, chord and sagitta of the given arc, or None if
+    /// the feature is not an arc.
+    pub fn arc_metrics(&self, fk: FeatureKey) -> Option<ArcMetrics> {
+        let arc = self.get_arc(fk)?;
+        let (radius, sweep) = (arc.start.distance(arc.center), arc.sweep());
+        Some(ArcMetrics {
+            length: radius * sweep,
+            chord: arc.start.distance(arc.end),
+            sagitta: radius * (1.0 - (sweep / 2.0).cos()),
+        })
+    }
+
+    /// Returns false if the given arc has no well-defined circle: its start
+    /// or end coincides with the center, or all three points lie on a line
+    /// with the center off to one side. Start and end either side of the
+    /// center make a valid semicircle.
+    pub fn arc_is_valid(&self, fk: FeatureKey) -> bool {
+        let arc = match self.get_arc(fk) {
+            Some(arc) => arc,
+            None => return false,
+        };
+        let (d_start, d_end) = (arc.start - arc.center, arc.end - arc.center);
+        let (r_start, r_end) = (d_start.length(), d_end.length());
+        if r_start < DEGENERATE_LINE_LENGTH || r_end < DEGENERATE_LINE_LENGTH {
+            return false;
+        }
+
+        let cross = d_start.x * d_end.y - d_end.x * d_start.y;
+        cross.abs() > ARC_COLLINEAR_TOLERANCE * r_start * r_end || d_start.dot(d_end) < 0.0
+    }
+
+    /// Lists every dimensioned value in the drawing along with its current
+    /// measurement, for checking a part against its drawing.
+    pub fn dimension_report(&self) -> Vec<DimensionEntry> {
+        use slotmap::Key;
+
+        self.constraints
+            .iter()
+            .filter_map(|(ck, c)| {
+                let (kind, feature, value, measured) = match c {
+                    Constraint::LineLength(_, fk, d, ..) => {
+                        let (a, b) = self.get_line_points(*fk)?;
+                        ("Length", format!("Line {:?}", fk.data()), *d, a.distance(b))
+                    }
+                    Constraint::CircleRadius(_, fk, r, ..) => match self.features.get(*fk)? {
+                        Feature::Circle(_, _, radius) => {
+                            ("Radius", format!("Circle {:?}", fk.data()), *r, *radius)
+                        }
+                        _ => return None,
+                    },
+                    Constraint::LineAngle(_, fk, angle) => {
+                        // Shown in degrees from vertical, as in the detailer.
+                        let value = (angle + 0.5 * std::f32::consts::PI).to_degrees();
+                        let mut measured = self.line_angle_degrees(*fk)?;
+                        measured += 360.0 * ((value - measured) / 360.0).round();
+                        ("Angle", format!("Line {:?}", fk.data()), value, measured)
+                    }
+                    _ => return None,
+                };
+                Some(DimensionEntry {
+                    constraint: ck,
+                    kind,
+                    feature,
+                    value,
+                    measured,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the dimension report as CSV, with a header row.
+    pub fn dimension_report_csv(&self) -> String {
+        let mut out = String::from("kind,feature,value,measured\n");
+        for e in self.dimension_report() {
+            out.push_str(&format!(
+                "{},{},{:.3},{:.3}\n",
+                e.kind, e.feature, e.value, e.measured
+            ));
+        }
+        out
+    }
+
+    /// Returns a structure suitable for serialization to disk. Features and
+    /// constraints are emitted in key order (points first), so an unchanged
+    /// drawing always serializes identically.
+    pub fn serialize(&self) -> SerializedDrawing {
+        let mut keys: Vec<FeatureKey> = self.features.keys().collect();
+        keys.sort();
+
+        // First pass just get points, as all other features depend on them
+        let mut feature_keys = HashMap::with_capacity(self.features.len());
+        let mut features: Vec<SerializedFeature> = Vec::with_capacity(self.features.len());
+        for is_point in [true, false] {
+            for fk in keys.iter() {
+                let f = self.features.get(*fk).unwrap();
+                if f.is_point() != is_point {
+                    continue;
+                }
+                feature_keys.insert(*fk, feature_keys.len());
+                features.push(f.serialize(&feature_keys).unwrap());
             }
-            feature_keys.insert(fk, feature_keys.len());
-            features.push(f.serialize(&feature_keys).unwrap());
         }
 
+        let mut constraints: Vec<(ConstraintKey, &Constraint)> = self.constraints.iter().collect();
+        constraints.sort_by_key(|(ck, _)| *ck);
+
         SerializedDrawing {
-            properties: if self.props != DrawingProperties::default() {
-                Some(self.props.clone())
-            } else {
-                None
-            },
+            version: SERIALIZED_VERSION,
+            properties: Some(self.props.clone()),
             features,
-            constraints: self
-                .constraints
-                .iter()
+            constraints: constraints
+                .into_iter()
                 .map(|(_ck, c)| c.serialize(&feature_keys).unwrap())
                 .collect(),
             groups: self
@@ -912,96 +2567,233 @@ impl Data {
                 .map(|g| g.serialize(&feature_keys).unwrap())
                 .collect(),
             viewport: self.vp.clone(),
+            reference_image: self.reference_image.clone(),
+            next_id: self.next_id,
         }
     }
 
-    pub fn load(&mut self, drawing: SerializedDrawing) -> Result<(), ()> {
-        self.props = drawing.properties.unwrap_or(DrawingProperties::default());
+    pub fn load(&mut self, drawing: SerializedDrawing) -> Result<(), LoadErr> {
+        let drawing = drawing.migrate()?;
+        self.props = drawing.properties.unwrap_or_default();
         self.features = HopSlotMap::default();
         self.constraints = ConstraintData::default();
         self.vp = drawing.viewport;
+        self.reference_image = drawing.reference_image;
 
         let mut feature_keys = HashMap::with_capacity(drawing.features.len());
 
         for (i, sf) in drawing.features.into_iter().enumerate() {
-            let fk = self
-                .features
-                .insert(Feature::deserialize(sf, &feature_keys).unwrap());
+            let fk = self.features.insert(
+                Feature::deserialize(sf, &feature_keys).map_err(|_| LoadErr::BadFeature(i))?,
+            );
             feature_keys.insert(i, fk);
         }
-        for sc in drawing.constraints.into_iter() {
-            self.add_constraint_impl(Constraint::deserialize(sc, &feature_keys).unwrap());
+        for (i, sc) in drawing.constraints.into_iter().enumerate() {
+            let c = Constraint::deserialize(sc, &feature_keys)
+                .map_err(|_| LoadErr::BadConstraint(i))?;
+            c.validate()
+                .map_err(|reason| LoadErr::InvalidConstraint(i, reason))?;
+            // Terms are rebuilt below, and the saved id is kept.
+            self.constraints.add(c);
         }
+        // Older files didn't save the next id, and mustn't hand out ids in use.
+        let max_id = (self.features.values().map(|f| f.meta().id))
+            .chain(self.constraints.iter().map(|(_, c)| c.meta().id))
+            .max()
+            .unwrap_or(0);
+        self.next_id = drawing.next_id.max(max_id + 1);
 
         self.groups = drawing
             .groups
             .into_iter()
-            .map(|sg| Group::deserialize(sg, &feature_keys).unwrap())
-            .collect();
+            .enumerate()
+            .map(|(i, sg)| Group::deserialize(sg, &feature_keys).map_err(|_| LoadErr::BadGroup(i)))
+            .collect::<Result<_, _>>()?;
 
         // println!("features: {:?}", self.features);
         // println!("constraints: {:?}", self.constraints);
+        self.rebuild_terms();
         self.solve_and_apply();
         Ok(())
     }
 
-    pub fn serialize_dxf(&self, flatten_tolerance: f64) -> Result<String, ()> {
-        let (points, idx_outer, idx_inner) = self.flatten_to_idxs(flatten_tolerance)?;
-        if idx_outer.len() > 1 {
-            return Err(());
-        }
+    /// Returns true if the geometry can be exported, which is when the solver
+    /// is consistent or the user has opted to export anyway.
+    pub fn export_allowed(&self) -> bool {
+        self.last_solve_error.is_none() || self.export_inconsistent
+    }
 
-        let mut out: String = String::from("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n4\n");
-        out.reserve(64 + idx_outer.len() * 16 + idx_inner.len() * 16);
+    pub fn serialize_dxf(&self, flatten_tolerance: f64) -> Result<String, ExportErr> {
+        use crate::GroupType;
+        let paths = self.flatten_groups(flatten_tolerance, self.props.kerf)?;
+        let layer_paths = |typ: GroupType| {
+            paths
+                .iter()
+                .filter(move |(gt, _)| gt == &typ)
+                .flat_map(|(_gt, paths)| paths.iter())
+        };
+        match layer_paths(GroupType::Boundary).count() {
+            0 => return Err(ExportErr::NoBoundaryGroup),
+            1 => {}
+            _ => return Err(ExportErr::MultiBoundaryGroup),
+        }
 
-        //lmn-laser utility seems to do this:
-        out.push_str("9\n");
-        out.push_str("$MEASUREMENT\n");
-        out.push_str("70\n");
-        out.push_str("1\n");
+        // Paths go on the layer of their group's features, if set.
+        let mut lines: Vec<(&str, kurbo::Point, kurbo::Point)> =
+            Vec::with_capacity(paths.len() * 32);
+        for typ in [GroupType::Boundary, GroupType::Hole, GroupType::Engrave] {
+            let default = match typ {
+                GroupType::Engrave => DXF_ENGRAVE_LAYER,
+                _ => "0",
+            };
+            for (g, (_gt, paths)) in self
+                .groups
+                .iter()
+                .zip(paths.iter())
+                .filter(|(_g, (gt, _))| gt == &typ)
+            {
+                let layer = g.layer(self).unwrap_or(default);
+                for path in paths {
+                    for pts in path.windows(2) {
+                        lines.push((layer, pts[0], pts[1]));
+                    }
+                }
+            }
+        }
+        for (fk, f) in self.features.iter() {
+            if let Feature::LineSegment(
+                FeatureMeta {
+                    bend_angle: Some(_),
+                    layer,
+                    ..
+                },
+                ..,
+            ) = f
+            {
+                // Flipped to match the orientation of group paths.
+                let (a, b) = self.get_line_points(fk).unwrap();
+                lines.push((
+                    layer.as_deref().unwrap_or(DXF_BEND_LAYER),
+                    kurbo::Affine::FLIP_Y * kurbo::Point::new(a.x as f64, a.y as f64),
+                    kurbo::Affine::FLIP_Y * kurbo::Point::new(b.x as f64, b.y as f64),
+                ));
+            }
+        }
 
-        out.push_str("0\n");
-        out.push_str("ENDSEC\n");
+        let mut layers: Vec<&str> = Vec::with_capacity(4);
+        for (layer, ..) in lines.iter() {
+            if !layers.contains(layer) {
+                layers.push(layer);
+            }
+        }
+        let mut out = dxf_begin(&layers);
+        out.reserve(lines.len() * 32);
+        for (layer, a, b) in lines {
+            dxf_emit_line(&mut out, layer, a, b);
+        }
+        dxf_end(&mut out);
+        Ok(out)
+    }
 
-        // Output lines
-        out.push_str("0\n");
-        out.push_str("SECTION\n");
-        out.push_str("2\n");
-        out.push_str("ENTITIES\n");
+    /// Serializes the groups as an SVG document in millimeters, with engraved
+    /// paths in blue. Paths are stroked at the SVG stroke width property.
+    pub fn serialize_svg(&self, flatten_tolerance: f64) -> Result<String, ExportErr> {
+        use crate::GroupType;
+        let paths = self.flatten_groups(flatten_tolerance, self.props.kerf)?;
+        match paths
+            .iter()
+            .filter(|(gt, _)| gt == &GroupType::Boundary)
+            .flat_map(|(_gt, paths)| paths.iter())
+            .count()
         {
-            let emit_line = |out: &mut String, start: kurbo::Point, end: kurbo::Point| {
-                out.push_str("0\n");
-                out.push_str("LINE\n");
-                out.push_str("8\n");
-                out.push_str("0\n");
-
-                out.push_str("10\n");
-                out.extend(format!("{}\n", start.x).chars());
-                out.push_str("20\n");
-                out.extend(format!("{}\n", start.y).chars());
-                out.push_str("11\n");
-                out.extend(format!("{}\n", end.x).chars());
-                out.push_str("21\n");
-                out.extend(format!("{}\n", end.y).chars());
-            };
-            for path in idx_outer.into_iter().chain(idx_inner.into_iter()) {
-                for inds in path.windows(2) {
-                    emit_line(&mut out, points[inds[0]], points[inds[1]]);
-                }
+            0 => return Err(ExportErr::NoBoundaryGroup),
+            1 => {}
+            _ => return Err(ExportErr::MultiBoundaryGroup),
+        }
+
+        // Group paths have Y pointing up, unlike SVG.
+        let paths: Vec<(GroupType, Vec<kurbo::Point>)> = paths
+            .into_iter()
+            .flat_map(|(gt, paths)| paths.into_iter().map(move |p| (gt, p)))
+            .map(|(gt, p)| {
+                (
+                    gt,
+                    p.into_iter().map(|p| kurbo::Affine::FLIP_Y * p).collect(),
+                )
+            })
+            .collect();
+        let bounds = paths.iter().flat_map(|(_gt, p)| p.iter()).fold(
+            kurbo::Rect::from_points(paths[0].1[0], paths[0].1[0]),
+            |r, p| r.union_pt(*p),
+        );
+
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"{x} {y} {w} {h}\">\n",
+            x = bounds.x0,
+            y = bounds.y0,
+            w = bounds.width(),
+            h = bounds.height(),
+        );
+        let stroke_width = self.props.svg_stroke_width();
+        for (gt, path) in paths {
+            out.push_str("<path d=\"");
+            for (i, p) in path.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}{} {} ",
+                    if i == 0 { "M" } else { "L" },
+                    p.x,
+                    p.y
+                ));
             }
+            out.push_str(&format!(
+                "Z\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                if gt == GroupType::Engrave {
+                    "blue"
+                } else {
+                    "black"
+                },
+                stroke_width,
+            ));
+        }
+        out.push_str("</svg>\n");
+        Ok(out)
+    }
+
+    /// Serializes the selected lines, arcs and circles as DXF, without regard
+    /// to groups. Features which share endpoints are joined into paths.
+    pub fn serialize_dxf_selection(&self, flatten_tolerance: f64) -> Result<String, ExportErr> {
+        let selection = crate::Group {
+            features: self.selected_features_matching(|f| {
+                matches!(
+                    f,
+                    Feature::LineSegment(..) | Feature::Arc(..) | Feature::Circle(..)
+                )
+            }),
+            ..crate::Group::default()
+        };
+        if selection.features.is_empty() {
+            return Err(ExportErr::NothingSelected);
+        }
+        if !selection.degenerate_arcs(self).is_empty() {
+            return Err(ExportErr::DegenerateArc(None));
         }
-        out.push_str("0\n");
-        out.push_str("ENDSEC\n");
 
-        out.push_str("0\n");
-        out.push_str("EOF");
+        let mut out = dxf_begin(&["0"]);
+        for path in selection.compute_path(self) {
+            for pts in flatten_path(&path, flatten_tolerance).windows(2) {
+                dxf_emit_line(&mut out, "0", pts[0], pts[1]);
+            }
+        }
+        dxf_end(&mut out);
         Ok(out)
     }
 
-    pub fn serialize_openscad(&self, flatten_tolerance: f64) -> Result<String, ()> {
+    pub fn serialize_openscad(&self, flatten_tolerance: f64) -> Result<String, ExportErr> {
         let (points, idx_outer, idx_inner) = self.flatten_to_idxs(flatten_tolerance)?;
-        if idx_outer.len() > 1 {
-            return Err(());
+        match idx_outer.len() {
+            0 => return Err(ExportErr::NoBoundaryGroup),
+            1 => {}
+            _ => return Err(ExportErr::MultiBoundaryGroup),
         }
 
         let mut out: String = String::from("polygon(\n  points = [\n    ");
@@ -1057,14 +2849,108 @@ impl Data {
         out.push_str("\n  ],\n  ");
         out.push_str("convexity = 10\n);");
 
+        if self.props.openscad_module.is_some() {
+            let name = openscad_identifier(&self.props.name);
+            out = format!(
+                "module {}() {{\n  {}\n}}\n\n{}();\n",
+                name,
+                out.replace('\n', "\n  "),
+                name
+            );
+        }
         Ok(out)
     }
 
-    pub fn flatten_to_idxs(
+    /// Flattens the paths of every group, returning them alongside the type
+    /// of the group they belong to. Boundaries are grown and holes shrunk by
+    /// half the kerf, if one is given.
+    fn flatten_groups(
+        &self,
+        flatten_tolerance: f64,
+        kerf: Option<f64>,
+    ) -> Result<Vec<FlattenedGroup>, ExportErr> {
+        use crate::GroupType;
+        self.groups
+            .iter()
+            .enumerate()
+            .map(|(i, g)| {
+                if !g.degenerate_arcs(self).is_empty() {
+                    return Err(ExportErr::DegenerateArc(Some(i)));
+                }
+                let mut out_paths: Vec<Vec<kurbo::Point>> = Vec::with_capacity(4);
+                let tolerance = g.flatten_tolerance.unwrap_or(flatten_tolerance);
+                for path in g.compute_path(self).into_iter() {
+                    let points = flatten_path(&path, tolerance);
+                    if points.len() > 0 {
+                        if points.len() < 3 || !points_closed(&points) {
+                            return Err(ExportErr::OpenPath(i));
+                        }
+                        let mut points = match (kerf, g.typ) {
+                            (Some(kerf), GroupType::Boundary) => {
+                                crate::l::offset_polygon(&points, kerf / 2.0)
+                            }
+                            (Some(kerf), GroupType::Hole) => {
+                                crate::l::offset_polygon(&points, -kerf / 2.0)
+                            }
+                            _ => points,
+                        };
+                        match (self.props.normalize_winding, g.typ) {
+                            (Some(()), GroupType::Boundary) => {
+                                crate::l::wind_polygon(&mut points, true)
+                            }
+                            (Some(()), GroupType::Hole) => {
+                                crate::l::wind_polygon(&mut points, false)
+                            }
+                            _ => {}
+                        }
+                        out_paths.push(points);
+                    }
+                }
+
+                Ok((g.typ, out_paths))
+            })
+            .collect()
+    }
+
+    /// Flattens the boundary and hole groups into closed polylines, within the
+    /// given tolerance unless a group overrides it. Points are in drawing units
+    /// with Y pointing up, and kerf compensation is applied.
+    pub fn outline_polylines(&self, flatten_tolerance: f64) -> Result<OutlineResult, ExportErr> {
+        self.outline_polylines_with_kerf(flatten_tolerance, self.props.kerf)
+    }
+
+    fn outline_polylines_with_kerf(
         &self,
         flatten_tolerance: f64,
-    ) -> Result<(Vec<kurbo::Point>, Vec<Vec<usize>>, Vec<Vec<usize>>), ()> {
+        kerf: Option<f64>,
+    ) -> Result<OutlineResult, ExportErr> {
         use crate::GroupType;
+        let mut out = OutlineResult::default();
+        for (gt, paths) in self.flatten_groups(flatten_tolerance, kerf)? {
+            match gt {
+                GroupType::Boundary => out.boundaries.extend(paths),
+                GroupType::Hole => out.holes.extend(paths),
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
+
+    /// Like outline_polylines(), but with points de-duplicated: returns the
+    /// points along with the indices making up each boundary and hole.
+    /// Rings follow the drawing's normalize_winding preference.
+    pub fn flatten_to_idxs(
+        &self,
+        flatten_tolerance: f64,
+    ) -> Result<(Vec<kurbo::Point>, Vec<Vec<usize>>, Vec<Vec<usize>>), ExportErr> {
+        self.flatten_to_idxs_with_kerf(flatten_tolerance, self.props.kerf)
+    }
+
+    fn flatten_to_idxs_with_kerf(
+        &self,
+        flatten_tolerance: f64,
+        kerf: Option<f64>,
+    ) -> Result<FlattenedIdxs, ExportErr> {
         let mut points: Vec<kurbo::Point> = Vec::with_capacity(128);
         let mut indices_outer: Vec<Vec<usize>> = Vec::with_capacity(2);
         let mut indices_inner: Vec<Vec<usize>> = Vec::with_capacity(6);
@@ -1082,41 +2968,10 @@ impl Data {
             }
         };
 
-        let paths: Vec<(GroupType, Vec<Vec<kurbo::Point>>)> = self
-            .groups
-            .iter()
-            .map(|g| {
-                let mut out_paths: Vec<Vec<kurbo::Point>> = Vec::with_capacity(4);
-                for path in g.compute_path(self).into_iter() {
-                    let mut points: Vec<kurbo::Point> = Vec::with_capacity(32);
-                    path.flatten(flatten_tolerance, |el| {
-                        use kurbo::PathEl;
-                        match el {
-                            PathEl::MoveTo(p) | PathEl::LineTo(p) => {
-                                if points.len() == 0 || points[points.len() - 1] != p {
-                                    points.push(p);
-                                }
-                            }
-                            PathEl::ClosePath => {}
-                            _ => panic!("unexpected element: {:?}", el),
-                        }
-                    });
-                    if points.len() > 0 {
-                        out_paths.push(points);
-                    }
-                }
-
-                (g.typ, out_paths)
-            })
-            .collect();
+        let outline = self.outline_polylines_with_kerf(flatten_tolerance, kerf)?;
 
         // Do boundaries first
-        for path_points in paths
-            .iter()
-            .filter(|(gt, _)| gt == &GroupType::Boundary)
-            .map(|(_gt, paths)| paths.iter())
-            .flatten()
-        {
+        for path_points in outline.boundaries.iter() {
             let mut idx: Vec<usize> = Vec::with_capacity(path_points.len());
             for point in path_points.iter() {
                 idx.push(point_idx(*point));
@@ -1124,12 +2979,7 @@ impl Data {
             indices_outer.push(idx);
         }
         // Now interior geometry
-        for path_points in paths
-            .iter()
-            .filter(|(gt, _)| gt == &GroupType::Hole)
-            .map(|(_gt, paths)| paths.iter())
-            .flatten()
-        {
+        for path_points in outline.holes.iter() {
             let mut idx: Vec<usize> = Vec::with_capacity(path_points.len());
             for point in path_points.iter() {
                 idx.push(point_idx(*point));
@@ -1140,6 +2990,42 @@ impl Data {
         Ok((points, indices_outer, indices_inner))
     }
 
+    /// Returns the total length of the boundary and hole paths, which is how
+    /// far a cutter travels while cutting the part.
+    pub fn total_cut_length(&self, flatten_tolerance: f64) -> Result<f64, ExportErr> {
+        let (points, idx_outer, idx_inner) = self.flatten_to_idxs(flatten_tolerance)?;
+        Ok(idx_outer
+            .iter()
+            .chain(idx_inner.iter())
+            .flat_map(|ring| ring.windows(2))
+            .fold(0.0, |acc, w| acc + points[w[0]].distance(points[w[1]])))
+    }
+
+    /// Triangulates the part (boundaries less holes) for shading, returning
+    /// triangles in drawing co-ordinates. Kerf is left out, so the shading
+    /// matches the drawn geometry.
+    pub fn fill_triangles(
+        &self,
+        flatten_tolerance: f64,
+    ) -> Result<Vec<[egui::Pos2; 3]>, ExportErr> {
+        let (points, outer, inner) = self.flatten_to_idxs_with_kerf(flatten_tolerance, None)?;
+        let edges: Vec<(kurbo::Point, kurbo::Point)> = outer
+            .iter()
+            .chain(inner.iter())
+            .flat_map(|ring| {
+                ring.iter()
+                    .zip(ring.iter().cycle().skip(1))
+                    .map(|(a, b)| (points[*a], points[*b]))
+            })
+            .collect();
+
+        // Undo the flip applied for export.
+        Ok(trapezoid_triangles(&edges)
+            .into_iter()
+            .map(|tri| tri.map(|p| egui::pos2(p.x as f32, -p.y as f32)))
+            .collect())
+    }
+
     pub fn part_paths(
         &self,
     ) -> Result<((f64, kurbo::BezPath), Vec<(CADOp, kurbo::BezPath)>), ExportErr> {
@@ -1153,6 +3039,14 @@ impl Data {
             .iter()
             .map(|g| (g, g.compute_path(self)))
             .collect();
+        for (i, (g, paths)) in paths.iter().enumerate() {
+            if !g.degenerate_arcs(self).is_empty() {
+                return Err(ExportErr::DegenerateArc(Some(i)));
+            }
+            if !paths.iter().all(path_closed) {
+                return Err(ExportErr::OpenPath(i));
+            }
+        }
 
         // Do boundaries first
         for (g, paths) in paths.iter().filter(|(g, _)| g.typ == GroupType::Boundary) {
@@ -1180,8 +3074,12 @@ impl Data {
             match g.typ {
                 GroupType::Boundary | GroupType::Hole => {}
                 GroupType::Extrude => {
+                    let amt = g.amt.unwrap_or(3.0);
                     for p in paths.into_iter() {
-                        ops.push((CADOp::Extrude(g.amt.unwrap_or(3.0), g.bottom.is_some()), p));
+                        ops.push(match g.symmetric {
+                            Some(()) => (CADOp::ExtrudeSymmetric(amt), p),
+                            None => (CADOp::Extrude(amt, g.bottom.is_some()), p),
+                        });
                     }
                 }
                 GroupType::Bore => {
@@ -1189,6 +3087,14 @@ impl Data {
                         ops.push((CADOp::Bore(g.amt.unwrap_or(3.0), g.bottom.is_some()), p));
                     }
                 }
+                GroupType::Engrave => {
+                    for p in paths.into_iter() {
+                        ops.push((
+                            CADOp::Bore(g.amt.unwrap_or(g.typ.default_amt()), g.bottom.is_some()),
+                            p,
+                        ));
+                    }
+                }
             }
         }
 
@@ -1238,9 +3144,331 @@ impl Data {
     }
 
     pub fn as_solid(&self) -> Result<truck_modeling::Solid, ExportErr> {
+        use crate::GroupType;
         let ((height, exterior), ops) = self.part_paths()?;
-        Ok(crate::l::three_d::extrude_from_paths(exterior, ops, height))
+        let solid = crate::l::three_d::extrude_from_paths(exterior, ops, height);
+
+        // The part is built upwards from the sketch plane, so shift it
+        // down if the boundary asks to be placed otherwise.
+        let boundary = self.groups.iter().find(|g| g.typ == GroupType::Boundary);
+        let offset = self.props.z_offset
+            + match boundary.map(|g| (g.symmetric, g.bottom)) {
+                Some((Some(()), _)) => -height / 2.0,
+                Some((None, Some(()))) => -height,
+                _ => 0.0,
+            };
+        if offset == 0.0 {
+            return Ok(solid);
+        }
+        Ok(truck_modeling::builder::translated(
+            &solid,
+            offset * truck_modeling::Vector3::unit_z(),
+        ))
+    }
+}
+
+/// Flattens the path into a polyline, dropping repeated points.
+fn flatten_path(path: &kurbo::BezPath, tolerance: f64) -> Vec<kurbo::Point> {
+    let mut points: Vec<kurbo::Point> = Vec::with_capacity(32);
+    path.flatten(tolerance, |el| {
+        use kurbo::PathEl;
+        match el {
+            PathEl::MoveTo(p) | PathEl::LineTo(p) => {
+                if points.len() == 0 || points[points.len() - 1] != p {
+                    points.push(p);
+                }
+            }
+            PathEl::ClosePath => {}
+            _ => panic!("unexpected element: {:?}", el),
+        }
+    });
+    points
+}
+
+/// Returns the given name as a snake_case OpenSCAD identifier, falling back
+/// to "part" if nothing usable is left.
+fn openscad_identifier(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let out = out.trim_end_matches('_');
+    match out.chars().next() {
+        None => "part".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", out),
+        Some(_) => out.to_string(),
+    }
+}
+
+/// Begins a document declaring the given layers, leaving the entities
+/// section open.
+fn dxf_begin(layers: &[&str]) -> String {
+    let mut out: String = String::from("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n4\n");
+    out.reserve(64 + layers.len() * 48);
+
+    //lmn-laser utility seems to do this:
+    out.push_str("9\n");
+    out.push_str("$MEASUREMENT\n");
+    out.push_str("70\n");
+    out.push_str("1\n");
+
+    out.push_str("0\n");
+    out.push_str("ENDSEC\n");
+
+    // Declare layers
+    out.push_str("0\nSECTION\n2\nTABLES\n");
+    out.push_str("0\nTABLE\n2\nLAYER\n");
+    out.push_str(&format!("70\n{}\n", layers.len()));
+    for layer in layers {
+        out.push_str("0\nLAYER\n2\n");
+        out.push_str(layer);
+        // No flags, white, continuous lines.
+        out.push_str("\n70\n0\n62\n7\n6\nCONTINUOUS\n");
+    }
+    out.push_str("0\nENDTAB\n");
+    out.push_str("0\nENDSEC\n");
+
+    // Output lines
+    out.push_str("0\n");
+    out.push_str("SECTION\n");
+    out.push_str("2\n");
+    out.push_str("ENTITIES\n");
+    out
+}
+
+fn dxf_emit_line(out: &mut String, layer: &str, start: kurbo::Point, end: kurbo::Point) {
+    out.push_str("0\n");
+    out.push_str("LINE\n");
+    out.push_str("8\n");
+    out.push_str(layer);
+    out.push('\n');
+
+    out.push_str("10\n");
+    out.extend(format!("{}\n", start.x).chars());
+    out.push_str("20\n");
+    out.extend(format!("{}\n", start.y).chars());
+    out.push_str("11\n");
+    out.extend(format!("{}\n", end.x).chars());
+    out.push_str("21\n");
+    out.extend(format!("{}\n", end.y).chars());
+}
+
+/// Closes the entities section and the document.
+fn dxf_end(out: &mut String) {
+    out.push_str("0\n");
+    out.push_str("ENDSEC\n");
+
+    out.push_str("0\n");
+    out.push_str("EOF");
+}
+
+/// Returns true if the flattened path ends where it starts.
+fn points_closed(points: &[kurbo::Point]) -> bool {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => first.distance(*last) < 1e-6,
+        _ => false,
+    }
+}
+
+/// Returns true if the path is explicitly closed or ends where it starts.
+fn path_closed(path: &kurbo::BezPath) -> bool {
+    use kurbo::PathEl;
+    let start = match path.elements().first() {
+        Some(PathEl::MoveTo(p)) => *p,
+        _ => return false,
+    };
+    match path.elements().last() {
+        Some(PathEl::ClosePath) => true,
+        Some(PathEl::LineTo(p)) | Some(PathEl::QuadTo(_, p)) | Some(PathEl::CurveTo(_, _, p)) => {
+            start.distance(*p) < 1e-6
+        }
+        _ => false,
+    }
+}
+
+/// Triangulates the region enclosed by the given edges, using the even-odd
+/// rule so that nested rings become holes. The region is cut into horizontal
+/// slabs between vertices, within which edges don't cross, and each span
+/// between a pair of edges is a trapezoid.
+fn trapezoid_triangles(edges: &[(kurbo::Point, kurbo::Point)]) -> Vec<[kurbo::Point; 3]> {
+    const EPSILON: f64 = 1e-9;
+    let mut ys: Vec<f64> = edges.iter().flat_map(|(a, b)| [a.y, b.y]).collect();
+    ys.sort_by(|a, b| a.total_cmp(b));
+    ys.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+
+    let mut out = Vec::with_capacity(2 * edges.len());
+    for slab in ys.windows(2) {
+        let (y0, y1) = (slab[0], slab[1]);
+        let ym = (y0 + y1) / 2.0;
+        // x co-ordinate of each spanning edge at the bottom, middle and top.
+        let mut spans: Vec<(f64, f64, f64)> = edges
+            .iter()
+            .filter(|(a, b)| a.y.min(b.y) < ym && a.y.max(b.y) > ym)
+            .map(|(a, b)| {
+                let x_at = |y: f64| a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y);
+                (x_at(y0), x_at(ym), x_at(y1))
+            })
+            .collect();
+        spans.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        for pair in spans.chunks_exact(2) {
+            let (l, r) = (pair[0], pair[1]);
+            let (a0, a1) = (kurbo::Point::new(l.0, y0), kurbo::Point::new(r.0, y0));
+            let (b0, b1) = (kurbo::Point::new(l.2, y1), kurbo::Point::new(r.2, y1));
+            out.push([a0, a1, b1]);
+            out.push([a0, b1, b0]);
+        }
+    }
+    out
+}
+
+/// Largest number of unknowns [`solve_linear_exact`] will take on, as rational
+/// elimination gets expensive quickly.
+const EXACT_SOLVE_MAX_VARS: usize = 48;
+
+/// Returns the current time, for timing solves, or None where there's no
+/// clock to read (the web).
+fn solve_clock() -> Option<std::time::Instant> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return Some(std::time::Instant::now());
+    #[cfg(target_arch = "wasm32")]
+    None
+}
+
+/// Solves the residuals for the unresolved variables using rational
+/// arithmetic, if they are all linear in those variables and determine them
+/// uniquely. Values are returned in the order of `unresolved`.
+fn solve_linear_exact(
+    known: &HashMap<eq::Variable, eq::Concrete>,
+    unresolved: &[eq::Variable],
+    residuals: &[eq::Expression],
+) -> Option<Vec<eq::Rational>> {
+    use eq::{Concrete, Rational};
+    let n = unresolved.len();
+    if n == 0 || n > EXACT_SOLVE_MAX_VARS || residuals.len() < n {
+        return None;
+    }
+    if known.values().any(|c| matches!(c, Concrete::Float(_))) {
+        return None;
+    }
+
+    // Anything which isn't rational arithmetic (roots, trig) evaluates to a
+    // float, which rules the system out.
+    let eval = |x: &[Rational]| -> Option<Vec<Rational>> {
+        let mut resolver: eq::StaticResolver = known
+            .iter()
+            .map(|(v, c)| (v.clone(), c.clone()))
+            .chain(
+                unresolved
+                    .iter()
+                    .cloned()
+                    .zip(x.iter().cloned().map(Concrete::Rational)),
+            )
+            .collect();
+        residuals
+            .iter()
+            .map(|r| match r.evaluate_1(&mut resolver) {
+                Ok(Concrete::Rational(r)) => Some(r),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let zero = Rational::from_integer(0.into());
+    let origin = vec![zero.clone(); n];
+    let offsets = eval(&origin)?;
+    let mut columns = Vec::with_capacity(n);
+    for j in 0..n {
+        let mut x = origin.clone();
+        x[j] = Rational::from_integer(1.into());
+        let col: Vec<Rational> = eval(&x)?
+            .into_iter()
+            .zip(offsets.iter())
+            .map(|(r, o)| r - o)
+            .collect();
+        columns.push(col);
+    }
+
+    // An affine function is exactly predicted by its offset and columns
+    // everywhere, so check a couple of arbitrary points.
+    for (num, den) in [(2, 3), (-5, 7)] {
+        let x: Vec<Rational> = (0..n)
+            .map(|j| Rational::new((num * (j as i64 + 1)).into(), den.into()))
+            .collect();
+        let actual = eval(&x)?;
+        for (i, a) in actual.iter().enumerate() {
+            let predicted = columns
+                .iter()
+                .zip(x.iter())
+                .fold(offsets[i].clone(), |acc, (col, xj)| acc + &col[i] * xj);
+            if &predicted != a {
+                return None;
+            }
+        }
     }
+
+    // Gaussian elimination on [A | -offsets].
+    let mut rows: Vec<Vec<Rational>> = (0..residuals.len())
+        .map(|i| {
+            columns
+                .iter()
+                .map(|col| col[i].clone())
+                .chain(std::iter::once(-offsets[i].clone()))
+                .collect()
+        })
+        .collect();
+    for c in 0..n {
+        let pivot = (c..rows.len()).find(|r| rows[*r][c] != zero)?;
+        rows.swap(c, pivot);
+        let pivot_row = rows[c].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r == c || row[c] == zero {
+                continue;
+            }
+            let f = &row[c] / &pivot_row[c];
+            for (v, p) in row[c..].iter_mut().zip(&pivot_row[c..]) {
+                *v -= &f * p;
+            }
+        }
+    }
+    // Leftover equations must already be satisfied.
+    if rows[n..].iter().any(|row| row[n] != zero) {
+        return None;
+    }
+
+    Some((0..n).map(|c| &rows[c][n] / &rows[c][c]).collect())
+}
+
+/// Returns the rank of the given matrix, using Gaussian elimination.
+fn matrix_rank(mut rows: Vec<Vec<f64>>) -> usize {
+    const EPSILON: f64 = 1e-6;
+    let cols = rows.first().map(|r| r.len()).unwrap_or(0);
+    let mut rank = 0;
+    for c in 0..cols {
+        let pivot = (rank..rows.len())
+            .filter(|r| rows[*r][c].abs() > EPSILON)
+            .max_by(|a, b| rows[*a][c].abs().total_cmp(&rows[*b][c].abs()));
+        let pivot = match pivot {
+            Some(p) => p,
+            None => continue,
+        };
+        rows.swap(rank, pivot);
+
+        let (top, rest) = rows.split_at_mut(rank + 1);
+        let pivot_row = &top[rank];
+        for row in rest.iter_mut() {
+            let f = row[c] / pivot_row[c];
+            for (v, p) in row[c..].iter_mut().zip(&pivot_row[c..]) {
+                *v -= f * p;
+            }
+        }
+        rank += 1;
+    }
+    rank
 }
 
 #[cfg(test)]
@@ -1249,6 +3477,61 @@ mod tests {
     use crate::{Axis, ConstraintMeta, DimensionDisplay, SerializedConstraint};
     use crate::{FeatureMeta, SerializedFeature};
 
+    fn pt(x: f32, y: f32) -> SerializedFeature {
+        SerializedFeature {
+            kind: "pt".to_string(),
+            x,
+            y,
+            ..SerializedFeature::default()
+        }
+    }
+
+    fn line(a: usize, b: usize) -> SerializedFeature {
+        SerializedFeature {
+            kind: "line".to_string(),
+            using_idx: vec![a, b],
+            ..SerializedFeature::default()
+        }
+    }
+
+    /// A 10x10 square (lines 4-7) as the boundary, with a triangle hole
+    /// (lines 11-13) between (2, 2), (6, 2) and (6, 5).
+    fn square_with_hole() -> SerializedDrawing {
+        SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 10.0),
+                pt(0.0, 10.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+                pt(2.0, 2.0),
+                pt(6.0, 2.0),
+                pt(6.0, 5.0),
+                line(8, 9),
+                line(9, 10),
+                line(10, 8),
+            ],
+            groups: vec![
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Boundary,
+                    name: "Boundary".into(),
+                    features_idx: vec![4, 5, 6, 7],
+                    ..crate::SerializedGroup::default()
+                },
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Hole,
+                    name: "Hole".into(),
+                    features_idx: vec![11, 12, 13],
+                    ..crate::SerializedGroup::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        }
+    }
+
     #[test]
     fn serialize_features() {
         let mut data = Data::default();
@@ -1309,6 +3592,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_stable() {
+        let mut data = Data::default();
+        let pts: Vec<FeatureKey> = [(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (9.0, 9.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                data.features
+                    .insert(Feature::Point(FeatureMeta::default(), x, y))
+            })
+            .collect();
+        data.features
+            .insert(Feature::LineSegment(FeatureMeta::default(), pts[0], pts[1]));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            pts[3],
+            9.0,
+            9.0,
+        ));
+        // Free up slots so later features reuse them.
+        data.delete_feature(pts[3]);
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), pts[1], pts[2]));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            pts[0],
+            0.0,
+            0.0,
+        ));
+
+        let to_ron = |d: &Data| ron::ser::to_string(&d.serialize()).unwrap();
+        let first = to_ron(&data);
+        assert_eq!(first, to_ron(&data));
+
+        let mut reloaded = Data::default();
+        reloaded.load(data.serialize()).unwrap();
+        assert_eq!(first.as_bytes(), to_ron(&reloaded).as_bytes());
+    }
+
     #[test]
     fn serialize_constraints() {
         let mut data = Data::default();
@@ -1404,6 +3733,10 @@ mod tests {
                         ..SerializedConstraint::default()
                     }
                 ],
+                version: SERIALIZED_VERSION,
+                properties: Some(DrawingProperties::default()),
+                // Five features and three constraints have taken ids 1 to 8.
+                next_id: 9,
                 ..SerializedDrawing::default()
             }
         );
@@ -1760,138 +4093,480 @@ mod tests {
     }
 
     #[test]
-    fn feature_also_deleted_from_group() {
-        let mut data = Data::default();
-        data.load(SerializedDrawing {
-            features: vec![SerializedFeature {
-                kind: "pt".to_string(),
-                using_idx: vec![],
-                ..SerializedFeature::default()
-            }],
-            groups: vec![group::SerializedGroup {
-                typ: group::GroupType::Hole,
-                name: "yeet".into(),
-                features_idx: vec![0],
-                ..group::SerializedGroup::default()
-            }],
-            ..SerializedDrawing::default()
-        })
-        .unwrap();
-
-        data.delete_feature(data.features_iter().map(|(fk, _f)| fk).nth(0).unwrap());
-
-        // Make sure that group no longer has any features
-        assert_eq!(
-            data.groups,
-            vec![Group {
-                typ: group::GroupType::Hole,
-                name: "yeet".into(),
-                features: vec![],
-                ..Group::default()
-            },],
-        );
-    }
-
-    #[test]
-    fn new_arc_constrains_midpoint() {
+    fn solve_line_lengths_ratio_edited() {
+        //   p0 ----- p1
+        // (0, 0)  (4, 0)
+        //   |
+        //   | d = ratio * d(p0, p1)
+        //   |
+        //  p2
         let mut data = Data::default();
         data.load(SerializedDrawing {
             features: vec![
                 SerializedFeature {
                     kind: "pt".to_string(),
                     using_idx: vec![],
-                    x: 0.0,
-                    y: 0.0,
                     ..SerializedFeature::default()
                 },
                 SerializedFeature {
                     kind: "pt".to_string(),
                     using_idx: vec![],
-                    x: 5.0,
-                    y: 0.0,
                     ..SerializedFeature::default()
                 },
-            ],
-            ..SerializedDrawing::default()
-        })
-        .unwrap();
-
-        // Simulate creating an Arc with the Arc tool
-        let (pt1, pt2) = (
-            data.features_iter().map(|(fk, _f)| fk).nth(0).unwrap(),
-            data.features_iter().map(|(fk, _f)| fk).nth(1).unwrap(),
-        );
-        let mut tools = crate::tools::Toolbar::default();
-        crate::Handler::default().handle(
-            &mut data,
-            &mut tools,
-            crate::handler::ToolResponse::NewArc(pt1, pt2),
-        );
-
-        // See if we now have a constraint that applies to the new midpoint,
-        // lerp'ing it to the midpoint of the line between
-        assert!(matches!(
-            data.constraints.iter().next().unwrap().1,
-            Constraint::PointLerpLine(_, _l_fk, mid_fk, amt)
-                if mid_fk == &data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap() &&
-                *amt == 0.5,
-        ));
-    }
-
-    #[test]
-    fn applying_horizontal_sets_line_length_cardinality_positive() {
-        let mut data = Data::default();
-        data.load(SerializedDrawing {
-            features: vec![
                 SerializedFeature {
                     kind: "pt".to_string(),
                     using_idx: vec![],
                     x: 0.0,
-                    y: 0.0,
+                    y: -5.0,
                     ..SerializedFeature::default()
                 },
                 SerializedFeature {
-                    kind: "pt".to_string(),
-                    using_idx: vec![],
-                    x: 5.0,
-                    y: 0.0,
+                    kind: "line".to_string(),
+                    using_idx: vec![0, 1],
                     ..SerializedFeature::default()
                 },
                 SerializedFeature {
                     kind: "line".to_string(),
-                    using_idx: vec![0, 1],
+                    using_idx: vec![0, 2],
                     ..SerializedFeature::default()
                 },
             ],
-            constraints: vec![SerializedConstraint {
-                kind: "length".to_string(),
-                feature_idx: vec![2],
-                amt: 5.0,
-                ..SerializedConstraint::default()
-            }],
-            ..SerializedDrawing::default()
-        })
-        .unwrap();
-
-        // Simulate creating a horizontal constraint
-        let line_fk = data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap();
-        let mut tools = crate::tools::Toolbar::default();
-        crate::Handler::default().handle(
-            &mut data,
-            &mut tools,
-            crate::handler::ToolResponse::NewLineCardinalConstraint(line_fk, true), // true = horizontal
+            constraints: vec![
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (0.0, 0.0),
+                    feature_idx: vec![0],
+                    ..SerializedConstraint::default()
+                },
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (4.0, 0.0),
+                    feature_idx: vec![1],
+                    ..SerializedConstraint::default()
+                },
+                SerializedConstraint {
+                    kind: "vertical".to_string(),
+                    feature_idx: vec![4],
+                    ..SerializedConstraint::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        let (l1, l2) = (
+            data.features_iter().map(|(fk, _f)| fk).nth(3).unwrap(),
+            data.features_iter().map(|(fk, _f)| fk).nth(4).unwrap(),
+        );
+        data.add_constraint(Constraint::LineLengthsEqual(
+            ConstraintMeta::default(),
+            l1,
+            l2,
+            Some(2.0),
+        ));
+        let point = data.features_iter().map(|(_fk, f)| f).nth(2).unwrap();
+        assert!(
+            matches!(point, Feature::Point(_, x, y) if x.abs() < 0.005 && (8.0 + y).abs() < 0.05 )
         );
 
-        // Make sure that line length constraint got updated with an axis
+        // Editing the ratio should re-solve so line 2 is half of line 1.
+        let ck = data
+            .constraints_iter()
+            .find(|(_ck, c)| matches!(c, Constraint::LineLengthsEqual(..)))
+            .map(|(ck, _c)| ck)
+            .unwrap();
+        if let Some(Constraint::LineLengthsEqual(_, _, _, ratio)) = data.constraint_mut(ck) {
+            *ratio = Some(0.5);
+        }
+        data.changed_in_ui();
+        let point = data.features_iter().map(|(_fk, f)| f).nth(2).unwrap();
+        assert!(
+            matches!(point, Feature::Point(_, x, y) if x.abs() < 0.005 && (2.0 + y).abs() < 0.05 )
+        );
+    }
+
+    #[test]
+    fn separate_dimension_labels() {
+        let mut data = Data::default();
+        let p = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let mut radius = |r: f32| {
+            let c = data
+                .features
+                .insert(Feature::Circle(FeatureMeta::default(), p, r));
+            data.add_constraint(Constraint::CircleRadius(
+                ConstraintMeta::default(),
+                c,
+                r,
+                DimensionDisplay::default(),
+            ));
+        };
+        radius(5.0);
+        radius(6.0);
+        let saved = data.serialize();
+
+        // Both labels start in the same spot, so one is nudged aside.
+        data.separate_dimension_labels();
+        assert_eq!(data.label_nudges.len(), 1);
+        let (ck, nudge) = data.label_nudges.iter().next().unwrap();
+        assert_ne!(*nudge, egui::Vec2::ZERO);
+
+        let c = data.constraints.get(*ck).unwrap();
+        let shown = data
+            .displayed_constraint(*ck, c)
+            .dimension_pos(&data)
+            .unwrap();
+        let want = c.dimension_pos(&data).unwrap() + *nudge;
+        assert!(shown.distance(want) < 0.01, "{:?} != {:?}", shown, want);
+        assert!(data.find_screen_constraint(want).is_some());
+
+        // Only the painted position moves, not the saved one.
+        assert_eq!(data.serialize(), saved);
+    }
+
+    #[test]
+    fn hover_threshold() {
+        let mut data = Data::default();
+        let base = data.hover_threshold();
+
+        // The threshold is in screen space, so zooming doesn't change it.
+        data.vp.zoom = 4.0;
+        assert_eq!(data.hover_threshold(), base);
+        data.vp.zoom = 0.25;
+        assert_eq!(data.hover_threshold(), base);
+
+        data.hover_sensitivity = 2.0;
+        assert_eq!(data.hover_threshold(), base * 2.0);
+    }
+
+    #[test]
+    fn duplicate_constraints_refused() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![pt(0.0, 0.0), pt(5.0, 1.0), line(0, 1)],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        let line = data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap();
+
+        data.add_constraint(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            line,
+            Axis::LeftRight,
+        ));
+        data.add_constraint(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            line,
+            Axis::LeftRight,
+        ));
+        assert_eq!(data.constraints_iter().count(), 1);
+
+        // A global angle on the same line would fight the cardinality constraint.
+        data.add_constraint(Constraint::LineAngle(ConstraintMeta::default(), line, 0.5));
+        assert_eq!(data.constraints_iter().count(), 1);
+        assert_eq!(data.constraints_by_feature(&line).len(), 1);
+    }
+
+    #[test]
+    fn feature_also_deleted_from_group() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![SerializedFeature {
+                kind: "pt".to_string(),
+                using_idx: vec![],
+                ..SerializedFeature::default()
+            }],
+            groups: vec![group::SerializedGroup {
+                typ: group::GroupType::Hole,
+                name: "yeet".into(),
+                features_idx: vec![0],
+                ..group::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        data.delete_feature(data.features_iter().map(|(fk, _f)| fk).nth(0).unwrap());
+
+        // Make sure that group no longer has any features
+        assert_eq!(
+            data.groups,
+            vec![Group {
+                typ: group::GroupType::Hole,
+                name: "yeet".into(),
+                features: vec![],
+                ..Group::default()
+            },],
+        );
+    }
+
+    #[test]
+    fn outline_polylines_square_with_hole() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 10.0),
+                pt(0.0, 10.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+                pt(3.0, 3.0),
+                pt(7.0, 3.0),
+                pt(7.0, 7.0),
+                pt(3.0, 7.0),
+                line(8, 9),
+                line(9, 10),
+                line(10, 11),
+                line(11, 8),
+            ],
+            groups: vec![
+                group::SerializedGroup {
+                    typ: group::GroupType::Boundary,
+                    name: "outside".into(),
+                    features_idx: vec![4, 5, 6, 7],
+                    ..group::SerializedGroup::default()
+                },
+                group::SerializedGroup {
+                    typ: group::GroupType::Hole,
+                    name: "hole".into(),
+                    features_idx: vec![12, 13, 14, 15],
+                    ..group::SerializedGroup::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        let outline = data.outline_polylines(0.1).unwrap();
+        assert_eq!(outline.boundaries.len(), 1);
+        assert_eq!(outline.holes.len(), 1);
+
+        let extent = |ring: &Vec<kurbo::Point>| {
+            ring.iter()
+                .fold(kurbo::Rect::from_points(ring[0], ring[0]), |r, p| {
+                    r.union_pt(*p)
+                })
+        };
+        for (ring, want) in [
+            (
+                &outline.boundaries[0],
+                kurbo::Rect::new(0.0, -10.0, 10.0, 0.0),
+            ),
+            (&outline.holes[0], kurbo::Rect::new(3.0, -7.0, 7.0, -3.0)),
+        ] {
+            // Four corners, with the first repeated to close the ring.
+            assert_eq!(ring.len(), 5, "{:?}", ring);
+            assert_eq!(ring.first(), ring.last());
+            assert_eq!(extent(ring), want);
+        }
+    }
+
+    #[test]
+    fn deleting_corner_breaks_group() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 10.0),
+                pt(0.0, 10.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+                pt(20.0, 20.0),
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Ye".into(),
+                features_idx: vec![4, 5, 6, 7],
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        let keys: Vec<FeatureKey> = data.features_iter().map(|(fk, _f)| fk).collect();
+
+        // Unrelated geometry doesn't affect the group.
+        assert!(data.delete_feature(keys[8]));
+        assert_eq!(data.broken_groups, Vec::<usize>::new());
+
+        // Deleting a corner also deletes both lines using it.
+        let mut broken = data.clone();
+        assert!(broken.delete_feature(keys[0]));
+        assert_eq!(broken.broken_groups, vec![0]);
+        assert_eq!(broken.groups[0].features.len(), 2);
+
+        // Deleting the whole loop removes the group's geometry, which is not a break.
+        for k in &keys[4..8] {
+            data.select_feature(*k, true);
+        }
+        data.selection_delete();
+        assert_eq!(data.broken_groups, Vec::<usize>::new());
+        assert!(data.groups[0].features.is_empty());
+    }
+
+    #[test]
+    fn auto_dimension_triangle() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(4.0, 6.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 0),
+            ],
+            constraints: vec![SerializedConstraint {
+                kind: "fixed".to_string(),
+                at: (0.0, 0.0),
+                feature_idx: vec![0],
+                ..SerializedConstraint::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        // Two free points.
+        assert_eq!(data.degrees_of_freedom(), 4);
+
+        // Lengths alone leave the triangle free to spin about the origin.
+        let mut lines_only = data.clone();
+        lines_only.select_type(&Feature::LineSegment(
+            FeatureMeta::default(),
+            FeatureKey::default(),
+            FeatureKey::default(),
+        ));
+        lines_only.auto_dimension_selection();
+        assert_eq!(lines_only.constraints_iter().count(), 4);
+        assert_eq!(lines_only.degrees_of_freedom(), 1);
+
+        data.select_all();
+        data.auto_dimension_selection();
+        assert_eq!(data.degrees_of_freedom(), 0);
+        assert_eq!(data.last_solve_error, None);
+        // Geometry is dimensioned where it was.
+        let points: Vec<_> = data
+            .features_iter()
+            .filter_map(|(_, f)| match f {
+                Feature::Point(_, x, y) => Some((*x, *y)),
+                _ => None,
+            })
+            .collect();
+        for ((x, y), (wx, wy)) in points
+            .into_iter()
+            .zip([(0.0, 0.0), (10.0, 0.0), (4.0, 6.0)])
+        {
+            assert!(
+                (x - wx).abs() < 1e-3 && (y - wy).abs() < 1e-3,
+                "{} {}",
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn new_arc_constrains_midpoint() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                SerializedFeature {
+                    kind: "pt".to_string(),
+                    using_idx: vec![],
+                    x: 0.0,
+                    y: 0.0,
+                    ..SerializedFeature::default()
+                },
+                SerializedFeature {
+                    kind: "pt".to_string(),
+                    using_idx: vec![],
+                    x: 5.0,
+                    y: 0.0,
+                    ..SerializedFeature::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        // Simulate creating an Arc with the Arc tool
+        let (pt1, pt2) = (
+            data.features_iter().map(|(fk, _f)| fk).nth(0).unwrap(),
+            data.features_iter().map(|(fk, _f)| fk).nth(1).unwrap(),
+        );
+        let mut tools = crate::tools::Toolbar::default();
+        crate::Handler::default().handle(
+            &mut data,
+            &mut tools,
+            crate::handler::ToolResponse::NewArc(pt1, pt2),
+        );
+
+        // See if we now have a constraint that applies to the new midpoint,
+        // lerp'ing it to the midpoint of the line between
         assert!(matches!(
             data.constraints.iter().next().unwrap().1,
-            Constraint::LineLength(_, c_fk, _amt, Some((Axis::LeftRight, false)), ..)
-                if c_fk == &line_fk,
+            Constraint::PointLerpLine(_, _l_fk, mid_fk, amt)
+                if mid_fk == &data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap() &&
+                *amt == 0.5,
         ));
     }
 
     #[test]
-    fn applying_horizontal_sets_line_length_cardinality_negative() {
+    fn new_point_snaps_to_intersection() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 5.0),
+                pt(10.0, 5.0),
+                pt(4.0, 0.0),
+                pt(4.0, 10.0),
+                line(0, 1),
+                line(2, 3),
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        // Click just off the crossing.
+        let mut tools = crate::tools::Toolbar::default();
+        crate::Handler::default().handle(
+            &mut data,
+            &mut tools,
+            crate::handler::ToolResponse::NewPoint(egui::Pos2 { x: 4.5, y: 5.5 }),
+        );
+
+        let (p_fk, p) = data.features_iter().last().unwrap();
+        assert!(matches!(p, Feature::Point(_, x, y) if *x == 4.0 && *y == 5.0));
+        let lerps: Vec<_> = data
+            .constraints_iter()
+            .filter_map(|(_, c)| match c {
+                Constraint::PointLerpLine(_, _, fk, amt) if fk == &p_fk => Some(*amt),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lerps.len(), 2);
+        assert!(lerps.contains(&0.4) && lerps.contains(&0.5), "{:?}", lerps);
+
+        // Nothing to snap to far away from the crossing.
+        crate::Handler::default().handle(
+            &mut data,
+            &mut tools,
+            crate::handler::ToolResponse::NewPoint(egui::Pos2 { x: 30.0, y: 30.0 }),
+        );
+        assert!(matches!(
+            data.features_iter().last().unwrap().1,
+            Feature::Point(_, x, y) if *x == 30.0 && *y == 30.0
+        ));
+        assert_eq!(data.constraints_iter().count(), 2);
+    }
+
+    #[test]
+    fn applying_horizontal_sets_line_length_cardinality_positive() {
         let mut data = Data::default();
         data.load(SerializedDrawing {
             features: vec![
@@ -1911,7 +4586,7 @@ mod tests {
                 },
                 SerializedFeature {
                     kind: "line".to_string(),
-                    using_idx: vec![1, 0],
+                    using_idx: vec![0, 1],
                     ..SerializedFeature::default()
                 },
             ],
@@ -1937,13 +4612,13 @@ mod tests {
         // Make sure that line length constraint got updated with an axis
         assert!(matches!(
             data.constraints.iter().next().unwrap().1,
-            Constraint::LineLength(_, c_fk, _amt, Some((Axis::LeftRight, true)), ..)
+            Constraint::LineLength(_, c_fk, _amt, Some((Axis::LeftRight, false)), ..)
                 if c_fk == &line_fk,
         ));
     }
 
     #[test]
-    fn applying_line_length_to_horizontal_sets_cardinality_positive() {
+    fn applying_horizontal_sets_line_length_cardinality_negative() {
         let mut data = Data::default();
         data.load(SerializedDrawing {
             features: vec![
@@ -1963,39 +4638,39 @@ mod tests {
                 },
                 SerializedFeature {
                     kind: "line".to_string(),
-                    using_idx: vec![0, 1],
+                    using_idx: vec![1, 0],
                     ..SerializedFeature::default()
                 },
             ],
             constraints: vec![SerializedConstraint {
-                kind: "horizontal".to_string(),
+                kind: "length".to_string(),
                 feature_idx: vec![2],
+                amt: 5.0,
                 ..SerializedConstraint::default()
             }],
             ..SerializedDrawing::default()
         })
         .unwrap();
 
-        // Simulate creating a line length constraint
+        // Simulate creating a horizontal constraint
         let line_fk = data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap();
         let mut tools = crate::tools::Toolbar::default();
         crate::Handler::default().handle(
             &mut data,
             &mut tools,
-            crate::handler::ToolResponse::NewLineLengthConstraint(line_fk),
+            crate::handler::ToolResponse::NewLineCardinalConstraint(line_fk, true), // true = horizontal
         );
 
-        // Make sure that the only constraint is the line length constraint we want
-        assert!(data.constraints.iter().len() == 1);
+        // Make sure that line length constraint got updated with an axis
         assert!(matches!(
             data.constraints.iter().next().unwrap().1,
-            Constraint::LineLength(_, c_fk, amt, Some((Axis::LeftRight, false)), ..)
-                if c_fk == &line_fk && *amt == 5.0,
+            Constraint::LineLength(_, c_fk, _amt, Some((Axis::LeftRight, true)), ..)
+                if c_fk == &line_fk,
         ));
     }
 
     #[test]
-    fn applying_line_length_to_horizontal_sets_cardinality_negative() {
+    fn applying_line_length_to_horizontal_sets_cardinality_positive() {
         let mut data = Data::default();
         data.load(SerializedDrawing {
             features: vec![
@@ -2009,7 +4684,59 @@ mod tests {
                 SerializedFeature {
                     kind: "pt".to_string(),
                     using_idx: vec![],
-                    x: -5.0,
+                    x: 5.0,
+                    y: 0.0,
+                    ..SerializedFeature::default()
+                },
+                SerializedFeature {
+                    kind: "line".to_string(),
+                    using_idx: vec![0, 1],
+                    ..SerializedFeature::default()
+                },
+            ],
+            constraints: vec![SerializedConstraint {
+                kind: "horizontal".to_string(),
+                feature_idx: vec![2],
+                ..SerializedConstraint::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        // Simulate creating a line length constraint
+        let line_fk = data.features_iter().map(|(fk, _f)| fk).nth(2).unwrap();
+        let mut tools = crate::tools::Toolbar::default();
+        crate::Handler::default().handle(
+            &mut data,
+            &mut tools,
+            crate::handler::ToolResponse::NewLineLengthConstraint(line_fk),
+        );
+
+        // Make sure that the only constraint is the line length constraint we want
+        assert!(data.constraints.iter().len() == 1);
+        assert!(matches!(
+            data.constraints.iter().next().unwrap().1,
+            Constraint::LineLength(_, c_fk, amt, Some((Axis::LeftRight, false)), ..)
+                if c_fk == &line_fk && *amt == 5.0,
+        ));
+    }
+
+    #[test]
+    fn applying_line_length_to_horizontal_sets_cardinality_negative() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                SerializedFeature {
+                    kind: "pt".to_string(),
+                    using_idx: vec![],
+                    x: 0.0,
+                    y: 0.0,
+                    ..SerializedFeature::default()
+                },
+                SerializedFeature {
+                    kind: "pt".to_string(),
+                    using_idx: vec![],
+                    x: -5.0,
                     y: 0.0,
                     ..SerializedFeature::default()
                 },
@@ -2171,6 +4898,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn compute_path_group_skips_construction() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(5.0, 0.0),
+                pt(5.0, 5.0),
+                SerializedFeature {
+                    kind: "line".to_string(),
+                    using_idx: vec![0, 1],
+                    ..SerializedFeature::default()
+                },
+                SerializedFeature {
+                    kind: "line".to_string(),
+                    using_idx: vec![1, 2],
+                    ..SerializedFeature::default()
+                },
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Ye".into(),
+                features_idx: vec![3, 4],
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        assert_eq!(data.groups[0].compute_path(&data).len(), 1);
+        assert_eq!(data.groups[0].stale_features(&data), vec![]);
+
+        // Flip the second line to construction after it was added to the group.
+        let l2 = data.groups[0].features[1];
+        if let Some(Feature::LineSegment(meta, ..)) = data.features.get_mut(l2) {
+            *meta = FeatureMeta::default_construction();
+        }
+
+        assert_eq!(data.groups[0].stale_features(&data), vec![l2]);
+        assert_eq!(
+            data.groups[0].compute_path(&data),
+            vec![kurbo::BezPath::from_vec(vec![
+                kurbo::PathEl::MoveTo(kurbo::Point { x: 0.0, y: 0.0 }),
+                kurbo::PathEl::LineTo(kurbo::Point { x: 5.0, y: 0.0 }),
+            ])]
+        );
+    }
+
     #[test]
     fn compute_path_group_line_arc_circle() {
         let mut data = Data::default();
@@ -2398,6 +5172,76 @@ mod tests {
   convexity = 10
 );"
         );
+
+        data.props.name = "My Part!".into();
+        data.props.openscad_module = Some(());
+        let scad = data.serialize_openscad(5.0).unwrap();
+        assert!(scad.starts_with("module my_part() {\n  polygon(\n    points = ["));
+        assert!(scad.ends_with("\n  );\n}\n\nmy_part();\n"), "{}", scad);
+    }
+
+    #[test]
+    fn openscad_identifier() {
+        assert_eq!(
+            super::openscad_identifier("Bracket v2 (final)"),
+            "bracket_v2_final"
+        );
+        assert_eq!(super::openscad_identifier("  3d-print "), "_3d_print");
+        assert_eq!(super::openscad_identifier("?!"), "part");
+        assert_eq!(super::openscad_identifier(""), "part");
+    }
+
+    #[test]
+    fn fill_triangles() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 10.0),
+                pt(0.0, 10.0),
+                pt(4.0, 4.0),
+                pt(6.0, 4.0),
+                pt(6.0, 6.0),
+                pt(4.0, 6.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+                line(4, 5),
+                line(5, 6),
+                line(6, 7),
+                line(7, 4),
+            ],
+            groups: vec![
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Boundary,
+                    name: "Square".into(),
+                    features_idx: vec![8, 9, 10, 11],
+                    ..crate::SerializedGroup::default()
+                },
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Hole,
+                    name: "Cutout".into(),
+                    features_idx: vec![12, 13, 14, 15],
+                    ..crate::SerializedGroup::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        let tris = data.fill_triangles(0.1).unwrap();
+        let area: f32 = tris
+            .iter()
+            .map(|[a, b, c]| ((*b - *a).x * (*c - *a).y - (*b - *a).y * (*c - *a).x).abs() / 2.0)
+            .sum();
+        assert!((area - 96.0).abs() < 0.001, "{}", area);
+        // Nothing is shaded inside the hole.
+        assert!(tris.iter().all(|t| {
+            let c = (t[0].to_vec2() + t[1].to_vec2() + t[2].to_vec2()) / 3.0;
+            !(c.x > 4.0 && c.x < 6.0 && c.y > 4.0 && c.y < 6.0)
+        }));
     }
 
     #[test]
@@ -2440,113 +5284,2642 @@ mod tests {
     }
 
     #[test]
-    fn as_solid_error_results() {
-        let features = vec![
-            SerializedFeature {
-                kind: "pt".to_string(),
-                using_idx: vec![],
-                x: 0.0,
-                y: 0.0,
-                ..SerializedFeature::default()
-            },
-            SerializedFeature {
-                kind: "pt".to_string(),
-                using_idx: vec![],
-                x: 25.0,
-                y: 0.0,
-                ..SerializedFeature::default()
-            },
-            SerializedFeature {
-                kind: "circle".to_string(),
-                using_idx: vec![0],
-                r: 50.0,
-                ..SerializedFeature::default()
-            },
-            SerializedFeature {
-                kind: "circle".to_string(),
-                using_idx: vec![1],
-                r: 50.0,
-                ..SerializedFeature::default()
-            },
-            SerializedFeature {
-                kind: "circle".to_string(),
-                using_idx: vec![0],
-                r: 26.0,
-                ..SerializedFeature::default()
-            },
-        ];
+    fn flatten_to_idxs_group_tolerance() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                SerializedFeature {
+                    kind: "circle".to_string(),
+                    using_idx: vec![0],
+                    r: 10.0,
+                    ..SerializedFeature::default()
+                },
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Hole,
+                name: "Ye".into(),
+                features_idx: vec![1],
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
 
-        {
-            let mut data = Data::default();
-            data.load(SerializedDrawing {
-                features: features.clone(),
-                groups: vec![crate::SerializedGroup {
-                    typ: crate::GroupType::Hole,
-                    name: "Not boundary".into(),
-                    features_idx: vec![2],
-                    ..crate::SerializedGroup::default()
-                }],
-                ..SerializedDrawing::default()
-            })
-            .unwrap();
+        let (coarse, _, _) = data.flatten_to_idxs(1.0).unwrap();
+        data.groups[0].flatten_tolerance = Some(0.01);
+        let (fine, _, idx_inner) = data.flatten_to_idxs(1.0).unwrap();
+        assert!(
+            fine.len() > coarse.len(),
+            "{} <= {}",
+            fine.len(),
+            coarse.len()
+        );
+        assert_eq!(idx_inner[0].len(), fine.len() + 1);
+    }
 
-            assert_eq!(data.as_solid(), Err(ExportErr::NoBoundaryGroup));
-        }
+    #[test]
+    fn flatten_to_idxs_kerf() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 10.0),
+                pt(0.0, 10.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Ye".into(),
+                features_idx: vec![4, 5, 6, 7],
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        data.props.kerf = Some(0.2);
 
-        {
-            let mut data = Data::default();
-            data.load(SerializedDrawing {
-                features: features.clone(),
-                groups: vec![
-                    crate::SerializedGroup {
-                        typ: crate::GroupType::Boundary,
-                        name: "Boundary".into(),
-                        features_idx: vec![2],
-                        ..crate::SerializedGroup::default()
-                    },
-                    crate::SerializedGroup {
-                        typ: crate::GroupType::Boundary,
-                        name: "Boundary 2".into(),
-                        features_idx: vec![3],
-                        ..crate::SerializedGroup::default()
-                    },
-                ],
-                ..SerializedDrawing::default()
-            })
-            .unwrap();
+        let (points, idx_outer, _) = data.flatten_to_idxs(0.1).unwrap();
+        assert_eq!(idx_outer[0].len(), 5);
+        for p in points {
+            assert!((p.x.abs() - 0.1).abs() < 1e-6 || (p.x - 10.1).abs() < 1e-6);
+            assert!((p.y.abs() - 0.1).abs() < 1e-6 || (p.y + 10.1).abs() < 1e-6);
+        }
 
-            assert_eq!(data.as_solid(), Err(ExportErr::MultiBoundaryGroup));
+        // Kerf is only for export, so the shading follows the drawn square.
+        for p in data.fill_triangles(0.1).unwrap().into_iter().flatten() {
+            assert!(p.x.abs() < 1e-4 || (p.x - 10.0).abs() < 1e-4, "{:?}", p);
+            assert!(p.y.abs() < 1e-4 || (p.y - 10.0).abs() < 1e-4, "{:?}", p);
         }
+    }
 
-        {
-            let mut data = Data::default();
-            data.load(SerializedDrawing {
-                features,
-                groups: vec![
-                    crate::SerializedGroup {
-                        typ: crate::GroupType::Boundary,
-                        name: "Boundary".into(),
-                        features_idx: vec![2],
-                        ..crate::SerializedGroup::default()
-                    },
-                    crate::SerializedGroup {
-                        typ: crate::GroupType::Hole,
-                        name: "cutout 1".into(),
-                        features_idx: vec![3],
-                        ..crate::SerializedGroup::default()
-                    },
-                    crate::SerializedGroup {
-                        typ: crate::GroupType::Hole,
-                        name: "cutout 2".into(),
-                        features_idx: vec![4],
-                        ..crate::SerializedGroup::default()
-                    },
-                ],
-                ..SerializedDrawing::default()
-            })
-            .unwrap();
+    #[test]
+    fn total_cut_length() {
+        let mut data = Data::default();
+        data.load(square_with_hole()).unwrap();
 
-            assert_eq!(data.as_solid(), Err(ExportErr::IntersectingGroups(0, 1)));
-        }
+        // A 10mm square around a 3-4-5 triangle.
+        let length = data.total_cut_length(0.1).unwrap();
+        assert!((length - 52.0).abs() < 1e-4, "{}", length);
+
+        data.groups.clear();
+        assert_eq!(data.total_cut_length(0.1), Ok(0.0));
+    }
+
+    #[test]
+    fn flatten_to_idxs_winding() {
+        let mut data = Data::default();
+        // Reverse the triangle.
+        let mut drawing = square_with_hole();
+        drawing.features.swap(9, 10);
+        data.load(drawing).unwrap();
+
+        let areas = |data: &Data| {
+            let (points, idx_outer, idx_inner) = data.flatten_to_idxs(0.1).unwrap();
+            let area = |idx: &Vec<usize>| {
+                let ring: Vec<_> = idx.iter().map(|i| points[*i]).collect();
+                crate::l::polygon_signed_area(&ring)
+            };
+            (area(&idx_outer[0]), area(&idx_inner[0]))
+        };
+
+        // As drawn, the boundary is clockwise and the hole counter-clockwise.
+        let (outer, inner) = areas(&data);
+        assert!(outer < 0.0 && inner > 0.0, "{} {}", outer, inner);
+
+        data.props.normalize_winding = Some(());
+        let (outer, inner) = areas(&data);
+        assert!((outer - 100.0).abs() < 1e-6, "{}", outer);
+        assert!((inner + 6.0).abs() < 1e-6, "{}", inner);
+    }
+
+    #[test]
+    fn engrave_dxf_layer() {
+        let mut data = Data::default();
+        let mut drawing = square_with_hole();
+        drawing.groups[1].typ = crate::GroupType::Engrave;
+        data.load(drawing).unwrap();
+
+        let dxf = data.serialize_dxf(0.1).unwrap();
+        assert_eq!(dxf.matches("\nLINE\n8\n0\n").count(), 4);
+        assert_eq!(
+            dxf.matches(&format!("\nLINE\n8\n{}\n", DXF_ENGRAVE_LAYER))
+                .count(),
+            3
+        );
+
+        // Engraved geometry is not part of the cut outline.
+        let (_, idx_outer, idx_inner) = data.flatten_to_idxs(0.1).unwrap();
+        assert_eq!((idx_outer.len(), idx_inner.len()), (1, 0));
+    }
+
+    #[test]
+    fn dxf_feature_layers() {
+        let mut drawing = square_with_hole();
+        drawing.groups[1].typ = crate::GroupType::Engrave;
+        for (i, f) in drawing.features.iter_mut().enumerate() {
+            f.meta.layer = match i {
+                4..=7 => Some("cut".to_string()),
+                11..=13 => Some("mark".to_string()),
+                _ => None,
+            };
+        }
+        let mut data = Data::default();
+        data.load(drawing).unwrap();
+
+        let dxf = data.serialize_dxf(0.1).unwrap();
+        assert!(
+            dxf.contains("0\nTABLE\n2\nLAYER\n70\n2\n0\nLAYER\n2\ncut\n"),
+            "{}",
+            dxf
+        );
+        assert_eq!(dxf.matches("\n0\nLAYER\n2\n").count(), 2);
+        assert_eq!(dxf.matches("\nLAYER\n2\nmark\n").count(), 1);
+        assert_eq!(dxf.matches("\nLINE\n8\ncut\n").count(), 4);
+        assert_eq!(dxf.matches("\nLINE\n8\nmark\n").count(), 3);
+        assert_eq!(dxf.matches("\nLINE\n8\n0\n").count(), 0);
+    }
+
+    /// A 10x20 triangle as the boundary, with a smaller one engraved inside.
+    fn svg_drawing() -> Data {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(10.0, 0.0),
+                pt(10.0, 20.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 0),
+                pt(6.0, 1.0),
+                pt(9.0, 1.0),
+                pt(9.0, 10.0),
+                line(6, 7),
+                line(7, 8),
+                line(8, 6),
+            ],
+            groups: vec![
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Boundary,
+                    name: "Boundary".into(),
+                    features_idx: vec![3, 4, 5],
+                    ..crate::SerializedGroup::default()
+                },
+                crate::SerializedGroup {
+                    typ: crate::GroupType::Engrave,
+                    name: "Engrave".into(),
+                    features_idx: vec![9, 10, 11],
+                    ..crate::SerializedGroup::default()
+                },
+            ],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn svg_export() {
+        let svg = svg_drawing().serialize_svg(0.1).unwrap();
+        assert!(svg.starts_with("<svg "), "{}", svg);
+        // SVG has Y pointing down like the canvas, so coordinates match the drawing.
+        assert!(
+            svg.contains("width=\"10mm\" height=\"20mm\" viewBox=\"0 0 10 20\""),
+            "{}",
+            svg
+        );
+        assert_eq!(svg.matches("<path ").count(), 2);
+        assert_eq!(svg.matches("stroke=\"black\"").count(), 1);
+        assert_eq!(svg.matches("stroke=\"blue\"").count(), 1);
+        assert!(svg.contains("M6 1 L9 1 L9 10 "), "{}", svg);
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert!(matches!(
+            Data::default().serialize_svg(0.1),
+            Err(ExportErr::NoBoundaryGroup)
+        ));
+    }
+
+    #[test]
+    fn svg_stroke_width() {
+        let mut data = svg_drawing();
+        // The on-screen line width doesn't carry into exports.
+        data.stroke_width = 3.0;
+        let svg = data.serialize_svg(0.1).unwrap();
+        assert_eq!(svg.matches("stroke-width=\"0.1\"").count(), 2, "{}", svg);
+
+        data.props.svg_stroke_width = Some(0.25);
+        let svg = data.serialize_svg(0.1).unwrap();
+        assert_eq!(svg.matches("stroke-width=\"0.25\"").count(), 2, "{}", svg);
+    }
+
+    #[test]
+    fn bend_dxf_layer() {
+        let mut data = Data::default();
+        let pts: Vec<FeatureKey> = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                data.features
+                    .insert(Feature::Point(FeatureMeta::default(), x, y))
+            })
+            .collect();
+        let lines: Vec<FeatureKey> = (0..4)
+            .map(|i| {
+                data.features.insert(Feature::LineSegment(
+                    FeatureMeta::default(),
+                    pts[i],
+                    pts[(i + 1) % 4],
+                ))
+            })
+            .collect();
+        data.groups.push(Group {
+            typ: crate::GroupType::Boundary,
+            features: lines,
+            ..Group::default()
+        });
+
+        let (m1, m2) = (
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0)),
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), 5.0, 10.0)),
+        );
+        data.features.insert(Feature::LineSegment(
+            FeatureMeta {
+                bend_angle: Some(90.0),
+                ..FeatureMeta::default_construction()
+            },
+            m1,
+            m2,
+        ));
+
+        let dxf = data.serialize_dxf(0.1).unwrap();
+        assert_eq!(dxf.matches("\nLINE\n8\n0\n").count(), 4);
+        assert!(dxf.contains(&format!(
+            "\nLINE\n8\n{}\n10\n5\n20\n0\n11\n5\n21\n-10\n",
+            DXF_BEND_LAYER
+        )));
+    }
+
+    #[test]
+    fn export_preferences_round_trip() {
+        let mut data = Data::default();
+        data.props.export_format = Some(crate::headless::ExportFormat::Stl);
+        data.props.stl_ascii = Some(());
+        data.props.kerf = Some(0.15);
+
+        let serialized = data.serialize();
+        assert_eq!(serialized.properties, Some(data.props.clone()));
+
+        let text = ron::ser::to_string(&serialized).unwrap();
+        let mut loaded = Data::default();
+        loaded
+            .load(ron::de::from_str::<SerializedDrawing>(&text).unwrap())
+            .unwrap();
+        assert_eq!(loaded.props, data.props);
+
+        // Files saved before these preferences existed still load.
+        assert_eq!(
+            ron::de::from_str::<DrawingProperties>(
+                "(name: \"\", flatten_tolerance: 0.05, solver_stop_err: 0.0005, solve_continuously: None)"
+            ),
+            Ok(DrawingProperties::default()),
+        );
+    }
+
+    #[test]
+    fn load_versions() {
+        // Files from before versioning have no version field, and omit
+        // default properties.
+        let legacy = ron::ser::to_string(&SerializedDrawing {
+            features: vec![pt(1.0, 2.0)],
+            ..SerializedDrawing::default()
+        })
+        .unwrap()
+        .replace("version:0,", "");
+        assert!(!legacy.contains("version"), "{}", legacy);
+        let drawing = ron::de::from_str::<SerializedDrawing>(&legacy).unwrap();
+        assert_eq!(drawing.version, 0);
+        let mut data = Data::default();
+        data.load(drawing).unwrap();
+        assert_eq!(data.features.len(), 1);
+        assert_eq!(data.props, DrawingProperties::default());
+
+        let serialized = data.serialize();
+        assert_eq!(serialized.version, SERIALIZED_VERSION);
+        assert_eq!(serialized.properties, Some(DrawingProperties::default()));
+        let text = ron::ser::to_string(&serialized).unwrap();
+        let mut loaded = Data::default();
+        loaded
+            .load(ron::de::from_str::<SerializedDrawing>(&text).unwrap())
+            .unwrap();
+        assert_eq!(loaded.serialize(), serialized);
+
+        assert_eq!(
+            Data::default().load(SerializedDrawing {
+                version: SERIALIZED_VERSION + 1,
+                ..SerializedDrawing::default()
+            }),
+            Err(LoadErr::UnsupportedVersion(SERIALIZED_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn line_angle_degrees() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 10.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        assert_eq!(data.line_angle_degrees(p1), None);
+        let angle = data.line_angle_degrees(l1).unwrap();
+        assert!((angle - 45.0).abs() < 0.001, "{}", angle);
+    }
+
+    #[test]
+    fn locked_feature() {
+        let mut data = Data::default();
+        let p1 = data.features.insert(Feature::Point(
+            FeatureMeta {
+                locked: true,
+                ..FeatureMeta::default()
+            },
+            0.0,
+            0.0,
+        ));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        assert!(data.feature_locked(l1));
+        assert!(!data.feature_locked(p2));
+        data.move_point(p1, egui::Pos2::new(3.0, 3.0));
+        assert_eq!(
+            data.features.get(p1),
+            Some(&Feature::Point(
+                FeatureMeta {
+                    locked: true,
+                    ..FeatureMeta::default()
+                },
+                0.0,
+                0.0
+            ))
+        );
+
+        // Constraints still apply.
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 1.0, 2.0));
+        assert_eq!(
+            data.get_line_points(l1).unwrap().0,
+            egui::Pos2::new(1.0, 2.0)
+        );
+
+        // The lock survives a save.
+        let mut reloaded = Data::default();
+        reloaded.load(data.serialize()).unwrap();
+        assert_eq!(
+            reloaded
+                .features_iter()
+                .filter(|(k, _)| reloaded.feature_locked(*k))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn reference_geometry_stays_put() {
+        let mut data = Data::default();
+        let mut point = |x: f32, y: f32| {
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), x, y))
+        };
+        let (p1, p2, p3, p4) = (
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(0.0, 5.0),
+            point(4.0, 5.0),
+        );
+        let reference = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let free = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p3, p4));
+
+        for fk in [p1, p2, reference] {
+            data.select_feature(fk, true);
+        }
+        data.selection_to_construction_locked();
+        data.add_constraint(Constraint::LineLengthsEqual(
+            ConstraintMeta::default(),
+            reference,
+            free,
+            None,
+        ));
+
+        // Only the free line can give.
+        assert_eq!(data.last_solve_error, None);
+        assert_eq!(
+            data.get_line_points(reference),
+            Some((egui::Pos2::new(0.0, 0.0), egui::Pos2::new(10.0, 0.0)))
+        );
+        let (a, b) = data.get_line_points(free).unwrap();
+        assert!((a.distance(b) - 10.0).abs() < 0.01, "{:?}", (a, b));
+    }
+
+    #[test]
+    fn circle_arc_conversion() {
+        let mut data = Data::default();
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let circle = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), center, 10.0));
+        data.add_constraint(Constraint::CircleRadius(
+            ConstraintMeta::default(),
+            circle,
+            10.0,
+            DimensionDisplay::default(),
+        ));
+
+        assert!(!data.arc_to_circle(circle));
+        assert!(data.circle_to_arc(circle, 0.0, 270.0));
+        let arc = data.get_arc(circle).unwrap();
+        assert!(arc.start.distance(egui::Pos2::new(10.0, 0.0)) < 0.001);
+        assert!(arc.end.distance(egui::Pos2::new(0.0, -10.0)) < 0.001);
+        assert!((arc.sweep().to_degrees() - 270.0).abs() < 0.01);
+        // The radius constraint can't apply to an arc.
+        assert_eq!(data.constraints_iter().count(), 0);
+        assert_eq!(data.features.len(), 4);
+
+        assert!(!data.circle_to_arc(circle, 0.0, 90.0));
+        assert!(data.arc_to_circle(circle));
+        assert_eq!(
+            data.features.get(circle),
+            Some(&Feature::Circle(FeatureMeta::default(), center, 10.0))
+        );
+        // The arc's ends went with it.
+        assert_eq!(data.features.len(), 2);
+    }
+
+    #[test]
+    fn arc_metrics() {
+        let mut data = Data::default();
+        let start = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let end = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), -10.0, 0.0));
+        let arc = data
+            .features
+            .insert(Feature::Arc(FeatureMeta::default(), start, center, end));
+
+        assert_eq!(data.arc_metrics(start), None);
+        let m = data.arc_metrics(arc).unwrap();
+        assert!(
+            (m.length - 10.0 * std::f32::consts::PI).abs() < 0.001,
+            "{:?}",
+            m
+        );
+        assert!((m.chord - 20.0).abs() < 0.001, "{:?}", m);
+        assert!((m.sagitta - 10.0).abs() < 0.001, "{:?}", m);
+    }
+
+    #[test]
+    fn degenerate_arc() {
+        let mut data = Data::default();
+        let start = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let end = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let arc = data
+            .features
+            .insert(Feature::Arc(FeatureMeta::default(), start, center, end));
+        data.groups.push(crate::Group {
+            typ: crate::GroupType::Boundary,
+            features: vec![arc],
+            ..crate::Group::default()
+        });
+
+        assert!(!data.arc_is_valid(arc));
+        assert_eq!(data.groups[0].degenerate_arcs(&data), vec![arc]);
+        assert!(data.groups[0].compute_path(&data).is_empty());
+        assert_eq!(
+            data.serialize_dxf(0.1),
+            Err(ExportErr::DegenerateArc(Some(0)))
+        );
+        assert_eq!(data.as_solid(), Err(ExportErr::DegenerateArc(Some(0))));
+        data.select_feature(arc, true);
+        assert_eq!(
+            data.serialize_dxf_selection(0.1),
+            Err(ExportErr::DegenerateArc(None))
+        );
+        data.selection_clear();
+
+        // Either side of the center is a semicircle.
+        data.move_point(end, egui::Pos2::new(-10.0, 0.0));
+        assert!(data.arc_is_valid(arc));
+        data.move_point(end, egui::Pos2::new(0.0, 0.0));
+        assert!(!data.arc_is_valid(arc));
+        assert!(!data.arc_is_valid(start));
+    }
+
+    #[test]
+    fn stable_ids_round_trip() {
+        let mut data = Data::default();
+        // Leaves the slot behind with a newer version, so keys differ on reload.
+        let dummy = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        data.features.remove(dummy);
+
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 2.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 3.0, 5.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 1.0, 2.0));
+        let ck = data.constraints_iter().next().unwrap().0;
+
+        let id = data.features[l].meta().id;
+        let constraint_id = data.constraints.get(ck).unwrap().meta().id;
+        assert_ne!(id, 0);
+        assert_ne!(constraint_id, 0);
+        assert_eq!(data.feature_by_id(id), Some(l));
+        assert_eq!(data.constraint_by_id(constraint_id), Some(ck));
+
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        let loaded_p1 = loaded.feature_by_id(data.features[p1].meta().id).unwrap();
+        assert_ne!(loaded_p1, p1);
+        assert_eq!(loaded.get_point_pos(loaded_p1), data.get_point_pos(p1));
+        let loaded_l = loaded.feature_by_id(id).unwrap();
+        assert_eq!(loaded.get_line_points(loaded_l), data.get_line_points(l));
+        assert!(matches!(
+            loaded
+                .constraints
+                .get(loaded.constraint_by_id(constraint_id).unwrap()),
+            Some(Constraint::Fixed(..))
+        ));
+
+        // A copy of a feature gets an id of its own.
+        let copy = data.features.insert(data.features[p2].clone());
+        data.assign_ids();
+        assert_ne!(data.features[copy].meta().id, data.features[p2].meta().id);
+        assert_eq!(data.feature_by_id(data.features[p2].meta().id), Some(p2));
+
+        // Ids are given out on insert, and never again once deleted, even
+        // after a reload.
+        let p3 = data.add_feature(Feature::Point(FeatureMeta::default(), 9.0, 9.0));
+        let p3_id = data.features[p3].meta().id;
+        assert!(p3_id > data.features[copy].meta().id);
+        data.delete_feature(p3);
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        let p4 = loaded.add_feature(Feature::Point(FeatureMeta::default(), 9.0, 9.0));
+        assert!(loaded.features[p4].meta().id > p3_id);
+    }
+
+    #[test]
+    fn dxf_selection() {
+        let mut data = Data::default();
+        assert_eq!(
+            data.serialize_dxf_selection(0.05),
+            Err(ExportErr::NothingSelected)
+        );
+
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 10.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p3));
+        data.features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p3, p1));
+        data.select_feature(l1, true);
+        data.select_feature(l2, true);
+        // Points are skipped rather than exported.
+        data.select_feature(p1, true);
+
+        let dxf = data.serialize_dxf_selection(0.05).unwrap();
+        assert!(dxf.starts_with("0\nSECTION\n2\nHEADER\n"));
+        assert!(dxf.ends_with("EOF"));
+        // The unselected closing line isn't included.
+        assert_eq!(dxf.matches("\nLINE\n").count(), 2);
+    }
+
+    #[test]
+    fn export_2d_error_results() {
+        let circle = |c: usize| SerializedFeature {
+            kind: "circle".to_string(),
+            using_idx: vec![c],
+            r: 10.0,
+            ..SerializedFeature::default()
+        };
+        let group = |typ: crate::GroupType, f: usize| crate::SerializedGroup {
+            typ,
+            name: format!("{:?}", typ),
+            features_idx: vec![f],
+            ..crate::SerializedGroup::default()
+        };
+        let load = |groups: Vec<crate::SerializedGroup>| {
+            let mut data = Data::default();
+            data.load(SerializedDrawing {
+                features: vec![pt(0.0, 0.0), pt(50.0, 0.0), circle(0), circle(1)],
+                groups,
+                ..SerializedDrawing::default()
+            })
+            .unwrap();
+            data
+        };
+
+        let data = load(vec![group(crate::GroupType::Hole, 2)]);
+        assert_eq!(data.serialize_dxf(0.1), Err(ExportErr::NoBoundaryGroup));
+        assert_eq!(
+            data.serialize_openscad(0.1),
+            Err(ExportErr::NoBoundaryGroup)
+        );
+
+        let data = load(vec![
+            group(crate::GroupType::Boundary, 2),
+            group(crate::GroupType::Boundary, 3),
+        ]);
+        assert_eq!(data.serialize_dxf(0.1), Err(ExportErr::MultiBoundaryGroup));
+        assert_eq!(
+            data.serialize_openscad(0.1),
+            Err(ExportErr::MultiBoundaryGroup)
+        );
+
+        let data = load(vec![group(crate::GroupType::Boundary, 2)]);
+        assert!(data.serialize_dxf(0.1).is_ok());
+        assert!(data.serialize_openscad(0.1).is_ok());
+        assert_eq!(
+            ExportErr::OpenPath(2).to_string(),
+            "group 2 is not a closed path"
+        );
+    }
+
+    #[test]
+    fn as_solid_error_results() {
+        let features = vec![
+            SerializedFeature {
+                kind: "pt".to_string(),
+                using_idx: vec![],
+                x: 0.0,
+                y: 0.0,
+                ..SerializedFeature::default()
+            },
+            SerializedFeature {
+                kind: "pt".to_string(),
+                using_idx: vec![],
+                x: 25.0,
+                y: 0.0,
+                ..SerializedFeature::default()
+            },
+            SerializedFeature {
+                kind: "circle".to_string(),
+                using_idx: vec![0],
+                r: 50.0,
+                ..SerializedFeature::default()
+            },
+            SerializedFeature {
+                kind: "circle".to_string(),
+                using_idx: vec![1],
+                r: 50.0,
+                ..SerializedFeature::default()
+            },
+            SerializedFeature {
+                kind: "circle".to_string(),
+                using_idx: vec![0],
+                r: 26.0,
+                ..SerializedFeature::default()
+            },
+        ];
+
+        {
+            let mut data = Data::default();
+            data.load(SerializedDrawing {
+                features: features.clone(),
+                groups: vec![crate::SerializedGroup {
+                    typ: crate::GroupType::Hole,
+                    name: "Not boundary".into(),
+                    features_idx: vec![2],
+                    ..crate::SerializedGroup::default()
+                }],
+                ..SerializedDrawing::default()
+            })
+            .unwrap();
+
+            assert_eq!(data.as_solid(), Err(ExportErr::NoBoundaryGroup));
+        }
+
+        {
+            let mut data = Data::default();
+            data.load(SerializedDrawing {
+                features: features.clone(),
+                groups: vec![
+                    crate::SerializedGroup {
+                        typ: crate::GroupType::Boundary,
+                        name: "Boundary".into(),
+                        features_idx: vec![2],
+                        ..crate::SerializedGroup::default()
+                    },
+                    crate::SerializedGroup {
+                        typ: crate::GroupType::Boundary,
+                        name: "Boundary 2".into(),
+                        features_idx: vec![3],
+                        ..crate::SerializedGroup::default()
+                    },
+                ],
+                ..SerializedDrawing::default()
+            })
+            .unwrap();
+
+            assert_eq!(data.as_solid(), Err(ExportErr::MultiBoundaryGroup));
+        }
+
+        {
+            let mut data = Data::default();
+            data.load(SerializedDrawing {
+                features,
+                groups: vec![
+                    crate::SerializedGroup {
+                        typ: crate::GroupType::Boundary,
+                        name: "Boundary".into(),
+                        features_idx: vec![2],
+                        ..crate::SerializedGroup::default()
+                    },
+                    crate::SerializedGroup {
+                        typ: crate::GroupType::Hole,
+                        name: "cutout 1".into(),
+                        features_idx: vec![3],
+                        ..crate::SerializedGroup::default()
+                    },
+                    crate::SerializedGroup {
+                        typ: crate::GroupType::Hole,
+                        name: "cutout 2".into(),
+                        features_idx: vec![4],
+                        ..crate::SerializedGroup::default()
+                    },
+                ],
+                ..SerializedDrawing::default()
+            })
+            .unwrap();
+
+            assert_eq!(data.as_solid(), Err(ExportErr::IntersectingGroups(0, 1)));
+        }
+    }
+
+    #[test]
+    fn open_path_rejected() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(5.0, 0.0),
+                pt(0.0, 5.0),
+                pt(5.0, 5.0),
+                line(0, 1),
+                line(2, 3),
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Boundary".into(),
+                features_idx: vec![4, 5],
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        assert_eq!(data.flatten_to_idxs(0.1), Err(ExportErr::OpenPath(0)));
+        assert_eq!(data.serialize_openscad(0.1), Err(ExportErr::OpenPath(0)));
+        assert_eq!(data.serialize_dxf(0.1), Err(ExportErr::OpenPath(0)));
+        assert_eq!(data.as_solid(), Err(ExportErr::OpenPath(0)));
+    }
+
+    #[test]
+    fn solve_status() {
+        assert_eq!(SolveStatus::from_error(None), SolveStatus::Solved);
+        assert_eq!(
+            SolveStatus::from_error(Some(0.002)),
+            SolveStatus::Converging
+        );
+        assert_eq!(
+            SolveStatus::from_error(Some(0.999)),
+            SolveStatus::Converging
+        );
+        assert_eq!(
+            SolveStatus::from_error(Some(1.0)),
+            SolveStatus::Inconsistent
+        );
+        assert_eq!(
+            SolveStatus::from_error(Some(250.0)),
+            SolveStatus::Inconsistent
+        );
+
+        let mut data = Data::default();
+        assert_eq!(data.solve_status(), SolveStatus::Solved);
+        data.last_solve_error = Some(3.5);
+        assert_eq!(data.solve_status(), SolveStatus::Inconsistent);
+    }
+
+    #[test]
+    fn export_allowed() {
+        let mut data = Data::default();
+        assert!(data.export_allowed());
+
+        data.last_solve_error = Some(2.5);
+        assert!(!data.export_allowed());
+
+        data.export_inconsistent = true;
+        assert!(data.export_allowed());
+    }
+
+    #[test]
+    fn load_rejects_invalid_constraint() {
+        let drawing = |amt: f32| SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(5.0, 0.0),
+                SerializedFeature {
+                    kind: "line".to_string(),
+                    using_idx: vec![0, 1],
+                    ..SerializedFeature::default()
+                },
+            ],
+            constraints: vec![SerializedConstraint {
+                kind: "length".to_string(),
+                feature_idx: vec![2],
+                amt,
+                ..SerializedConstraint::default()
+            }],
+            ..SerializedDrawing::default()
+        };
+
+        let mut data = Data::default();
+        assert_eq!(
+            data.load(drawing(f32::NAN)),
+            Err(LoadErr::InvalidConstraint(0, "line length is not a number"))
+        );
+        assert_eq!(
+            data.load(drawing(-2.0)),
+            Err(LoadErr::InvalidConstraint(0, "line length is negative"))
+        );
+        assert_eq!(data.load(drawing(4.0)), Ok(()));
+        assert_eq!(data.last_solve_error, None);
+    }
+
+    #[test]
+    fn distribute_selection() {
+        let mut data = Data::default();
+        let points: Vec<FeatureKey> = [(0.0, 1.0), (7.0, 2.0), (1.5, 0.0), (20.0, 3.0), (9.0, 4.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                data.features
+                    .insert(Feature::Point(FeatureMeta::default(), x, y))
+            })
+            .collect();
+        for p in points.iter() {
+            data.select_feature(*p, true);
+        }
+
+        data.distribute_selection(true);
+        let mut xs: Vec<(f32, f32)> = points
+            .iter()
+            .map(|k| match data.features.get(*k) {
+                Some(Feature::Point(_, x, y)) => (*x, *y),
+                _ => unreachable!(),
+            })
+            .collect();
+        // Vertical positions are left alone.
+        assert_eq!(
+            xs.iter().map(|(_, y)| *y).collect::<Vec<_>>(),
+            vec![1.0, 2.0, 0.0, 3.0, 4.0]
+        );
+        xs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(
+            xs.iter().map(|(x, _)| *x).collect::<Vec<_>>(),
+            vec![0.0, 5.0, 10.0, 15.0, 20.0]
+        );
+    }
+
+    #[test]
+    fn resolver_from_geometry() {
+        let mut data = Data::default();
+        let p = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 1.0));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p, 2.5, -4.0));
+        assert_eq!(data.last_solve_error, None);
+
+        let c = data.constraints_iter().next().unwrap().1.clone();
+        let mut resolver = data.resolver_from_geometry();
+        for e in c.equations(&mut data) {
+            let residual = e
+                .as_residual()
+                .unwrap()
+                .evaluate_1(&mut resolver)
+                .unwrap()
+                .as_f64();
+            assert!(residual.abs() < 0.0001, "{}: {}", e, residual);
+        }
+
+        // Moving the point off its fixed co-ordinates shows up in the residuals.
+        if let Some(Feature::Point(_, x, _)) = data.features.get_mut(p) {
+            *x = 3.5;
+        }
+        let mut resolver = data.resolver_from_geometry();
+        let worst = c
+            .equations(&mut data)
+            .iter()
+            .map(|e| {
+                e.as_residual()
+                    .unwrap()
+                    .evaluate_1(&mut resolver)
+                    .unwrap()
+                    .as_f64()
+                    .abs()
+            })
+            .fold(0.0, f64::max);
+        assert!((worst - 1.0).abs() < 0.0001, "{}", worst);
+    }
+
+    #[test]
+    fn point_offset_drags() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 1.0));
+
+        data.add_constraint(Constraint::PointOffset(
+            ConstraintMeta::default(),
+            p1,
+            p2,
+            3.0,
+            -2.0,
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        data.move_point(p1, egui::Pos2 { x: 5.0, y: 5.0 });
+        assert_eq!(data.last_solve_error, None);
+        let (a, b) = (
+            data.features.get(p1).unwrap().start_point(&data),
+            data.features.get(p2).unwrap().start_point(&data),
+        );
+        assert!(
+            ((b - a) - egui::Vec2::new(3.0, -2.0)).length() < 0.001,
+            "{:?}",
+            b - a
+        );
+        // The solver shares the correction between both points, so the
+        // second point follows rather than the first snapping back.
+        assert!(a.x > 2.5 && a.y > 2.5, "{:?}", a);
+    }
+
+    #[test]
+    fn dimension_report() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 4.0, 0.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 4.0, 2.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p3));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        for (l, d) in [(l1, 5.0), (l2, 3.0)] {
+            data.add_constraint(Constraint::LineLength(
+                ConstraintMeta::default(),
+                l,
+                d,
+                None,
+                DimensionDisplay::default(),
+            ));
+        }
+
+        let report = data.dimension_report();
+        assert_eq!(report.len(), 2);
+        for (entry, (l, d)) in report.iter().zip([(l1, 5.0), (l2, 3.0)]) {
+            use slotmap::Key;
+            assert_eq!(entry.kind, "Length");
+            assert_eq!(entry.feature, format!("Line {:?}", l.data()));
+            assert_eq!(entry.value, d);
+            assert!((entry.measured - d).abs() < 0.01, "{:?}", entry);
+        }
+
+        let csv = data.dimension_report_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.starts_with("kind,feature,value,measured\n"));
+        assert!(csv.contains(",5.000,5.00"), "{}", csv);
+    }
+
+    #[test]
+    fn line_midpoint_fixed() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 4.0, 2.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        data.add_constraint(Constraint::LineMidpointFixed(
+            ConstraintMeta::default(),
+            l1,
+            5.0,
+            5.0,
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        let (a, b) = data.get_line_points(l1).unwrap();
+        assert!(
+            (a.lerp(b, 0.5) - egui::Pos2::new(5.0, 5.0)).length() < 0.001,
+            "{:?} {:?}",
+            a,
+            b
+        );
+        // Neither endpoint is pinned, so both share the move.
+        assert_ne!(a, egui::Pos2::new(0.0, 0.0));
+        assert_ne!(b, egui::Pos2::new(4.0, 2.0));
+    }
+
+    #[test]
+    fn point_along_arc() {
+        let mut data = Data::default();
+        let start = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let end = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 10.0));
+        let arc = data
+            .features
+            .insert(Feature::Arc(FeatureMeta::default(), start, center, end));
+        let p = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 5.0));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            start,
+            10.0,
+            0.0,
+        ));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            center,
+            0.0,
+            0.0,
+        ));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), end, 0.0, 10.0));
+        let end_fixed = data.constraints_by_feature(&end)[0];
+
+        let point_at = |data: &Data, x: f32, y: f32| match data.features.get(p) {
+            Some(Feature::Point(_, px, py)) => {
+                assert!(
+                    (px - x).abs() < 0.01 && (py - y).abs() < 0.01,
+                    "({}, {}) != ({}, {})",
+                    px,
+                    py,
+                    x,
+                    y
+                );
+            }
+            _ => unreachable!(),
+        };
+
+        // Halfway around a quarter circle.
+        data.add_constraint(Constraint::PointAlongArc(
+            ConstraintMeta::default(),
+            p,
+            arc,
+            0.5,
+        ));
+        let r = 10.0 * std::f32::consts::FRAC_1_SQRT_2;
+        point_at(&data, r, r);
+
+        // Widening the arc to 135 degrees moves the point to 67.5 degrees.
+        if let Some(Constraint::Fixed(_, _, x, _)) = data.constraint_mut(end_fixed) {
+            *x = -10.0;
+        }
+        data.changed_in_ui();
+        let a = 3.0 * std::f32::consts::PI / 8.0;
+        point_at(&data, 10.0 * a.cos(), 10.0 * a.sin());
+
+        // Survives a round trip.
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        assert!(loaded
+            .constraints_iter()
+            .any(|(_, c)| matches!(c, Constraint::PointAlongArc(_, _, _, t) if *t == 0.5)));
+    }
+
+    #[test]
+    fn point_along_arc_feeds_back_into_arc() {
+        let mut data = Data::default();
+        let start = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let end = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 10.0));
+        let arc = data
+            .features
+            .insert(Feature::Arc(FeatureMeta::default(), start, center, end));
+        let p = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 5.0));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            start,
+            10.0,
+            0.0,
+        ));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            center,
+            0.0,
+            0.0,
+        ));
+        // The end of the arc follows the point, so each pass moves the arc
+        // the point is placed along.
+        data.add_constraint(Constraint::PointOffset(
+            ConstraintMeta::default(),
+            p,
+            end,
+            -8.0,
+            4.0,
+        ));
+        data.add_constraint(Constraint::PointAlongArc(
+            ConstraintMeta::default(),
+            p,
+            arc,
+            0.5,
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        let sweep = data.get_arc(arc).unwrap().sweep();
+        let (px, py) = match data.features.get(p) {
+            Some(Feature::Point(_, x, y)) => (*x, *y),
+            _ => unreachable!(),
+        };
+        let want = sweep / 2.0;
+        assert!(
+            (px - 10.0 * want.cos()).abs() < 0.001 && (py - 10.0 * want.sin()).abs() < 0.001,
+            "({}, {}) not halfway along a sweep of {}",
+            px,
+            py,
+            sweep
+        );
+    }
+
+    #[test]
+    fn on_perpendicular_bisector() {
+        // Both a mostly-horizontal and a vertical pair, which lead the
+        // equation with different axes.
+        for (b, start) in [((10.0, 4.0), (1.0, 8.0)), ((0.0, 10.0), (7.0, 2.0))] {
+            let mut data = Data::default();
+            let a_fk = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+            let b_fk = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), b.0, b.1));
+            let p = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), start.0, start.1));
+            data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), a_fk, 0.0, 0.0));
+            data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), b_fk, b.0, b.1));
+
+            data.add_constraint(Constraint::OnPerpendicularBisector(
+                ConstraintMeta::default(),
+                p,
+                a_fk,
+                b_fk,
+            ));
+            assert_eq!(data.last_solve_error, None);
+            let (a, b, p) = (
+                data.get_point_pos(a_fk).unwrap(),
+                data.get_point_pos(b_fk).unwrap(),
+                data.get_point_pos(p).unwrap(),
+            );
+            assert!(
+                (p.distance(a) - p.distance(b)).abs() < 0.01,
+                "{:?} is not equidistant from {:?} & {:?}",
+                p,
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn point_offset_from_line() {
+        let mut data = Data::default();
+        let a_fk = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let b_fk = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let p = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 2.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), a_fk, b_fk));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), a_fk, 0.0, 0.0));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            b_fk,
+            10.0,
+            0.0,
+        ));
+        let b_fixed = data.constraints_by_feature(&b_fk)[0];
+        data.add_constraint(Constraint::PointOffsetFromLine(
+            ConstraintMeta::default(),
+            p,
+            l,
+            3.0,
+            true,
+        ));
+
+        // On the right of a -> b, so below it with Y pointing down.
+        let offset = |data: &Data| {
+            let (a, b) = data.get_line_points(l).unwrap();
+            let (ab, ap) = (b - a, data.get_point_pos(p).unwrap() - a);
+            (ab.x * ap.y - ab.y * ap.x) / ab.length()
+        };
+        assert_eq!(data.last_solve_error, None);
+        assert!((offset(&data) - 3.0).abs() < 0.01, "{}", offset(&data));
+
+        // The point follows the line as it's rotated, staying on its right.
+        if let Some(Constraint::Fixed(_, _, x, y)) = data.constraint_mut(b_fixed) {
+            (*x, *y) = (6.0, 8.0);
+        }
+        data.changed_in_ui();
+        assert_eq!(data.last_solve_error, None);
+        assert!((offset(&data) - 3.0).abs() < 0.01, "{}", offset(&data));
+
+        // Survives a round trip.
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        assert!(loaded.constraints_iter().any(|(_, c)| matches!(
+            c,
+            Constraint::PointOffsetFromLine(_, _, _, d, true) if *d == 3.0
+        )));
+    }
+
+    #[test]
+    fn line_tangent_two_circles() {
+        let mut data = Data::default();
+        let mut circle = |x: f32| {
+            let p = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), x, 0.0));
+            data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p, x, 0.0));
+            let c = data
+                .features
+                .insert(Feature::Circle(FeatureMeta::default(), p, 5.0));
+            data.add_constraint(Constraint::CircleRadius(
+                ConstraintMeta::default(),
+                c,
+                5.0,
+                DimensionDisplay::default(),
+            ));
+            (p, c)
+        };
+        let ((p1, c1), (p2, c2)) = (circle(0.0), circle(20.0));
+        let a = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), -2.0, 7.0));
+        let b = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 22.0, 6.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), a, b));
+        data.add_constraint(Constraint::LineTangentTwoCircles(
+            ConstraintMeta::default(),
+            l,
+            c1,
+            c2,
+            false,
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        // Both centers are a radius from the line, on the same side of it.
+        let offset = |data: &Data, p: FeatureKey| {
+            let (a, b) = data.get_line_points(l).unwrap();
+            let (ab, ap) = (b - a, data.get_point_pos(p).unwrap() - a);
+            (ab.x * ap.y - ab.y * ap.x) / ab.length()
+        };
+        let (d1, d2) = (offset(&data, p1), offset(&data, p2));
+        assert!((d1.abs() - 5.0).abs() < 0.01, "{}", d1);
+        assert!((d2 - d1).abs() < 0.01, "{} != {}", d2, d1);
+
+        // Survives a round trip.
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        assert!(loaded
+            .constraints_iter()
+            .any(|(_, c)| matches!(c, Constraint::LineTangentTwoCircles(_, _, _, _, false))));
+
+        // An internal tangent crosses between them.
+        let ck = data.constraints_by_feature(&l)[0];
+        if let Some(Constraint::LineTangentTwoCircles(.., internal)) = data.constraint_mut(ck) {
+            *internal = true;
+        }
+        data.changed_in_ui();
+        assert_eq!(data.last_solve_error, None);
+        let (d1, d2) = (offset(&data, p1), offset(&data, p2));
+        assert!((d1.abs() - 5.0).abs() < 0.01, "{}", d1);
+        assert!((d2 + d1).abs() < 0.01, "{} != -{}", d2, d1);
+    }
+
+    #[test]
+    fn three_lines_equal_angle() {
+        let mut data = Data::default();
+        let mut point = |x: f32, y: f32| {
+            data.features
+                .insert(Feature::Point(FeatureMeta::default(), x, y))
+        };
+        let (center, a, b, c) = (
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(-4.0, 8.0),
+            point(-5.0, -7.0),
+        );
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta::default(),
+            center,
+            0.0,
+            0.0,
+        ));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), a, 10.0, 0.0));
+        let mut lines = Vec::new();
+        for p in [a, b, c] {
+            let l = data
+                .features
+                .insert(Feature::LineSegment(FeatureMeta::default(), center, p));
+            data.add_constraint(Constraint::LineLength(
+                ConstraintMeta::default(),
+                l,
+                10.0,
+                None,
+                DimensionDisplay::default(),
+            ));
+            lines.push(l);
+        }
+        data.add_constraint(Constraint::ThreeLinesEqualAngle(
+            ConstraintMeta::default(),
+            lines[0],
+            lines[1],
+            lines[2],
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        // A symmetric Y, with the free arms at +/-120 degrees.
+        let (y, x) = (10.0 * 3.0f32.sqrt() / 2.0, -5.0);
+        for (p, want) in [(b, egui::Pos2::new(x, y)), (c, egui::Pos2::new(x, -y))] {
+            let got = data.get_point_pos(p).unwrap();
+            assert!(got.distance(want) < 0.01, "{:?} != {:?}", got, want);
+        }
+
+        // Survives a round trip.
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        assert!(loaded
+            .constraints_iter()
+            .any(|(_, c)| matches!(c, Constraint::ThreeLinesEqualAngle(..))));
+    }
+
+    #[test]
+    fn rebuild_terms() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        data.terms.get_feature_term(p1, TermType::PositionX);
+        data.terms.get_feature_term(p2, TermType::PositionX);
+
+        // Mutate the features behind the allocator's back.
+        data.features.remove(p1);
+        let c = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), p2, 3.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 1.0));
+        data.rebuild_terms();
+
+        let mut names = HashSet::new();
+        for (fk, t) in [
+            (p2, TermType::PositionX),
+            (p2, TermType::PositionY),
+            (p3, TermType::PositionX),
+            (p3, TermType::PositionY),
+            (c, TermType::ScalarRadius),
+        ] {
+            let v: eq::Variable = (&data.terms.existing_feature_term(fk, t).unwrap()).into();
+            assert!(names.insert(v.clone()), "duplicate variable {}", v);
+            assert_eq!(data.terms.get_var_ref(&v).unwrap().for_feature, Some(fk));
+        }
+        assert_eq!(
+            data.terms.existing_feature_term(p1, TermType::PositionX),
+            None
+        );
+    }
+
+    #[test]
+    fn origin_point() {
+        let mut data = Data::default();
+        let a = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 0.0));
+        let b = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 2.0, 2.0));
+        assert_eq!(data.origin_point(), None);
+
+        // Fixed somewhere other than the origin doesn't count.
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), a, 1.0, 0.0));
+        assert_eq!(data.origin_point(), None);
+
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), b, 0.0, 0.0));
+        assert_eq!(data.origin_point(), Some(b));
+    }
+
+    #[test]
+    fn point_expr_centroid() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 4.0, 2.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 9.0, 9.0));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p2, 4.0, 2.0));
+
+        let parse = |f: &str| eq::Expression::parse(f, false).unwrap();
+        data.add_constraint(Constraint::PointExpr(
+            ConstraintMeta::default(),
+            p3,
+            vec![p1, p2],
+            parse("(x0 + x1) / 2"),
+            parse("(y0 + y1) / 2"),
+        ));
+        assert_eq!(data.last_solve_error, None);
+        assert_eq!(
+            data.features.get(p3),
+            Some(&Feature::Point(FeatureMeta::default(), 2.0, 1.0))
+        );
+
+        // The formulas survive a round trip, referring to the same points.
+        let mut loaded = Data::default();
+        loaded.load(data.serialize()).unwrap();
+        let exprs: Vec<_> = loaded
+            .constraints
+            .iter()
+            .filter_map(|(_ck, c)| match c {
+                Constraint::PointExpr(_, _, refs, fx, fy) => {
+                    Some((refs.len(), fx.to_string(), fy.to_string()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            exprs,
+            vec![(
+                2,
+                parse("(x0 + x1) / 2").to_string(),
+                parse("(y0 + y1) / 2").to_string()
+            )]
+        );
+
+        assert!(Constraint::check_point_expr(&parse("x0 + y1"), 2).is_ok());
+        assert!(Constraint::check_point_expr(&parse("x2"), 2).is_err());
+        assert!(Constraint::check_point_expr(&parse("d0 * 2"), 2).is_err());
+    }
+
+    #[test]
+    fn related_constraints() {
+        let mut data = Data::default();
+        let mut line = |x: f32| {
+            let p1 = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), x, 0.0));
+            let p2 = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), x, 5.0));
+            data.features
+                .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2))
+        };
+        let lines: Vec<_> = (0..5).map(|i| line(i as f32 * 10.0)).collect();
+
+        for pair in lines[..4].windows(2) {
+            data.add_constraint(Constraint::LineLengthsEqual(
+                ConstraintMeta::default(),
+                pair[0],
+                pair[1],
+                None,
+            ));
+        }
+        data.add_constraint(Constraint::LineAngle(
+            ConstraintMeta::default(),
+            lines[4],
+            0.0,
+        ));
+
+        let chain: Vec<_> = data
+            .constraints
+            .iter()
+            .filter(|(_ck, c)| matches!(c, Constraint::LineLengthsEqual(..)))
+            .map(|(ck, _c)| ck)
+            .collect();
+        assert_eq!(chain.len(), 3);
+
+        for ck in chain.iter() {
+            let mut related = data.related_constraints(*ck);
+            related.sort();
+            let mut want: Vec<_> = chain.iter().filter(|k| *k != ck).copied().collect();
+            want.sort();
+            assert_eq!(related, want);
+        }
+    }
+
+    #[test]
+    fn bruteforce_subdivisions() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 0.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l1,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+
+        let mut length_err = |bits: usize| {
+            // Start each search from the same, unsolved position.
+            *data.features.get_mut(p2).unwrap() = Feature::Point(FeatureMeta::default(), 1.0, 0.0);
+            data.bruteforce_params.subdivision_bits = bits;
+            data.bruteforce_solve();
+            let (a, b) = data.get_line_points(l1).unwrap();
+            (a.distance(b) - 5.0).abs()
+        };
+
+        // Two positions per variable can't step far enough to reach the
+        // solution, but sixteen can.
+        let (coarse, fine) = (length_err(1), length_err(4));
+        assert!(coarse > 2.0, "{}", coarse);
+        assert!(fine < 0.5, "{}", fine);
+    }
+
+    #[test]
+    fn bruteforce_in_background() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 0.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l1,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+        *data.features.get_mut(p2).unwrap() = Feature::Point(FeatureMeta::default(), 1.0, 0.0);
+
+        data.bruteforce_params.subdivision_bits = 4;
+        data.start_bruteforce_solve();
+        assert!(data.pending_bruteforce.is_running());
+        while data.poll_bruteforce_solve() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(!data.pending_bruteforce.is_running());
+
+        let (a, b) = data.get_line_points(l1).unwrap();
+        assert!((a.distance(b) - 5.0).abs() < 0.5, "{:?} {:?}", a, b);
+    }
+
+    #[test]
+    fn redundant_constraint_warning() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 1.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        data.add_constraint(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            l1,
+            Axis::LeftRight,
+        ));
+        assert!(data.constraint_warnings.is_empty());
+
+        let angle = Constraint::LineAngle(ConstraintMeta::default(), l1, 0.0);
+        assert!(data.redundancy_warning(&angle).is_some());
+        data.add_constraint(angle);
+        assert_eq!(data.constraint_warnings.len(), 1);
+        // The constraint is still added, and solves alongside the other.
+        assert_eq!(data.constraints.iter().count(), 2);
+        assert_eq!(data.last_solve_error, None);
+        // Angles are measured from the second point back to the first.
+        let (a, b) = data.get_line_points(l1).unwrap();
+        let angle = (a.y - b.y).atan2(a.x - b.x);
+        assert!(angle.abs() < 0.001, "line at {} radians", angle);
+
+        // An angle off the axis contradicts it, and is refused outright.
+        let off_axis = Constraint::LineAngle(ConstraintMeta::default(), l1, 30f32.to_radians());
+        assert!(data.redundancy_warning(&off_axis).is_none());
+        data.add_constraint(off_axis);
+        assert_eq!(data.constraints.iter().count(), 2);
+        assert_eq!(data.constraint_warnings.len(), 1);
+        assert_eq!(data.last_solve_error, None);
+
+        // Fixing both ends makes dimensioning the line redundant too.
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p2, 5.0, 0.0));
+        assert_eq!(data.constraint_warnings.len(), 1);
+        assert!(data
+            .redundancy_warning(&Constraint::LineLength(
+                ConstraintMeta::default(),
+                l1,
+                5.0,
+                None,
+                DimensionDisplay::default(),
+            ))
+            .is_some());
+    }
+
+    #[test]
+    fn move_point_solves_connected_only() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 3.0, 1.0));
+        let line = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 20.0, 20.0));
+        let p4 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 30.0, 20.0));
+
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0., 0.));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            line,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p4, 30., 20.));
+        assert_eq!(data.last_solve_error, None);
+        // The line, plus the independent x & y terms of the second fixed point.
+        assert_eq!(Data::partition_equations(data.equations()).len(), 3);
+
+        let cluster: Vec<Feature> = [p1, p2, p4]
+            .iter()
+            .map(|k| data.features.get(*k).unwrap().clone())
+            .collect();
+
+        // Moving the isolated point must not touch either cluster.
+        data.move_point(p3, egui::Pos2 { x: 25.0, y: 25.0 });
+        assert_eq!(
+            data.features.get(p3),
+            Some(&Feature::Point(FeatureMeta::default(), 25.0, 25.0))
+        );
+        for (k, f) in [p1, p2, p4].iter().zip(cluster.iter()) {
+            assert_eq!(data.features.get(*k), Some(f));
+        }
+
+        // Moving a constrained point still solves its own cluster.
+        data.move_point(p2, egui::Pos2 { x: 10.0, y: 0.0 });
+        assert_eq!(data.last_solve_error, None);
+        let d = data.features.get(p2).unwrap().start_point(&data);
+        assert!(
+            (d.distance(egui::Pos2::ZERO) - 5.0).abs() < 0.001,
+            "{:?}",
+            d
+        );
+        assert_eq!(data.features.get(p4), Some(&cluster[2]));
+    }
+
+    #[test]
+    fn equations_debug_fixed() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 1.5, -2.0));
+
+        let (x, y) = (
+            data.terms.get_feature_term(p1, TermType::PositionX),
+            data.terms.get_feature_term(p1, TermType::PositionY),
+        );
+        assert_eq!(
+            data.equations_debug(),
+            vec![format!("{} = (3/2)", x), format!("{} = (-2/1)", y)],
+        );
+    }
+
+    #[test]
+    fn scroll_zoom() {
+        let (cursor, center) = (egui::Pos2::new(100.0, 50.0), egui::Pos2::new(400.0, 300.0));
+        let start = Viewport {
+            x: 10.0,
+            y: -20.0,
+            zoom: 2.0,
+        };
+
+        let mut data = Data {
+            vp: start.clone(),
+            ..Data::default()
+        };
+        let under_cursor = data.vp.screen_to_point(cursor);
+        data.scroll_zoom(230.0, cursor, center);
+        let to_cursor = data.vp.clone();
+        assert!(to_cursor.zoom < start.zoom);
+        assert!(to_cursor.screen_to_point(cursor).distance(under_cursor) < 0.001);
+
+        data.vp = start.clone();
+        data.zoom_to_cursor = false;
+        let under_center = data.vp.screen_to_point(center);
+        data.scroll_zoom(230.0, cursor, center);
+        let to_center = data.vp.clone();
+        assert!(to_center.screen_to_point(center).distance(under_center) < 0.001);
+
+        // Same zoom step, but about different points.
+        assert_eq!(to_center.zoom, to_cursor.zoom);
+        assert!(!to_center.approx_eq(&to_cursor, 0.001));
+    }
+
+    #[test]
+    fn export_equations() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 1.5, -2.0));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l,
+            4.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+
+        assert_eq!(
+            data.export_equations(),
+            "# Constraint 1v1: fixed\n\
+             x0 = (3/2)\n\
+             y0 = (-2/1)\n\
+             \n\
+             # Constraint 2v1: length\n\
+             d1 = (4/1)\n\
+             d1 = sqrt_pm((((x2 - x0))^2 + ((y2 - y0))^2))\n",
+        );
+    }
+
+    #[test]
+    fn selection_labels_center() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 5.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p3));
+        for l in [l1, l2] {
+            data.add_constraint(Constraint::LineLength(
+                ConstraintMeta::default(),
+                l,
+                5.0,
+                None,
+                DimensionDisplay {
+                    x: 3.0,
+                    y: 4.0,
+                    ..DimensionDisplay::default()
+                },
+            ));
+        }
+        let cks: Vec<ConstraintKey> = data.constraints_iter().map(|(ck, _)| ck).collect();
+
+        let dd = |data: &Data, ck: ConstraintKey| match data.constraints.get(ck) {
+            Some(Constraint::LineLength(.., dd)) => dd.clone(),
+            c => panic!("unexpected constraint: {:?}", c),
+        };
+
+        data.selected_map
+            .insert(SelectedElement::Constraint(cks[0]), 0);
+        data.selection_labels_center(true);
+        assert!(dd(&data, cks[0]).approx_eq(
+            &DimensionDisplay {
+                x: 0.0,
+                y: 4.0,
+                ..DimensionDisplay::default()
+            },
+            1e-6
+        ));
+        assert!(dd(&data, cks[1]).approx_eq(
+            &DimensionDisplay {
+                x: 3.0,
+                y: 4.0,
+                ..DimensionDisplay::default()
+            },
+            1e-6
+        ));
+
+        data.selected_map
+            .insert(SelectedElement::Constraint(cks[0]), 0);
+        data.selected_map
+            .insert(SelectedElement::Constraint(cks[1]), 1);
+        data.selection_labels_center(false);
+        assert!(dd(&data, cks[0]).approx_eq(
+            &DimensionDisplay {
+                x: 0.0,
+                y: 0.0,
+                ..DimensionDisplay::default()
+            },
+            1e-6
+        ));
+        assert!(dd(&data, cks[1]).approx_eq(
+            &DimensionDisplay {
+                x: 3.0,
+                y: 0.0,
+                ..DimensionDisplay::default()
+            },
+            1e-6
+        ));
+    }
+
+    #[test]
+    fn adjust_selected_dimensions() {
+        let mut data = Data::default();
+        let mut lines = Vec::new();
+        for i in 0..3 {
+            let y = i as f32 * 10.0;
+            let p1 = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), 0.0, y));
+            let p2 = data
+                .features
+                .insert(Feature::Point(FeatureMeta::default(), 5.0, y));
+            lines.push(
+                data.features
+                    .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2)),
+            );
+            data.add_constraint(Constraint::LineLength(
+                ConstraintMeta::default(),
+                lines[i],
+                5.0,
+                None,
+                DimensionDisplay::default(),
+            ));
+        }
+        let cks: Vec<ConstraintKey> = data.constraints_iter().map(|(ck, _)| ck).collect();
+        data.select_constraint(cks[0], true);
+        data.select_constraint(cks[1], true);
+
+        data.adjust_selected_dimensions(0.0, 1.1);
+
+        let lengths: Vec<f32> = cks
+            .iter()
+            .map(|ck| match data.constraints.get(*ck) {
+                Some(Constraint::LineLength(_, _, d, ..)) => *d,
+                c => panic!("unexpected constraint: {:?}", c),
+            })
+            .collect();
+        assert!((lengths[0] - 5.5).abs() < 1e-5, "{:?}", lengths);
+        assert!((lengths[1] - 5.5).abs() < 1e-5, "{:?}", lengths);
+        assert_eq!(lengths[2], 5.0);
+        // The geometry follows the new dimensions.
+        let (a, b) = data.get_line_points(lines[0]).unwrap();
+        assert!((a.distance(b) - 5.5).abs() < 0.01, "{}", a.distance(b));
+    }
+
+    #[test]
+    fn linear_solve_exact() {
+        let mut data = Data::default();
+        let a = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let b = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 5.0));
+        let mid = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 3.0, 2.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), a, b));
+
+        // The midpoint and end of the line depend on each other, which
+        // substitution alone can't untangle.
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), a, 1.0, 2.0));
+        data.add_constraint(Constraint::PointLerpLine(
+            ConstraintMeta::default(),
+            l,
+            mid,
+            0.5,
+        ));
+        data.add_constraint(Constraint::PointOffset(
+            ConstraintMeta::default(),
+            mid,
+            b,
+            3.0,
+            -1.5,
+        ));
+
+        assert_eq!(data.last_solve_error, None);
+        assert_eq!(data.get_point_pos(b), Some(egui::pos2(7.0, -1.0)));
+        assert_eq!(data.get_point_pos(mid), Some(egui::pos2(4.0, 0.5)));
+    }
+
+    #[test]
+    fn on_solved_hook() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 9.0, 1.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let c = calls.clone();
+        data.on_solved = SolveHook(Some(Box::new(move |err| c.borrow_mut().push(err))));
+
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        assert_eq!(*calls.borrow(), vec![None]);
+
+        // Lines too short to span two fixed points report an error.
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p3));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p3, 10.0, 0.0));
+        for l in [l, l2] {
+            data.add_constraint(Constraint::LineLength(
+                ConstraintMeta::default(),
+                l,
+                2.0,
+                None,
+                DimensionDisplay::default(),
+            ));
+        }
+        assert_eq!(calls.borrow().len(), 4);
+        assert_eq!(calls.borrow()[3], data.last_solve_error);
+        assert!(data.last_solve_error.is_some());
+
+        // Clones don't carry the hook.
+        data.clone().solve_and_apply();
+        assert_eq!(calls.borrow().len(), 4);
+    }
+
+    #[test]
+    fn line_length_range() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 25.0, 0.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            l,
+            Axis::LeftRight,
+        ));
+        data.add_constraint(Constraint::LineLengthRange(
+            ConstraintMeta::default(),
+            l,
+            10.0,
+            20.0,
+        ));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta {
+                priority: 0,
+                ..ConstraintMeta::default()
+            },
+            l,
+            25.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+
+        // Pulled past the maximum, the line settles at it.
+        let (a, b) = data.get_line_points(l).unwrap();
+        assert!((a.distance(b) - 20.0).abs() < 0.05, "{}", a.distance(b));
+
+        // Anywhere within the range is left alone.
+        let (pull, _) = data
+            .constraints
+            .iter()
+            .find(|(_ck, c)| matches!(c, Constraint::LineLength(..)))
+            .unwrap();
+        data.delete_constraint(pull);
+        if let Some(Feature::Point(_, x, _)) = data.features.get_mut(p2) {
+            *x = 15.0;
+        }
+        data.solve_and_apply();
+        let (a, b) = data.get_line_points(l).unwrap();
+        assert!((a.distance(b) - 15.0).abs() < 0.05, "{}", a.distance(b));
+    }
+
+    #[test]
+    fn soft_constraint_yields() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 9.0, 1.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        // Only the first point is pinned: the second is free to slide along
+        // the line, and is pulled short of its length by a soft constraint.
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 0.0, 0.0));
+        data.add_constraint(Constraint::LineAlongCardinal(
+            ConstraintMeta::default(),
+            l1,
+            Axis::LeftRight,
+        ));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l1,
+            10.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+        data.add_constraint(Constraint::Fixed(
+            ConstraintMeta {
+                priority: 10,
+                ..ConstraintMeta::default()
+            },
+            p2,
+            5.0,
+            0.0,
+        ));
+        assert_eq!(data.last_solve_error, None);
+
+        // The hard constraints win...
+        let (a, b) = data.get_line_points(l1).unwrap();
+        assert!((a.distance(b) - 10.0).abs() < 0.01, "{:?} {:?}", a, b);
+        assert!(b.y.abs() < 0.01, "{:?}", b);
+        // ...leaving the soft constraint unsatisfied.
+        let soft_residual = b.distance(egui::Pos2::new(5.0, 0.0));
+        assert!(soft_residual > 1.0, "{:?}", b);
+    }
+
+    #[test]
+    fn zero_length_line_terms() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 2.0, 3.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 2.0, 3.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+
+        for t in [
+            TermType::ScalarDistance,
+            TermType::ScalarGlobalCos,
+            TermType::ScalarGlobalSin,
+        ] {
+            let term = data.terms.get_feature_term(l1, t);
+            assert_eq!(data.term_current_value(&term), None);
+        }
+
+        data.add_constraint(Constraint::Fixed(ConstraintMeta::default(), p1, 2.0, 3.0));
+        data.add_constraint(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l1,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+        let (a, b) = data.get_line_points(l1).unwrap();
+        assert!(a.x.is_finite() && a.y.is_finite(), "{:?}", a);
+        assert!(b.x.is_finite() && b.y.is_finite(), "{:?}", b);
+    }
+
+    #[test]
+    fn apply_solved_negative_magnitudes() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let c1 = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), p1, 2.0));
+
+        let r = data.terms.get_feature_term(c1, TermType::ScalarRadius);
+        assert!(data.apply_solved(&r, -3.5));
+        assert_eq!(
+            data.features.get(c1),
+            Some(&Feature::Circle(FeatureMeta::default(), p1, 3.5))
+        );
+
+        let d = data.terms.get_feature_term(l1, TermType::ScalarDistance);
+        assert!(!data.apply_solved(&d, -4.0));
+        assert_eq!(
+            data.get_line_points(l1),
+            Some((egui::Pos2::new(0.0, 0.0), egui::Pos2::new(5.0, 0.0)))
+        );
+    }
+
+    #[test]
+    fn as_solid_symmetric() {
+        let circle = |r: f32| SerializedFeature {
+            kind: "circle".to_string(),
+            using_idx: vec![0],
+            r,
+            ..SerializedFeature::default()
+        };
+        let group = |typ: crate::GroupType, amt: f64, idx: usize| crate::SerializedGroup {
+            typ,
+            name: format!("{:?}", typ),
+            features_idx: vec![idx],
+            amt: Some(amt),
+            symmetric: Some(()),
+            ..crate::SerializedGroup::default()
+        };
+
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![pt(0.0, 0.0), circle(10.0), circle(3.0)],
+            groups: vec![group(crate::GroupType::Boundary, 4.0, 1)],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        let z_range = |data: &Data| {
+            let solid = data.as_solid().unwrap();
+            solid
+                .boundaries()
+                .iter()
+                .flat_map(|s| s.vertex_iter())
+                .map(|v| v.get_point().z)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), z| {
+                    (lo.min(z), hi.max(z))
+                })
+        };
+
+        let (lo, hi) = z_range(&data);
+        assert!(
+            (lo + 2.0).abs() < 1e-9 && (hi - 2.0).abs() < 1e-9,
+            "{}..{}",
+            lo,
+            hi
+        );
+
+        // A symmetric extrusion adds half its thickness to each face.
+        data.groups.push(Group {
+            typ: crate::GroupType::Extrude,
+            name: "Extrude".into(),
+            features: vec![data.features.iter().nth(2).unwrap().0],
+            amt: Some(2.0),
+            symmetric: Some(()),
+            ..Group::default()
+        });
+        let (lo, hi) = z_range(&data);
+        assert!(
+            (lo + 3.0).abs() < 1e-9 && (hi - 3.0).abs() < 1e-9,
+            "{}..{}",
+            lo,
+            hi
+        );
+    }
+
+    #[test]
+    fn initials_from_neighbors() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 30.0, 40.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), f32::NAN, f32::NAN));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        data.add_constraint_impl(Constraint::LineLength(
+            ConstraintMeta::default(),
+            l,
+            5.0,
+            None,
+            DimensionDisplay::default(),
+        ));
+
+        let equations = data.equations();
+        let (_known, unresolved, _residuals, _weights, initials) =
+            data.subsolve(equations, Vec::new()).unwrap();
+        let (x, y) = (
+            data.terms.get_feature_term(p2, TermType::PositionX),
+            data.terms.get_feature_term(p2, TermType::PositionY),
+        );
+        for (v, initial) in unresolved.iter().zip(initials) {
+            let term = data.terms.get_var_ref(v).unwrap();
+            if term == x {
+                assert!((initial - 30.0).abs() < 1.0, "{}", initial);
+            } else if term == y {
+                assert!((initial - 40.0).abs() < 1.0, "{}", initial);
+            }
+        }
+        assert!(unresolved.contains(&(&x).into()));
+
+        // Without a length, the line's is guessed from the guessed endpoint
+        // too, rather than from the endpoint which was never placed.
+        data.constraints = ConstraintData::default();
+        data.add_constraint_impl(Constraint::LineAngle(ConstraintMeta::default(), l, 0.0));
+        let equations = data.equations();
+        let (_known, unresolved, _residuals, _weights, initials) =
+            data.subsolve(equations, Vec::new()).unwrap();
+        let d = data.terms.get_feature_term(l, TermType::ScalarDistance);
+        for (v, initial) in unresolved.iter().zip(initials) {
+            let term = data.terms.get_var_ref(v).unwrap();
+            if term == d {
+                assert!((initial - 0.47 * std::f64::consts::SQRT_2).abs() < 0.01);
+            }
+            assert!(initial.is_finite(), "{:?} = {}", term, initial);
+        }
+        assert!(unresolved.contains(&(&d).into()));
+    }
+
+    #[test]
+    fn as_solid_z_offset() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                SerializedFeature {
+                    kind: "circle".to_string(),
+                    using_idx: vec![0],
+                    r: 10.0,
+                    ..SerializedFeature::default()
+                },
+            ],
+            groups: vec![crate::SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Boundary".into(),
+                features_idx: vec![1],
+                amt: Some(4.0),
+                ..crate::SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+
+        let z_range = |data: &Data| {
+            data.as_solid()
+                .unwrap()
+                .boundaries()
+                .iter()
+                .flat_map(|s| s.vertex_iter())
+                .map(|v| v.get_point().z)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), z| {
+                    (lo.min(z), hi.max(z))
+                })
+        };
+        let (lo, hi) = z_range(&data);
+        assert!(lo.abs() < 1e-9 && (hi - 4.0).abs() < 1e-9, "{}..{}", lo, hi);
+
+        data.props.z_offset = 2.0;
+        let (lo, hi) = z_range(&data);
+        assert!(
+            (lo - 2.0).abs() < 1e-9 && (hi - 6.0).abs() < 1e-9,
+            "{}..{}",
+            lo,
+            hi
+        );
+    }
+
+    #[test]
+    fn toggle_selection_construction() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data.features.insert(Feature::Point(
+            FeatureMeta::default_construction(),
+            5.0,
+            0.0,
+        ));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let c1 = data
+            .features
+            .insert(Feature::Circle(FeatureMeta::default(), p1, 2.0));
+
+        data.select_feature(p1, true);
+        data.select_feature(p2, true);
+        data.select_feature(l1, true);
+        data.toggle_selection_construction();
+
+        let construction = |data: &Data, fk| data.features.get(fk).unwrap().is_construction();
+        assert!(construction(&data, p1));
+        assert!(!construction(&data, p2));
+        assert!(construction(&data, l1));
+        assert!(!construction(&data, c1)); // not selected
+
+        data.toggle_selection_construction();
+        assert!(!construction(&data, p1));
+        assert!(construction(&data, p2));
+        assert!(!construction(&data, l1));
+    }
+
+    #[test]
+    fn selection_to_construction_locked() {
+        let mut data = Data::default();
+        data.load(square_with_hole()).unwrap();
+        let (_, _, holes) = data.flatten_to_idxs(0.1).unwrap();
+        assert_eq!(holes.len(), 1);
+
+        // Turn the triangle into reference geometry.
+        let triangle: Vec<FeatureKey> = data.groups[1].features.clone();
+        for fk in triangle.iter() {
+            data.select_feature(*fk, true);
+        }
+        data.selection_to_construction_locked();
+
+        for fk in triangle.iter() {
+            let meta = data.features.get(*fk).unwrap().meta();
+            assert!(meta.construction && meta.locked, "{:?}", meta);
+        }
+        let square = data.groups[0].features[0];
+        let meta = data.features.get(square).unwrap().meta();
+        assert!(!meta.construction && !meta.locked); // not selected
+
+        let (_, outer, holes) = data.flatten_to_idxs(0.1).unwrap();
+        assert_eq!(outer.len(), 1);
+        assert!(holes.is_empty(), "{:?}", holes);
+    }
+
+    #[test]
+    fn independent_instances() {
+        use crate::handler::{Handler, ToolResponse};
+        let mut tools = crate::tools::Toolbar::default();
+        let mut handler = Handler::default();
+        let (mut a, mut b) = (Data::default(), Data::default());
+
+        handler.handle(
+            &mut a,
+            &mut tools,
+            ToolResponse::NewPoint(egui::Pos2::new(1.0, 2.0)),
+        );
+        let pa = a.features.keys().next().unwrap();
+        handler.handle(&mut a, &mut tools, ToolResponse::NewFixedConstraint(pa));
+        a.select_feature(pa, true);
+        a.vp.zoom = 2.0;
+        assert_eq!(a.features.len(), 1);
+        assert_eq!(a.constraints_iter().count(), 1);
+
+        assert_eq!(b.features.len(), 0);
+        assert_eq!(b.constraints_iter().count(), 0);
+        assert!(b.selected_map.is_empty());
+        assert_eq!(b.vp, Viewport::default());
+
+        handler.handle(
+            &mut b,
+            &mut tools,
+            ToolResponse::NewPoint(egui::Pos2::new(5.0, 5.0)),
+        );
+        let pb = b.features.keys().next().unwrap();
+        b.delete_feature(pb);
+        assert_eq!(b.features.len(), 0);
+        assert_eq!(a.features.len(), 1);
+        let Some(Feature::Point(_, x, y)) = a.features.get(pa) else {
+            panic!("point missing");
+        };
+        // Fixed constraints default to the origin.
+        assert!(x.abs() < 0.001 && y.abs() < 0.001, "{} {}", x, y);
+    }
+
+    #[test]
+    fn chamfer() {
+        let mut data = Data::default();
+        // Free slots which sort before the lines, for the chamfer to reuse.
+        let scratch: Vec<FeatureKey> = (0..3)
+            .map(|_| {
+                data.features
+                    .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0))
+            })
+            .collect();
+        let p0 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let corner = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 10.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p0, corner));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), corner, p2));
+        data.groups.push(Group {
+            features: vec![l1, l2],
+            ..Group::default()
+        });
+
+        data.assign_ids();
+        for k in scratch {
+            data.features.remove(k);
+        }
+        let ids = |data: &Data| [l1, l2].map(|l| data.features[l].meta().id);
+        let before = ids(&data);
+
+        assert_eq!(data.chamfer(corner, 10.0), Err(())); // as long as a line
+        assert_eq!(data.chamfer(l1, 2.0), Err(())); // not a point
+        let c = data.chamfer(corner, 2.0).unwrap();
+
+        // The lines keep their ids, and the bevel gets one of its own.
+        assert_eq!(ids(&data), before);
+        assert!(!before.contains(&data.features[c].meta().id));
+        assert_eq!(data.feature_by_id(before[0]), Some(l1));
+
+        assert!(data.features.get(corner).is_none());
+        let (a, b) = data.get_line_points(c).unwrap();
+        assert_eq!(
+            (a, b),
+            (egui::Pos2::new(2.0, 0.0), egui::Pos2::new(0.0, 2.0))
+        );
+        assert_eq!(
+            data.get_line_points(l1).unwrap(),
+            (egui::Pos2::new(10.0, 0.0), a)
+        );
+        assert_eq!(
+            data.get_line_points(l2).unwrap(),
+            (b, egui::Pos2::new(0.0, 10.0))
+        );
+        assert_eq!(data.groups[0].features, vec![l1, l2, c]);
+        assert_eq!(data.features.len(), 7);
+    }
+
+    #[test]
+    fn duplicate_lines() {
+        let mut data = Data::default();
+        let p1 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let p2 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 0.0));
+        let p3 = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 5.0, 5.0));
+        let l1 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p1, p2));
+        let l2 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p3));
+        let l3 = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), p2, p1));
+        assert_eq!(data.find_duplicate_lines(), vec![(l1, l3)]);
+
+        data.groups.push(Group {
+            features: vec![l3, l2],
+            ..Group::default()
+        });
+        assert_eq!(data.merge_duplicate_lines(), 1);
+        assert!(data.features.get(l3).is_none());
+        assert!(data.features.get(l1).is_some());
+        assert_eq!(data.groups[0].features, vec![l1, l2]);
+        assert_eq!(data.find_duplicate_lines(), vec![]);
     }
 }
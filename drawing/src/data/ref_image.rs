@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+/// A raster image shown behind the drawing, for tracing over.
+///
+/// Only the path is saved with the drawing: the pixels are loaded
+/// separately by whoever opens it.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, PartialEq)]
+pub struct RefImage {
+    pub path: String,
+    /// Drawing-space position of the top-left corner of the image.
+    pub x: f32,
+    pub y: f32,
+    /// Size of the image in pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Drawing millimeters per image pixel.
+    pub scale: f32,
+    /// In the range [0, 1].
+    pub opacity: f32,
+    /// Prevents the image being dragged around the canvas.
+    pub locked: bool,
+
+    #[serde(skip)]
+    pub bytes: Option<Arc<[u8]>>,
+}
+
+impl RefImage {
+    /// Scale new images start at, in mm per pixel.
+    pub const DEFAULT_SCALE: f32 = 0.1;
+    /// Opacity new images start at.
+    pub const DEFAULT_OPACITY: f32 = 0.5;
+
+    /// Returns an image with the given pixels, with its top-left corner at
+    /// the origin. It starts locked, so dragging over it still box-selects.
+    pub fn new(path: String, bytes: Arc<[u8]>, width: u32, height: u32) -> Self {
+        Self {
+            path,
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+            scale: Self::DEFAULT_SCALE,
+            opacity: Self::DEFAULT_OPACITY,
+            locked: true,
+            bytes: Some(bytes),
+        }
+    }
+
+    /// URI the pixels are registered with egui under.
+    pub fn uri(&self) -> String {
+        format!("bytes://reference/{}", self.path)
+    }
+
+    /// Returns the drawing-space rectangle covered by the image.
+    pub fn drawing_rect(&self) -> egui::Rect {
+        egui::Rect::from_min_size(
+            egui::Pos2::new(self.x, self.y),
+            egui::Vec2::new(self.width as f32, self.height as f32) * self.scale,
+        )
+    }
+
+    /// Returns the pixel of the image under the given drawing-space point,
+    /// which may lie outside the image.
+    pub fn pixel_at(&self, p: egui::Pos2) -> egui::Pos2 {
+        ((p - egui::Pos2::new(self.x, self.y)) / self.scale).to_pos2()
+    }
+
+    /// Scales the image about the given drawing-space point, keeping the
+    /// part of the image under it in place.
+    pub fn scale_about(&mut self, p: egui::Pos2, scale: f32) {
+        let anchor = self.pixel_at(p);
+        self.scale = scale;
+        self.x = p.x - anchor.x * scale;
+        self.y = p.y - anchor.y * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform() {
+        let mut img = RefImage::new("a.png".into(), Arc::from(vec![]), 200, 100);
+        img.x = 5.0;
+        img.y = -10.0;
+        img.scale = 0.5;
+
+        assert_eq!(
+            img.drawing_rect(),
+            egui::Rect::from_min_max(egui::Pos2::new(5.0, -10.0), egui::Pos2::new(105.0, 40.0))
+        );
+        assert_eq!(
+            img.pixel_at(egui::Pos2::new(55.0, 15.0)),
+            egui::Pos2::new(100.0, 50.0)
+        );
+
+        // The pixel under the anchor stays put.
+        img.scale_about(egui::Pos2::new(55.0, 15.0), 2.0);
+        assert_eq!(
+            img.pixel_at(egui::Pos2::new(55.0, 15.0)),
+            egui::Pos2::new(100.0, 50.0)
+        );
+        assert_eq!(
+            img.drawing_rect(),
+            egui::Rect::from_min_max(
+                egui::Pos2::new(-145.0, -85.0),
+                egui::Pos2::new(255.0, 115.0)
+            )
+        );
+    }
+}
@@ -18,6 +18,31 @@ impl Viewport {
             y: (p.y - self.y) / self.zoom,
         }
     }
+    /// Moves the viewport by some delta in screen-space, such as from
+    /// dragging the canvas.
+    pub fn pan(&mut self, screen_delta: egui::Vec2) {
+        self.x -= screen_delta.x * self.zoom;
+        self.y -= screen_delta.y * self.zoom;
+    }
+    /// Sets the zoom, keeping the drawing position under the given
+    /// screen point where it is.
+    pub fn zoom_about(&mut self, p: egui::Pos2, zoom: f32) {
+        let anchor = self.screen_to_point(p);
+        self.zoom = zoom;
+        self.x = anchor.x - zoom * p.x;
+        self.y = anchor.y - zoom * p.y;
+    }
+    /// Returns the zoom at which one drawing millimeter is shown at its
+    /// physical size, on a display with the given DPI and pixels-per-point.
+    pub fn true_scale_zoom(dpi: f32, pixels_per_point: f32) -> f32 {
+        25.4 * pixels_per_point / dpi
+    }
+    /// Returns true if every field is within tolerance of the other viewport.
+    pub fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        (self.x - other.x).abs() <= tolerance
+            && (self.y - other.y).abs() <= tolerance
+            && (self.zoom - other.zoom).abs() <= tolerance
+    }
     pub fn translate_rect(&self, r: egui::Rect) -> egui::Rect {
         egui::Rect {
             min: self.translate_point(r.min),
@@ -35,3 +60,75 @@ impl Default for Viewport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pan() {
+        let mut vp = Viewport::default();
+        vp.pan(egui::Vec2 { x: 10.0, y: -5.0 });
+        assert_eq!((vp.x, vp.y), (-10.0, 5.0));
+
+        // Deltas are in screen-space, so scale with zoom.
+        let mut vp = Viewport {
+            x: 1.0,
+            y: 1.0,
+            zoom: 2.0,
+        };
+        let before = vp.translate_point(egui::Pos2 { x: 3.0, y: 4.0 });
+        vp.pan(egui::Vec2 { x: 10.0, y: 10.0 });
+        assert_eq!((vp.x, vp.y), (-19.0, -19.0));
+        assert_eq!(
+            vp.translate_point(egui::Pos2 { x: 3.0, y: 4.0 }),
+            before + egui::Vec2 { x: 10.0, y: 10.0 }
+        );
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = Viewport::default();
+        let b = Viewport {
+            x: 1e-9,
+            ..Viewport::default()
+        };
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 1e-6));
+        assert!(!a.approx_eq(&b, 0.0));
+
+        let round_trip: Viewport = ron::from_str(&ron::to_string(&b).unwrap()).unwrap();
+        assert!(round_trip.approx_eq(&b, 1e-6));
+        assert!(!a.approx_eq(
+            &Viewport {
+                zoom: 1.1,
+                ..Viewport::default()
+            },
+            1e-6
+        ));
+    }
+
+    #[test]
+    fn true_scale_zoom() {
+        let mut vp = Viewport {
+            x: 12.0,
+            y: -3.0,
+            zoom: 2.0,
+        };
+        let center = egui::Pos2 { x: 400.0, y: 300.0 };
+        let before = vp.screen_to_point(center);
+
+        // 96 DPI at 1 pixel per point: 1mm should be 96/25.4 points.
+        vp.zoom_about(center, Viewport::true_scale_zoom(96.0, 1.0));
+        let (a, b) = (
+            vp.translate_point(egui::Pos2 { x: 0.0, y: 0.0 }),
+            vp.translate_point(egui::Pos2 { x: 1.0, y: 0.0 }),
+        );
+        assert!(((b.x - a.x) - 96.0 / 25.4).abs() < 0.0001);
+        assert!(vp.screen_to_point(center).distance(before) < 0.0001);
+
+        // HiDPI: two pixels per point halves the points per mm.
+        let z = Viewport::true_scale_zoom(192.0, 2.0);
+        assert!((1.0 / z - 192.0 / 25.4 / 2.0).abs() < 0.0001);
+    }
+}
@@ -6,16 +6,70 @@ slotmap::new_key_type! {
     pub struct FeatureKey;
 }
 
-const POINT_SIZE: egui::Vec2 = egui::Vec2 { x: 4.5, y: 4.5 };
+/// Radius (in screen pixels) points are drawn with by default.
+pub(crate) const DEFAULT_POINT_RADIUS: f32 = 4.5;
+
+/// The glyph points are drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PointStyle {
+    Dot,
+    /// Marks the exact location of the point, even at high zoom.
+    Cross,
+    #[default]
+    Square,
+}
 
-#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
+impl PointStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PointStyle::Dot => "Dot",
+            PointStyle::Cross => "Cross",
+            PointStyle::Square => "Square",
+        }
+    }
+}
+
+/// Returns the horizontal and vertical arms of a cross centered on the given
+/// screen position.
+fn point_cross(center: egui::Pos2, radius: f32) -> [[egui::Pos2; 2]; 2] {
+    let (h, v) = (egui::vec2(radius, 0.), egui::vec2(0., radius));
+    [[center - h, center + h], [center - v, center + v]]
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct FeatureMeta {
     pub construction: bool,
+    /// Marks a line as a sheet-metal bend, by the given angle in degrees.
+    pub bend_angle: Option<f32>,
+    /// Identifies the feature across saves, unlike its key. Zero until the
+    /// drawing assigns one.
+    #[serde(default)]
+    pub id: u64,
+    /// Prevents the feature being dragged, though the solver may still move it.
+    #[serde(default)]
+    pub locked: bool,
+    /// Name of the layer the feature is exported on, where the format has them.
+    #[serde(default)]
+    pub layer: Option<String>,
+}
+
+// The id identifies a feature rather than describing it, so two features
+// with the same geometry and settings are equal regardless of their ids.
+impl PartialEq for FeatureMeta {
+    fn eq(&self, other: &Self) -> bool {
+        self.construction == other.construction
+            && self.bend_angle == other.bend_angle
+            && self.locked == other.locked
+            && self.layer == other.layer
+    }
 }
 
 impl FeatureMeta {
     pub fn default_construction() -> Self {
-        Self { construction: true }
+        Self {
+            construction: true,
+            ..Self::default()
+        }
     }
 }
 
@@ -30,6 +84,9 @@ pub struct SerializedFeature {
     pub r: f32,
     pub n: Option<usize>,
     pub gear_info: Option<GearInfo>,
+    pub ry: Option<f32>,
+    pub rotation: Option<f32>,
+    pub angles: Option<(f32, f32)>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
@@ -59,6 +116,8 @@ pub enum Feature {
     Circle(FeatureMeta, FeatureKey, f32),                 // center, radius
     SpurGear(FeatureMeta, FeatureKey, GearInfo),          // center, gear details
     RegularPoly(FeatureMeta, FeatureKey, usize, f32),     // center, num_sides, apothem
+    Ellipse(FeatureMeta, FeatureKey, f32, f32, f32),      // center, rx, ry, rotation
+    ArcCenter(FeatureMeta, FeatureKey, f32, f32, f32),    // center, radius, start_deg, end_deg
 }
 
 impl Default for Feature {
@@ -69,7 +128,7 @@ impl Default for Feature {
 
 impl PartialEq<Feature> for Feature {
     fn eq(&self, other: &Feature) -> bool {
-        use Feature::{Arc, Circle, LineSegment, Point, RegularPoly, SpurGear};
+        use Feature::{Arc, ArcCenter, Circle, Ellipse, LineSegment, Point, RegularPoly, SpurGear};
         match (self, other) {
             (Point(_, x1, y1), Point(_, x2, y2)) => x1 == x2 && y1 == y2,
             (LineSegment(_, p00, p01), LineSegment(_, p10, p11)) => {
@@ -104,6 +163,18 @@ impl PartialEq<Feature> for Feature {
             (RegularPoly(_, p0, n0, a0, ..), RegularPoly(_, p1, n1, a1, ..)) => {
                 p0 == p1 && n0 == n1 && (a1 - a0).abs() < 0.005
             }
+            (Ellipse(_, p0, rx0, ry0, rot0), Ellipse(_, p1, rx1, ry1, rot1)) => {
+                p0 == p1
+                    && (rx1 - rx0).abs() < 0.005
+                    && (ry1 - ry0).abs() < 0.005
+                    && (rot1 - rot0).abs() < 0.005
+            }
+            (ArcCenter(_, p0, r0, s0, e0), ArcCenter(_, p1, r1, s1, e1)) => {
+                p0 == p1
+                    && (r1 - r0).abs() < 0.005
+                    && (s1 - s0).abs() < 0.005
+                    && (e1 - e0).abs() < 0.005
+            }
             _ => false,
         }
     }
@@ -121,6 +192,32 @@ impl Feature {
             Feature::Circle(meta, ..) => meta.construction,
             Feature::SpurGear(meta, ..) => meta.construction,
             Feature::RegularPoly(meta, ..) => meta.construction,
+            Feature::Ellipse(meta, ..) => meta.construction,
+            Feature::ArcCenter(meta, ..) => meta.construction,
+        }
+    }
+    pub fn meta(&self) -> &FeatureMeta {
+        match self {
+            Feature::Point(meta, ..) => meta,
+            Feature::LineSegment(meta, ..) => meta,
+            Feature::Arc(meta, ..) => meta,
+            Feature::Circle(meta, ..) => meta,
+            Feature::SpurGear(meta, ..) => meta,
+            Feature::RegularPoly(meta, ..) => meta,
+            Feature::Ellipse(meta, ..) => meta,
+            Feature::ArcCenter(meta, ..) => meta,
+        }
+    }
+    pub fn meta_mut(&mut self) -> &mut FeatureMeta {
+        match self {
+            Feature::Point(meta, ..) => meta,
+            Feature::LineSegment(meta, ..) => meta,
+            Feature::Arc(meta, ..) => meta,
+            Feature::Circle(meta, ..) => meta,
+            Feature::SpurGear(meta, ..) => meta,
+            Feature::RegularPoly(meta, ..) => meta,
+            Feature::Ellipse(meta, ..) => meta,
+            Feature::ArcCenter(meta, ..) => meta,
         }
     }
 
@@ -132,6 +229,8 @@ impl Feature {
             Feature::Circle(_, p, ..) => [Some(*p), None, None],
             Feature::SpurGear(_, p, ..) => [Some(*p), None, None],
             Feature::RegularPoly(_, p, ..) => [Some(*p), None, None],
+            Feature::Ellipse(_, p, ..) => [Some(*p), None, None],
+            Feature::ArcCenter(_, p, ..) => [Some(*p), None, None],
         }
     }
 
@@ -192,6 +291,24 @@ impl Feature {
                 let r = a / (std::f32::consts::PI / *n as f32).cos();
                 p.bb(drawing).expand(r)
             }
+            Feature::Ellipse(_, p, rx, ry, ..) => {
+                let p = drawing.features.get(*p).unwrap();
+                p.bb(drawing).expand(rx.max(*ry))
+            }
+            Feature::ArcCenter(..) => {
+                use kurbo::Shape;
+                let r = self.kurbo_arc_center(drawing).unwrap().bounding_box();
+                egui::Rect {
+                    min: egui::Pos2 {
+                        x: r.x0 as f32,
+                        y: r.y0 as f32,
+                    },
+                    max: egui::Pos2 {
+                        x: r.x1 as f32,
+                        y: r.y1 as f32,
+                    },
+                }
+            }
         }
     }
 
@@ -291,6 +408,32 @@ impl Feature {
                     .powi(2)
                     .min(((x_diff.powi(2) + y_diff.powi(2)).sqrt() - a / vp.zoom).powi(2))
             }
+
+            Feature::Ellipse(_, p, rx, ry, rotation) => {
+                let p = match drawing.features.get(*p).unwrap() {
+                    Feature::Point(_, x1, y1) => egui::Pos2 { x: *x1, y: *y1 },
+                    _ => unreachable!(),
+                };
+                // Work in the frame of the ellipse, approximating the closest point
+                // on the ellipse by scaling the hover point onto its boundary.
+                let d = egui::emath::Rot2::from_angle(-rotation) * (vp.screen_to_point(hp) - p);
+                let k = ((d.x / rx).powi(2) + (d.y / ry).powi(2)).sqrt();
+                if k < f32::EPSILON {
+                    return (rx.min(*ry) / vp.zoom).powi(2);
+                }
+
+                ((d - d / k).length() / vp.zoom).powi(2)
+            }
+
+            Feature::ArcCenter(..) => {
+                let center = vp.translate_point(self.center_point(drawing));
+                let (start, end) = (
+                    vp.translate_point(self.start_point(drawing)),
+                    vp.translate_point(self.end_point(drawing)),
+                );
+
+                Arc { start, center, end }.distance_to_point_sq(&hp)
+            }
         }
     }
 
@@ -302,23 +445,40 @@ impl Feature {
         painter: &egui::Painter,
     ) {
         match self {
-            Feature::Point(meta, _, _) => {
-                painter.rect_filled(
-                    params
-                        .vp
-                        .translate_rect(self.bb(drawing))
-                        .expand2(POINT_SIZE),
-                    egui::Rounding::ZERO,
-                    if params.selected {
-                        params.colors.selected
-                    } else if params.hovered {
-                        params.colors.hover
-                    } else if meta.construction {
-                        params.colors.point.gamma_multiply(0.35)
-                    } else {
-                        params.colors.point
-                    },
-                );
+            Feature::Point(meta, x, y) => {
+                let color = if params.selected {
+                    params.colors.selected
+                } else if params.hovered {
+                    params.colors.hover
+                } else if meta.construction {
+                    params.colors.point.gamma_multiply(0.35)
+                } else {
+                    params.colors.point
+                };
+                let center = params.vp.translate_point(egui::Pos2 { x: *x, y: *y });
+
+                match params.point_style {
+                    PointStyle::Dot => painter.circle_filled(center, params.point_radius, color),
+                    PointStyle::Cross => {
+                        for arm in point_cross(center, params.point_radius) {
+                            painter.line_segment(
+                                arm,
+                                egui::Stroke {
+                                    width: 1.5 * params.stroke_width,
+                                    color,
+                                },
+                            );
+                        }
+                    }
+                    PointStyle::Square => painter.rect_filled(
+                        egui::Rect::from_center_size(
+                            center,
+                            egui::Vec2::splat(2. * params.point_radius),
+                        ),
+                        egui::Rounding::ZERO,
+                        color,
+                    ),
+                }
             }
 
             Feature::LineSegment(meta, p1, p2) => {
@@ -337,7 +497,7 @@ impl Feature {
                 painter.line_segment(
                     [p1, p2],
                     egui::Stroke {
-                        width: 1.,
+                        width: params.stroke_width,
                         color: if params.selected {
                             params.colors.selected
                         } else if params.hovered {
@@ -361,7 +521,7 @@ impl Feature {
                 } else {
                     params.colors.line
                 };
-                let stroke = egui::Stroke::new(1.0, color);
+                let stroke = egui::Stroke::new(params.stroke_width, color);
 
                 if let Some(a) = self.kurbo_arc(drawing) {
                     let start = drawing.features.get(*p1).unwrap().start_point(drawing);
@@ -398,7 +558,7 @@ impl Feature {
                     p,
                     *r / params.vp.zoom,
                     egui::Stroke {
-                        width: 1.,
+                        width: params.stroke_width,
                         color: if params.selected {
                             params.colors.selected
                         } else if params.hovered {
@@ -430,7 +590,7 @@ impl Feature {
                 };
 
                 let stroke = egui::Stroke {
-                    width: 1.,
+                    width: params.stroke_width,
                     color: if params.selected {
                         params.colors.selected
                     } else if params.hovered {
@@ -532,7 +692,7 @@ impl Feature {
                 let a = a / params.vp.zoom;
 
                 let stroke = egui::Stroke {
-                    width: 1.,
+                    width: params.stroke_width,
                     color: if params.selected {
                         params.colors.selected
                     } else if params.hovered {
@@ -556,6 +716,75 @@ impl Feature {
                     painter.line_segment([(x0, y0).into(), (x1, y1).into()], stroke);
                 }
             }
+
+            Feature::Ellipse(meta, ..) => {
+                let stroke = egui::Stroke {
+                    width: params.stroke_width,
+                    color: if params.selected {
+                        params.colors.selected
+                    } else if params.hovered {
+                        params.colors.hover
+                    } else if meta.construction {
+                        params.colors.line.gamma_multiply(0.35)
+                    } else {
+                        params.colors.line
+                    },
+                };
+
+                use kurbo::Shape;
+                if let Some(e) = self.kurbo_ellipse(drawing) {
+                    for s in e.path_segments(0.1) {
+                        if let kurbo::PathSeg::Cubic(kurbo::CubicBez { p0, p1, p2, p3 }) = s {
+                            let shape = egui::epaint::CubicBezierShape::from_points_stroke(
+                                [p0, p1, p2, p3].map(|p| {
+                                    params.vp.translate_point(egui::Pos2 {
+                                        x: p.x as f32,
+                                        y: p.y as f32,
+                                    })
+                                }),
+                                false,
+                                egui::Color32::TRANSPARENT,
+                                stroke,
+                            );
+                            painter.add(shape);
+                        }
+                    }
+                }
+            }
+
+            Feature::ArcCenter(meta, ..) => {
+                let color = if params.selected {
+                    params.colors.selected
+                } else if params.hovered {
+                    params.colors.hover
+                } else if meta.construction {
+                    params.colors.line.gamma_multiply(0.35)
+                } else {
+                    params.colors.line
+                };
+                let stroke = egui::Stroke::new(params.stroke_width, color);
+
+                if let Some(a) = self.kurbo_arc_center(drawing) {
+                    let start = self.start_point(drawing);
+
+                    let mut last = (start.x, start.y);
+                    a.to_cubic_beziers(0.1, |p1, p2, p| {
+                        let shape = egui::epaint::CubicBezierShape::from_points_stroke(
+                            [
+                                params.vp.translate_point(last.into()),
+                                params.vp.translate_point((p1.x as f32, p1.y as f32).into()),
+                                params.vp.translate_point((p2.x as f32, p2.y as f32).into()),
+                                params.vp.translate_point((p.x as f32, p.y as f32).into()),
+                            ],
+                            false,
+                            egui::Color32::TRANSPARENT,
+                            stroke,
+                        );
+                        painter.add(shape);
+                        last = (p.x as f32, p.y as f32);
+                    })
+                }
+            }
         }
     }
 
@@ -636,6 +865,31 @@ impl Feature {
                     ..SerializedFeature::default()
                 })
             }
+            Feature::Ellipse(meta, p, rx, ry, rotation) => {
+                let p_idx = fk_to_idx.get(p).ok_or(())?;
+
+                Ok(SerializedFeature {
+                    kind: "ellipse".to_string(),
+                    meta: meta.clone(),
+                    using_idx: vec![*p_idx],
+                    r: *rx,
+                    ry: Some(*ry),
+                    rotation: Some(*rotation),
+                    ..SerializedFeature::default()
+                })
+            }
+            Feature::ArcCenter(meta, p, r, start_deg, end_deg) => {
+                let p_idx = fk_to_idx.get(p).ok_or(())?;
+
+                Ok(SerializedFeature {
+                    kind: "arc_center".to_string(),
+                    meta: meta.clone(),
+                    using_idx: vec![*p_idx],
+                    r: *r,
+                    angles: Some((*start_deg, *end_deg)),
+                    ..SerializedFeature::default()
+                })
+            }
         }
     }
 
@@ -703,6 +957,37 @@ impl Feature {
                     sf.r,
                 ))
             }
+            "ellipse" => {
+                if sf.using_idx.is_empty() {
+                    return Err(());
+                }
+                let ry = sf.ry.ok_or(())?;
+                let rotation = sf.rotation.unwrap_or(0.0);
+                let radius_ok = |r: f32| r.is_finite() && r > 0.0;
+                if !radius_ok(sf.r) || !radius_ok(ry) || !rotation.is_finite() {
+                    return Err(());
+                }
+                Ok(Self::Ellipse(
+                    sf.meta,
+                    *idx_to_fk.get(&sf.using_idx[0]).ok_or(())?,
+                    sf.r,
+                    ry,
+                    rotation,
+                ))
+            }
+            "arc_center" => {
+                if sf.using_idx.is_empty() {
+                    return Err(());
+                }
+                let (start_deg, end_deg) = sf.angles.ok_or(())?;
+                Ok(Self::ArcCenter(
+                    sf.meta,
+                    *idx_to_fk.get(&sf.using_idx[0]).ok_or(())?,
+                    sf.r,
+                    start_deg,
+                    end_deg,
+                ))
+            }
             _ => Err(()),
         }
     }
@@ -746,6 +1031,51 @@ impl Feature {
         }
     }
 
+    fn kurbo_ellipse(&self, drawing: &Data) -> Option<kurbo::Ellipse> {
+        match self {
+            Feature::Ellipse(_, p, rx, ry, rotation) => {
+                let p = drawing.features.get(*p).unwrap().start_point(drawing);
+
+                Some(kurbo::Ellipse::new(
+                    (p.x as f64, p.y as f64),
+                    (*rx as f64, *ry as f64),
+                    *rotation as f64,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the arc described by an ArcCenter feature, sweeping from the start
+    /// angle to the end angle in the direction of increasing angle.
+    fn kurbo_arc_center(&self, drawing: &Data) -> Option<kurbo::Arc> {
+        match self {
+            Feature::ArcCenter(_, _, r, start_deg, end_deg) => {
+                let c = self.center_point(drawing);
+                let mut sweep = (end_deg - start_deg).rem_euclid(360.0);
+                if sweep < f32::EPSILON {
+                    sweep = 360.0;
+                }
+
+                Some(kurbo::Arc {
+                    center: (c.x as f64, c.y as f64).into(),
+                    radii: (*r as f64, *r as f64).into(),
+                    start_angle: start_deg.to_radians() as f64,
+                    sweep_angle: sweep.to_radians() as f64,
+                    x_rotation: 0.0,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn center_point(&self, drawing: &Data) -> egui::Pos2 {
+        match self {
+            Feature::ArcCenter(_, p, ..) => drawing.features.get(*p).unwrap().start_point(drawing),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn bezier_path(&self, drawing: &Data) -> kurbo::BezPath {
         let mut out = kurbo::BezPath::default();
 
@@ -855,6 +1185,17 @@ impl Feature {
                     }
                 }
             }
+
+            Feature::Ellipse(..) => {
+                if let Some(e) = self.kurbo_ellipse(drawing) {
+                    out = e.into_path(0.1);
+                }
+            }
+            Feature::ArcCenter(..) => {
+                if let Some(a) = self.kurbo_arc_center(drawing) {
+                    out = a.into_path(0.1);
+                }
+            }
         };
         out
     }
@@ -912,6 +1253,14 @@ impl Feature {
                         y: 0.0,
                     }
             }
+
+            Feature::Ellipse(_, p, rx, _ry, rotation) => {
+                drawing.features.get(*p).unwrap().start_point(drawing)
+                    + egui::Vec2::angled(*rotation) * *rx
+            }
+            Feature::ArcCenter(_, _, r, start_deg, _) => {
+                self.center_point(drawing) + egui::Vec2::angled(start_deg.to_radians()) * *r
+            }
         }
     }
 
@@ -967,6 +1316,14 @@ impl Feature {
                         y: 0.0,
                     }
             }
+
+            Feature::Ellipse(_, p, rx, _ry, rotation) => {
+                drawing.features.get(*p).unwrap().start_point(drawing)
+                    + egui::Vec2::angled(*rotation) * *rx
+            }
+            Feature::ArcCenter(_, _, r, _, end_deg) => {
+                self.center_point(drawing) + egui::Vec2::angled(end_deg.to_radians()) * *r
+            }
         }
     }
 }
@@ -1044,6 +1401,31 @@ mod tests {
                 ..SerializedFeature::default()
             }),
         );
+        assert_eq!(
+            Feature::Ellipse(FeatureMeta::default(), point_key, 6.9, 4.2, 0.5)
+                .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedFeature {
+                kind: "ellipse".to_string(),
+                meta: FeatureMeta::default(),
+                using_idx: vec![42],
+                r: 6.9,
+                ry: Some(4.2),
+                rotation: Some(0.5),
+                ..SerializedFeature::default()
+            }),
+        );
+        assert_eq!(
+            Feature::ArcCenter(FeatureMeta::default(), point_key, 5.0, 10.0, 100.0)
+                .serialize(&HashMap::from([(point_key, 42)])),
+            Ok(SerializedFeature {
+                kind: "arc_center".to_string(),
+                meta: FeatureMeta::default(),
+                using_idx: vec![42],
+                r: 5.0,
+                angles: Some((10.0, 100.0)),
+                ..SerializedFeature::default()
+            }),
+        );
     }
 
     #[test]
@@ -1125,5 +1507,135 @@ mod tests {
                 6.9,
             )),
         );
+        assert_eq!(
+            Feature::deserialize(
+                SerializedFeature {
+                    kind: "ellipse".to_string(),
+                    using_idx: vec![1],
+                    r: 6.9,
+                    ry: Some(4.2),
+                    rotation: Some(0.5),
+                    ..SerializedFeature::default()
+                },
+                &HashMap::from([(1, FeatureKey::null())]),
+            ),
+            Ok(Feature::Ellipse(
+                FeatureMeta::default(),
+                FeatureKey::null(),
+                6.9,
+                4.2,
+                0.5,
+            )),
+        );
+        // Missing ry
+        assert_eq!(
+            Feature::deserialize(
+                SerializedFeature {
+                    kind: "ellipse".to_string(),
+                    using_idx: vec![1],
+                    r: 6.9,
+                    ..SerializedFeature::default()
+                },
+                &HashMap::from([(1, FeatureKey::null())]),
+            ),
+            Err(()),
+        );
+        // Degenerate or non-finite radii
+        for (r, ry) in [
+            (0.0, 4.2),
+            (6.9, -1.0),
+            (f32::NAN, 4.2),
+            (6.9, f32::INFINITY),
+        ] {
+            assert_eq!(
+                Feature::deserialize(
+                    SerializedFeature {
+                        kind: "ellipse".to_string(),
+                        using_idx: vec![1],
+                        r,
+                        ry: Some(ry),
+                        ..SerializedFeature::default()
+                    },
+                    &HashMap::from([(1, FeatureKey::null())]),
+                ),
+                Err(()),
+            );
+        }
+        assert_eq!(
+            Feature::deserialize(
+                SerializedFeature {
+                    kind: "arc_center".to_string(),
+                    using_idx: vec![1],
+                    r: 5.0,
+                    angles: Some((10.0, 100.0)),
+                    ..SerializedFeature::default()
+                },
+                &HashMap::from([(1, FeatureKey::null())]),
+            ),
+            Ok(Feature::ArcCenter(
+                FeatureMeta::default(),
+                FeatureKey::null(),
+                5.0,
+                10.0,
+                100.0,
+            )),
+        );
+        // Missing angles
+        assert_eq!(
+            Feature::deserialize(
+                SerializedFeature {
+                    kind: "arc_center".to_string(),
+                    using_idx: vec![1],
+                    r: 5.0,
+                    ..SerializedFeature::default()
+                },
+                &HashMap::from([(1, FeatureKey::null())]),
+            ),
+            Err(()),
+        );
+    }
+
+    #[test]
+    fn arc_center_quarter() {
+        let mut data = Data::default();
+        let center = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 1.0, 1.0));
+        let a = Feature::ArcCenter(FeatureMeta::default(), center, 10.0, 0.0, 90.0);
+        let within = |a: egui::Pos2, b: egui::Pos2| a.distance(b) < 0.001;
+
+        assert!(within(a.start_point(&data), egui::Pos2::new(11.0, 1.0)));
+        assert!(within(a.end_point(&data), egui::Pos2::new(1.0, 11.0)));
+
+        let bb = a.bb(&data);
+        assert!(within(bb.min, egui::Pos2::new(1.0, 1.0)), "{:?}", bb);
+        assert!(within(bb.max, egui::Pos2::new(11.0, 11.0)), "{:?}", bb);
+
+        let path = a.bezier_path(&data);
+        assert!(matches!(
+            path.elements().first(),
+            Some(kurbo::PathEl::MoveTo(p)) if (p.x - 11.0).abs() < 0.001 && (p.y - 1.0).abs() < 0.001
+        ));
+        let end = path.elements().last().and_then(|e| e.end_point()).unwrap();
+        assert!((end.x - 1.0).abs() < 0.001 && (end.y - 11.0).abs() < 0.001);
+
+        // Wrapping past 360 degrees sweeps the short way around.
+        let a = Feature::ArcCenter(FeatureMeta::default(), center, 10.0, 315.0, 45.0);
+        let bb = a.bb(&data);
+        assert!((bb.max.x - 11.0).abs() < 0.001, "{:?}", bb);
+        assert!(bb.min.x > 1.0, "{:?}", bb);
+    }
+
+    #[test]
+    fn point_cross_centered() {
+        let center = egui::Pos2::new(12.5, -3.0);
+        let arms = point_cross(center, DEFAULT_POINT_RADIUS);
+        for [a, b] in arms {
+            assert_eq!(a.lerp(b, 0.5), center);
+            assert_eq!(a.distance(b), 2. * DEFAULT_POINT_RADIUS);
+        }
+        // One arm horizontal, the other vertical.
+        assert_eq!(arms[0][0].y, center.y);
+        assert_eq!(arms[1][0].x, center.x);
     }
 }
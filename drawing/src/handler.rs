@@ -7,12 +7,18 @@ pub enum ToolResponse {
     Handled,
     SwitchToPointer,
     NewPoint(egui::Pos2),
+    NewPointOnLine(FeatureKey, egui::Pos2), // line, screen position
+    NewPointOnCircle(FeatureKey, egui::Pos2), // circle, screen position
     NewLineSegment(FeatureKey, FeatureKey),
     NewArc(FeatureKey, FeatureKey),
-    NewCircle(FeatureKey, egui::Pos2),
+    NewCircle(FeatureKey, egui::Pos2, bool), // center, screen position, snap radius
     NewSpurGear(FeatureKey),
     NewRegularPoly(FeatureKey),
+    NewEllipse(FeatureKey),
+    NewArcCenter(FeatureKey),
     Delete(FeatureKey),
+    CircleToArc(FeatureKey),
+    ArcToCircle(FeatureKey),
 
     NewFixedConstraint(FeatureKey),
     NewLineLengthConstraint(FeatureKey),
@@ -22,6 +28,17 @@ pub enum ToolResponse {
     NewEqual(FeatureKey, FeatureKey),
     NewParallelLine(FeatureKey, FeatureKey),
     NewGlobalAngleConstraint(FeatureKey),
+    SelectionLinesEqual,
+    SelectionLinesParallel,
+    SelectionPointsOffset,
+    SelectionLinesMidpointFixed,
+    SelectionLinesLengthRange,
+    SelectionPointExpr,
+    SelectionPointAlongArc,
+    SelectionPointOnBisector,
+    SelectionPointOffsetFromLine,
+    SelectionLineTangentCircles,
+    SelectionThreeLinesEqualAngle,
 
     ConstraintDelete(ConstraintKey),
     ConstraintLinesEqualRemoveMultiplier(ConstraintKey),
@@ -46,6 +63,9 @@ impl Handler {
                 drawing.groups.remove(idx);
             }
             ToolResponse::NewPoint(pos) => {
+                if drawing.snap_points_enabled && self.new_point_at_intersection(drawing, pos) {
+                    return;
+                }
                 let pos = drawing.vp.screen_to_point(pos);
                 let p = Feature::Point(FeatureMeta::default(), pos.x, pos.y);
 
@@ -53,7 +73,61 @@ impl Handler {
                     return;
                 }
 
-                drawing.features.insert(p);
+                drawing.add_feature(p);
+            }
+            ToolResponse::NewPointOnLine(l_fk, pos) => {
+                if !drawing.snap_points_enabled {
+                    return self.handle(drawing, tools, ToolResponse::NewPoint(pos));
+                }
+                if self.new_point_at_intersection(drawing, pos) {
+                    return;
+                }
+                let pos = drawing.vp.screen_to_point(pos);
+                let (a, b) = match drawing.features.get(l_fk) {
+                    Some(Feature::LineSegment(_, p1, p2)) => {
+                        match (drawing.features.get(*p1), drawing.features.get(*p2)) {
+                            (Some(Feature::Point(_, x1, y1)), Some(Feature::Point(_, x2, y2))) => {
+                                (egui::Pos2 { x: *x1, y: *y1 }, egui::Pos2 { x: *x2, y: *y2 })
+                            }
+                            _ => return,
+                        }
+                    }
+                    _ => return,
+                };
+
+                let (pos, t) = crate::l::project_point_onto_line(pos, a, b);
+                let p = Feature::Point(FeatureMeta::default(), pos.x, pos.y);
+                if drawing.feature_exists(&p) {
+                    return;
+                }
+                let p_fk = drawing.add_feature(p);
+                drawing.add_constraint(Constraint::PointLerpLine(
+                    ConstraintMeta::default(),
+                    l_fk,
+                    p_fk,
+                    t,
+                ));
+            }
+
+            ToolResponse::NewPointOnCircle(c_fk, pos) => {
+                if !drawing.snap_points_enabled {
+                    return self.handle(drawing, tools, ToolResponse::NewPoint(pos));
+                }
+                let pos = drawing.vp.screen_to_point(pos);
+                let (center, r) = match drawing.features.get(c_fk) {
+                    Some(Feature::Circle(_, p, r)) => match drawing.features.get(*p) {
+                        Some(Feature::Point(_, x, y)) => (egui::Pos2 { x: *x, y: *y }, *r),
+                        _ => return,
+                    },
+                    _ => return,
+                };
+
+                let pos = crate::l::project_point_onto_circle(pos, center, r);
+                let p = Feature::Point(FeatureMeta::default(), pos.x, pos.y);
+                if drawing.feature_exists(&p) {
+                    return;
+                }
+                drawing.add_feature(p);
             }
 
             ToolResponse::NewLineSegment(p1, p2) => {
@@ -63,7 +137,7 @@ impl Handler {
                     return;
                 }
 
-                drawing.features.insert(l);
+                drawing.add_feature(l);
             }
 
             ToolResponse::NewArc(fk1, fk2) => {
@@ -80,7 +154,7 @@ impl Handler {
 
                 // Create the midpoint point.
                 let mid = p1.lerp(p2, 0.5);
-                let mid_fk = drawing.features.insert(Feature::Point(
+                let mid_fk = drawing.add_feature(Feature::Point(
                     FeatureMeta::default_construction(),
                     mid.x,
                     mid.y,
@@ -89,7 +163,7 @@ impl Handler {
                 // Create a line between the points if none exists.
                 let line_fk = match drawing.find_line_between(&fk1, &fk2) {
                     Some(fk) => fk,
-                    None => drawing.features.insert(Feature::LineSegment(
+                    None => drawing.add_feature(Feature::LineSegment(
                         FeatureMeta::default_construction(),
                         fk1,
                         fk2,
@@ -106,23 +180,27 @@ impl Handler {
 
                 // Finally, create the arc feature.
                 let a = Feature::Arc(FeatureMeta::default(), fk1, mid_fk, fk2);
-                drawing.features.insert(a);
+                drawing.add_feature(a);
 
                 tools.clear();
             }
-            ToolResponse::NewCircle(center, pos) => {
+            ToolResponse::NewCircle(center, pos, snap) => {
                 let pos = drawing.vp.screen_to_point(pos);
                 let center_pos = match drawing.features.get(center) {
                     Some(Feature::Point(_, x, y, ..)) => egui::Pos2 { x: *x, y: *y },
                     _ => unreachable!(),
                 };
 
-                let p = Feature::Circle(FeatureMeta::default(), center, center_pos.distance(pos));
+                let mut radius = center_pos.distance(pos);
+                if snap {
+                    radius = crate::tools::snap_radius(radius);
+                }
+                let p = Feature::Circle(FeatureMeta::default(), center, radius);
 
                 if drawing.feature_exists(&p) {
                     return;
                 }
-                drawing.features.insert(p);
+                drawing.add_feature(p);
                 tools.clear();
             }
             ToolResponse::NewSpurGear(p_center) => {
@@ -133,7 +211,7 @@ impl Handler {
                     return;
                 }
 
-                drawing.features.insert(g);
+                drawing.add_feature(g);
                 tools.clear();
             }
             ToolResponse::NewRegularPoly(p_center) => {
@@ -143,13 +221,41 @@ impl Handler {
                     return;
                 }
 
-                drawing.features.insert(g);
+                drawing.add_feature(g);
+                tools.clear();
+            }
+            ToolResponse::NewEllipse(p_center) => {
+                let e = Feature::Ellipse(FeatureMeta::default(), p_center, 6.0, 3.0, 0.0);
+
+                if drawing.feature_exists(&e) {
+                    return;
+                }
+
+                drawing.add_feature(e);
+                tools.clear();
+            }
+
+            ToolResponse::NewArcCenter(p_center) => {
+                let a = Feature::ArcCenter(FeatureMeta::default(), p_center, 5.0, 0.0, 90.0);
+
+                if drawing.feature_exists(&a) {
+                    return;
+                }
+
+                drawing.add_feature(a);
                 tools.clear();
             }
 
             ToolResponse::Delete(k) => {
                 drawing.delete_feature(k);
             }
+            ToolResponse::CircleToArc(k) => {
+                // Leave a quarter open, for the user to adjust.
+                drawing.circle_to_arc(k, 0.0, 270.0);
+            }
+            ToolResponse::ArcToCircle(k) => {
+                drawing.arc_to_circle(k);
+            }
             ToolResponse::ConstraintDelete(k) => {
                 drawing.delete_constraint(k);
             }
@@ -324,6 +430,179 @@ impl Handler {
                     _ => {}
                 }
             }
+            ToolResponse::SelectionLinesEqual | ToolResponse::SelectionLinesParallel => {
+                // Chain the selected lines: l1 to l2, l2 to l3, and so on.
+                let lines = drawing.selected_lines();
+                for pair in lines.windows(2) {
+                    drawing.add_constraint(match c {
+                        ToolResponse::SelectionLinesEqual => Constraint::LineLengthsEqual(
+                            ConstraintMeta::default(),
+                            pair[0],
+                            pair[1],
+                            None,
+                        ),
+                        _ => Constraint::LinesParallel(ConstraintMeta::default(), pair[0], pair[1]),
+                    });
+                }
+            }
+            ToolResponse::SelectionPointsOffset => {
+                // Keep the second point at its current offset from the first.
+                if let [p1, p2] = drawing.selected_points()[..] {
+                    if let (Some(Feature::Point(_, x1, y1)), Some(Feature::Point(_, x2, y2))) =
+                        (drawing.features.get(p1), drawing.features.get(p2))
+                    {
+                        let (dx, dy) = (x2 - x1, y2 - y1);
+                        drawing.add_constraint(Constraint::PointOffset(
+                            ConstraintMeta::default(),
+                            p1,
+                            p2,
+                            dx,
+                            dy,
+                        ));
+                    }
+                }
+            }
+            ToolResponse::SelectionLinesMidpointFixed => {
+                // Pin each line's midpoint where it currently is.
+                for l in drawing.selected_lines() {
+                    if let Some((a, b)) = drawing.get_line_points(l) {
+                        let mid = a.lerp(b, 0.5);
+                        drawing.add_constraint(Constraint::LineMidpointFixed(
+                            ConstraintMeta::default(),
+                            l,
+                            mid.x,
+                            mid.y,
+                        ));
+                    }
+                }
+            }
+            ToolResponse::SelectionLinesLengthRange => {
+                // Start each range closed around the line's current length,
+                // for the user to widen.
+                for l in drawing.selected_lines() {
+                    if let Some((a, b)) = drawing.get_line_points(l) {
+                        let d = a.distance(b);
+                        drawing.add_constraint(Constraint::LineLengthRange(
+                            ConstraintMeta::default(),
+                            l,
+                            d,
+                            d,
+                        ));
+                    }
+                }
+            }
+            ToolResponse::SelectionPointExpr => {
+                // Place the last selected point at the centroid of the others,
+                // as a starting formula for the user to edit.
+                let mut points = drawing.selected_points();
+                if let Some(p) = points.pop().filter(|_| !points.is_empty()) {
+                    let n = points.len();
+                    let centroid = |axis: char| {
+                        let sum = (0..n)
+                            .map(|i| format!("{}{}", axis, i))
+                            .collect::<Vec<_>>()
+                            .join(" + ");
+                        eq::Expression::parse(&format!("({}) / {}", sum, n), false).unwrap()
+                    };
+                    let (fx, fy) = (centroid('x'), centroid('y'));
+                    drawing.add_constraint(Constraint::PointExpr(
+                        ConstraintMeta::default(),
+                        p,
+                        points,
+                        fx,
+                        fy,
+                    ));
+                }
+            }
+            ToolResponse::SelectionPointOnBisector => {
+                // Keep the last selected point equidistant from the other two.
+                if let [a, b, p] = drawing.selected_points()[..] {
+                    drawing.add_constraint(Constraint::OnPerpendicularBisector(
+                        ConstraintMeta::default(),
+                        p,
+                        a,
+                        b,
+                    ));
+                }
+            }
+            ToolResponse::SelectionPointOffsetFromLine => {
+                // Keep the point at its current distance from the line, on the same side.
+                let (points, lines) = (drawing.selected_points(), drawing.selected_lines());
+                if let ([p], [l]) = (points.as_slice(), lines.as_slice()) {
+                    if let (Some(pos), Some((a, b))) =
+                        (drawing.get_point_pos(*p), drawing.get_line_points(*l))
+                    {
+                        let (ab, ap) = (b - a, pos - a);
+                        let cross = ab.x * ap.y - ab.y * ap.x;
+                        let d = cross.abs() / ab.length();
+                        drawing.add_constraint(Constraint::PointOffsetFromLine(
+                            ConstraintMeta::default(),
+                            *p,
+                            *l,
+                            if d.is_finite() { d } else { 0.0 },
+                            cross >= 0.0,
+                        ));
+                    }
+                }
+            }
+            ToolResponse::SelectionLineTangentCircles => {
+                // Tangent on whichever side of the line each center currently is, so
+                // the tangent is internal if the line already passes between them.
+                let (lines, circles) = (drawing.selected_lines(), drawing.selected_circles());
+                if let ([l], [c1, c2]) = (lines.as_slice(), circles.as_slice()) {
+                    let side = |c: &FeatureKey| match (
+                        drawing.features.get(*c),
+                        drawing.get_line_points(*l),
+                    ) {
+                        (Some(Feature::Circle(_, p, ..)), Some((a, b))) => {
+                            drawing.get_point_pos(*p).map(|p| {
+                                let (ab, ap) = (b - a, p - a);
+                                ab.x * ap.y - ab.y * ap.x >= 0.0
+                            })
+                        }
+                        _ => None,
+                    };
+                    let internal = matches!((side(c1), side(c2)), (Some(s1), Some(s2)) if s1 != s2);
+                    drawing.add_constraint(Constraint::LineTangentTwoCircles(
+                        ConstraintMeta::default(),
+                        *l,
+                        *c1,
+                        *c2,
+                        internal,
+                    ));
+                }
+            }
+            ToolResponse::SelectionThreeLinesEqualAngle => {
+                if let [l1, l2, l3] = drawing.selected_lines()[..] {
+                    drawing.add_constraint(Constraint::ThreeLinesEqualAngle(
+                        ConstraintMeta::default(),
+                        l1,
+                        l2,
+                        l3,
+                    ));
+                }
+            }
+            ToolResponse::SelectionPointAlongArc => {
+                // Keep the point at its current angle around the arc, clamped to the arc.
+                let (points, arcs) = (drawing.selected_points(), drawing.selected_arcs());
+                if let ([p], [a]) = (points.as_slice(), arcs.as_slice()) {
+                    if let (Some(arc), Some(Feature::Point(_, x, y))) =
+                        (drawing.get_arc(*a), drawing.features.get(*p))
+                    {
+                        let to_point = crate::l::Arc {
+                            end: egui::Pos2::new(*x, *y),
+                            ..arc
+                        };
+                        let t = (to_point.sweep() / arc.sweep()).clamp(0.0, 1.0);
+                        drawing.add_constraint(Constraint::PointAlongArc(
+                            ConstraintMeta::default(),
+                            *p,
+                            *a,
+                            if t.is_finite() { t } else { 0.0 },
+                        ));
+                    }
+                }
+            }
             ToolResponse::ConstraintLinesEqualRemoveMultiplier(ck) => {
                 match drawing.constraints.get_mut(ck) {
                     Some(Constraint::LineLengthsEqual(_meta, _l1, _l2, multiplier)) => {
@@ -396,7 +675,7 @@ impl Handler {
             ToolResponse::ArrayWizard(k, pos, info) => {
                 let mut last_point = k;
                 for n in 0..info.array_wizard_count {
-                    let new_k = drawing.features.insert(Feature::Point(
+                    let new_k = drawing.add_feature(Feature::Point(
                         FeatureMeta::default_construction(),
                         pos.x
                             + info
@@ -409,7 +688,7 @@ impl Handler {
                                 .extend((n + 1) as f32 * info.array_wizard_separation)
                                 .y,
                     ));
-                    let line = drawing.features.insert(Feature::LineSegment(
+                    let line = drawing.add_feature(Feature::LineSegment(
                         FeatureMeta::default_construction(),
                         last_point,
                         new_k,
@@ -437,4 +716,101 @@ impl Handler {
             }
         }
     }
+
+    /// Creates a point on the crossing of two lines near the given screen
+    /// position, constraining it to both lines. Returns false if there was
+    /// no crossing close enough to snap to.
+    fn new_point_at_intersection(&mut self, drawing: &mut Data, pos: egui::Pos2) -> bool {
+        let (l1, l2, pos) = match drawing.find_screen_line_intersection(pos) {
+            Some(i) => i,
+            None => return false,
+        };
+
+        let p_fk = drawing
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), pos.x, pos.y));
+        for l_fk in [l1, l2] {
+            if let Some((a, b)) = drawing.get_line_points(l_fk) {
+                let (_, t) = crate::l::project_point_onto_line(pos, a, b);
+                drawing.add_constraint(Constraint::PointLerpLine(
+                    ConstraintMeta::default(),
+                    l_fk,
+                    p_fk,
+                    t,
+                ));
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_lines_equal_chains() {
+        let mut data = Data::default();
+        let points: Vec<FeatureKey> = [(0.0, 0.0), (5.0, 0.0), (5.0, 3.0), (0.0, 8.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                data.features
+                    .insert(Feature::Point(FeatureMeta::default(), x, y))
+            })
+            .collect();
+        let lines: Vec<FeatureKey> = points
+            .windows(2)
+            .map(|p| {
+                data.features
+                    .insert(Feature::LineSegment(FeatureMeta::default(), p[0], p[1]))
+            })
+            .collect();
+        for l in lines.iter().rev() {
+            data.select_feature(*l, true);
+        }
+        data.select_feature(points[0], true);
+
+        Handler::default().handle(
+            &mut data,
+            &mut Toolbar::default(),
+            ToolResponse::SelectionLinesEqual,
+        );
+
+        let mut constraints: Vec<(FeatureKey, FeatureKey)> = data
+            .constraints_iter()
+            .filter_map(|(_, c)| match c {
+                Constraint::LineLengthsEqual(_, l1, l2, None) => Some((*l1, *l2)),
+                _ => None,
+            })
+            .collect();
+        constraints.sort();
+        let mut want = vec![(lines[2], lines[1]), (lines[1], lines[0])];
+        want.sort();
+        assert_eq!(constraints, want);
+        assert_eq!(data.constraints_iter().count(), 2);
+    }
+
+    #[test]
+    fn new_point_on_line_existing() {
+        let mut data = Data::default();
+        let a = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 0.0, 0.0));
+        let b = data
+            .features
+            .insert(Feature::Point(FeatureMeta::default(), 10.0, 0.0));
+        let l = data
+            .features
+            .insert(Feature::LineSegment(FeatureMeta::default(), a, b));
+
+        // Clicking the end of the line projects onto the existing endpoint.
+        let at = data.vp.translate_point(egui::Pos2::new(10.0, 0.0));
+        Handler::default().handle(
+            &mut data,
+            &mut Toolbar::default(),
+            ToolResponse::NewPointOnLine(l, at),
+        );
+        assert_eq!(data.features_iter().count(), 3);
+        assert_eq!(data.constraints_iter().count(), 0);
+    }
 }
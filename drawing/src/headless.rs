@@ -0,0 +1,233 @@
+//! Loading, solving and exporting of drawings without any UI.
+use crate::data::ExportErr;
+use crate::{Data, SerializedDrawing};
+
+/// Output formats which a drawing can be converted to.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ExportFormat {
+    Dxf,
+    OpenSCAD,
+    Stl,
+    Obj,
+    Svg,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Dxf => "dxf",
+            ExportFormat::OpenSCAD => "scad",
+            ExportFormat::Stl => "stl",
+            ExportFormat::Obj => "obj",
+            ExportFormat::Svg => "svg",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Dxf => "AutoCAD DXF",
+            ExportFormat::OpenSCAD => "OpenSCAD",
+            ExportFormat::Stl => "STL",
+            ExportFormat::Obj => "OBJ",
+            ExportFormat::Svg => "SVG",
+        }
+    }
+
+    pub fn all() -> &'static [ExportFormat] {
+        &[
+            ExportFormat::Dxf,
+            ExportFormat::OpenSCAD,
+            ExportFormat::Stl,
+            ExportFormat::Obj,
+            ExportFormat::Svg,
+        ]
+    }
+}
+
+/// Loads and solves the given drawing, returning it exported in the
+/// given format. The drawing's flatten tolerance is used.
+pub fn convert(serialized: SerializedDrawing, format: ExportFormat) -> Result<Vec<u8>, ExportErr> {
+    let mut data = Data::default();
+    data.load(serialized).map_err(|_| ExportErr::LoadFailed)?;
+    if let Some(err) = data.last_solve_error {
+        return Err(ExportErr::SolverInconsistent(err));
+    }
+
+    export(&data, format)
+}
+
+/// Exports an already-solved drawing in the given format, honoring the
+/// drawing's export preferences.
+pub fn export(data: &Data, format: ExportFormat) -> Result<Vec<u8>, ExportErr> {
+    let tolerance = data.props.flatten_tolerance;
+    match format {
+        ExportFormat::Dxf => data.serialize_dxf(tolerance).map(|s| s.into_bytes()),
+        ExportFormat::OpenSCAD => data.serialize_openscad(tolerance).map(|s| s.into_bytes()),
+        ExportFormat::Svg => data.serialize_svg(tolerance).map(|s| s.into_bytes()),
+        ExportFormat::Stl => {
+            let solid = data.as_solid()?;
+            let tolerance = data.props.mesh_tolerance();
+            Ok(match data.props.stl_ascii {
+                Some(()) => crate::l::three_d::solid_to_stl_ascii(solid, tolerance),
+                None => crate::l::three_d::solid_to_stl(solid, tolerance),
+            })
+        }
+        ExportFormat::Obj => {
+            // Named after the boundary group, without materials as only
+            // the one file is returned.
+            let solid = data.as_solid()?;
+            let name = data
+                .groups
+                .iter()
+                .find(|g| g.typ == crate::GroupType::Boundary && !g.name.trim().is_empty())
+                .map_or("part".to_string(), |g| g.name.clone());
+            let (obj, _mtl) = crate::l::three_d::solid_to_obj_named(
+                vec![(name, solid)],
+                data.props.mesh_tolerance(),
+                None,
+            );
+            Ok(obj)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SerializedConstraint, SerializedFeature, SerializedGroup};
+
+    fn square() -> SerializedDrawing {
+        let pt = |x: f32, y: f32| SerializedFeature {
+            kind: "pt".to_string(),
+            x,
+            y,
+            ..SerializedFeature::default()
+        };
+        let line = |a: usize, b: usize| SerializedFeature {
+            kind: "line".to_string(),
+            using_idx: vec![a, b],
+            ..SerializedFeature::default()
+        };
+
+        SerializedDrawing {
+            features: vec![
+                pt(0.0, 0.0),
+                pt(9.0, 1.0),
+                pt(10.0, 10.0),
+                pt(1.0, 9.0),
+                line(0, 1),
+                line(1, 2),
+                line(2, 3),
+                line(3, 0),
+            ],
+            constraints: vec![
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (0.0, 0.0),
+                    feature_idx: vec![0],
+                    ..SerializedConstraint::default()
+                },
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (10.0, 0.0),
+                    feature_idx: vec![1],
+                    ..SerializedConstraint::default()
+                },
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (10.0, 10.0),
+                    feature_idx: vec![2],
+                    ..SerializedConstraint::default()
+                },
+                SerializedConstraint {
+                    kind: "fixed".to_string(),
+                    at: (0.0, 10.0),
+                    feature_idx: vec![3],
+                    ..SerializedConstraint::default()
+                },
+            ],
+            groups: vec![SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Boundary".into(),
+                features_idx: vec![4, 5, 6, 7],
+                ..SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        }
+    }
+
+    #[test]
+    fn load_solve_dxf() {
+        let dxf = String::from_utf8(convert(square(), ExportFormat::Dxf).unwrap()).unwrap();
+
+        assert!(dxf.starts_with("0\nSECTION\n2\nHEADER\n"));
+        assert!(dxf.ends_with("EOF"));
+        assert_eq!(dxf.matches("\nLINE\n").count(), 4);
+        // The solver should have pulled the points onto their fixed positions.
+        let lines: Vec<&str> = dxf.lines().collect();
+        for pair in lines.chunks(2) {
+            if matches!(pair[0], "10" | "20" | "11" | "21") {
+                let v: f64 = pair[1].parse().unwrap();
+                assert!(v.abs() < 0.001 || (v.abs() - 10.0).abs() < 0.001, "{}", v);
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_tolerance() {
+        let mut data = Data::default();
+        data.load(SerializedDrawing {
+            features: vec![
+                SerializedFeature {
+                    kind: "pt".to_string(),
+                    ..SerializedFeature::default()
+                },
+                SerializedFeature {
+                    kind: "circle".to_string(),
+                    using_idx: vec![0],
+                    r: 10.0,
+                    ..SerializedFeature::default()
+                },
+            ],
+            groups: vec![SerializedGroup {
+                typ: crate::GroupType::Boundary,
+                name: "Boundary".into(),
+                features_idx: vec![1],
+                ..SerializedGroup::default()
+            }],
+            ..SerializedDrawing::default()
+        })
+        .unwrap();
+        data.props.stl_ascii = Some(());
+
+        let mut facets = |tolerance: f64| {
+            data.props.mesh_tolerance = Some(tolerance);
+            let stl = String::from_utf8(export(&data, ExportFormat::Stl).unwrap()).unwrap();
+            stl.matches("facet normal").count()
+        };
+        let (coarse, fine) = (facets(1.0), facets(0.01));
+        assert!(coarse > 0);
+        assert!(fine > coarse, "{} <= {}", fine, coarse);
+    }
+
+    #[test]
+    fn obj_named_after_boundary() {
+        let mut d = square();
+        d.groups[0].name = "Mounting plate".into();
+        let obj = String::from_utf8(convert(d, ExportFormat::Obj).unwrap()).unwrap();
+
+        assert!(obj.starts_with("o Mounting_plate\nv "), "{}", &obj[..40]);
+        assert!(!obj.contains("mtllib") && !obj.contains("usemtl"));
+        assert!(obj.lines().any(|l| l.starts_with("f ")));
+    }
+
+    #[test]
+    fn load_failure() {
+        let mut d = square();
+        d.features[4].using_idx = vec![0, 99];
+        assert_eq!(
+            convert(d, ExportFormat::OpenSCAD),
+            Err(ExportErr::LoadFailed)
+        );
+    }
+}
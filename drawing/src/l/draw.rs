@@ -287,3 +287,126 @@ pub fn length_tick(
         );
     }
 }
+
+/// Approximate screen-space size of a dimension label.
+pub const DIMENSION_LABEL_SIZE: egui::Vec2 = egui::Vec2::new(60., 15.);
+const LABEL_SPACING: f32 = 2.0;
+const MAX_LABEL_NUDGES: usize = 8;
+
+/// Returns the smallest vertical shift which moves the label clear of all the
+/// occupied rects, trying alternately below and above. Returns zero if the
+/// label is already clear or no shift within reach would clear it.
+pub fn label_nudge(label: egui::Rect, occupied: &[egui::Rect]) -> egui::Vec2 {
+    let step = label.height() + LABEL_SPACING;
+    for i in 0..=2 * MAX_LABEL_NUDGES {
+        let n = ((i + 1) / 2) as f32;
+        let nudge = egui::Vec2::new(0., if i % 2 == 1 { n * step } else { -n * step });
+        if !occupied
+            .iter()
+            .any(|o| o.intersects(label.translate(nudge)))
+        {
+            return nudge;
+        }
+    }
+    egui::Vec2::ZERO
+}
+
+const CARDINAL_ARROW_OFFSET: f32 = 8.0;
+const CARDINAL_ARROW_LENGTH: f32 = 12.0;
+
+/// Returns the screen-space (tail, tip) of the arrow showing the direction
+/// a cardinal constraint enforces. The arrow sits beside the midpoint of the
+/// line, offset along the axis in the direction it points.
+pub fn cardinal_arrow_points(
+    mid: egui::Pos2,
+    axis: &crate::Axis,
+    negative: bool,
+) -> (egui::Pos2, egui::Pos2) {
+    let dir = match (axis, negative) {
+        (crate::Axis::LeftRight, false) => egui::Vec2::RIGHT,
+        (crate::Axis::LeftRight, true) => egui::Vec2::LEFT,
+        (crate::Axis::TopBottom, false) => egui::Vec2::DOWN,
+        (crate::Axis::TopBottom, true) => egui::Vec2::UP,
+    };
+
+    let tail = mid + dir * CARDINAL_ARROW_OFFSET;
+    (tail, tail + dir * CARDINAL_ARROW_LENGTH)
+}
+
+pub fn cardinal_arrow(
+    a: egui::Pos2,
+    b: egui::Pos2,
+    axis: &crate::Axis,
+    negative: bool,
+    painter: &egui::Painter,
+    params: &crate::PaintParams,
+) {
+    let mid = params.vp.translate_point(a.lerp(b, 0.5));
+    let (tail, tip) = cardinal_arrow_points(mid, axis, negative);
+
+    arrow(
+        tail,
+        tip,
+        2.0,
+        egui::Stroke {
+            width: 1.,
+            color: egui::Color32::LIGHT_BLUE,
+        },
+        painter,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Axis;
+
+    #[test]
+    fn label_nudge_resolves_overlap() {
+        let a = egui::Rect::from_center_size(egui::pos2(100., 100.), DIMENSION_LABEL_SIZE);
+        let b = a.translate(egui::vec2(20., 5.));
+        assert!(a.intersects(b));
+
+        let nudge = label_nudge(b, &[a]);
+        assert_eq!(
+            nudge,
+            egui::vec2(0., DIMENSION_LABEL_SIZE.y + LABEL_SPACING)
+        );
+        assert!(!a.intersects(b.translate(nudge)));
+
+        // Blocked just below, so it goes further out.
+        let below = a.translate(egui::vec2(0., DIMENSION_LABEL_SIZE.y + LABEL_SPACING));
+        let nudge = label_nudge(b, &[a, below]);
+        assert_eq!(
+            nudge,
+            egui::vec2(0., 2. * (DIMENSION_LABEL_SIZE.y + LABEL_SPACING))
+        );
+        assert!(!below.intersects(b.translate(nudge)));
+
+        // Nothing to avoid.
+        let far = a.translate(egui::vec2(200., 0.));
+        assert_eq!(label_nudge(far, &[a]), egui::Vec2::ZERO);
+    }
+
+    #[test]
+    fn cardinal_arrow_placement() {
+        let mid = egui::Pos2 { x: 50.0, y: 20.0 };
+
+        assert_eq!(
+            cardinal_arrow_points(mid, &Axis::LeftRight, false),
+            (egui::pos2(58.0, 20.0), egui::pos2(70.0, 20.0))
+        );
+        assert_eq!(
+            cardinal_arrow_points(mid, &Axis::LeftRight, true),
+            (egui::pos2(42.0, 20.0), egui::pos2(30.0, 20.0))
+        );
+        assert_eq!(
+            cardinal_arrow_points(mid, &Axis::TopBottom, false),
+            (egui::pos2(50.0, 28.0), egui::pos2(50.0, 40.0))
+        );
+        assert_eq!(
+            cardinal_arrow_points(mid, &Axis::TopBottom, true),
+            (egui::pos2(50.0, 12.0), egui::pos2(50.0, 0.0))
+        );
+    }
+}
@@ -140,6 +140,13 @@ impl Arc {
         (angle_start, angle_end)
     }
 
+    /// Returns the angle swept from the start to the end, in radians. Arcs
+    /// sweep in the direction of increasing angle.
+    pub fn sweep(&self) -> f32 {
+        let (start, end) = self.angles();
+        (end - start).rem_euclid(std::f32::consts::TAU)
+    }
+
     pub fn distance_to_point_sq(&self, point: &Pos2) -> f32 {
         let d_center = self.center.distance(*point);
         let mut a_point = f32::atan2(point.y - self.center.y, point.x - self.center.x);
@@ -170,3 +177,219 @@ impl Arc {
         }
     }
 }
+
+/// Returns the point where the line segments a1-a2 and b1-b2 cross, if they do.
+pub fn line_intersection(a1: Pos2, a2: Pos2, b1: Pos2, b2: Pos2) -> Option<Pos2> {
+    LineSegment { p1: a1, p2: a2 }.intersection_line(&LineSegment { p1: b1, p2: b2 })
+}
+
+/// Projects p onto the line segment a-b, returning the projected point
+/// and how far along the segment it lies (0 = a, 1 = b).
+pub fn project_point_onto_line(p: Pos2, a: Pos2, b: Pos2) -> (Pos2, f32) {
+    let ab = b - a;
+    let l2 = ab.length_sq();
+    if l2 < f32::EPSILON {
+        return (a, 0.0);
+    }
+
+    let t = ((p - a).dot(ab) / l2).clamp(0.0, 1.0);
+    (a + ab * t, t)
+}
+
+/// Projects p onto the circumference of the circle with the given center and radius.
+pub fn project_point_onto_circle(p: Pos2, center: Pos2, r: f32) -> Pos2 {
+    let d = p - center;
+    if d.length_sq() < f32::EPSILON {
+        // Every point on the circumference is equally close, pick the rightmost.
+        return center + egui::Vec2::new(r, 0.0);
+    }
+    center + d.normalized() * r
+}
+
+/// Converts a radius and angle in degrees about the origin into X & Y
+/// coordinates. Drawing Y points down, so angles are measured from the +X
+/// axis counter-clockwise as seen on screen.
+pub fn polar_to_cartesian(r: f32, theta_deg: f32) -> (f32, f32) {
+    let (sin, cos) = theta_deg.to_radians().sin_cos();
+    (r * cos, -r * sin)
+}
+
+/// Converts X & Y coordinates into a radius and angle in degrees about the
+/// origin, the inverse of polar_to_cartesian(). The angle is in (-180, 180].
+pub fn cartesian_to_polar(x: f32, y: f32) -> (f32, f32) {
+    (x.hypot(y), (-y).atan2(x).to_degrees())
+}
+
+/// Rounds the value to the nearest multiple of step. Non-positive steps
+/// leave the value as-is.
+pub fn snap_to_increment(value: f32, step: f32) -> f32 {
+    if step <= 0.0 {
+        return value;
+    }
+    (value / step).round() * step
+}
+
+/// Returns the signed area of a polygon, positive if its points go
+/// counter-clockwise (with Y pointing up).
+pub fn polygon_signed_area(points: &[kurbo::Point]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Reverses the polygon if needed, so it winds counter-clockwise if ccw
+/// is set and clockwise otherwise.
+pub fn wind_polygon(points: &mut [kurbo::Point], ccw: bool) {
+    let area = polygon_signed_area(points);
+    if area != 0.0 && (area > 0.0) != ccw {
+        points.reverse();
+    }
+}
+
+/// Offsets a closed polygon outwards by d (inwards if d is negative), using
+/// mitered corners. The polygon must repeat its first point as its last;
+/// open polylines are returned unchanged.
+pub fn offset_polygon(points: &[kurbo::Point], d: f64) -> Vec<kurbo::Point> {
+    if points.len() < 4 || points[0] != points[points.len() - 1] {
+        return points.to_vec();
+    }
+    let ring = &points[..points.len() - 1];
+    let n = ring.len();
+
+    // The winding tells us which side of each edge is outside.
+    let sign = if polygon_signed_area(ring) > 0.0 {
+        1.0
+    } else {
+        -1.0
+    };
+    let normal = |a: kurbo::Point, b: kurbo::Point| {
+        let e = b - a;
+        kurbo::Vec2::new(e.y, -e.x).normalize() * sign
+    };
+
+    let mut out: Vec<kurbo::Point> = (0..n)
+        .map(|i| {
+            let (prev, p, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            let (n1, n2) = (normal(prev, p), normal(p, next));
+            let m = (n1 + n2).normalize();
+            let cos = m.dot(n1);
+            if !m.is_finite() || cos.abs() < 1e-6 {
+                p + n1 * d
+            } else {
+                p + m * (d / cos)
+            }
+        })
+        .collect();
+    out.push(out[0]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_onto_line() {
+        let (a, b) = (Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0));
+
+        assert_eq!(
+            project_point_onto_line(Pos2::new(2.5, 3.0), a, b),
+            (Pos2::new(2.5, 0.0), 0.25)
+        );
+        // Beyond either end is clamped to the segment.
+        assert_eq!(
+            project_point_onto_line(Pos2::new(-4.0, -1.0), a, b),
+            (a, 0.0)
+        );
+        assert_eq!(
+            project_point_onto_line(Pos2::new(14.0, 1.0), a, b),
+            (b, 1.0)
+        );
+        // Degenerate segment.
+        assert_eq!(project_point_onto_line(Pos2::new(3.0, 3.0), a, a), (a, 0.0));
+
+        let (p, t) = project_point_onto_line(Pos2::new(0.0, 10.0), a, Pos2::new(10.0, 10.0));
+        assert!((p - Pos2::new(5.0, 5.0)).length() < 0.0001);
+        assert!((t - 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn project_onto_circle() {
+        let c = Pos2::new(2.0, 2.0);
+
+        assert_eq!(
+            project_point_onto_circle(Pos2::new(5.0, 2.0), c, 2.0),
+            Pos2::new(4.0, 2.0)
+        );
+        assert_eq!(
+            project_point_onto_circle(Pos2::new(2.0, 1.0), c, 2.0),
+            Pos2::new(2.0, 0.0)
+        );
+        assert_eq!(project_point_onto_circle(c, c, 2.0), Pos2::new(4.0, 2.0));
+
+        let p = project_point_onto_circle(Pos2::new(7.0, 7.0), c, 3.0);
+        assert!((p.distance(c) - 3.0).abs() < 0.0001);
+        assert!((p.x - p.y).abs() < 0.0001);
+    }
+
+    #[test]
+    fn snap_increment() {
+        assert_eq!(snap_to_increment(7.4, 1.0), 7.0);
+        assert_eq!(snap_to_increment(7.6, 1.0), 8.0);
+        assert_eq!(snap_to_increment(7.4, 0.5), 7.5);
+        assert_eq!(snap_to_increment(7.4, 0.0), 7.4);
+    }
+
+    #[test]
+    fn polar_conversion() {
+        let close = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| {
+            (x1 - x2).abs() < 0.0001 && (y1 - y2).abs() < 0.0001
+        };
+
+        assert!(close(polar_to_cartesian(10.0, 0.0), (10.0, 0.0)));
+        // Upwards on screen, which is -Y.
+        assert!(close(polar_to_cartesian(10.0, 90.0), (0.0, -10.0)));
+        assert!(close(
+            polar_to_cartesian(2.0, 225.0),
+            (-2f32.sqrt(), 2f32.sqrt())
+        ));
+        assert!(close(polar_to_cartesian(0.0, 45.0), (0.0, 0.0)));
+
+        assert!(close(cartesian_to_polar(0.0, -5.0), (5.0, 90.0)));
+        assert!(close(cartesian_to_polar(-3.0, 4.0), (5.0, -126.869896)));
+        let (r, theta) = cartesian_to_polar(-3.0, 4.0);
+        assert!(close(polar_to_cartesian(r, theta), (-3.0, 4.0)));
+    }
+
+    #[test]
+    fn offset_square() {
+        let pts = |v: &[(f64, f64)]| -> Vec<kurbo::Point> {
+            v.iter().map(|(x, y)| kurbo::Point::new(*x, *y)).collect()
+        };
+        let ccw = pts(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.), (0., 0.)]);
+        let mut cw = ccw.clone();
+        cw.reverse();
+
+        let grown = pts(&[(-1., -1.), (11., -1.), (11., 11.), (-1., 11.), (-1., -1.)]);
+        let shrunk = pts(&[(1., 1.), (9., 1.), (9., 9.), (1., 9.), (1., 1.)]);
+        let close = |a: &[kurbo::Point], b: &[kurbo::Point]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.distance(*b) < 1e-9)
+        };
+
+        // Orientation doesn't change which way is out.
+        assert!(close(&offset_polygon(&ccw, 1.0), &grown));
+        assert!(close(&offset_polygon(&ccw, -1.0), &shrunk));
+        let mut cw_grown = grown.clone();
+        cw_grown.reverse();
+        assert!(close(&offset_polygon(&cw, 1.0), &cw_grown));
+
+        // Open polylines are left alone.
+        let open = pts(&[(0., 0.), (10., 0.), (10., 10.)]);
+        assert_eq!(offset_polygon(&open, 1.0), open);
+    }
+}
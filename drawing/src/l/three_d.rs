@@ -2,19 +2,72 @@ use crate::data::CADOp;
 use std::collections::{HashMap, HashSet};
 use truck_modeling::*;
 
-fn wire_from_path(path: kurbo::BezPath, verts: &mut HashMap<(u64, u64), Vertex>) -> Wire {
-    let mut vert = |p: kurbo::Point| {
-        let (x, y) = (p.x, p.y);
-
-        let k = (x.to_bits(), y.to_bits());
-        if let Some(v) = verts.get(&k) {
-            v.clone()
-        } else {
-            let v = builder::vertex(Point3::new(x, y, 0.0));
-            verts.insert(k, v.clone());
-            v
+fn vertex_at(p: kurbo::Point, verts: &mut HashMap<(u64, u64), Vertex>) -> Vertex {
+    let k = (p.x.to_bits(), p.y.to_bits());
+    if let Some(v) = verts.get(&k) {
+        v.clone()
+    } else {
+        let v = builder::vertex(Point3::new(p.x, p.y, 0.0));
+        verts.insert(k, v.clone());
+        v
+    }
+}
+
+/// Returns the circle a closed path traces, if it is made up entirely
+/// of cubic segments lying on one circle (such as the path of a circle
+/// feature, or of two arcs forming a circle).
+fn path_circle(path: &kurbo::BezPath) -> Option<kurbo::Circle> {
+    use kurbo::{ParamCurve, Shape};
+    const ROUNDNESS: f64 = 1e-3;
+
+    let bb = path.bounding_box();
+    let (center, r) = (bb.center(), bb.width() / 2.0);
+    if r <= 0.0 || (bb.height() / 2.0 - r).abs() > r * ROUNDNESS {
+        return None;
+    }
+
+    let mut segments = 0;
+    for seg in path.segments() {
+        let c = match seg {
+            kurbo::PathSeg::Cubic(c) => c,
+            _ => return None,
+        };
+        for t in [0.0, 0.25, 0.5, 0.75] {
+            if (c.eval(t).distance(center) - r).abs() > r * ROUNDNESS {
+                return None;
+            }
         }
-    };
+        segments += 1;
+    }
+
+    (segments >= 2).then_some(kurbo::Circle::new(center, r))
+}
+
+/// Builds a wire of two exact circular arcs, going counter-clockwise
+/// if ccw is set and clockwise otherwise.
+fn circle_wire(c: kurbo::Circle, ccw: bool, verts: &mut HashMap<(u64, u64), Vertex>) -> Wire {
+    let (cx, cy, r) = (c.center.x, c.center.y, c.radius);
+    let right = vertex_at(kurbo::Point::new(cx + r, cy), verts);
+    let left = vertex_at(kurbo::Point::new(cx - r, cy), verts);
+    let (up, down) = (Point3::new(cx, cy + r, 0.0), Point3::new(cx, cy - r, 0.0));
+
+    let (first, second) = if ccw { (up, down) } else { (down, up) };
+    vec![
+        builder::circle_arc(&right, &left, first),
+        builder::circle_arc(&left, &right, second),
+    ]
+    .into()
+}
+
+fn wire_from_path(path: kurbo::BezPath, verts: &mut HashMap<(u64, u64), Vertex>) -> Wire {
+    // Circles get exact arcs, so their swept faces are truly round rather
+    // than an approximation limited by the bezier fit.
+    if let Some(c) = path_circle(&path) {
+        use kurbo::Shape;
+        return circle_wire(c, path.area() > 0.0, verts);
+    }
+
+    let mut vert = |p: kurbo::Point| vertex_at(p, verts);
 
     let mut edges = Vec::with_capacity(path.elements().len());
     let mut last: Option<Vertex> = None;
@@ -85,6 +138,18 @@ pub fn extrude_from_paths(
 ) -> Solid {
     use kurbo::Shape;
     let mut verts: HashMap<(u64, u64), Vertex> = HashMap::with_capacity(32);
+    // Symmetric extrusions are built as an extrusion on the top and
+    // another on the bottom, each of half the thickness.
+    let ops: Vec<(CADOp, kurbo::BezPath)> = ops
+        .into_iter()
+        .flat_map(|(op, p)| match op {
+            CADOp::ExtrudeSymmetric(amt) => vec![
+                (CADOp::Extrude(amt / 2.0, false), p.clone()),
+                (CADOp::Extrude(amt / 2.0, true), p),
+            ],
+            op => vec![(op, p)],
+        })
+        .collect();
     let op_parent_idx = op_parents(&ops);
 
     let ea = exterior.area();
@@ -238,6 +303,7 @@ pub fn extrude_from_paths(
                         base.extend(b.into_iter().skip(1));
                         done_parents.insert(i as isize, (base.len() - 1, top_idx));
                     }
+                    CADOp::ExtrudeSymmetric(_) => unreachable!(), // split up above
                 }
                 done.insert(i);
             }
@@ -260,7 +326,7 @@ pub fn extrude_from_paths(
     Solid::new(vec![base])
 }
 
-pub fn solid_to_stl(s: Solid, tolerance: f64) -> Vec<u8> {
+fn solid_to_mesh(s: Solid, tolerance: f64) -> truck_polymesh::PolygonMesh {
     use truck_meshalgo::tessellation::MeshableShape;
     use truck_meshalgo::tessellation::MeshedShape;
     let mut mesh = s.triangulation(tolerance).to_polygon();
@@ -269,22 +335,28 @@ pub fn solid_to_stl(s: Solid, tolerance: f64) -> Vec<u8> {
     mesh.put_together_same_attrs()
         .remove_degenerate_faces()
         .remove_unused_attrs();
+    mesh
+}
+
+pub fn solid_to_stl(s: Solid, tolerance: f64) -> Vec<u8> {
+    solid_to_stl_typed(s, tolerance, truck_polymesh::stl::STLType::Binary)
+}
+
+pub fn solid_to_stl_ascii(s: Solid, tolerance: f64) -> Vec<u8> {
+    solid_to_stl_typed(s, tolerance, truck_polymesh::stl::STLType::ASCII)
+}
+
+fn solid_to_stl_typed(s: Solid, tolerance: f64, typ: truck_polymesh::stl::STLType) -> Vec<u8> {
+    let mesh = solid_to_mesh(s, tolerance);
 
     let mut out = Vec::with_capacity(1024);
-    truck_polymesh::stl::write(&mesh, &mut out, truck_polymesh::stl::STLType::Binary).unwrap();
+    truck_polymesh::stl::write(&mesh, &mut out, typ).unwrap();
 
     out
 }
 
 pub fn solid_to_obj(s: Solid, tolerance: f64) -> Vec<u8> {
-    use truck_meshalgo::tessellation::MeshableShape;
-    use truck_meshalgo::tessellation::MeshedShape;
-    let mut mesh = s.triangulation(tolerance).to_polygon();
-
-    use truck_meshalgo::filters::OptimizingFilter;
-    mesh.put_together_same_attrs()
-        .remove_degenerate_faces()
-        .remove_unused_attrs();
+    let mesh = solid_to_mesh(s, tolerance);
 
     let mut out = Vec::with_capacity(1024);
     truck_polymesh::obj::write(&mesh, &mut out).unwrap();
@@ -292,6 +364,83 @@ pub fn solid_to_obj(s: Solid, tolerance: f64) -> Vec<u8> {
     out
 }
 
+const MTL_COLORS: [(f32, f32, f32); 4] = [
+    (0.8, 0.8, 0.8),
+    (0.3, 0.5, 0.8),
+    (0.8, 0.4, 0.3),
+    (0.4, 0.7, 0.4),
+];
+
+/// Emits each named part as its own object in a single OBJ, each using a
+/// material of the same name. Returns the OBJ and the MTL text, the latter
+/// of which should be saved as `mtl_file` alongside the OBJ. Without an
+/// `mtl_file`, the OBJ references no materials.
+pub fn solid_to_obj_named(
+    parts: Vec<(String, Solid)>,
+    tolerance: f64,
+    mtl_file: Option<&str>,
+) -> (Vec<u8>, String) {
+    use std::fmt::Write;
+    let (mut obj, mut mtl) = (String::with_capacity(1024), String::new());
+    if let Some(mtl_file) = mtl_file {
+        writeln!(obj, "mtllib {}", mtl_file).unwrap();
+    }
+
+    // OBJ indices are global across objects, so offset each part
+    // by the number of attributes emitted before it.
+    let (mut pos_offset, mut uv_offset, mut nor_offset) = (1, 1, 1);
+    for (i, (name, s)) in parts.into_iter().enumerate() {
+        let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+        let mesh = solid_to_mesh(s, tolerance);
+
+        let (r, g, b) = MTL_COLORS[i % MTL_COLORS.len()];
+        writeln!(mtl, "newmtl {}\nKd {} {} {}\n", name, r, g, b).unwrap();
+
+        writeln!(obj, "o {}", name).unwrap();
+        if mtl_file.is_some() {
+            writeln!(obj, "usemtl {}", name).unwrap();
+        }
+        for p in mesh.positions() {
+            writeln!(obj, "v {} {} {}", p.x, p.y, p.z).unwrap();
+        }
+        for uv in mesh.uv_coords() {
+            writeln!(obj, "vt {} {}", uv.x, uv.y).unwrap();
+        }
+        for n in mesh.normals() {
+            writeln!(obj, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+        }
+        for face in mesh.face_iter() {
+            obj.push('f');
+            for v in face {
+                match (v.uv, v.nor) {
+                    (None, None) => write!(obj, " {}", v.pos + pos_offset),
+                    (Some(uv), None) => {
+                        write!(obj, " {}/{}", v.pos + pos_offset, uv + uv_offset)
+                    }
+                    (None, Some(nor)) => {
+                        write!(obj, " {}//{}", v.pos + pos_offset, nor + nor_offset)
+                    }
+                    (Some(uv), Some(nor)) => write!(
+                        obj,
+                        " {}/{}/{}",
+                        v.pos + pos_offset,
+                        uv + uv_offset,
+                        nor + nor_offset
+                    ),
+                }
+                .unwrap();
+            }
+            obj.push('\n');
+        }
+
+        pos_offset += mesh.positions().len();
+        uv_offset += mesh.uv_coords().len();
+        nor_offset += mesh.normals().len();
+    }
+
+    (obj.into_bytes(), mtl)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -516,6 +665,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extrude_circle_exact() {
+        use kurbo::Shape;
+
+        let circle = kurbo::Circle::new((10.0, 10.0), 5.0).into_path(0.1);
+        assert!(path_circle(&circle).is_some());
+        let square = kurbo::Rect::new(1.0, 1.0, 5.0, 5.0).into_path(0.1);
+        assert!(path_circle(&square).is_none());
+
+        let cylinder = extrude_from_paths(circle, vec![], 4.0);
+        // Two circular arcs make up the side, plus the top and bottom.
+        assert_eq!(cylinder.face_iter().count(), 4);
+
+        let tris = |tolerance: f64| {
+            let mesh = cylinder.triangulation(tolerance).to_polygon();
+            for p in mesh.positions() {
+                let d = ((p.x - 10.0).powi(2) + (p.y - 10.0).powi(2)).sqrt();
+                // Every vertex lies on the circle, or is the cap center.
+                assert!((d - 5.0).abs() < 1e-9 || d < 5.0, "{:?} at {}", p, d);
+            }
+            mesh.tri_faces().len() + 2 * mesh.quad_faces().len()
+        };
+        let (coarse, fine) = (tris(0.5), tris(0.01));
+        assert!(fine > coarse, "{} <= {}", fine, coarse);
+
+        // The side should be round on any sample, not just at mesh vertices.
+        let side = cylinder.face_iter().nth(1).unwrap();
+        for e in side.boundary_iters()[0].clone() {
+            let curve = e.oriented_curve();
+            for i in 0..=10 {
+                let (t0, t1) = curve.parameter_range();
+                let p = curve.subs(t0 + (t1 - t0) * i as f64 / 10.0);
+                let d = ((p.x - 10.0).powi(2) + (p.y - 10.0).powi(2)).sqrt();
+                assert!((d - 5.0).abs() < 1e-9, "{:?} at {}", p, d);
+            }
+        }
+    }
+
     #[test]
     fn extrude_hole() {
         use kurbo::Shape;
@@ -629,4 +816,51 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn obj_named_parts() {
+        use kurbo::Shape;
+
+        let part = |x: f64| {
+            extrude_from_paths(
+                kurbo::Rect {
+                    x0: x,
+                    y0: 1.0,
+                    x1: x + 4.0,
+                    y1: 5.0,
+                }
+                .into_path(0.1),
+                vec![],
+                2.0,
+            )
+        };
+
+        let (obj, mtl) = solid_to_obj_named(
+            vec![
+                ("left part".into(), part(1.0)),
+                ("right".into(), part(10.0)),
+            ],
+            0.1,
+            Some("parts.mtl"),
+        );
+        let obj = String::from_utf8(obj).unwrap();
+
+        assert_eq!(obj.lines().filter(|l| l.starts_with("o ")).count(), 2);
+        assert!(obj.contains("o left_part\nusemtl left_part\n"));
+        assert!(obj.contains("o right\nusemtl right\n"));
+        assert!(obj.starts_with("mtllib parts.mtl\n"));
+        assert_eq!(mtl.lines().filter(|l| l.starts_with("newmtl ")).count(), 2);
+
+        // Faces of the second part must index past the vertices of the first.
+        let (first, second) = obj.split_at(obj.find("o right").unwrap());
+        let first_verts = first.lines().filter(|l| l.starts_with("v ")).count();
+        let min_second_idx = second
+            .lines()
+            .filter(|l| l.starts_with("f "))
+            .flat_map(|l| l.split_whitespace().skip(1))
+            .map(|v| v.split('/').next().unwrap().parse::<usize>().unwrap())
+            .min()
+            .unwrap();
+        assert_eq!(min_second_idx, first_verts + 1);
+    }
 }
@@ -3,21 +3,60 @@
 pub mod l;
 
 mod data;
-pub use data::{group::*, Data, Hover, SelectedElement, SerializedDrawing, Viewport};
+pub use data::{
+    group::*, ArcMetrics, Data, DimensionEntry, ExportErr, Hover, LoadErr, OutlineResult, RefImage,
+    SelectedElement, SerializedDrawing, SolveHook, SolveStatus, Viewport, SERIALIZED_VERSION,
+};
 mod feature;
-pub use feature::{Feature, FeatureKey, FeatureMeta, GearInfo, SerializedFeature};
+pub use feature::{Feature, FeatureKey, FeatureMeta, GearInfo, PointStyle, SerializedFeature};
 mod constraints;
 pub use constraints::{
     Axis, Constraint, ConstraintKey, ConstraintMeta, DimensionDisplay, SerializedConstraint,
 };
 pub mod handler;
+pub mod headless;
 mod system;
+pub mod testing;
 pub use handler::Handler;
 pub mod tools;
 
 pub const CONSTRUCTION_IMG: egui::ImageSource<'static> =
     egui::include_image!("../../assets/emoji_u1f6a7.png");
 
+/// Temp data recording the widget ID of the canvas last shown.
+const CANVAS_ID: &str = "drawing_canvas";
+/// How far (in screen pixels) each arrow key press pans the view.
+const KEYBOARD_PAN_AMOUNT: f32 = 40.0;
+/// Radius (in screen pixels) of the solver status badge.
+const SOLVE_BADGE_RADIUS: f32 = 6.0;
+/// Opacity of unselected geometry while the selection is isolated.
+const ISOLATE_DIM_ALPHA: f32 = 0.2;
+/// Opacity of the shading drawn over the part when fill is enabled.
+const FILL_ALPHA: f32 = 0.12;
+/// Width (in screen pixels) the scale bar is kept at or below.
+const SCALE_BAR_TARGET_WIDTH: f32 = 120.0;
+/// Half the length (in screen pixels) of the arms of the origin crosshair.
+const ORIGIN_MARKER_SIZE: f32 = 10.0;
+
+/// Parses a dimension typed by the user, which may be an arithmetic
+/// expression such as `25.4/2`. Falls back to parsing a plain number.
+/// Returns the widget ID of the drawing canvas last shown, so other panels
+/// can tell its keyboard focus apart from their own widgets'.
+pub fn canvas_id(ctx: &egui::Context) -> Option<egui::Id> {
+    ctx.data(|d| d.get_temp::<egui::Id>(egui::Id::new(CANVAS_ID)))
+}
+
+fn parse_dimension(input: &str) -> Option<f64> {
+    match eq::Expression::parse(input, false) {
+        Ok(exp) => exp
+            .evaluate_1(&mut eq::StaticResolver::new([]))
+            .ok()
+            .map(|c| c.as_f64()),
+        Err(_) => input.trim().parse().ok(),
+    }
+    .filter(|v| v.is_finite())
+}
+
 /// Colors describes the colors with which different elements should be styled.
 #[derive(Clone, Debug, Default)]
 pub struct Colors {
@@ -28,6 +67,22 @@ pub struct Colors {
     text: egui::Color32,
 }
 
+impl Colors {
+    /// Returns the colors a feature should be painted with. While the selection
+    /// is isolated, everything other than selected or hovered geometry is dimmed.
+    fn for_feature(&self, selected: bool, hovered: bool, isolate: bool) -> Colors {
+        if !isolate || selected || hovered {
+            return self.clone();
+        }
+        Colors {
+            point: self.point.gamma_multiply(ISOLATE_DIM_ALPHA),
+            line: self.line.gamma_multiply(ISOLATE_DIM_ALPHA),
+            text: self.text.gamma_multiply(ISOLATE_DIM_ALPHA),
+            ..self.clone()
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PaintParams {
     selected: bool,
@@ -37,11 +92,17 @@ pub struct PaintParams {
     vp: Viewport,
     colors: Colors,
     font_id: egui::FontId,
+    point_style: PointStyle,
+    /// Width (in screen pixels) of lines and curves.
+    stroke_width: f32,
+    /// Radius (in screen pixels) of points.
+    point_radius: f32,
 }
 
 #[derive(Clone, Debug, Copy)]
 enum DragState {
     SelectBox(egui::Pos2),
+    RefImage(egui::Vec2), // offset of the image from the cursor
     Point(FeatureKey, egui::Vec2),
     Line(FeatureKey, egui::Vec2, egui::Vec2, egui::Vec2), // p1, p2, offset
     Circle(FeatureKey, egui::Vec2, egui::Vec2),           // center, offset
@@ -71,6 +132,7 @@ pub struct Widget<'a> {
     length_ticks: Vec<f32>,
     center_next_frame: bool,
     autozoom_next_frame: bool,
+    id: egui::Id,
 }
 
 impl<'a> Widget<'a> {
@@ -82,6 +144,7 @@ impl<'a> Widget<'a> {
         let length_ticks = Vec::with_capacity(8);
         let center_next_frame = false;
         let autozoom_next_frame = false;
+        let id = egui::Id::new("drawing");
 
         Self {
             drawing,
@@ -90,9 +153,17 @@ impl<'a> Widget<'a> {
             length_ticks,
             center_next_frame,
             autozoom_next_frame,
+            id,
         }
     }
 
+    /// Sets the source of the ID under which the widget keeps its input
+    /// state. Widgets showing different drawings must use different sources.
+    pub fn id_source(mut self, id_source: impl std::hash::Hash) -> Self {
+        self.id = egui::Id::new(id_source);
+        self
+    }
+
     // handle_inputs returns the what the user is interacting with in the drawing, if any.
     fn handle_input(
         &mut self,
@@ -103,39 +174,43 @@ impl<'a> Widget<'a> {
     ) -> Option<Input> {
         // Handle: zooming
         if let Some(hp) = hp {
-            // println!("hp: {:?}", hp);
-            use std::ops::Add;
-            let hp = hp.add(-egui::Vec2 {
-                x: response.rect.width() / 2.,
-                y: response.rect.height() / 2.,
-            });
-
             let scroll_delta = ui.input(|i| i.scroll_delta);
             if scroll_delta.y != 0. {
-                let m = self.drawing.vp.translate_point(hp);
-
-                self.drawing.vp.zoom *= f32::exp(-1. * scroll_delta.y * 0.1823216 / 230.);
-                if self.drawing.vp.zoom < 0.05 {
-                    self.drawing.vp.zoom = 0.05;
-                }
-                let after = self.drawing.vp.translate_point(hp);
-
-                // println!("{:?} => {:?}", m, after);
-
-                self.drawing.vp.x -= (m.x - after.x) * self.drawing.vp.zoom;
-                self.drawing.vp.y -= (m.y - after.y) * self.drawing.vp.zoom;
+                self.drawing
+                    .scroll_zoom(scroll_delta.y, hp, response.rect.center());
             }
         }
 
         // Handle: panning
-        if response.dragged_by(egui::PointerButton::Secondary) {
-            let egui::Vec2 { x, y } = response.drag_delta();
-            self.drawing.vp.x -= x * self.drawing.vp.zoom;
-            self.drawing.vp.y -= y * self.drawing.vp.zoom;
+        if response.dragged_by(self.drawing.pan_button)
+            || response.dragged_by(egui::PointerButton::Middle)
+        {
+            self.drawing.vp.pan(response.drag_delta());
+        }
+        if response.has_focus() && self.drawing.selected_map.is_empty() {
+            let delta = ui.input(|i| {
+                let mut d = egui::Vec2::ZERO;
+                if i.key_pressed(egui::Key::ArrowLeft) {
+                    d.x += KEYBOARD_PAN_AMOUNT;
+                }
+                if i.key_pressed(egui::Key::ArrowRight) {
+                    d.x -= KEYBOARD_PAN_AMOUNT;
+                }
+                if i.key_pressed(egui::Key::ArrowUp) {
+                    d.y += KEYBOARD_PAN_AMOUNT;
+                }
+                if i.key_pressed(egui::Key::ArrowDown) {
+                    d.y -= KEYBOARD_PAN_AMOUNT;
+                }
+                d
+            });
+            if delta != egui::Vec2::ZERO {
+                self.drawing.vp.pan(delta);
+            }
         }
 
         // Handle: selection, dragging
-        let state_id = egui::Id::new("_drawing_input_state");
+        let state_id = self.id.with("_drawing_input_state");
         let current_input = if let Some(hp) = hp {
             let drag_state = match (
                 hover,
@@ -145,6 +220,22 @@ impl<'a> Widget<'a> {
                 self.drawing.drag_features_enabled,
                 self.drawing.drag_dimensions_enabled,
             ) {
+                // Dragging an unlocked reference image
+                (Hover::None, true, false, false, _, _)
+                    if self.drawing.reference_image.as_ref().is_some_and(|img| {
+                        !img.locked
+                            && img
+                                .drawing_rect()
+                                .contains(self.drawing.vp.screen_to_point(hp))
+                    }) =>
+                {
+                    let img = self.drawing.reference_image.as_ref().unwrap();
+                    let offset =
+                        egui::Pos2::new(img.x, img.y) - self.drawing.vp.screen_to_point(hp);
+                    let state = DragState::RefImage(offset);
+                    ui.memory_mut(|mem| mem.data.insert_temp(state_id, state));
+                    Some(state)
+                }
                 // dragging a box to select
                 (Hover::None, true, false, false, _, _) => {
                     let state = DragState::SelectBox(self.drawing.vp.screen_to_point(hp));
@@ -162,7 +253,7 @@ impl<'a> Widget<'a> {
                     false,
                     true,
                     _,
-                ) => {
+                ) if !self.drawing.feature_locked(*k) => {
                     let offset = self.drawing.vp.screen_to_point(hp) - egui::Pos2::new(*px, *py);
                     let state = DragState::Point(*k, offset);
                     ui.memory_mut(|mem| mem.data.insert_temp(state_id, state));
@@ -179,7 +270,7 @@ impl<'a> Widget<'a> {
                     false,
                     true,
                     _,
-                ) => {
+                ) if !self.drawing.feature_locked(*k) => {
                     let (a, b) = self.drawing.get_line_points(*k).unwrap();
 
                     let offset = self.drawing.vp.screen_to_point(hp).to_vec2();
@@ -198,7 +289,7 @@ impl<'a> Widget<'a> {
                     false,
                     true,
                     _,
-                ) => {
+                ) if !self.drawing.feature_locked(*k) => {
                     let center_pt = if let Some(Feature::Point(_, x, y, ..)) =
                         self.drawing.features.get(*center)
                     {
@@ -321,6 +412,16 @@ impl<'a> Widget<'a> {
                     }
                 }
 
+                (Some(DragState::RefImage(offset)), _) => {
+                    if released {
+                        ui.memory_mut(|mem| mem.data.remove::<DragState>(state_id));
+                    }
+                    if let Some(img) = self.drawing.reference_image.as_mut() {
+                        let p = self.drawing.vp.screen_to_point(hp) + offset;
+                        (img.x, img.y) = (p.x, p.y);
+                    }
+                    None
+                }
                 (Some(DragState::Point(fk, offset)), _) => {
                     if released {
                         ui.memory_mut(|mem| mem.data.remove::<DragState>(state_id));
@@ -453,6 +554,32 @@ impl<'a> Widget<'a> {
             self.drawing.selection_delete();
         }
 
+        // Handle: Alt-X / Alt-Y centers selected dimension labels
+        if response.has_focus() && !self.drawing.selected_map.is_empty() {
+            let (x, y) = ui.input(|i| {
+                (
+                    i.modifiers.alt && i.key_pressed(egui::Key::X),
+                    i.modifiers.alt && i.key_pressed(egui::Key::Y),
+                )
+            });
+            if x || y {
+                self.drawing.selection_labels_center(x);
+            }
+        }
+
+        // Handle: Alt-I toggles isolating the selection
+        if response.has_focus() && ui.input(|i| i.modifiers.alt && i.key_pressed(egui::Key::I)) {
+            self.drawing.isolate_selection = !self.drawing.isolate_selection;
+        }
+
+        // Handle: G toggles construction on the selection
+        if response.has_focus()
+            && !self.drawing.selected_map.is_empty()
+            && ui.input(|i| i.key_pressed(egui::Key::G))
+        {
+            self.drawing.toggle_selection_construction();
+        }
+
         // Handle: Q cycles dragging settings
         if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Q)) {
             self.drawing.cycle_drag_setting();
@@ -461,6 +588,43 @@ impl<'a> Widget<'a> {
         current_input
     }
 
+    fn solve_badge_rect(rect: egui::Rect) -> egui::Rect {
+        egui::Rect::from_center_size(
+            rect.right_top()
+                + egui::Vec2 {
+                    x: -3.0 * SOLVE_BADGE_RADIUS,
+                    y: 3.0 * SOLVE_BADGE_RADIUS,
+                },
+            egui::Vec2::splat(4.0 * SOLVE_BADGE_RADIUS),
+        )
+    }
+
+    fn draw_solve_badge(
+        &self,
+        painter: &egui::Painter,
+        response: &egui::Response,
+        badge_rect: egui::Rect,
+        hovered: bool,
+    ) {
+        let status = self.drawing.solve_status();
+        painter.circle(
+            badge_rect.center(),
+            SOLVE_BADGE_RADIUS,
+            status.color(),
+            egui::Stroke::new(if hovered { 2.0 } else { 1.0 }, egui::Color32::DARK_GRAY),
+        );
+
+        if hovered {
+            response.clone().on_hover_text_at_pointer(match status {
+                SolveStatus::Solved => "Solved.".to_string(),
+                _ => format!(
+                    "Solver inconsistent, avg err: {:.3}mm\n\nClick to solve again.",
+                    self.drawing.last_solve_error.unwrap_or(0.0)
+                ),
+            });
+        }
+    }
+
     fn set_focus(&self, ui: &egui::Ui, response: &egui::Response) {
         ui.memory_mut(|mem| {
             mem.request_focus(response.id);
@@ -468,7 +632,10 @@ impl<'a> Widget<'a> {
                 response.id,
                 egui::EventFilter {
                     escape: true,
-                    ..Default::default()
+                    // Arrow keys pan, rather than moving focus elsewhere,
+                    // and Tab is left for the selection panel.
+                    arrows: true,
+                    tab: true,
                 },
             );
         });
@@ -498,6 +665,14 @@ impl<'a> Widget<'a> {
     ) {
         self.length_ticks.clear();
 
+        if let Some(img) = &self.drawing.reference_image {
+            Self::draw_reference_image(ui, painter, img, base_params);
+        }
+        if self.drawing.show_fill {
+            self.draw_fill(painter, base_params);
+        }
+        self.draw_origin(painter, base_params);
+
         // Draw features, points first
         for point_pass in [true, false] {
             for (k, v) in self.drawing.features_iter() {
@@ -528,13 +703,23 @@ impl<'a> Widget<'a> {
                 let pp = PaintParams {
                     hovered,
                     selected,
+                    colors: base_params.colors.for_feature(
+                        selected,
+                        hovered,
+                        self.drawing.isolate_selection,
+                    ),
                     ..base_params.clone()
                 };
                 v.paint(self.drawing, k, &pp, painter);
             }
         }
 
-        // Draw constraints
+        // Draw constraints, keeping labels apart unless one is being dragged
+        if matches!(current_input, Some(Input::ConstraintDrag(..))) {
+            self.drawing.label_nudges.clear();
+        } else {
+            self.drawing.separate_dimension_labels();
+        }
         for (k, v) in self.drawing.constraints_iter() {
             let hovered = match hover {
                 Hover::Constraint { k: hk, .. } => hk == k,
@@ -551,7 +736,9 @@ impl<'a> Widget<'a> {
                 selected,
                 ..base_params.clone()
             };
-            v.paint(self.drawing, k, &pp, painter);
+            self.drawing
+                .displayed_constraint(k, v)
+                .paint(self.drawing, k, &pp, painter);
         }
 
         // Draw equal ticks
@@ -605,9 +792,130 @@ impl<'a> Widget<'a> {
         self.tools
             .paint(ui, painter, response, hp, &base_params, self.drawing);
 
+        self.draw_scale_bar(painter, base_params);
         self.draw_debug(ui, painter, hp, &base_params);
     }
 
+    /// Returns the longest 1, 2 or 5 times a power of ten millimeters which
+    /// spans no more than the given number of screen pixels at the given zoom,
+    /// along with the number of decimal places needed to print it.
+    fn scale_bar_length(zoom: f32, target_px: f32) -> (f32, usize) {
+        let max_mm = target_px * zoom;
+        let exp = max_mm.log10().floor() as i32;
+        let base = 10f32.powi(exp);
+        let mul = [5.0, 2.0, 1.0]
+            .into_iter()
+            .find(|m| m * base <= max_mm)
+            .unwrap_or(1.0);
+        (mul * base, (-exp).max(0) as usize)
+    }
+
+    /// Draws the reference image behind everything else, once its pixels
+    /// are loaded.
+    fn draw_reference_image(
+        ui: &egui::Ui,
+        painter: &egui::Painter,
+        img: &RefImage,
+        base_params: &PaintParams,
+    ) {
+        let bytes = match &img.bytes {
+            Some(bytes) => bytes.clone(),
+            None => return,
+        };
+        let rect = base_params.vp.translate_rect(img.drawing_rect());
+        let poll = egui::Image::from_bytes(img.uri(), bytes).load_for_size(ui.ctx(), rect.size());
+        if let Ok(egui::load::TexturePoll::Ready { texture }) = poll {
+            painter.image(
+                texture.id,
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE.gamma_multiply(img.opacity.clamp(0.0, 1.0)),
+            );
+        }
+    }
+
+    /// Shades the part beneath the geometry. Nothing is drawn if the groups
+    /// don't form a valid part.
+    fn draw_fill(&self, painter: &egui::Painter, base_params: &PaintParams) {
+        let tris = match self
+            .drawing
+            .fill_triangles(self.drawing.props.flatten_tolerance)
+        {
+            Ok(tris) => tris,
+            Err(_) => return,
+        };
+
+        let color = base_params.colors.line.gamma_multiply(FILL_ALPHA);
+        let mut mesh = egui::Mesh::default();
+        for tri in tris {
+            let idx = mesh.vertices.len() as u32;
+            for p in tri {
+                mesh.colored_vertex(base_params.vp.translate_point(p), color);
+            }
+            mesh.add_triangle(idx, idx + 1, idx + 2);
+        }
+        painter.add(egui::Shape::mesh(mesh));
+    }
+
+    /// Marks the drawing origin with a crosshair, circling it if a point is
+    /// fixed there so that a missing origin stands out.
+    fn draw_origin(&self, painter: &egui::Painter, base_params: &PaintParams) {
+        let center = base_params.vp.translate_point(egui::Pos2::ZERO);
+        if !base_params.rect.expand(ORIGIN_MARKER_SIZE).contains(center) {
+            return;
+        }
+
+        let color = base_params.colors.text.gamma_multiply(0.6);
+        let stroke = egui::Stroke { width: 1., color };
+        let (h, v) = (
+            egui::Vec2::X * ORIGIN_MARKER_SIZE,
+            egui::Vec2::Y * ORIGIN_MARKER_SIZE,
+        );
+        painter.line_segment([center - h, center + h], stroke);
+        painter.line_segment([center - v, center + v], stroke);
+
+        if let Some(p) = self
+            .drawing
+            .origin_point()
+            .and_then(|fk| self.drawing.get_point_pos(fk))
+        {
+            painter.circle_stroke(
+                base_params.vp.translate_point(p),
+                ORIGIN_MARKER_SIZE * 0.6,
+                egui::Stroke {
+                    width: 1.5,
+                    color: base_params.colors.selected,
+                },
+            );
+        }
+    }
+
+    fn draw_scale_bar(&self, painter: &egui::Painter, base_params: &PaintParams) {
+        let (mm, decimals) = Widget::scale_bar_length(self.drawing.vp.zoom, SCALE_BAR_TARGET_WIDTH);
+        if !mm.is_finite() || mm <= 0.0 {
+            return;
+        }
+        let width = mm / self.drawing.vp.zoom;
+
+        let right = base_params.rect.right_bottom() + egui::Vec2 { x: -12.0, y: -12.0 };
+        let left = right - egui::Vec2 { x: width, y: 0.0 };
+        let stroke = egui::Stroke {
+            width: 1.,
+            color: base_params.colors.text,
+        };
+        painter.line_segment([left, right], stroke);
+        for end in [left, right] {
+            painter.line_segment([end, end - egui::Vec2 { x: 0.0, y: 5.0 }], stroke);
+        }
+        painter.text(
+            left.lerp(right, 0.5) - egui::Vec2 { x: 0.0, y: 3.0 },
+            egui::Align2::CENTER_BOTTOM,
+            format!("{:.*} mm", decimals, mm),
+            base_params.font_id.clone(),
+            base_params.colors.text,
+        );
+    }
+
     fn show_line_dimension_popover(&mut self, ui: &egui::Ui, ck: ConstraintKey) {
         if let Some(Constraint::LineLength(_, fk, _, _, dd)) = self.drawing.constraints.get(ck) {
             if let Some(Feature::LineSegment(_, f1, f2)) = self.drawing.features.get(*fk) {
@@ -639,8 +947,10 @@ impl<'a> Widget<'a> {
                             egui::Frame::popup(ui.style()).show(ui, |ui| {
                                 let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
 
-                                let dv =
-                                    ui.add_sized([75., text_height * 1.4], egui::DragValue::new(d));
+                                let dv = ui.add_sized(
+                                    [75., text_height * 1.4],
+                                    egui::DragValue::new(d).custom_parser(parse_dimension),
+                                );
 
                                 if dv.changed() {
                                     if *d < 0. {
@@ -651,9 +961,9 @@ impl<'a> Widget<'a> {
                                 if dv.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Escape))
                                 {
                                     ui.memory_mut(|mem| {
-                                        mem.data.remove::<DragState>(egui::Id::new(
-                                            "_drawing_input_state",
-                                        ))
+                                        mem.data.remove::<DragState>(
+                                            self.id.with("_drawing_input_state"),
+                                        )
                                     });
                                 }
                             });
@@ -733,6 +1043,8 @@ impl<'a> Widget<'a> {
                                         }
                                         ui.add_space(4.);
 
+                                        ui.toggle_value(&mut meta.locked, "🔒")
+                                            .on_hover_text("Prevent dragging");
                                         ui.add(egui::Checkbox::without_text(
                                             &mut meta.construction,
                                         ));
@@ -744,16 +1056,8 @@ impl<'a> Widget<'a> {
                             });
 
                             // If constrained to fixed co-ordinates, show those coords
-                            if let Some(Constraint::Fixed(_, _, x, y)) = constraints
-                                .get_using_feature_and_type(
-                                    &k,
-                                    std::mem::discriminant(&Constraint::Fixed(
-                                        ConstraintMeta::default(),
-                                        k,
-                                        0.,
-                                        0.,
-                                    )),
-                                )
+                            if let Some((_, _, x, y)) =
+                                constraints.iter_fixed().find(|(_, fk, ..)| *fk == k)
                             {
                                 ui.add_space(4.);
                                 ui.horizontal(|ui| {
@@ -761,7 +1065,10 @@ impl<'a> Widget<'a> {
                                     ui.with_layout(
                                         egui::Layout::right_to_left(egui::Align::TOP),
                                         |ui| {
-                                            ui.label(format!("({}, {})", x, y));
+                                            let (r, theta) = crate::l::cartesian_to_polar(x, y);
+                                            ui.label(format!("({}, {})", x, y)).on_hover_text(
+                                                format!("r {:.3} ∠ {:.2}°", r, theta),
+                                            );
                                         },
                                     );
                                 });
@@ -943,6 +1250,8 @@ impl<'a> Widget<'a> {
                                         }
                                         ui.add_space(4.);
 
+                                        ui.toggle_value(&mut meta.locked, "🔒")
+                                            .on_hover_text("Prevent dragging");
                                         ui.add(egui::Checkbox::without_text(
                                             &mut meta.construction,
                                         ));
@@ -954,17 +1263,8 @@ impl<'a> Widget<'a> {
                             });
 
                             // If constrained to a certain length, show that
-                            if let Some(Constraint::LineLength(_, _, length, cardinality, ..)) =
-                                constraints.get_using_feature_and_type(
-                                    &k,
-                                    std::mem::discriminant(&Constraint::LineLength(
-                                        ConstraintMeta::default(),
-                                        k,
-                                        0.0,
-                                        None,
-                                        constraints::DimensionDisplay::default(),
-                                    )),
-                                )
+                            if let Some((_, _, length, cardinality)) =
+                                constraints.iter_line_lengths().find(|(_, fk, ..)| *fk == k)
                             {
                                 ui.add_space(4.);
                                 ui.horizontal(|ui| {
@@ -993,15 +1293,9 @@ impl<'a> Widget<'a> {
                             }
 
                             // If constrained to be horizontal/vertical, show that
-                            if let Some(Constraint::LineAlongCardinal(_, _, axis, ..)) = constraints
-                                .get_using_feature_and_type(
-                                    &k,
-                                    std::mem::discriminant(&Constraint::LineAlongCardinal(
-                                        ConstraintMeta::default(),
-                                        k,
-                                        constraints::Axis::TopBottom,
-                                    )),
-                                )
+                            if let Some((_, _, axis)) = constraints
+                                .iter_line_cardinals()
+                                .find(|(_, fk, _)| *fk == k)
                             {
                                 ui.add_space(4.);
                                 ui.horizontal(|ui| {
@@ -1078,6 +1372,12 @@ impl<'a> Widget<'a> {
         self.autozoom_next_frame = true;
     }
 
+    /// Zooms about the center of the canvas so one screen point covers the
+    /// given number of drawing millimeters. See [`Viewport::true_scale_zoom`].
+    pub fn set_scale(&mut self, mm_per_screen_point: f32) {
+        self.drawing.pending_scale = Some(mm_per_screen_point);
+    }
+
     pub fn show(mut self, ui: &mut egui::Ui) -> DrawResponse {
         use egui::Sense;
         let (rect, mut response) = ui.allocate_exact_size(
@@ -1091,7 +1391,7 @@ impl<'a> Widget<'a> {
         ui.set_clip_rect(rect);
 
         // First-frame initialization
-        let state_id = ui.make_persistent_id("drawing");
+        let state_id = ui.make_persistent_id(self.id);
         let has_init = ui
             .memory_mut(|mem| mem.data.get_temp::<bool>(state_id))
             .unwrap_or(false);
@@ -1113,6 +1413,13 @@ impl<'a> Widget<'a> {
             );
             self.drawing.vp.zoom = x_r.max(y_r);
         }
+        if self.drawing.poll_bruteforce_solve() {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        if let Some(zoom) = self.drawing.pending_scale.take() {
+            self.drawing.vp.zoom_about(rect.center(), zoom);
+        }
         if self.center_next_frame {
             let bounds = self.drawing.bounds();
             self.drawing.vp.x = -rect.width() / 2. * self.drawing.vp.zoom + bounds.center().x;
@@ -1126,7 +1433,12 @@ impl<'a> Widget<'a> {
             .unwrap_or(Hover::None);
 
         // Handle input
-        let current_input = if let Some(c) = self.tools.handle_input(ui, hp, &hover, &response) {
+        let badge_rect = Self::solve_badge_rect(rect);
+        let badge_hovered = hp.map(|hp| badge_rect.contains(hp)).unwrap_or(false);
+        let current_input = if badge_hovered && response.clicked() {
+            self.drawing.changed_in_ui();
+            None
+        } else if let Some(c) = self.tools.handle_input(ui, hp, &hover, &response) {
             self.handler.handle(self.drawing, self.tools, c);
             self.set_focus(ui, &response);
             None
@@ -1149,6 +1461,9 @@ impl<'a> Widget<'a> {
                 text: ui.visuals().text_color(),
             },
             font_id: egui::TextStyle::Body.resolve(ui.style()),
+            point_style: self.drawing.point_style,
+            stroke_width: self.drawing.stroke_width,
+            point_radius: self.drawing.point_radius,
 
             selected: false,
             hovered: false,
@@ -1164,6 +1479,7 @@ impl<'a> Widget<'a> {
             current_input,
             &base_params,
         );
+        self.draw_solve_badge(painter, &response, badge_rect, badge_hovered);
 
         if self.drawing.props.solve_continuously.is_some()
             && self.drawing.last_solve_error.is_some()
@@ -1174,6 +1490,7 @@ impl<'a> Widget<'a> {
             }
         }
 
+        ui.data_mut(|d| d.insert_temp(egui::Id::new(CANVAS_ID), response.id));
         DrawResponse {}
     }
 }
@@ -1182,8 +1499,161 @@ pub struct DrawResponse {}
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
 
     #[test]
     fn simplifications() {}
+
+    #[test]
+    fn scale_bar_length() {
+        // 1 screen pixel per millimeter.
+        assert_eq!(Widget::scale_bar_length(1.0, 120.0), (100.0, 0));
+        assert_eq!(Widget::scale_bar_length(1.0, 60.0), (50.0, 0));
+        assert_eq!(Widget::scale_bar_length(0.2, 120.0), (20.0, 0));
+        // Zoomed right in, the bar measures fractions of a millimeter.
+        let (mm, decimals) = Widget::scale_bar_length(0.004, 120.0);
+        assert!((mm - 0.2).abs() < 1e-6, "{}", mm);
+        assert_eq!(decimals, 1);
+        assert_eq!(format!("{:.*} mm", decimals, mm), "0.2 mm");
+    }
+
+    #[test]
+    fn isolate_colors() {
+        let colors = Colors {
+            point: egui::Color32::GREEN,
+            line: egui::Color32::GRAY,
+            selected: egui::Color32::RED,
+            hover: egui::Color32::YELLOW,
+            text: egui::Color32::WHITE,
+        };
+        let dimmed = egui::Color32::GRAY.gamma_multiply(ISOLATE_DIM_ALPHA);
+
+        // Not isolating: nothing changes.
+        assert_eq!(colors.for_feature(false, false, false).line, colors.line);
+        // Isolating: only unselected, unhovered geometry is dimmed.
+        assert_eq!(colors.for_feature(false, false, true).line, dimmed);
+        assert_eq!(colors.for_feature(true, false, true).line, colors.line);
+        assert_eq!(colors.for_feature(false, true, true).line, colors.line);
+        // Selection and hover colors are never dimmed.
+        assert_eq!(
+            colors.for_feature(false, false, true).selected,
+            colors.selected
+        );
+    }
+
+    #[test]
+    fn arrow_keys_keep_panning() {
+        let mut data = Data::default();
+        let (mut handler, mut tools) = (Handler::default(), tools::Toolbar::default());
+
+        let ctx = egui::Context::default();
+        let mut frame = |events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(800.0, 600.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                // Somewhere for arrow navigation to move focus to.
+                egui::SidePanel::left("left").show(ctx, |ui| {
+                    let _ = ui.button("Left");
+                });
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    Widget::new(&mut data, &mut handler, &mut tools).show(ui);
+                });
+            });
+            data.vp.x
+        };
+        let click = |pressed| egui::Event::PointerButton {
+            pos: egui::pos2(400.0, 300.0),
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        let arrow = egui::Event::Key {
+            key: egui::Key::ArrowLeft,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        };
+
+        // Clicking the canvas focuses it.
+        frame(vec![egui::Event::PointerMoved(egui::pos2(400.0, 300.0))]);
+        frame(vec![click(true)]);
+        let mut last = frame(vec![click(false)]);
+        for _ in 0..3 {
+            let x = frame(vec![arrow.clone()]);
+            assert!(x < last, "{} !< {}", x, last);
+            last = x;
+        }
+    }
+
+    #[test]
+    fn reference_image_drag_needs_unlocking() {
+        let mut data = Data::default();
+        let mut img = RefImage::new("a.png".into(), std::sync::Arc::from(vec![]), 100, 100);
+        (img.x, img.y, img.scale) = (-1000.0, -1000.0, 20.0);
+        data.reference_image = Some(img);
+        let (mut handler, mut tools) = (Handler::default(), tools::Toolbar::default());
+
+        let ctx = egui::Context::default();
+        let mut frame = |data: &mut Data, events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(800.0, 600.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    Widget::new(data, &mut handler, &mut tools).show(ui);
+                });
+            });
+        };
+        let button = |pos, pressed| egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        let mut drag = |data: &mut Data| {
+            let (from, to) = (egui::pos2(300.0, 200.0), egui::pos2(500.0, 400.0));
+            frame(data, vec![egui::Event::PointerMoved(from)]);
+            frame(data, vec![button(from, true)]);
+            frame(
+                data,
+                vec![egui::Event::PointerMoved(egui::pos2(400.0, 300.0))],
+            );
+            frame(data, vec![egui::Event::PointerMoved(to)]);
+            frame(data, vec![button(to, false)]);
+            let img = data.reference_image.as_ref().unwrap();
+            (img.x, img.y)
+        };
+
+        // New images start locked, so dragging over one box-selects.
+        assert!(data.reference_image.as_ref().unwrap().locked);
+        assert_eq!(drag(&mut data), (-1000.0, -1000.0));
+
+        data.reference_image.as_mut().unwrap().locked = false;
+        assert_ne!(drag(&mut data), (-1000.0, -1000.0));
+    }
+
+    #[test]
+    fn dimension_arithmetic() {
+        let close = |s: &str, want: f64| {
+            let got = parse_dimension(s).unwrap();
+            assert!((got - want).abs() < 1e-9, "{}: {} != {}", s, got, want);
+        };
+        close("25.4/2", 12.7);
+        close("10+5", 15.0);
+        close(" 3 ", 3.0);
+        close("2*(1.5+0.5)", 4.0);
+        assert_eq!(parse_dimension("abc"), None);
+        assert_eq!(parse_dimension("1/0"), None);
+    }
 }
@@ -51,6 +51,10 @@ impl Into<eq::Variable> for &TermRef {
 
 /// Allocates terms for parameters of different entities which
 /// need to be referenced or solved.
+///
+/// Each live feature is assigned its own base index, and variable names are
+/// formed from a type prefix and that index, so names never collide. Bases of
+/// deleted features are recycled, keeping names within `eq::MAX_VAR_LENGTH`.
 #[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
 pub struct TermAllocator {
     top: usize,
@@ -133,6 +137,15 @@ impl TermAllocator {
         }
     }
 
+    /// Returns the term for the given feature, if one has already been allocated.
+    pub fn existing_feature_term(&self, fk: FeatureKey, t: TermType) -> Option<TermRef> {
+        self.by_feature.get(&fk).map(|base| TermRef {
+            t,
+            base: *base,
+            for_feature: Some(fk),
+        })
+    }
+
     fn alloc_base(&mut self) -> usize {
         if let Some(base) = self.free.pop() {
             return base;
@@ -156,3 +169,48 @@ impl TermAllocator {
 
     pub fn inform_new_constraint(&mut self, _ck: ConstraintKey) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Feature, FeatureMeta};
+    use slotmap::HopSlotMap;
+    use std::collections::HashSet;
+
+    #[test]
+    fn variable_names_unique() {
+        let mut features: HopSlotMap<FeatureKey, Feature> = HopSlotMap::default();
+        let mut terms = TermAllocator::default();
+        let mut names: HashSet<eq::Variable> = HashSet::new();
+
+        let types = [
+            TermType::ScalarDistance,
+            TermType::PositionX,
+            TermType::PositionY,
+            TermType::ScalarRadius,
+            TermType::ScalarGlobalCos,
+            TermType::ScalarGlobalSin,
+        ];
+
+        let keys: Vec<FeatureKey> = (0..2000)
+            .map(|i| features.insert(Feature::Point(FeatureMeta::default(), i as f32, 0.0)))
+            .collect();
+        // Free up some bases so they get recycled.
+        for fk in keys.iter().step_by(7) {
+            features.remove(*fk);
+            terms.get_feature_term(*fk, TermType::PositionX);
+            terms.delete_feature(*fk);
+        }
+
+        for (fk, _) in features.iter() {
+            for t in types.iter() {
+                let tr = terms.get_feature_term(fk, t.clone());
+                let v: eq::Variable = (&tr).into();
+
+                assert!(names.insert(v.clone()), "duplicate variable {}", v);
+                assert_eq!(terms.get_var_ref(&v), Some(tr));
+            }
+        }
+        assert_eq!(names.len(), features.len() * types.len());
+    }
+}
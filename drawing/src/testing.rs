@@ -0,0 +1,145 @@
+//! Helpers for checking the solver against saved drawings.
+use crate::{Data, SerializedDrawing};
+use std::path::Path;
+
+/// Outcome of solving a drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveReport {
+    pub converged: bool,
+    /// Average residual error of the numeric solver, if it didn't converge.
+    pub avg_err: Option<f64>,
+    /// Iterations the numeric solver took, which is zero if it wasn't needed.
+    pub iterations: usize,
+}
+
+/// Loads and solves the given drawing, reporting how the solver fared.
+///
+/// Panics if the drawing fails to load, as a broken fixture is a bug in the
+/// test rather than the solver.
+pub fn solve_fixture(serialized: SerializedDrawing) -> SolveReport {
+    solve_fixture_data(serialized).1
+}
+
+/// Like [solve_fixture], but also returns the solved drawing so positions
+/// can be checked.
+pub fn solve_fixture_data(serialized: SerializedDrawing) -> (Data, SolveReport) {
+    let mut data = Data::default();
+    if let Err(e) = data.load(serialized) {
+        panic!("failed to load fixture: {:?}", e);
+    }
+
+    let report = SolveReport {
+        converged: data.last_solve_error.is_none(),
+        avg_err: data.last_solve_error,
+        iterations: data.last_solve_iterations,
+    };
+    (data, report)
+}
+
+/// Loads and solves every `.ron` fixture in a directory, in name order and
+/// keyed by file stem. The caller provides the parser so the crate doesn't
+/// need to depend on one.
+///
+/// Panics if the directory or any fixture in it can't be read or parsed.
+pub fn solve_fixture_dir<E: std::fmt::Debug>(
+    dir: impl AsRef<Path>,
+    parse: impl Fn(&str) -> Result<SerializedDrawing, E>,
+) -> Vec<(String, Data, SolveReport)> {
+    let dir = dir.as_ref();
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|p| p.extension() == Some("ron".as_ref()))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|p| {
+            let text = std::fs::read_to_string(&p)
+                .unwrap_or_else(|e| panic!("failed to read {}: {}", p.display(), e));
+            let serialized =
+                parse(&text).unwrap_or_else(|e| panic!("failed to parse {}: {:?}", p.display(), e));
+            let (data, report) = solve_fixture_data(serialized);
+            let name = p.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, data, report)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Feature;
+
+    fn fixtures() -> Vec<(String, Data, SolveReport)> {
+        solve_fixture_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), |text| {
+            ron::de::from_str::<SerializedDrawing>(text)
+        })
+    }
+
+    fn points(data: &Data) -> Vec<(f32, f32)> {
+        data.features_iter()
+            .filter_map(|(_, f)| match f {
+                Feature::Point(_, x, y) => Some((*x, *y)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn assert_near(got: (f32, f32), want: (f32, f32)) {
+        assert!(
+            (got.0 - want.0).abs() < 1e-3 && (got.1 - want.1).abs() < 1e-3,
+            "got {:?}, want {:?}",
+            got,
+            want
+        );
+    }
+
+    /// Distance from c to the line through a and b.
+    fn dist_to_line(c: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        ((c.0 - a.0) * dy - (c.1 - a.1) * dx).abs() / dx.hypot(dy)
+    }
+
+    #[test]
+    fn fixtures_solve() {
+        let fixtures = fixtures();
+        assert_eq!(
+            fixtures
+                .iter()
+                .map(|(n, ..)| n.as_str())
+                .collect::<Vec<_>>(),
+            vec!["equidistant_points", "tangent_circles"],
+        );
+
+        for (name, data, report) in fixtures {
+            assert!(report.converged, "{}: {:?}", name, report);
+            assert_eq!(report.avg_err, None, "{}", name);
+
+            let pts = points(&data);
+            match name.as_str() {
+                "equidistant_points" => {
+                    assert_near(pts[0], (0.0, 0.0));
+                    assert_near(pts[1], (10.0, 4.0));
+                    // 10 from both fixed points, on the side it started.
+                    assert_near(pts[2], (1.8706, 9.8235));
+                }
+                "tangent_circles" => {
+                    assert_near(pts[0], (0.0, 0.0));
+                    assert_near(pts[1], (30.0, 0.0));
+                    let (a, b) = (pts[2], pts[3]);
+                    assert!(
+                        (dist_to_line(pts[0], a, b) - 10.0).abs() < 1e-3,
+                        "{:?}",
+                        pts
+                    );
+                    assert!((dist_to_line(pts[1], a, b) - 5.0).abs() < 1e-3, "{:?}", pts);
+                    // Both circles are under the line, so the tangent is external.
+                    assert!(a.1 > 0.0 && b.1 > 0.0, "{:?}", pts);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
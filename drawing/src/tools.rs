@@ -5,6 +5,13 @@ use crate::FeatureKey;
 
 const TOOL_ICON_SIZE: egui::Vec2 = egui::Vec2 { x: 32.0, y: 32.0 };
 const TOOL_ICON_STROKE: f32 = 1.;
+/// Increment new circle radii snap to while shift is held, in mm.
+const CIRCLE_RADIUS_SNAP: f32 = 1.0;
+
+/// Snaps a circle radius while shift is held, never down to zero.
+pub(crate) fn snap_radius(r: f32) -> f32 {
+    crate::l::snap_to_increment(r, CIRCLE_RADIUS_SNAP).max(CIRCLE_RADIUS_SNAP)
+}
 
 fn tool_icon_offsets(idx: usize) -> (f32, f32) {
     let offset_x = 5. + (idx % 2) as f32 * (TOOL_ICON_SIZE.x + 2. * TOOL_ICON_STROKE);
@@ -337,6 +344,79 @@ fn regular_poly_tool_icon(b: egui::Rect, painter: &egui::Painter) {
     );
 }
 
+fn ellipse_tool_icon(b: egui::Rect, painter: &egui::Painter) {
+    let c = b.center();
+
+    use kurbo::Shape;
+    let e = kurbo::Ellipse::new((c.x as f64, c.y as f64), (11.0, 6.0), -0.4);
+    for s in e.path_segments(0.1) {
+        if let kurbo::PathSeg::Cubic(kurbo::CubicBez { p0, p1, p2, p3 }) = s {
+            painter.add(egui::epaint::CubicBezierShape::from_points_stroke(
+                [p0, p1, p2, p3].map(|p| egui::Pos2 {
+                    x: p.x as f32,
+                    y: p.y as f32,
+                }),
+                false,
+                egui::Color32::TRANSPARENT,
+                egui::Stroke {
+                    width: TOOL_ICON_STROKE,
+                    color: egui::Color32::WHITE,
+                },
+            ));
+        }
+    }
+    painter.rect_filled(
+        egui::Rect {
+            min: c + egui::Vec2 { x: -1.5, y: -1.5 },
+            max: c + egui::Vec2 { x: 1.5, y: 1.5 },
+        },
+        egui::Rounding::ZERO,
+        egui::Color32::GREEN,
+    );
+}
+
+fn arc_center_tool_icon(b: egui::Rect, painter: &egui::Painter) {
+    let c = b.center() + egui::Vec2 { x: -5.0, y: 5.0 };
+
+    let a = kurbo::Arc {
+        center: (c.x as f64, c.y as f64).into(),
+        radii: (13.0, 13.0).into(),
+        start_angle: -std::f64::consts::FRAC_PI_2,
+        sweep_angle: std::f64::consts::FRAC_PI_2,
+        x_rotation: 0.0,
+    };
+    let mut last = c + egui::Vec2 { x: 0.0, y: -13.0 };
+    a.to_cubic_beziers(0.1, |p1, p2, p| {
+        let p = egui::Pos2 {
+            x: p.x as f32,
+            y: p.y as f32,
+        };
+        painter.add(egui::epaint::CubicBezierShape::from_points_stroke(
+            [
+                last,
+                (p1.x as f32, p1.y as f32).into(),
+                (p2.x as f32, p2.y as f32).into(),
+                p,
+            ],
+            false,
+            egui::Color32::TRANSPARENT,
+            egui::Stroke {
+                width: TOOL_ICON_STROKE,
+                color: egui::Color32::WHITE,
+            },
+        ));
+        last = p;
+    });
+    painter.rect_filled(
+        egui::Rect {
+            min: c + egui::Vec2 { x: -1.5, y: -1.5 },
+            max: c + egui::Vec2 { x: 1.5, y: 1.5 },
+        },
+        egui::Rounding::ZERO,
+        egui::Color32::GREEN,
+    );
+}
+
 #[derive(Debug, Default, Clone)]
 enum Tool {
     #[default]
@@ -346,6 +426,8 @@ enum Tool {
     Circle(Option<FeatureKey>),
     Gear,
     RegularPoly,
+    Ellipse,
+    ArcCenter,
     Fixed,
     Dimension,
     Horizontal,
@@ -365,6 +447,8 @@ impl Tool {
             Tool::Circle(_) => "Create Circle",
             Tool::Gear => "Create spur gear",
             Tool::RegularPoly => "Create regular polygon",
+            Tool::Ellipse => "Create ellipse",
+            Tool::ArcCenter => "Create arc from center",
             Tool::Fixed => "Constrain to co-ords",
             Tool::Dimension => "Constrain length/radius",
             Tool::Horizontal => "Constrain horizontal",
@@ -376,21 +460,26 @@ impl Tool {
         }
     }
     pub fn key(&self) -> Option<&'static str> {
+        self.hotkey().map(|k| k.name())
+    }
+    fn hotkey(&self) -> Option<egui::Key> {
         match self {
-            Tool::Point => Some("P"),
-            Tool::Line(_) => Some("L"),
-            Tool::Arc(_) => Some("R"),
-            Tool::Circle(_) => Some("C"),
+            Tool::Point => Some(egui::Key::P),
+            Tool::Line(_) => Some(egui::Key::L),
+            Tool::Arc(_) => Some(egui::Key::R),
+            Tool::Circle(_) => Some(egui::Key::C),
             Tool::Gear => None,
             Tool::RegularPoly => None,
-            Tool::Fixed => Some("S"),
-            Tool::Dimension => Some("D"),
-            Tool::Horizontal => Some("H"),
-            Tool::Vertical => Some("V"),
-            Tool::Lerp(_) => Some("I"),
-            Tool::Equal(_) => Some("E"),
+            Tool::Ellipse => None,
+            Tool::ArcCenter => None,
+            Tool::Fixed => Some(egui::Key::S),
+            Tool::Dimension => Some(egui::Key::D),
+            Tool::Horizontal => Some(egui::Key::H),
+            Tool::Vertical => Some(egui::Key::V),
+            Tool::Lerp(_) => Some(egui::Key::I),
+            Tool::Equal(_) => Some(egui::Key::E),
             Tool::Parallel(_) => None,
-            Tool::Angle => Some("N"),
+            Tool::Angle => Some(egui::Key::N),
         }
     }
     pub fn long_tooltip(&self) -> Option<&'static str> {
@@ -398,9 +487,11 @@ impl Tool {
             Tool::Point => Some("Creates points.\n\nClick anywhere in free space to create a point."),
             Tool::Line(_) => Some("Creates lines from existing points.\n\nClick on the first point and then the second to create a line."),
             Tool::Arc(_) => Some("Creates a circular arc between points.\n\nClick on the first point and then the second to create an arc. A center point will be automatically created."),
-            Tool::Circle(_) => Some("Creates a circle around some center point.\n\nClick on the center point, and then again in empty space to create the circle."),
+            Tool::Circle(_) => Some("Creates a circle around some center point.\n\nClick on the center point, and then again in empty space to create the circle. Hold shift to snap the radius to whole millimeters."),
             Tool::Gear => Some("Creates an external spur gear around some center point.\n\nClick on the center point to create the gear."),
             Tool::RegularPoly => Some("Creates a regular polygon around some center point.\n\nClick on the center point to create the polygon."),
+            Tool::Ellipse => Some("Creates an ellipse around some center point.\n\nClick on the center point to create the ellipse. Radii and rotation can be changed later in the selection UI."),
+            Tool::ArcCenter => Some("Creates a circular arc around some center point.\n\nClick on the center point to create the arc. Radius and start/end angles can be changed later in the selection UI."),
             Tool::Fixed => Some("Constraints a point to be at specific co-ordinates.\n\nClick a point to constrain it to (0,0). Co-ordinates can be changed later in the selection UI."),
             Tool::Dimension => Some("Sets the dimensions of a line or circle.\n\nClick a line/circle to constrain it to its current length/radius respectively. The constrained value can be changed later in the selection UI."),
             Tool::Horizontal => Some("Constrains a line to be horizontal."),
@@ -420,6 +511,8 @@ impl Tool {
             (Tool::Circle(_), Tool::Circle(_)) => true,
             (Tool::Gear, Tool::Gear) => true,
             (Tool::RegularPoly, Tool::RegularPoly) => true,
+            (Tool::Ellipse, Tool::Ellipse) => true,
+            (Tool::ArcCenter, Tool::ArcCenter) => true,
             (Tool::Fixed, Tool::Fixed) => true,
             (Tool::Dimension, Tool::Dimension) => true,
             (Tool::Horizontal, Tool::Horizontal) => true,
@@ -440,6 +533,8 @@ impl Tool {
             Tool::Arc(None),
             Tool::Gear,
             Tool::RegularPoly,
+            Tool::Ellipse,
+            Tool::ArcCenter,
             Tool::Fixed,
             Tool::Dimension,
             Tool::Horizontal,
@@ -480,6 +575,22 @@ impl Tool {
                         || response.drag_released_by(egui::PointerButton::Primary),
                 ) {
                     (Hover::None, true, _) => Some(ToolResponse::NewPoint(hp)),
+                    (
+                        Hover::Feature {
+                            k,
+                            feature: crate::Feature::LineSegment(..),
+                        },
+                        true,
+                        _,
+                    ) => Some(ToolResponse::NewPointOnLine(*k, hp)),
+                    (
+                        Hover::Feature {
+                            k,
+                            feature: crate::Feature::Circle(meta, ..),
+                        },
+                        true,
+                        _,
+                    ) if meta.construction => Some(ToolResponse::NewPointOnCircle(*k, hp)),
                     (Hover::Feature { .. } | Hover::Constraint { .. }, true, _) => None,
                     (_, _, true) => Some(ToolResponse::Handled), // catch drag events
 
@@ -652,7 +763,8 @@ impl Tool {
                     // Has first point, clicked anywhere
                     (_, Some(starting_point), true) => {
                         let starting_point = starting_point.clone();
-                        Some(ToolResponse::NewCircle(starting_point, hp))
+                        let snap = response.ctx.input(|i| i.modifiers.shift);
+                        Some(ToolResponse::NewCircle(starting_point, hp, snap))
                     }
 
                     // No first point, clicked empty space or line or arc
@@ -727,6 +839,44 @@ impl Tool {
                 }
                 None
             }
+            Tool::Ellipse => {
+                if response.clicked() {
+                    return match hover {
+                        Hover::Feature {
+                            k,
+                            feature: crate::Feature::Point(..),
+                        } => Some(ToolResponse::NewEllipse(*k)),
+                        _ => Some(ToolResponse::SwitchToPointer),
+                    };
+                }
+
+                // Intercept drag events.
+                if response.drag_started_by(egui::PointerButton::Primary)
+                    || response.drag_released_by(egui::PointerButton::Primary)
+                {
+                    return Some(ToolResponse::Handled);
+                }
+                None
+            }
+            Tool::ArcCenter => {
+                if response.clicked() {
+                    return match hover {
+                        Hover::Feature {
+                            k,
+                            feature: crate::Feature::Point(..),
+                        } => Some(ToolResponse::NewArcCenter(*k)),
+                        _ => Some(ToolResponse::SwitchToPointer),
+                    };
+                }
+
+                // Intercept drag events.
+                if response.drag_started_by(egui::PointerButton::Primary)
+                    || response.drag_released_by(egui::PointerButton::Primary)
+                {
+                    return Some(ToolResponse::Handled);
+                }
+                None
+            }
 
             Tool::Fixed => {
                 if response.clicked() {
@@ -1146,19 +1296,23 @@ impl Tool {
                     _ => unreachable!(),
                 };
                 let c: egui::Pos2 = (x, y).into();
+                let mut radius = c.distance(params.vp.screen_to_point(hp));
+                if response.ctx.input(|i| i.modifiers.shift) {
+                    radius = snap_radius(radius);
+                }
 
                 painter.circle_stroke(
                     params.vp.translate_point(c),
-                    c.distance(params.vp.screen_to_point(hp)) / params.vp.zoom,
+                    radius / params.vp.zoom,
                     egui::Stroke {
                         width: TOOL_ICON_STROKE,
                         color: egui::Color32::WHITE,
                     },
                 );
 
-                response
-                    .clone()
-                    .on_hover_text_at_pointer("new circle: click to set radius");
+                response.clone().on_hover_text_at_pointer(
+                    "new circle: click to set radius (shift: snap to whole mm)",
+                );
             }
             Tool::Gear => {
                 response
@@ -1170,6 +1324,16 @@ impl Tool {
                     .clone()
                     .on_hover_text_at_pointer("new n-poly: click center point");
             }
+            Tool::Ellipse => {
+                response
+                    .clone()
+                    .on_hover_text_at_pointer("new ellipse: click center point");
+            }
+            Tool::ArcCenter => {
+                response
+                    .clone()
+                    .on_hover_text_at_pointer("new arc: click center point");
+            }
 
             Tool::Fixed => {
                 response.clone().on_hover_text_at_pointer("constrain (x,y)");
@@ -1240,6 +1404,8 @@ impl Tool {
             Tool::Circle(_) => circle_tool_icon,
             Tool::Gear => gear_tool_icon,
             Tool::RegularPoly => regular_poly_tool_icon,
+            Tool::Ellipse => ellipse_tool_icon,
+            Tool::ArcCenter => arc_center_tool_icon,
             Tool::Fixed => fixed_tool_icon,
             Tool::Dimension => dim_tool_icon,
             Tool::Horizontal => horizontal_tool_icon,
@@ -1297,6 +1463,28 @@ impl Toolbar {
         self.current = None;
     }
 
+    /// Returns the name of the tool equipped by the given hotkey, if any.
+    pub fn tool_for_hotkey(key: &str) -> Option<&'static str> {
+        Tool::all()
+            .iter()
+            .find(|t| t.key() == Some(key))
+            .map(|t| t.name())
+    }
+
+    /// Returns the tool whose hotkey was pressed, if any. Keys pressed with
+    /// Alt or Ctrl held are left for the widget's own shortcuts.
+    fn hotkey_tool<'a>(
+        modifiers: egui::Modifiers,
+        pressed: impl Fn(egui::Key) -> bool,
+    ) -> Option<&'a Tool> {
+        if modifiers.alt || modifiers.command {
+            return None;
+        }
+        Tool::all()
+            .iter()
+            .find(|t| t.hotkey().map(&pressed).unwrap_or(false))
+    }
+
     pub fn handle_input(
         &mut self,
         ui: &mut egui::Ui,
@@ -1312,73 +1500,11 @@ impl Toolbar {
 
         // Hotkeys for switching tools
         if response.has_focus() && !response.dragged() {
-            let (l, p, s, d, v, h, i2, e, r, c, n) = ui.input(|i| {
-                if i.events.len() == 0 {
-                    (
-                        false, false, false, false, false, false, false, false, false, false, false,
-                    )
-                } else {
-                    (
-                        i.key_pressed(egui::Key::L),
-                        i.key_pressed(egui::Key::P),
-                        i.key_pressed(egui::Key::S),
-                        i.key_pressed(egui::Key::D),
-                        i.key_pressed(egui::Key::V),
-                        i.key_pressed(egui::Key::H),
-                        i.key_pressed(egui::Key::I),
-                        i.key_pressed(egui::Key::E),
-                        i.key_pressed(egui::Key::R),
-                        i.key_pressed(egui::Key::C),
-                        i.key_pressed(egui::Key::N),
-                    )
-                }
-            });
-            match (l, p, s, d, v, h, i2, e, r, c, n) {
-                (true, _, _, _, _, _, _, _, _, _, _) => {
-                    self.current = Some(Tool::Line(None));
-                    return Some(ToolResponse::Handled);
-                }
-                (_, true, _, _, _, _, _, _, _, _, _) => {
-                    self.current = Some(Tool::Point);
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, true, _, _, _, _, _, _, _, _) => {
-                    self.current = Some(Tool::Fixed);
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, true, _, _, _, _, _, _, _) => {
-                    self.current = Some(Tool::Dimension);
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, true, _, _, _, _, _, _) => {
-                    self.current = Some(Tool::Vertical);
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, true, _, _, _, _, _) => {
-                    self.current = Some(Tool::Horizontal);
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, _, true, _, _, _, _) => {
-                    self.current = Some(Tool::Lerp(None));
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, _, _, true, _, _, _) => {
-                    self.current = Some(Tool::Equal(None));
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, _, _, _, true, _, _) => {
-                    self.current = Some(Tool::Arc(None));
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, _, _, _, _, true, _) => {
-                    self.current = Some(Tool::Circle(None));
-                    return Some(ToolResponse::Handled);
-                }
-                (_, _, _, _, _, _, _, _, _, _, true) => {
-                    self.current = Some(Tool::Angle);
-                    return Some(ToolResponse::Handled);
-                }
-                _ => {}
+            let pressed =
+                ui.input(|i| Self::hotkey_tool(i.modifiers, |k| i.key_pressed(k)).cloned());
+            if let Some(tool) = pressed {
+                self.current = Some(tool);
+                return Some(ToolResponse::Handled);
             }
         }
 
@@ -1506,3 +1632,32 @@ impl Toolbar {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkeys_unique() {
+        let keys: Vec<_> = Tool::all().iter().filter_map(|t| t.key()).collect();
+        for (i, k) in keys.iter().enumerate() {
+            assert!(!keys[i + 1..].contains(k), "duplicate hotkey {}", k);
+        }
+
+        assert_eq!(Toolbar::tool_for_hotkey("L"), Some("Create Line"));
+        assert_eq!(Toolbar::tool_for_hotkey("N"), Some(Tool::Angle.name()));
+        assert_eq!(Toolbar::tool_for_hotkey("Z"), None);
+    }
+
+    #[test]
+    fn hotkey_tool_modifiers() {
+        let pressed = |k| k == egui::Key::I;
+        assert!(matches!(
+            Toolbar::hotkey_tool(egui::Modifiers::NONE, pressed),
+            Some(Tool::Lerp(_))
+        ));
+        // Alt-I isolates the selection rather than equipping a tool.
+        assert!(Toolbar::hotkey_tool(egui::Modifiers::ALT, pressed).is_none());
+        assert!(Toolbar::hotkey_tool(egui::Modifiers::COMMAND, pressed).is_none());
+    }
+}
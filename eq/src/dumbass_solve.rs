@@ -46,6 +46,19 @@ impl Default for DumbassSolverParams {
     }
 }
 
+/// How hard a solve was, returned alongside its results.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DumbassSolveStats {
+    /// The number of iterations taken.
+    pub iterations: usize,
+    /// The total residual after each iteration.
+    pub residuals: Vec<f64>,
+}
+
+/// The results of a solve, or the average error and best effort if it
+/// failed to converge.
+pub type DumbassSolveResult = Result<Vec<(Variable, f64)>, (f64, Vec<(Variable, f64)>)>;
+
 #[derive(Clone, Debug, PartialEq)]
 enum Jacobian {
     Func(Expression),
@@ -59,6 +72,8 @@ pub struct DumbassSolverState {
     vars: Vec<Variable>,
     residuals: Vec<Expression>,
     jacobians: Vec<Jacobian>,
+    // weight of each residual, 1.0 unless it is soft.
+    weights: Vec<f64>,
 }
 
 impl DumbassSolverState {
@@ -116,13 +131,30 @@ impl DumbassSolverState {
             // println!("residual: {}", r);
         }
 
+        let weights = vec![1.0; residuals.len()];
         Self {
             resolved: concrete,
             vars: solve_for,
             residuals,
             jacobians,
+            weights,
         }
     }
+
+    /// Sets the weight of each residual, in the order they were given to new().
+    ///
+    /// Residuals weighted below 1.0 are soft: they pull on the solution in
+    /// proportion to their weight, but are allowed to remain unsatisfied when
+    /// they conflict with the full-weight residuals.
+    pub fn with_weights(mut self, weights: Vec<f64>) -> Self {
+        assert_eq!(weights.len(), self.residuals.len());
+        self.weights = weights;
+        self
+    }
+
+    fn is_soft(&self, row: usize) -> bool {
+        self.weights[row] < 1.0
+    }
 }
 
 /// Iterative gradient-descent newton-method-vibes solver.
@@ -277,7 +309,7 @@ impl DumbassSolver {
             if res.is_nan() {
                 res = f64::INFINITY;
             }
-            fx[row] = res.clamp(-999999.0, 999999.0);
+            fx[row] = res.clamp(-999999.0, 999999.0) * st.weights[row];
         }
 
         // Compute total error
@@ -322,15 +354,35 @@ impl DumbassSolver {
     pub fn solve(
         &mut self,
         st: &mut DumbassSolverState,
-    ) -> Result<Vec<(Variable, f64)>, (f64, Vec<(Variable, f64)>)> {
-        let mut total_fx = f64::MAX;
-        while self.iteration < self.params.max_iter {
-            total_fx = self.solve_step(st);
+    ) -> (DumbassSolveResult, DumbassSolveStats) {
+        let mut stats = DumbassSolveStats::default();
+        let result = self.solve_inner(st, &mut stats);
+        stats.iterations = stats.residuals.len();
+        (result, stats)
+    }
 
-            if (total_fx.abs() / st.vars.len() as f64) < self.params.terminate_at_avg_fx {
-                break;
+    fn solve_inner(
+        &mut self,
+        st: &mut DumbassSolverState,
+        stats: &mut DumbassSolveStats,
+    ) -> DumbassSolveResult {
+        let mut total_fx = self.descend(st, stats);
+
+        // Soft residuals may never reach zero if they conflict with other
+        // residuals. Having let them pull the guesses their way, drop them
+        // and descend again so the full-weight residuals are met.
+        let has_soft = (0..st.residuals.len()).any(|row| st.is_soft(row));
+        if self.iteration >= self.params.max_iter && has_soft {
+            let weights = st.weights.clone();
+            for w in st.weights.iter_mut().filter(|w| **w < 1.0) {
+                *w = 0.0;
             }
-            self.iteration += 1;
+            self.iteration = 0;
+            self.adj_sign_hash = None;
+            self.momentum = self.params.momentum_windup;
+            self.momentum_div = self.params.momentum_div;
+            total_fx = self.descend(st, stats);
+            st.weights = weights;
         }
 
         let results = st
@@ -345,6 +397,22 @@ impl DumbassSolver {
             Err((total_fx, results))
         }
     }
+
+    /// Steps until the residuals are within tolerance or max_iter is
+    /// reached, returning the final total residual.
+    fn descend(&mut self, st: &mut DumbassSolverState, stats: &mut DumbassSolveStats) -> f64 {
+        let mut total_fx = f64::MAX;
+        while self.iteration < self.params.max_iter {
+            total_fx = self.solve_step(st);
+            stats.residuals.push(total_fx);
+
+            if (total_fx.abs() / st.vars.len() as f64) < self.params.terminate_at_avg_fx {
+                break;
+            }
+            self.iteration += 1;
+        }
+        total_fx
+    }
 }
 
 #[cfg(test)]
@@ -402,7 +470,7 @@ mod tests {
         // Set some initial conditions.
         solver.x[0] = 0.001;
         solver.x[1] = 1.000;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration <= 8);
         assert!(ret[0].1 < 0.1);
@@ -413,7 +481,7 @@ mod tests {
         solver = DumbassSolver::new(&state);
         solver.x[0] = 1.0;
         solver.x[1] = 1.0;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration <= 8);
         // trashy check but gets the point across.
@@ -441,7 +509,7 @@ mod tests {
 
         solver.x[0] = 1.000;
         solver.x[1] = 3.000;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration < 50);
         assert!(ret[0].1 < 0.0001);
@@ -473,7 +541,7 @@ mod tests {
         solver.x[1] = -62.0;
         solver.x[0] = -3000.0;
         solver.x[1] = -3000.0;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration < 50);
         let dist_leg_1 = (ret[0].1.powi(2) + ret[1].1.powi(2)).sqrt();
@@ -485,13 +553,54 @@ mod tests {
         solver.x[1] = 62.0;
         solver.x[0] = 800.0;
         solver.x[1] = 800.0;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration < 70);
         let dist_leg_1 = (ret[0].1.powi(2) + ret[1].1.powi(2)).sqrt();
         assert!(dist_leg_1 > 87.9 && dist_leg_1 < 88.1);
     }
 
+    #[test]
+    fn stats() {
+        let mut state = DumbassSolverState::new(
+            HashMap::from([
+                ("x0".into(), Concrete::Float(0.0)),
+                ("y0".into(), Concrete::Float(0.0)),
+            ]),
+            vec!["x1".into(), "y1".into()],
+            vec![Expression::parse("5 - sqrt((x1-x0)^2 + (y1-y0)^2)", false).unwrap()],
+        );
+        let params = DumbassSolverParams::default();
+        let max_iter = params.max_iter;
+        let mut solver = DumbassSolver::new_with_initials(params, &state, vec![0.001, 1.0]);
+        let (ret, stats) = solver.solve(&mut state);
+
+        assert!(ret.is_ok());
+        assert!(stats.iterations > 0 && stats.iterations <= max_iter);
+        assert_eq!(stats.residuals.len(), stats.iterations);
+        assert!(stats.residuals.last().unwrap().abs() < stats.residuals[0].abs());
+    }
+
+    #[test]
+    fn soft_residual_yields() {
+        let mut state = DumbassSolverState::new(
+            HashMap::new(),
+            vec!["x1".into()],
+            vec![
+                Expression::parse("10 - x1", false).unwrap(),
+                Expression::parse("5 - x1", false).unwrap(),
+            ],
+        )
+        .with_weights(vec![1.0, 0.1]);
+        let mut solver =
+            DumbassSolver::new_with_initials(DumbassSolverParams::default(), &state, vec![0.0]);
+        let ret = solver.solve(&mut state).0.unwrap();
+
+        // The full-weight residual is met, leaving the soft one unsatisfied.
+        assert!((10.0 - ret[0].1).abs() < 0.001, "{}", ret[0].1);
+        assert!((5.0 - ret[0].1).abs() > 4.9);
+    }
+
     #[test]
     fn simple() {
         let mut state = DumbassSolverState::new(
@@ -503,7 +612,7 @@ mod tests {
 
         // Set some initial conditions.
         solver.x[0] = 0.001;
-        let ret = solver.solve(&mut state).unwrap();
+        let ret = solver.solve(&mut state).0.unwrap();
 
         assert!(solver.iteration <= 80);
         assert!((10.0 - ret[0].1).abs() < 0.001);
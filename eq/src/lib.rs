@@ -61,6 +61,9 @@ pub mod solve {
 
 pub const MAX_VAR_LENGTH: usize = 12;
 
+/// Tolerance used when checking whether two values are equal.
+const CHECK_EPSILON: f64 = 1e-9;
+
 /// Algebraic unknown, identified by a name up to 12 characters long.
 pub type Variable = heapless::String<MAX_VAR_LENGTH>;
 
@@ -119,6 +122,12 @@ impl StaticResolver {
     }
 }
 
+impl FromIterator<(Variable, Concrete)> for StaticResolver {
+    fn from_iter<I: IntoIterator<Item = (Variable, Concrete)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 #[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub enum TrigOp {
     Sin,
@@ -182,6 +191,15 @@ pub enum Expression {
     Power(Box<Self>, Box<Self>),
     /// Dynamically evaluated function.
     Func(Func),
+
+    /// Whether the first expression is greater than or equal to the second.
+    GreaterEqual(Box<Self>, Box<Self>),
+    /// Whether the first expression is less than or equal to the second.
+    LessEqual(Box<Self>, Box<Self>),
+    /// Whether the first expression is greater than the second.
+    Greater(Box<Self>, Box<Self>),
+    /// Whether the first expression is less than the second.
+    Less(Box<Self>, Box<Self>),
 }
 
 /// An operation to apply when rearranging the equation
@@ -210,7 +228,11 @@ impl Expression {
             | Expression::Product(a, b)
             | Expression::Quotient(a, b)
             | Expression::Power(a, b)
-            | Expression::Equal(a, b) => {
+            | Expression::Equal(a, b)
+            | Expression::GreaterEqual(a, b)
+            | Expression::LessEqual(a, b)
+            | Expression::Greater(a, b)
+            | Expression::Less(a, b) => {
                 a.walk(cb);
                 b.walk(cb);
             }
@@ -239,7 +261,11 @@ impl Expression {
             | Expression::Product(a, b)
             | Expression::Quotient(a, b)
             | Expression::Power(a, b)
-            | Expression::Equal(a, b) => {
+            | Expression::Equal(a, b)
+            | Expression::GreaterEqual(a, b)
+            | Expression::LessEqual(a, b)
+            | Expression::Greater(a, b)
+            | Expression::Less(a, b) => {
                 a.walk_mut(cb);
                 b.walk_mut(cb);
             }
@@ -266,6 +292,15 @@ impl Expression {
                 Expression::Sum(_, _) | Expression::Difference(_, _) | Expression::Neg(_) => {
                     cost += 2;
                 }
+                // An equation or comparison costs as much as the difference
+                // of its sides, which is what it becomes as a residual.
+                Expression::Equal(_, _)
+                | Expression::GreaterEqual(_, _)
+                | Expression::LessEqual(_, _)
+                | Expression::Greater(_, _)
+                | Expression::Less(_, _) => {
+                    cost += 2;
+                }
                 Expression::Product(_, _) => {
                     cost += 4;
                 }
@@ -287,7 +322,6 @@ impl Expression {
                 Expression::Func(_) => {
                     cost += 45;
                 }
-                _ => {}
             };
             true
         });
@@ -334,6 +368,10 @@ impl Expression {
             Expression::Func(_) => 1,
 
             Expression::Equal(a, b) => panic!("num_solutions() called on {:?} = {:?}", a, b),
+            Expression::GreaterEqual(_, _)
+            | Expression::LessEqual(_, _)
+            | Expression::Greater(_, _)
+            | Expression::Less(_, _) => panic!("num_solutions() called on comparison {:?}", self),
         }
     }
 
@@ -410,6 +448,10 @@ impl Expression {
             Expression::Variable(v) => Ok(r.resolve_variable(v)?),
 
             Expression::Equal(a, b) => panic!("evaluate_1() called on {:?} = {:?}", a, b),
+            Expression::GreaterEqual(_, _)
+            | Expression::LessEqual(_, _)
+            | Expression::Greater(_, _)
+            | Expression::Less(_, _) => panic!("evaluate_1() called on comparison {:?}", self),
 
             Expression::Func(f) => {
                 let mut params: heapless::Vec<Concrete, 8> = heapless::Vec::new();
@@ -537,6 +579,10 @@ impl Expression {
             Expression::Variable(v) => Ok(r.resolve_variable(v)?),
 
             Expression::Equal(a, b) => panic!("evaluate() called on {:?} = {:?}", a, b),
+            Expression::GreaterEqual(_, _)
+            | Expression::LessEqual(_, _)
+            | Expression::Greater(_, _)
+            | Expression::Less(_, _) => panic!("evaluate() called on comparison {:?}", self),
 
             Expression::Func(f) => {
                 let mut params: heapless::Vec<Concrete, 8> = heapless::Vec::new();
@@ -551,6 +597,58 @@ impl Expression {
         }
     }
 
+    /// Returns true if the expression has a single value regardless of
+    /// any variables, once simplified.
+    pub fn is_constant(&self) -> bool {
+        if self.is_coefficient() {
+            return true;
+        }
+
+        let mut e = self.clone();
+        e.simplify();
+        let mut has_vars = false;
+        e.walk(&mut |e| {
+            if let Expression::Variable(_) = e {
+                has_vars = true;
+            }
+            !has_vars
+        });
+        !has_vars
+    }
+
+    /// Returns the exact value of the expression if it is made up only of
+    /// integer/rational arithmetic.
+    pub fn try_as_rational(&self) -> Option<Rational> {
+        match self {
+            Expression::Integer(i) => Some(Rational::from_integer(i.clone())),
+            Expression::Rational(r, _) => Some(r.clone()),
+            Expression::Neg(a) => Some(-a.try_as_rational()?),
+            Expression::Sum(a, b) => Some(a.try_as_rational()? + b.try_as_rational()?),
+            Expression::Difference(a, b) => Some(a.try_as_rational()? - b.try_as_rational()?),
+            Expression::Product(a, b) => Some(a.try_as_rational()? * b.try_as_rational()?),
+            Expression::Quotient(a, b) => {
+                let b = b.try_as_rational()?;
+                if b == Rational::from_integer(0.into()) {
+                    return None;
+                }
+                Some(a.try_as_rational()? / b)
+            }
+            Expression::Power(a, b) => {
+                use num::{ToPrimitive, Zero};
+                let (a, b) = (a.try_as_rational()?, b.try_as_rational()?);
+                if !b.is_integer() {
+                    return None;
+                }
+                let b = b.to_i32()?;
+                if a.is_zero() && b < 0 {
+                    return None;
+                }
+                Some(a.pow(b))
+            }
+            _ => None,
+        }
+    }
+
     fn is_coefficient(&self) -> bool {
         match self {
             Expression::Integer(_) => true,
@@ -568,7 +666,11 @@ impl Expression {
             | Expression::Product(a, b)
             | Expression::Quotient(a, b)
             | Expression::Power(a, b)
-            | Expression::Equal(a, b) => {
+            | Expression::Equal(a, b)
+            | Expression::GreaterEqual(a, b)
+            | Expression::LessEqual(a, b)
+            | Expression::Greater(a, b)
+            | Expression::Less(a, b) => {
                 a.simplify();
                 b.simplify();
             }
@@ -1071,6 +1173,20 @@ impl Expression {
         self.normalize();
 
         match self {
+            Expression::Equal(a, b) => {
+                // Trivially-true equations fold to 0 = 0.
+                let same_constant = match (a.try_as_rational(), b.try_as_rational()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => false,
+                };
+                if a == b || same_constant {
+                    *self = Expression::Equal(
+                        Box::new(Expression::Integer(0.into())),
+                        Box::new(Expression::Integer(0.into())),
+                    );
+                }
+            }
+
             Expression::Quotient(a, b) => {
                 // Divison by two identical terms is a 1.
                 if a == b {
@@ -1447,6 +1563,11 @@ impl Expression {
                 ))),
             },
             Expression::Neg(a) => Expression::Neg(Box::new(a.d_wrt(v))),
+            // d|a| = a' * a / |a|, undefined where a is zero.
+            Expression::Abs(a) => Expression::Quotient(
+                Box::new(Expression::Product(Box::new(a.d_wrt(v)), a.clone())),
+                Box::new(self.clone()),
+            ),
             Expression::Sum(a, b) => Expression::Sum(Box::new(a.d_wrt(v)), Box::new(b.d_wrt(v))),
             Expression::Difference(a, b) => {
                 Expression::Difference(Box::new(a.d_wrt(v)), Box::new(b.d_wrt(v)))
@@ -1455,7 +1576,7 @@ impl Expression {
                 let (da, db) = (a.d_wrt(v), b.d_wrt(v));
                 Expression::Sum(
                     Box::new(Expression::Product(a.clone(), Box::new(db))),
-                    Box::new(Expression::Product(a.clone(), Box::new(da))),
+                    Box::new(Expression::Product(b.clone(), Box::new(da))),
                 )
             }
             Expression::Quotient(a, b) => {
@@ -1554,6 +1675,27 @@ impl Expression {
         }
     }
 
+    /// Evaluates a comparison, returning whether it holds. Both sides must
+    /// resolve to a single value; equality is checked within a small tolerance.
+    pub fn check<R: Resolver>(&self, r: &mut R) -> Result<bool, ResolveErr> {
+        let (a, b) = match self {
+            Expression::Equal(a, b)
+            | Expression::GreaterEqual(a, b)
+            | Expression::LessEqual(a, b)
+            | Expression::Greater(a, b)
+            | Expression::Less(a, b) => (a.evaluate_1(r)?.as_f64(), b.evaluate_1(r)?.as_f64()),
+            _ => return Err(ResolveErr::NotImplementedOrWhatever),
+        };
+
+        Ok(match self {
+            Expression::Equal(_, _) => (a - b).abs() < CHECK_EPSILON,
+            Expression::GreaterEqual(_, _) => a >= b,
+            Expression::LessEqual(_, _) => a <= b,
+            Expression::Greater(_, _) => a > b,
+            _ => a < b,
+        })
+    }
+
     pub fn parse<'a>(
         expression: &'a str,
         simplify: bool,
@@ -1654,6 +1796,10 @@ impl Display for Expression {
             },
 
             Expression::Equal(a, b) => write!(f, "{} = {}", a, b),
+            Expression::GreaterEqual(a, b) => write!(f, "{} >= {}", a, b),
+            Expression::LessEqual(a, b) => write!(f, "{} <= {}", a, b),
+            Expression::Greater(a, b) => write!(f, "{} > {}", a, b),
+            Expression::Less(a, b) => write!(f, "{} < {}", a, b),
             Expression::Sum(a, b) => write!(f, "({} + {})", a, b),
             Expression::Difference(a, b) => write!(f, "({} - {})", a, b),
             Expression::Quotient(a, b) => write!(f, "({} / {})", a, b),
@@ -1910,14 +2056,8 @@ mod tests {
         assert_eq!(
             Expression::parse("12 + 1 / 3 = 37/3", true),
             Ok(Expression::Equal(
-                Box::new(Expression::Rational(
-                    Rational::new(37.into(), 3.into()),
-                    true,
-                )),
-                Box::new(Expression::Rational(
-                    Rational::new(37.into(), 3.into()),
-                    true
-                ))
+                Box::new(Expression::Integer(0.into())),
+                Box::new(Expression::Integer(0.into())),
             ))
         );
         assert_eq!(
@@ -2697,6 +2837,36 @@ mod tests {
                 Concrete::Float(f) if (f - 1.0).abs() < 0.001));
     }
 
+    #[test]
+    fn derivative_wrt_product() {
+        // d/dx (a * b) = a * db + b * da, so each factor is kept against the
+        // derivative of the other.
+        assert_eq!(
+            Expression::parse("x * y", false)
+                .unwrap()
+                .derivative_wrt(&"x".into()),
+            Expression::parse("y", false).unwrap(),
+        );
+        assert_eq!(
+            Expression::parse("x * y", false)
+                .unwrap()
+                .derivative_wrt(&"y".into()),
+            Expression::parse("x", false).unwrap(),
+        );
+
+        // Neither factor is constant: 2xy + y - 1 at (3, 5).
+        assert!(matches!(
+            Expression::parse("(x + 1) * (x * y - 1)", false)
+                .unwrap()
+                .derivative_wrt(&"x".into())
+                .evaluate_1(&mut StaticResolver::new([
+                    ("x".into(), Concrete::Float(3.0)),
+                    ("y".into(), Concrete::Float(5.0)),
+                ]))
+                .unwrap(),
+            Concrete::Float(f) if (f - 34.0).abs() < 0.001));
+    }
+
     #[test]
     fn derivative_wrt() {
         assert_eq!(
@@ -2821,6 +2991,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check() {
+        assert_eq!(
+            Expression::parse("3 < 5", false)
+                .unwrap()
+                .check(&mut StaticResolver::new([])),
+            Ok(true),
+        );
+        assert_eq!(
+            Expression::parse("3 > 5", false)
+                .unwrap()
+                .check(&mut StaticResolver::new([])),
+            Ok(false),
+        );
+
+        let len = Expression::parse("len >= 5", false).unwrap();
+        assert_eq!(
+            len,
+            Expression::GreaterEqual(
+                Box::new(Expression::Variable("len".into())),
+                Box::new(Expression::Integer(5.into())),
+            )
+        );
+        assert_eq!(
+            len.check(&mut StaticResolver::new([(
+                "len".into(),
+                Concrete::Float(5.0)
+            )])),
+            Ok(true),
+        );
+        assert_eq!(
+            len.check(&mut StaticResolver::new([(
+                "len".into(),
+                Concrete::Float(4.5)
+            )])),
+            Ok(false),
+        );
+        assert_eq!(
+            len.check(&mut StaticResolver::new([])),
+            Err(ResolveErr::UnknownVar("len".into())),
+        );
+        assert_eq!(
+            Expression::parse("len + 1", false)
+                .unwrap()
+                .check(&mut StaticResolver::new([])),
+            Err(ResolveErr::NotImplementedOrWhatever),
+        );
+    }
+
     #[test]
     fn as_residual() {
         assert_eq!(
@@ -2859,4 +3078,71 @@ mod tests {
             Expression::parse("((x2 - x3) * (y1 - y4)) - ((y2 - y3) * (x1 - x4))", false).unwrap(),
         );
     }
+
+    #[test]
+    fn cost_equal() {
+        let lhs = Expression::parse("x + 2", false).unwrap();
+        let eq = Expression::parse("x + 2 = 5", false).unwrap();
+        // The sides plus the cost of the equation itself.
+        assert_eq!(
+            eq.cost(),
+            lhs.cost() + Expression::Integer(5.into()).cost() + 2
+        );
+        assert_eq!(
+            Expression::parse("x < 5", false).unwrap().cost(),
+            eq.cost() - lhs.cost() + Expression::Variable("x".into()).cost()
+        );
+    }
+
+    #[test]
+    fn simplify_equal_fold() {
+        let truth = Expression::Equal(
+            Box::new(Expression::Integer(0.into())),
+            Box::new(Expression::Integer(0.into())),
+        );
+
+        assert_eq!(Expression::parse("5 = 5", true), Ok(truth.clone()));
+        assert_eq!(Expression::parse("x + y = x + y", true), Ok(truth.clone()));
+        assert_eq!(Expression::parse("5/2 = 10/4", true), Ok(truth.clone()));
+        assert_eq!(Expression::parse("2 = 4/2", true), Ok(truth.clone()));
+        assert_eq!(truth.as_residual(), Ok(Expression::Integer(0.into())));
+
+        assert_eq!(
+            Expression::parse("5 = 6", true),
+            Ok(Expression::Equal(
+                Box::new(Expression::Integer(5.into())),
+                Box::new(Expression::Integer(6.into())),
+            ))
+        );
+    }
+
+    #[test]
+    fn constants() {
+        let e = Expression::parse("2 + 3", false).unwrap();
+        assert!(e.is_constant());
+        assert_eq!(e.try_as_rational(), Some(Rational::from_integer(5.into())));
+
+        let e = Expression::parse("1/2 + 1/2", false).unwrap();
+        assert!(e.is_constant());
+        assert_eq!(e.try_as_rational(), Some(Rational::from_integer(1.into())));
+
+        let e = Expression::parse("x + 1", false).unwrap();
+        assert!(!e.is_constant());
+        assert_eq!(e.try_as_rational(), None);
+
+        // Variables which cancel out leave a constant.
+        assert!(Expression::parse("x - x + 1", false).unwrap().is_constant());
+        assert_eq!(
+            Expression::parse("(2/3)^2", false)
+                .unwrap()
+                .try_as_rational(),
+            Some(Rational::new(4.into(), 9.into()))
+        );
+        assert_eq!(
+            Expression::parse("1 / (2 - 2)", false)
+                .unwrap()
+                .try_as_rational(),
+            None
+        );
+    }
 }
@@ -91,10 +91,19 @@ pub(super) fn parse_expr<'a>() -> impl Parser<'a, &'a str, Expression> {
         );
 
         let eq = sum.clone().foldl(
-            op('=')
-                .to(Expression::Equal as fn(_, _) -> _)
-                .then(sum)
-                .repeated(),
+            choice((
+                just(">=")
+                    .padded()
+                    .to(Expression::GreaterEqual as fn(_, _) -> _),
+                just("<=")
+                    .padded()
+                    .to(Expression::LessEqual as fn(_, _) -> _),
+                op('>').to(Expression::Greater as fn(_, _) -> _),
+                op('<').to(Expression::Less as fn(_, _) -> _),
+                op('=').to(Expression::Equal as fn(_, _) -> _),
+            ))
+            .then(sum)
+            .repeated(),
             |lhs, (op, rhs)| op(Box::new(lhs), Box::new(rhs)),
         );
 
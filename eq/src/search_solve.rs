@@ -5,6 +5,10 @@ use na::{DVector, Dyn, OVector};
 use num::ToPrimitive;
 use std::collections::HashMap;
 
+/// Largest total number of bits (unknowns times subdivision bits) a
+/// brute-force search will cover, giving at most 2^16 residual evaluations.
+pub const MAX_SEARCH_BITS: usize = 16;
+
 /// Brute-force search solver, iterating outwards exponentially
 /// and keeping track of the best values (producing the smallest
 /// least-squares sum of residuals).
@@ -94,9 +98,18 @@ impl SearchSolver {
     // }
 
     // TODO: API that lets you do bits of work at a time
+    /// Tries each variable at 2^search_bits positions. search_bits is reduced
+    /// so the whole search stays within MAX_SEARCH_BITS, and if even one bit
+    /// per variable would exceed it no search is done and the residual is
+    /// infinite.
     pub fn bruteforce(mut self, search_bits: usize) -> (f64, Vec<(Variable, f64)>) {
-        let total = 2usize.pow((self.vars.len() * search_bits) as u32);
-        let var_states = 2usize.pow(search_bits as u32);
+        let search_bits = search_bits.min(MAX_SEARCH_BITS / self.vars.len().max(1));
+        let total = u32::try_from(self.vars.len() * search_bits)
+            .ok()
+            .and_then(|bits| 2usize.checked_pow(bits))
+            .filter(|_| search_bits > 0)
+            .unwrap_or(0);
+        let var_states = 1usize << search_bits;
 
         while self.iteration < total {
             // Update guesses
@@ -157,11 +170,18 @@ impl SearchSolver {
     }
 }
 
-/// The step parameters for ExpSearchIter.
-#[derive(Clone, Debug)]
+/// The step parameters for ExpSearchIter, and how a search
+/// using them should be run.
+#[derive(Clone, Debug, PartialEq)]
 pub struct ExpSearchParams {
     pub step: f64,
     pub exp: f64,
+
+    /// Each variable is tried at 2^subdivision_bits positions per pass,
+    /// as passed to SearchSolver::bruteforce().
+    pub subdivision_bits: usize,
+    /// Number of searches to run, each refining the last with a finer step.
+    pub passes: usize,
 }
 
 impl Default for ExpSearchParams {
@@ -169,6 +189,8 @@ impl Default for ExpSearchParams {
         Self {
             step: 0.707,
             exp: 1.33,
+            subdivision_bits: 3,
+            passes: 3,
         }
     }
 }
@@ -240,6 +262,7 @@ mod tests {
             ExpSearchParams {
                 step: 0.2,
                 exp: 1.0,
+                ..ExpSearchParams::default()
             },
             5.0,
         );
@@ -259,6 +282,7 @@ mod tests {
             ExpSearchParams {
                 step: 0.1,
                 exp: 2.0,
+                ..ExpSearchParams::default()
             },
             5.0,
         );
@@ -304,4 +328,27 @@ mod tests {
         assert!(residual_sq < 5.0);
         assert!((5.0 - (guesses[0].1.powi(2) + guesses[1].1.powi(2)).sqrt()) < 0.2);
     }
+
+    #[test]
+    fn search_solver_bruteforce_bounded() {
+        let solver = |n: usize| {
+            SearchSolver::new(
+                ExpSearchParams::default(),
+                HashMap::new(),
+                (0..n).map(|i| format!("v{}", i).as_str().into()).collect(),
+                vec![Expression::parse("88 - v0", false).unwrap()],
+                vec![85.0; n],
+            )
+        };
+
+        // 64 bits per variable would overflow; it's capped to the whole budget.
+        let (residual_sq, guesses) = solver(1).bruteforce(64);
+        assert!(residual_sq < 2.0);
+        assert!((88.0 - guesses[0].1).abs() < 0.3);
+
+        // Too many variables to try even two positions each.
+        let (residual_sq, guesses) = solver(MAX_SEARCH_BITS + 1).bruteforce(8);
+        assert_eq!(residual_sq, f64::INFINITY);
+        assert_eq!(guesses.len(), MAX_SEARCH_BITS + 1);
+    }
 }
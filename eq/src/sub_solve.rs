@@ -107,6 +107,8 @@ pub struct SubSolverState {
     resolved: HashMap<Variable, SolvePlan>,
     // expressions expected to be ordered in increasing complexity.
     vars_by_eq: HashMap<Variable, EquivalentExpressions>,
+    // variables in the order they were resolved by substitution.
+    resolution_order: Vec<Variable>,
 }
 
 impl SubSolverState {
@@ -176,6 +178,12 @@ impl SubSolverState {
             ..SubSolverState::default()
         })
     }
+
+    /// Returns the variables resolved by substitution, in the order they
+    /// were resolved. Useful for debugging.
+    pub fn resolution_order(&self) -> &[Variable] {
+        &self.resolution_order
+    }
 }
 
 impl super::Resolver for SubSolverState {
@@ -223,6 +231,7 @@ impl SubSolver {
 
         // Store the equation as a resolved value.
         if !st.resolved.contains_key(var) {
+            st.resolution_order.push(var.clone());
             // As a special case, if the equation only has one solution
             // then we store the numeric result rather than the equation.
             if out.expr.num_solutions() == 1 {
@@ -340,15 +349,43 @@ impl SubSolver {
         });
     }
 
+    // Orders variables so those appearing in the fewest and cheapest
+    // equations are attempted first, keeping substituted expressions small.
+    fn order_by_difficulty(st: &SubSolverState, vars: &[Variable]) -> Vec<Variable> {
+        let mut keyed: Vec<((usize, usize), Variable)> = vars
+            .iter()
+            .map(|v| {
+                let mut num_eqs = 0;
+                let mut cost = usize::MAX;
+                for (lhs_var, ee) in st.vars_by_eq.iter() {
+                    for info in ee.exprs.iter() {
+                        if lhs_var == v {
+                            num_eqs += 1;
+                            cost = cost.min(info.cost);
+                        } else if info.references.contains_key(v) {
+                            num_eqs += 1;
+                        }
+                    }
+                }
+                ((num_eqs, cost), v.clone())
+            })
+            .collect();
+
+        // Stable, so ties keep the order given by sort_vars_by_base().
+        keyed.sort_by_key(|(k, _)| *k);
+        keyed.into_iter().map(|(_, v)| v).collect()
+    }
+
     fn try_solve(&mut self, st: &mut SubSolverState) -> Vec<Variable> {
         let vars = self.all_vars(st);
         if st.done_substitution {
             return vars;
         }
+        let order = SubSolver::order_by_difficulty(st, &vars);
 
         'outer_loop: for _i in 0..vars.len() {
             // Find the next variable which is simplest to solve.
-            for v in vars.iter() {
+            for v in order.iter() {
                 if st.resolved.contains_key(&v) {
                     continue;
                 };
@@ -369,7 +406,7 @@ impl SubSolver {
             // Oh no! There wasn't a simple substitution to be done this round.
             // Lets try rearranging equations that have the right variables
             // to be solved for the target.
-            for v in vars.iter() {
+            for v in order.iter() {
                 if st.resolved.contains_key(&v) {
                     continue;
                 };
@@ -678,6 +715,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resolution_order() {
+        let mut state = SubSolverState::new(
+            HashMap::from([
+                (
+                    "x".into(),
+                    Concrete::Rational(Rational::from_integer(3.into())),
+                ),
+                (
+                    "y".into(),
+                    Concrete::Rational(Rational::from_integer(4.into())),
+                ),
+            ]),
+            vec![
+                Expression::parse("a = sqrt(x^2 + y^2)", false).unwrap(),
+                Expression::parse("b = x", false).unwrap(),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            SubSolver::default()
+                .find(&mut state, &"a".into())
+                .unwrap()
+                .as_f64(),
+            5.0
+        );
+        // b is trivially determined, so should be resolved before a.
+        assert_eq!(
+            state.resolution_order(),
+            &["b".into(), "a".into()] as &[Variable]
+        );
+    }
+
     #[test]
     fn solve_rect() {
         // rectangle with point 0 at (0, 0), with the other points defined
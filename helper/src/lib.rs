@@ -124,6 +124,12 @@ impl State {
             (&"Equal", &"E", &"When applied to lines, constrains the lines to have the same length as each other.\n\nWhen applied to circles, constrains the radius to be equal for both circles."),
             (&"Parallel", &"", &"Constrains lines to be parallel to each other.\n\nThe solver for this constraint doesn't work so well :/"),
             (&"Angle", &"N", &"Constrains a line to have a certain angle."),
+            ("Length range", "", "Constrains a line's length to stay between a minimum and maximum, rather than at an exact value. Select lines and press 'Length range' in the selection UI; the range starts at the current length and can be widened there."),
+            ("Bisector", "", "Keeps a point equidistant from two others, i.e. on the perpendicular bisector between them. Select the two points and then the constrained point, and press 'Bisector' in the selection UI."),
+            ("Offset from line", "", "Keeps a point at a fixed perpendicular distance from a line, on the side it started on. Select a point and a line, and press 'Offset from line' in the selection UI."),
+            ("Common tangent", "", "Constrains a line to be tangent to two circles. Select a line and two circles, and press 'Common tangent' in the selection UI. If the line passes between the circles the tangent is internal, otherwise external."),
+            ("Equal angles", "", "Spaces three lines 120 degrees apart. Select three lines and press 'Equal angles' in the selection UI."),
+            ("Point along arc", "", "Constrains a point to be a certain fraction of the way around an arc. Select a point and an arc, and press 'Along arc' in the selection UI. The fraction starts where the point is and can be changed later in the selection UI."),
         ];
 
     fn getting_started_layout_job(&mut self, ui: &egui::Ui) -> LayoutJob {
@@ -210,7 +216,7 @@ impl State {
         uij.append(").\n", 0.0, base.clone());
 
         uij.append(
-            "Use your right-mouse button to pan about your drawing, and the scroll-wheel to zoom in and out.\n",
+            "Use your right (or middle) mouse button or the arrow keys to pan about your drawing, and the scroll-wheel to zoom in and out.\n",
             0.0,
             base.clone(),
         );
@@ -252,7 +258,56 @@ impl State {
                 ..base.clone()
             },
         );
-        uij.append(" key.\n\n", 0.0, base.clone());
+        uij.append(" key.\n", 0.0, base.clone());
+        uij.append("Press ", 0.0, base.clone());
+        uij.append(
+            " G ",
+            0.0,
+            egui::TextFormat {
+                background: egui::Color32::from_gray(HOTKEY_BACKGROUND_WHITENESS),
+                ..base.clone()
+            },
+        );
+        uij.append(
+            " to toggle construction on the selection, ",
+            0.0,
+            base.clone(),
+        );
+        uij.append(
+            "Alt-I",
+            0.0,
+            egui::TextFormat {
+                background: egui::Color32::from_gray(HOTKEY_BACKGROUND_WHITENESS),
+                ..base.clone()
+            },
+        );
+        uij.append(
+            " to dim everything but the selection, and ",
+            0.0,
+            base.clone(),
+        );
+        uij.append(
+            "Alt-X",
+            0.0,
+            egui::TextFormat {
+                background: egui::Color32::from_gray(HOTKEY_BACKGROUND_WHITENESS),
+                ..base.clone()
+            },
+        );
+        uij.append(" / ", 0.0, base.clone());
+        uij.append(
+            "Alt-Y",
+            0.0,
+            egui::TextFormat {
+                background: egui::Color32::from_gray(HOTKEY_BACKGROUND_WHITENESS),
+                ..base.clone()
+            },
+        );
+        uij.append(
+            " to center the selected dimension labels horizontally/vertically.\n\n",
+            0.0,
+            base.clone(),
+        );
 
         uij.append(
             "At this stage, you should have a drawing composed of a bunch of haphazard lines or whatever. Lets go-ahead and make it something meaningful by constraining it to form a part we actually want!\n",
@@ -323,3 +378,39 @@ impl State {
         uij
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constraint_hotkeys() {
+        // The tool each help entry's hotkey should equip.
+        let want = |name: &str| match name {
+            "Fixed" => "Constrain to co-ords",
+            "Dimension" => "Constrain length/radius",
+            "Horizontal" => "Constrain horizontal",
+            "Vertical" => "Constrain vertical",
+            "Point along line" => "Constrain point along line",
+            "Equal" => "Constrain equal",
+            "Angle" => "Constain line angle",
+            _ => panic!("no tool expected for {}", name),
+        };
+
+        let mut seen: Vec<&str> = Vec::new();
+        for (name, hotkey, _) in State::CONSTRAINTS {
+            if hotkey.is_empty() {
+                continue;
+            }
+            assert_eq!(
+                drawing::tools::Toolbar::tool_for_hotkey(hotkey),
+                Some(want(name)),
+                "{} hotkey {} equips the wrong tool",
+                name,
+                hotkey
+            );
+            assert!(!seen.contains(hotkey), "duplicate hotkey {}", hotkey);
+            seen.push(hotkey);
+        }
+    }
+}
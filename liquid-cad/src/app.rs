@@ -8,11 +8,20 @@ fn execute<F: std::future::Future<Output = ()> + 'static>(f: F) {
     wasm_bindgen_futures::spawn_local(f);
 }
 
+/// Storage key every open drawing is saved under. Older versions saved only
+/// the one drawing, under eframe::APP_KEY.
+const DRAWINGS_KEY: &str = "drawings";
+
+/// A file name and its contents.
+type NamedBytes = (String, Vec<u8>);
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct App {
     #[serde(skip)]
-    drawing: drawing::Data,
+    drawings: Vec<drawing::Data>,
+    #[serde(skip)]
+    active: usize,
     #[serde(skip)]
     handler: drawing::Handler,
     #[serde(skip)]
@@ -30,11 +39,14 @@ pub struct App {
     last_path: Option<std::path::PathBuf>,
     #[serde(skip)]
     wasm_open_channel: (Sender<(String, String)>, Receiver<(String, String)>),
+    #[serde(skip)]
+    wasm_image_channel: (Sender<NamedBytes>, Receiver<NamedBytes>),
 }
 
 impl Default for App {
     fn default() -> Self {
-        let drawing = drawing::Data::default();
+        let drawings = vec![drawing::Data::default()];
+        let active = 0;
         let tools = drawing::tools::Toolbar::default();
         let handler = drawing::Handler::default();
         let detailer_state = detailer::State::default();
@@ -45,10 +57,12 @@ impl Default for App {
 
         let last_path = None;
         let wasm_open_channel = channel();
+        let wasm_image_channel = channel();
         let show_help = true;
 
         Self {
-            drawing,
+            drawings,
+            active,
             handler,
             tools,
             detailer_state,
@@ -57,6 +71,7 @@ impl Default for App {
             show_help,
             last_path,
             wasm_open_channel,
+            wasm_image_channel,
         }
     }
 }
@@ -69,11 +84,24 @@ impl App {
         let mut app = Self::default();
 
         if let Some(storage) = cc.storage {
-            if let Some(saved) =
-                eframe::get_value::<drawing::SerializedDrawing>(storage, eframe::APP_KEY)
-            {
-                if app.drawing.load(saved).err().is_some() {
-                    println!("Failed to load diagram from storage");
+            let saved = eframe::get_value::<Vec<drawing::SerializedDrawing>>(storage, DRAWINGS_KEY)
+                .or_else(|| {
+                    eframe::get_value::<drawing::SerializedDrawing>(storage, eframe::APP_KEY)
+                        .map(|sd| vec![sd])
+                });
+            if let Some(saved) = saved.filter(|s| !s.is_empty()) {
+                app.drawings = Vec::with_capacity(saved.len());
+                for sd in saved {
+                    let mut d = drawing::Data::default();
+                    if d.load(sd).err().is_some() {
+                        println!("Failed to load diagram from storage");
+                        continue;
+                    }
+                    Self::load_reference_pixels(&mut d);
+                    app.drawings.push(d);
+                }
+                if app.drawings.is_empty() {
+                    app.drawings.push(drawing::Data::default());
                 } else {
                     app.show_help = false;
                 }
@@ -85,6 +113,14 @@ impl App {
         app
     }
 
+    fn active_drawing(&self) -> &drawing::Data {
+        &self.drawings[self.active]
+    }
+
+    fn active_drawing_mut(&mut self) -> &mut drawing::Data {
+        &mut self.drawings[self.active]
+    }
+
     fn export_str_as(&mut self, type_name: &'static str, ext_name: &'static str, data: Vec<u8>) {
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -158,7 +194,7 @@ impl App {
             let file = f.save_file();
 
             if let Some(path) = file {
-                let sd = &self.drawing.serialize();
+                let sd = &self.active_drawing().serialize();
 
                 match std::fs::write(
                     path.clone(),
@@ -184,7 +220,7 @@ impl App {
 
         #[cfg(target_arch = "wasm32")]
         {
-            let sd = (&self.drawing.serialize()).clone();
+            let sd = self.active_drawing().serialize();
             let task = rfd::AsyncFileDialog::new()
                 .set_file_name(file_name)
                 .save_file();
@@ -203,6 +239,79 @@ impl App {
         }
     }
 
+    /// Reads back the pixels of the drawing's reference image, which aren't
+    /// saved with the drawing.
+    fn load_reference_pixels(drawing: &mut drawing::Data) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(img) = drawing.reference_image.as_mut() {
+            if img.bytes.is_none() {
+                img.bytes = std::fs::read(&img.path).ok().map(|b| b.into());
+            }
+        }
+    }
+
+    fn set_reference_image(&mut self, path: String, bytes: Vec<u8>) {
+        let dims = image::io::Reader::new(std::io::Cursor::new(&bytes))
+            .with_guessed_format()
+            .map_err(image::ImageError::from)
+            .and_then(|r| r.into_dimensions());
+        match dims {
+            Ok((width, height)) => {
+                self.active_drawing_mut().reference_image =
+                    Some(drawing::RefImage::new(path, bytes.into(), width, height));
+            }
+            Err(e) => {
+                self.toasts.add(egui_toast::Toast {
+                    text: format!("Image load failed: {:?}", e).into(),
+                    kind: egui_toast::ToastKind::Error,
+                    options: egui_toast::ToastOptions::default()
+                        .duration_in_seconds(5.0)
+                        .show_progress(true),
+                });
+            }
+        }
+    }
+
+    pub fn import_reference_image(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use rfd::FileDialog;
+            let file = FileDialog::new()
+                .add_filter("PNG image", &["png"])
+                .pick_file();
+
+            if let Some(path) = file {
+                match std::fs::read(path.clone()) {
+                    Ok(b) => self.set_reference_image(path.to_string_lossy().into_owned(), b),
+                    Err(e) => {
+                        self.toasts.add(egui_toast::Toast {
+                            text: format!("Read failed: {:?}", e).into(),
+                            kind: egui_toast::ToastKind::Error,
+                            options: egui_toast::ToastOptions::default()
+                                .duration_in_seconds(5.0)
+                                .show_progress(true),
+                        });
+                    }
+                }
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let sender = self.wasm_image_channel.0.clone();
+            let task = rfd::AsyncFileDialog::new()
+                .add_filter("PNG image", &["png"])
+                .pick_file();
+            execute(async move {
+                let file = task.await;
+                if let Some(file) = file {
+                    let bytes = file.read().await;
+                    let _ = sender.send((file.file_name(), bytes));
+                }
+            });
+        }
+    }
+
     pub fn open_from(&mut self) {
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -216,7 +325,7 @@ impl App {
                 match std::fs::read(path.clone()) {
                     Ok(b) => match ron::de::from_bytes(&b) {
                         Ok(d) => {
-                            if let Some(e) = self.drawing.load(d).err() {
+                            if let Some(e) = self.active_drawing_mut().load(d).err() {
                                 self.toasts.add(egui_toast::Toast {
                                     text: format!("Load failed: {:?}", e).into(),
                                     kind: egui_toast::ToastKind::Error,
@@ -226,6 +335,7 @@ impl App {
                                 });
                             } else {
                                 self.last_path = Some(path);
+                                Self::load_reference_pixels(self.active_drawing_mut());
                             }
                         }
 
@@ -272,7 +382,9 @@ impl App {
 impl eframe::App for App {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, &self.drawing.serialize());
+        let drawings: Vec<drawing::SerializedDrawing> =
+            self.drawings.iter().map(|d| d.serialize()).collect();
+        eframe::set_value(storage, DRAWINGS_KEY, &drawings);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -285,7 +397,7 @@ impl eframe::App for App {
         if let Ok((fname, contents)) = self.wasm_open_channel.1.try_recv() {
             match ron::de::from_str(&contents) {
                 Ok(d) => {
-                    if let Some(e) = self.drawing.load(d).err() {
+                    if let Some(e) = self.active_drawing_mut().load(d).err() {
                         self.toasts.add(egui_toast::Toast {
                             text: format!("Load failed: {:?}", e).into(),
                             kind: egui_toast::ToastKind::Error,
@@ -310,6 +422,11 @@ impl eframe::App for App {
             }
         }
 
+        #[cfg(target_arch = "wasm32")]
+        if let Ok((fname, bytes)) = self.wasm_image_channel.1.try_recv() {
+            self.set_reference_image(fname, bytes);
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
@@ -367,71 +484,93 @@ impl eframe::App for App {
                         center = true;
                         zoom = true;
                     }
+                    if ui.button("Reference image...").clicked() {
+                        self.import_reference_image();
+                    }
                     ui.separator();
                     if ui.button("Solve step").clicked() {
-                        self.drawing.changed_in_ui();
+                        self.active_drawing_mut().changed_in_ui();
+                    }
+                    if ui.button("Merge duplicate lines").clicked() {
+                        let n = self.active_drawing_mut().merge_duplicate_lines();
+                        self.toasts.add(egui_toast::Toast {
+                            text: format!("Removed {} duplicate line(s)", n).into(),
+                            kind: egui_toast::ToastKind::Info,
+                            options: egui_toast::ToastOptions::default()
+                                .duration_in_seconds(3.0)
+                                .show_progress(true),
+                        });
                     }
                     // if ui.button("Bruteforce solve").clicked() {
-                    //     self.drawing.bruteforce_solve();
+                    //     self.drawings[self.active].bruteforce_solve();
                     // }
                 });
                 ui.add_space(8.0);
 
                 ui.menu_button("Selection", |ui| {
                     if ui.button("Clear   (Esc)").clicked() {
-                        self.drawing.selection_clear();
+                        self.active_drawing_mut().selection_clear();
                     }
                     if ui.button("Select all   (Ctrl-A)").clicked() {
-                        self.drawing.select_all();
+                        self.active_drawing_mut().select_all();
                     }
                     ui.menu_button("Select feature", |ui| {
                         ui.horizontal(|ui| {
                             ui.add(egui::Image::new(drawing::CONSTRUCTION_IMG).rounding(5.0));
                             ui.checkbox(
-                                &mut self.drawing.select_action_inc_construction,
+                                &mut self.active_drawing_mut().select_action_inc_construction,
                                 "include construction features",
                             );
                         });
                         ui.separator();
                         use slotmap::Key;
                         if ui.button("Points").clicked() {
-                            self.drawing.select_type(&drawing::Feature::Point(
-                                drawing::FeatureMeta::default(),
-                                0.,
-                                0.,
-                            ));
+                            self.active_drawing_mut()
+                                .select_type(&drawing::Feature::Point(
+                                    drawing::FeatureMeta::default(),
+                                    0.,
+                                    0.,
+                                ));
                         }
                         if ui.button("Lines").clicked() {
-                            self.drawing.select_type(&drawing::Feature::LineSegment(
-                                drawing::FeatureMeta::default(),
-                                drawing::FeatureKey::null(),
-                                drawing::FeatureKey::null(),
-                            ));
+                            self.active_drawing_mut()
+                                .select_type(&drawing::Feature::LineSegment(
+                                    drawing::FeatureMeta::default(),
+                                    drawing::FeatureKey::null(),
+                                    drawing::FeatureKey::null(),
+                                ));
                         }
                         if ui.button("Circles").clicked() {
-                            self.drawing.select_type(&drawing::Feature::Circle(
-                                drawing::FeatureMeta::default(),
-                                drawing::FeatureKey::null(),
-                                0.,
-                            ));
+                            self.active_drawing_mut()
+                                .select_type(&drawing::Feature::Circle(
+                                    drawing::FeatureMeta::default(),
+                                    drawing::FeatureKey::null(),
+                                    0.,
+                                ));
                         }
                         if ui.button("Arcs").clicked() {
-                            self.drawing.select_type(&drawing::Feature::Arc(
-                                drawing::FeatureMeta::default(),
-                                drawing::FeatureKey::null(),
-                                drawing::FeatureKey::null(),
-                                drawing::FeatureKey::null(),
-                            ));
+                            self.active_drawing_mut()
+                                .select_type(&drawing::Feature::Arc(
+                                    drawing::FeatureMeta::default(),
+                                    drawing::FeatureKey::null(),
+                                    drawing::FeatureKey::null(),
+                                    drawing::FeatureKey::null(),
+                                ));
                         }
                     });
 
+                    ui.checkbox(
+                        &mut self.active_drawing_mut().isolate_selection,
+                        "Isolate selection   (Alt-I)",
+                    );
+
                     ui.separator();
                     ui.menu_button("Dimension label", |ui| {
-                        if ui.button("Center in-axis").clicked() {
-                            self.drawing.selection_labels_center(true);
+                        if ui.button("Center in-axis   (Alt-X)").clicked() {
+                            self.active_drawing_mut().selection_labels_center(true);
                         }
-                        if ui.button("Center cross-axis").clicked() {
-                            self.drawing.selection_labels_center(false);
+                        if ui.button("Center cross-axis   (Alt-Y)").clicked() {
+                            self.active_drawing_mut().selection_labels_center(false);
                         }
                     });
                 });
@@ -445,21 +584,21 @@ impl eframe::App for App {
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                     ui.checkbox(
-                        &mut self.drawing.drag_dimensions_enabled,
+                        &mut self.active_drawing_mut().drag_dimensions_enabled,
                         "Allow dragging dimensions",
                     );
                     ui.add_space(4.0);
                     ui.separator();
                     ui.add_space(4.0);
                     ui.checkbox(
-                        &mut self.drawing.drag_features_enabled,
+                        &mut self.active_drawing_mut().drag_features_enabled,
                         "Allow dragging features",
                     );
                     ui.add_space(10.0);
 
                     let amt = ctx.animate_bool_with_time(
                         "error_display".into(),
-                        self.drawing.last_solve_error.is_some(),
+                        self.active_drawing().last_solve_error.is_some(),
                         0.4,
                     );
                     ui.style_mut().visuals.override_text_color =
@@ -469,21 +608,25 @@ impl eframe::App for App {
                         .add(
                             egui::Label::new(format!(
                                 "⚠ Solver inconsistency! avg: {:.3}mm",
-                                self.drawing.last_solve_error.unwrap_or(0.0)
+                                self.active_drawing().last_solve_error.unwrap_or(0.0)
                             ))
                             .sense(egui::Sense::click()),
                         )
                         .clicked()
                     {
-                        self.drawing.changed_in_ui();
+                        self.active_drawing_mut().changed_in_ui();
                     };
                 });
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            let mut main_widget =
-                drawing::Widget::new(&mut self.drawing, &mut self.handler, &mut self.tools);
+            let mut main_widget = drawing::Widget::new(
+                &mut self.drawings[self.active],
+                &mut self.handler,
+                &mut self.tools,
+            )
+            .id_source(("drawing", self.active));
             if center {
                 main_widget.center();
             }
@@ -495,7 +638,7 @@ impl eframe::App for App {
 
         detailer::Widget::new(
             &mut self.detailer_state,
-            &mut self.drawing,
+            &mut self.drawings[self.active],
             &mut self.tools,
             &mut self.handler,
             &mut self.toasts,
@@ -518,6 +661,29 @@ impl eframe::App for App {
         //         ctx.inspection_ui(ui);
         //     });
 
+        let drawing = &mut self.drawings[self.active];
+        for i in drawing.broken_groups.drain(..) {
+            if let Some(g) = drawing.groups.get(i) {
+                self.toasts.add(egui_toast::Toast {
+                    text: format!("Group '{}' is no longer a closed path", g.name).into(),
+                    kind: egui_toast::ToastKind::Warning,
+                    options: egui_toast::ToastOptions::default()
+                        .duration_in_seconds(5.0)
+                        .show_progress(true),
+                });
+            }
+        }
+
+        for w in drawing.constraint_warnings.drain(..) {
+            self.toasts.add(egui_toast::Toast {
+                text: w.into(),
+                kind: egui_toast::ToastKind::Warning,
+                options: egui_toast::ToastOptions::default()
+                    .duration_in_seconds(5.0)
+                    .show_progress(true),
+            });
+        }
+
         self.toasts.show(ctx);
 
         if let Some((type_name, ext, data)) = pending_export {